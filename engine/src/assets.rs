@@ -1,4 +1,11 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Context;
+use rayon::prelude::*;
 
 use crate::{ReadOnlyString, graphics::image::Image};
 
@@ -6,12 +13,15 @@ use crate::{ReadOnlyString, graphics::image::Image};
 pub struct AssetStore {
     // TODO: Actual asset store implementation
     images: HashMap<ReadOnlyString, Image>,
+    /// The files images were loaded from, for images that came from disk.
+    sources: HashMap<ReadOnlyString, PathBuf>,
 }
 
 impl AssetStore {
     pub fn new() -> Self {
         Self {
             images: HashMap::new(),
+            sources: HashMap::new(),
         }
     }
 
@@ -22,12 +32,142 @@ impl AssetStore {
         data: &[u8],
     ) -> anyhow::Result<Image> {
         let image = Image::from_mem(data)?;
-        self.images.insert(name.into(), image.clone()); // This clone is cheap due to Image using Arc internally
+        let name = name.into();
+        self.sources.remove(&name);
+        self.images.insert(name, image.clone()); // This clone is cheap due to Image using Arc internally
         Ok(image)
     }
 
+    /// Decodes several images in parallel and adds them to the asset store.
+    ///
+    /// If any image fails to decode, nothing is added and the error names the failing image.
+    pub fn add_images_parallel(&mut self, images: &[(&str, &[u8])]) -> anyhow::Result<()> {
+        let decoded = images
+            .par_iter()
+            .map(|(name, data)| {
+                Image::from_mem(data)
+                    .map(|image| (*name, image))
+                    .with_context(|| format!("Failed to decode image {name:?}"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        for (name, image) in decoded {
+            self.sources.remove(name);
+            self.images.insert(name.into(), image);
+        }
+        Ok(())
+    }
+
+    /// Loads every `.png` file in `dir` in parallel, adding each under its file stem, e.g.
+    /// `stone.png` as `"stone"`. Subdirectories aren't searched.
+    ///
+    /// Returns the names of the added images, sorted. If any image fails to load, nothing is added
+    /// and the error names the failing file.
+    pub fn add_images_from_dir(&mut self, dir: &Path) -> anyhow::Result<Vec<String>> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read image directory {}", dir.display()))?
+        {
+            let path = entry?.path();
+            let is_png = path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+            if is_png && path.is_file() {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let loaded = paths
+            .par_iter()
+            .map(|path| {
+                let name = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .with_context(|| format!("{} has a name that isn't UTF-8", path.display()))?;
+                let image = path
+                    .to_str()
+                    .context("path isn't UTF-8")
+                    .and_then(Image::from_file)
+                    .with_context(|| format!("Failed to load image {}", path.display()))?;
+                Ok((name.to_string(), image, path.clone()))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let mut names = Vec::with_capacity(loaded.len());
+        for (name, image, path) in loaded {
+            let key: ReadOnlyString = name.as_str().into();
+            self.images.insert(key.clone(), image);
+            self.sources.insert(key, path);
+            names.push(name);
+        }
+        Ok(names)
+    }
+
     /// Retrieves an image by name.
     pub fn get_image(&self, name: &str) -> Option<Image> {
         self.images.get(name).cloned()
     }
+
+    /// Returns the file an image was loaded from, if it was loaded from disk.
+    pub fn image_source(&self, name: &str) -> Option<&Path> {
+        self.sources.get(name).map(PathBuf::as_path)
+    }
+
+    /// Returns every image whose name starts with `prefix`, sorted by name.
+    pub fn images_with_prefix(&self, prefix: &str) -> Vec<(&str, Image)> {
+        let mut images = self
+            .images
+            .iter()
+            .filter(|(name, _)| name.starts_with(prefix))
+            .map(|(name, image)| (&**name, image.clone()))
+            .collect::<Vec<_>>();
+        images.sort_by_key(|(name, _)| *name);
+        images
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// Encodes a solid colored PNG.
+    fn png(width: u32, height: u32, color: [u8; 4]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image::RgbaImage::from_pixel(width, height, image::Rgba(color))
+            .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn parallel_decode_keeps_names() {
+        let images = (0..8u8)
+            .map(|i| (format!("image_{i}"), png(4 + i as u32, 4, [i, 0, 0, 255])))
+            .collect::<Vec<_>>();
+        let sources = images
+            .iter()
+            .map(|(name, bytes)| (name.as_str(), bytes.as_slice()))
+            .collect::<Vec<_>>();
+
+        let mut store = AssetStore::new();
+        store.add_images_parallel(&sources).unwrap();
+        for i in 0..8u8 {
+            let image = store.get_image(&format!("image_{i}")).unwrap();
+            assert_eq!(image.dimensions(), (4 + i as u32, 4));
+            assert_eq!(&image.pixel_bytes()[..4], &[i, 0, 0, 255]);
+        }
+    }
+
+    #[test]
+    fn parallel_decode_names_the_broken_image() {
+        let good = png(2, 2, [255; 4]);
+        let mut store = AssetStore::new();
+        let error = store
+            .add_images_parallel(&[("good", &good), ("broken", b"not a png")])
+            .unwrap_err();
+        assert!(format!("{error}").contains("broken"), "{error}");
+        assert_eq!(store.get_image("good"), None);
+    }
 }
@@ -0,0 +1,103 @@
+use std::{cell::RefCell, fmt::Debug, rc::Rc};
+
+use crate::{FloatPosition, ReadOnlyString};
+
+/// Plays sounds. Implement this to plug an audio library into the game.
+///
+/// No audio library is bundled; the default backend is `NoopAudio`.
+pub trait AudioBackend {
+    /// Plays the sound with the given ID, either at a position in the world or, if `position`
+    /// is `None`, without any positioning (e.g. for UI sounds).
+    fn play(&mut self, sound: &str, position: Option<FloatPosition>);
+}
+
+/// An audio backend that ignores every sound.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopAudio;
+
+impl AudioBackend for NoopAudio {
+    fn play(&mut self, _sound: &str, _position: Option<FloatPosition>) {}
+}
+
+/// A sound played through a `RecordingAudio` backend.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayedSound {
+    pub sound: ReadOnlyString,
+    pub position: Option<FloatPosition>,
+}
+
+/// An audio backend that records every sound instead of playing it.
+///
+/// Clones share the same recording, so a clone can be kept to inspect the sounds after the
+/// backend is handed to `Audio`.
+#[derive(Debug, Clone, Default)]
+pub struct RecordingAudio {
+    played: Rc<RefCell<Vec<PlayedSound>>>,
+}
+
+impl RecordingAudio {
+    /// Creates a backend with nothing recorded.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns every sound played so far, in order.
+    pub fn played(&self) -> Vec<PlayedSound> {
+        self.played.borrow().clone()
+    }
+
+    /// Forgets all recorded sounds.
+    pub fn clear(&self) {
+        self.played.borrow_mut().clear();
+    }
+}
+
+impl AudioBackend for RecordingAudio {
+    fn play(&mut self, sound: &str, position: Option<FloatPosition>) {
+        self.played.borrow_mut().push(PlayedSound {
+            sound: sound.into(),
+            position,
+        });
+    }
+}
+
+/// The audio component. Sounds played through it are forwarded to the current backend.
+pub struct Audio {
+    backend: Box<dyn AudioBackend>,
+}
+
+impl Audio {
+    /// Creates an audio component using the given backend.
+    pub fn new(backend: impl AudioBackend + 'static) -> Self {
+        Self {
+            backend: Box::new(backend),
+        }
+    }
+
+    /// Creates an audio component that plays nothing.
+    pub fn noop() -> Self {
+        Self::new(NoopAudio)
+    }
+
+    /// Replaces the backend sounds are played through.
+    pub fn set_backend(&mut self, backend: impl AudioBackend + 'static) {
+        self.backend = Box::new(backend);
+    }
+
+    /// Plays a sound through the backend. See `AudioBackend::play`.
+    pub fn play(&mut self, sound: &str, position: Option<FloatPosition>) {
+        self.backend.play(sound, position);
+    }
+}
+
+impl Default for Audio {
+    fn default() -> Self {
+        Self::noop()
+    }
+}
+
+impl Debug for Audio {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Audio").finish_non_exhaustive()
+    }
+}
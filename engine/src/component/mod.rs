@@ -46,6 +46,21 @@ impl ComponentStore {
     ///
     /// There must be no other references to the database when calling this method.
     pub fn insert<T: 'static>(&mut self, component: T) -> ComponentHandle<T> {
+        self.insert_node(ResourceNode::new(component))
+    }
+
+    /// Inserts a component that is never borrowed mutably into the database.
+    ///
+    /// The component is kept behind an `Rc`, so `get_rc` can share it cheaply, e.g. with
+    /// closures. `get_mut` panics for it. Anything that still needs to change inside it must use
+    /// interior mutability.
+    ///
+    /// There must be no other references to the database when calling this method.
+    pub fn insert_immutable<T: 'static>(&mut self, component: T) -> ComponentHandle<T> {
+        self.insert_node(ResourceNode::new_immutable(component))
+    }
+
+    fn insert_node<T: 'static>(&mut self, node: ResourceNode) -> ComponentHandle<T> {
         if self.map.contains_key(&TypeId::of::<T>()) {
             panic!(
                 "Component of type {} already exists in State",
@@ -56,10 +71,36 @@ impl ComponentStore {
         let mut_map =
             Rc::get_mut(&mut self.map).expect("Cannot insert component into shared State");
 
-        mut_map.insert(TypeId::of::<T>(), ResourceNode::new(component));
+        mut_map.insert(TypeId::of::<T>(), node);
         self.handle_for::<T>()
     }
 
+    /// Removes a component from the database, returning it if it was present.
+    ///
+    /// There must be no other references to the database when calling this method. Handles to
+    /// the component stay valid, but find nothing until a component of the type is inserted
+    /// again.
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        let mut_map =
+            Rc::get_mut(&mut self.map).expect("Cannot remove component from shared State");
+
+        mut_map
+            .remove(&TypeId::of::<T>())
+            .map(ResourceNode::into_inner)
+    }
+
+    /// Inserts a component into the database, returning the component it replaced if one of the
+    /// same type was present.
+    ///
+    /// There must be no other references to the database when calling this method.
+    pub fn replace<T: 'static>(&mut self, component: T) -> Option<T> {
+        let mut_map = Rc::get_mut(&mut self.map).expect("Cannot replace component in shared State");
+
+        mut_map
+            .insert(TypeId::of::<T>(), ResourceNode::new(component))
+            .map(ResourceNode::into_inner)
+    }
+
     /// Creates a handle for a component of the specified type.
     pub fn handle_for<T: 'static>(&self) -> ComponentHandle<T> {
         ComponentHandle::new(self.handle())
@@ -69,6 +110,17 @@ impl ComponentStore {
     pub fn handle(&self) -> ComponentStoreHandle {
         ComponentStoreHandle::new(self)
     }
+
+    /// Returns the type names of every component in the database, sorted alphabetically.
+    pub fn type_names(&self) -> Vec<&'static str> {
+        let mut type_names = self
+            .map
+            .values()
+            .map(|component| component.type_name)
+            .collect::<Vec<_>>();
+        type_names.sort_unstable();
+        type_names
+    }
 }
 
 impl Debug for ComponentStore {
@@ -79,10 +131,7 @@ impl Debug for ComponentStore {
                 write!(f, "{}", self.0)
             }
         }
-        let mut type_names: Vec<TyDbg> = vec![];
-        for component in self.map.iter() {
-            type_names.push(TyDbg(component.1.type_name));
-        }
+        let type_names: Vec<TyDbg> = self.type_names().into_iter().map(TyDbg).collect();
         f.debug_struct("State")
             .field("resources", &type_names)
             .finish()
@@ -112,6 +161,23 @@ impl<T> ComponentHandle<T> {
     pub fn get_mut(&self) -> std::cell::RefMut<'_, T> {
         self.handle.get_mut::<T>()
     }
+
+    /// Gets a shared pointer to the component, which must have been inserted with
+    /// `insert_immutable`.
+    pub fn get_rc(&self) -> Rc<T> {
+        self.handle.get_rc::<T>()
+    }
+
+    /// Gets a reference to the component, or `None` if it isn't in the database, e.g. because
+    /// it was removed.
+    pub fn get_checked(&self) -> Option<Ref<'_, T>> {
+        self.handle.get_checked::<T>()
+    }
+
+    /// Gets a mutable reference to the component, or `None` if it isn't in the database.
+    pub fn get_mut_checked(&self) -> Option<std::cell::RefMut<'_, T>> {
+        self.handle.get_mut_checked::<T>()
+    }
 }
 
 impl<T> Debug for ComponentHandle<T> {
@@ -173,6 +239,11 @@ mod get_impls {
 
     macro_rules! impl_get {
         () => {
+            /// Returns true if a component of the specified type is in the database.
+            pub fn contains<T: 'static>(&self) -> bool {
+                self.get_map().contains_key(&std::any::TypeId::of::<T>())
+            }
+
             /// Gets a reference to a component of the specified type.
             pub fn get_checked<T: 'static>(&self) -> Option<std::cell::Ref<'_, T>> {
                 let component = self.get_map().get(&std::any::TypeId::of::<T>())?;
@@ -191,13 +262,29 @@ mod get_impls {
                 }
             }
 
+            /// Gets a shared pointer to a component of the specified type, which must have been
+            /// inserted with `insert_immutable`.
+            pub fn get_rc<T: 'static>(&self) -> std::rc::Rc<T> {
+                match self.get_map().get(&std::any::TypeId::of::<T>()) {
+                    Some(component) => component.downcast_rc(),
+                    None => panic!(
+                        "Component {} not found in ComponentDB",
+                        std::any::type_name::<T>()
+                    ),
+                }
+            }
+
             /// Gets a mutable reference to a component of the specified type.
+            ///
+            /// Panics if the component is immutable.
             pub fn get_mut_checked<T: 'static>(&self) -> Option<std::cell::RefMut<'_, T>> {
                 let component = self.get_map().get(&std::any::TypeId::of::<T>())?;
                 Some(unsafe { component.downcast_mut_unchecked() })
             }
 
             /// Gets a mutable reference to a component of the specified type.
+            ///
+            /// Panics if the component is missing or immutable.
             pub fn get_mut<T: 'static>(&self) -> std::cell::RefMut<'_, T> {
                 if let Some(component) = self.get_mut_checked::<T>() {
                     component
@@ -219,3 +306,111 @@ mod get_impls {
         impl_get!();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+
+    #[test]
+    fn insert_remove_reinsert() {
+        let mut store = ComponentStore::new();
+        for i in 0..3 {
+            store.insert(Health(i));
+            assert_eq!(store.get::<Health>().0, i);
+            assert_eq!(store.remove::<Health>(), Some(Health(i)));
+            assert!(store.get_checked::<Health>().is_none());
+        }
+        assert_eq!(store.remove::<Health>(), None);
+
+        assert_eq!(store.replace(Health(1)), None);
+        assert_eq!(store.replace(Health(2)), Some(Health(1)));
+        assert_eq!(store.get::<Health>().0, 2);
+    }
+
+    #[test]
+    fn stale_handles_find_nothing() {
+        let mut store = ComponentStore::new();
+        let handle = store.insert(Health(10));
+        store.remove::<Health>();
+        store.finish_initialization();
+
+        assert!(handle.get_checked().is_none());
+        assert!(handle.get_mut_checked().is_none());
+    }
+
+    #[test]
+    fn handles_see_reinserted_components() {
+        let mut store = ComponentStore::new();
+        let handle = store.insert(Health(10));
+        store.remove::<Health>();
+        store.insert(Health(20));
+        store.finish_initialization();
+
+        assert_eq!(handle.get().0, 20);
+        handle.get_mut().0 += 1;
+        assert_eq!(store.get::<Health>().0, 21);
+    }
+
+    #[test]
+    fn type_names_and_contains() {
+        let mut store = ComponentStore::new();
+        store.insert(Health(10));
+        store.insert(String::from("name"));
+        let handle = store.handle();
+        assert_eq!(
+            store.type_names(),
+            ["alloc::string::String", "engine::component::tests::Health"]
+        );
+        assert!(store.contains::<Health>());
+        assert!(!store.contains::<u32>());
+
+        store.remove::<Health>();
+        assert!(!store.contains::<Health>());
+        assert_eq!(store.type_names(), ["alloc::string::String"]);
+        store.finish_initialization();
+        assert!(!handle.contains::<Health>());
+        assert!(handle.contains::<String>());
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot remove component from shared State")]
+    fn remove_needs_an_unshared_store() {
+        let mut store = ComponentStore::new();
+        store.insert(Health(10));
+        store.finish_initialization();
+        store.remove::<Health>();
+    }
+
+    #[test]
+    fn rc_clones_share_the_component() {
+        let mut store = ComponentStore::new();
+        let handle = store.insert_immutable(Health(10));
+        store.finish_initialization();
+
+        let a = handle.get_rc();
+        let b = store.get_rc::<Health>();
+        assert!(Rc::ptr_eq(&a, &b));
+        assert_eq!(*a, Health(10));
+        assert_eq!(*handle.get(), Health(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "is immutable and can't be borrowed mutably")]
+    fn immutable_components_cant_be_borrowed_mutably() {
+        let mut store = ComponentStore::new();
+        let handle = store.insert_immutable(Health(10));
+        store.finish_initialization();
+        handle.get_mut();
+    }
+
+    #[test]
+    #[should_panic(expected = "is mutable and can't be shared as an Rc")]
+    fn mutable_components_arent_shared_as_rcs() {
+        let mut store = ComponentStore::new();
+        store.insert(Health(10));
+        store.get_rc::<Health>();
+    }
+}
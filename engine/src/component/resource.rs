@@ -1,11 +1,21 @@
-use std::cell::RefCell;
+use std::{any::Any, cell::RefCell, rc::Rc};
+
+/// How a resource is stored.
+#[derive(Debug)]
+pub enum Storage {
+    /// Borrowed mutably through `get_mut`.
+    Mutable(RefCell<Box<dyn Any>>),
+    /// Shared behind an `Rc`, and never borrowed mutably. The `RefCell` only exists so `get` can
+    /// hand out the same `Ref` type as for mutable resources.
+    Immutable(RefCell<Rc<dyn Any>>),
+}
 
 /// Internal representation of a resource.
 /// This contains various metadata about the resource.
 #[derive(Debug)]
 pub struct ResourceNode {
     pub type_name: &'static str,
-    pub data: RefCell<Box<dyn std::any::Any>>,
+    pub data: Storage,
 }
 
 impl ResourceNode {
@@ -13,7 +23,41 @@ impl ResourceNode {
     pub fn new<T: 'static + std::any::Any>(data: T) -> Self {
         Self {
             type_name: std::any::type_name::<T>(),
-            data: RefCell::new(Box::new(data)),
+            data: Storage::Mutable(RefCell::new(Box::new(data))),
+        }
+    }
+
+    /// Creates a new ResourceNode from the given data, which can't be borrowed mutably.
+    pub fn new_immutable<T: 'static + std::any::Any>(data: T) -> Self {
+        Self {
+            type_name: std::any::type_name::<T>(),
+            data: Storage::Immutable(RefCell::new(Rc::new(data))),
+        }
+    }
+
+    /// Takes the resource out of the node.
+    ///
+    /// # Panics
+    /// Panics if T doesn't match the actual type of the resource, or if the resource is immutable
+    /// and clones from `get_rc` are still alive.
+    pub fn into_inner<T: 'static>(self) -> T {
+        match self.data {
+            Storage::Mutable(data) => *data
+                .into_inner()
+                .downcast::<T>()
+                .expect("Resource type mismatch during downcast"),
+            Storage::Immutable(data) => {
+                let rc = data
+                    .into_inner()
+                    .downcast::<T>()
+                    .expect("Resource type mismatch during downcast");
+                Rc::try_unwrap(rc).unwrap_or_else(|_| {
+                    panic!(
+                        "Cannot take immutable component {} out of State while it's shared",
+                        self.type_name
+                    )
+                })
+            }
         }
     }
 
@@ -22,24 +66,59 @@ impl ResourceNode {
     /// # Safety
     /// The caller must ensure that the type T matches the actual type of the resource.
     pub unsafe fn downcast_ref_unchecked<T: 'static>(&self) -> std::cell::Ref<'_, T> {
-        std::cell::Ref::map(self.data.borrow(), |b| {
-            // Currently we do only use downcast_ref, but in the future this might be turned into a manual pointer cast for performance reasons.
-            // Don't make safety promises you can't keep!
-            b.downcast_ref::<T>()
-                .expect("Resource type mismatch during downcast")
-        })
+        // Currently we do only use downcast_ref, but in the future this might be turned into a manual pointer cast for performance reasons.
+        // Don't make safety promises you can't keep!
+        match &self.data {
+            Storage::Mutable(data) => std::cell::Ref::map(data.borrow(), |b| {
+                b.downcast_ref::<T>()
+                    .expect("Resource type mismatch during downcast")
+            }),
+            Storage::Immutable(data) => std::cell::Ref::map(data.borrow(), |b| {
+                b.downcast_ref::<T>()
+                    .expect("Resource type mismatch during downcast")
+            }),
+        }
     }
 
     /// Downcasts the resource to the specified mutable type.
     ///
     /// # Safety
     /// The caller must ensure that the type T matches the actual type of the resource.
+    ///
+    /// # Panics
+    /// Panics if the resource is immutable.
     pub unsafe fn downcast_mut_unchecked<T: 'static>(&self) -> std::cell::RefMut<'_, T> {
-        std::cell::RefMut::map(self.data.borrow_mut(), |b| {
-            // Currently we do only use downcast_mut, but in the future this might be turned into a manual pointer cast for performance reasons.
-            // Don't make safety promises you can't keep!
-            b.downcast_mut::<T>()
-                .expect("Resource type mismatch during downcast")
-        })
+        match &self.data {
+            Storage::Mutable(data) => std::cell::RefMut::map(data.borrow_mut(), |b| {
+                // Currently we do only use downcast_mut, but in the future this might be turned into a manual pointer cast for performance reasons.
+                // Don't make safety promises you can't keep!
+                b.downcast_mut::<T>()
+                    .expect("Resource type mismatch during downcast")
+            }),
+            Storage::Immutable(_) => panic!(
+                "Component {} is immutable and can't be borrowed mutably; it was inserted with \
+                 `insert_immutable`",
+                self.type_name
+            ),
+        }
+    }
+
+    /// Returns a shared pointer to an immutable resource.
+    ///
+    /// # Panics
+    /// Panics if T doesn't match the actual type of the resource, or if the resource is mutable.
+    pub fn downcast_rc<T: 'static>(&self) -> Rc<T> {
+        match &self.data {
+            Storage::Immutable(data) => data
+                .borrow()
+                .clone()
+                .downcast::<T>()
+                .expect("Resource type mismatch during downcast"),
+            Storage::Mutable(_) => panic!(
+                "Component {} is mutable and can't be shared as an Rc; insert it with \
+                 `insert_immutable` instead",
+                self.type_name
+            ),
+        }
     }
 }
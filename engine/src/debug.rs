@@ -1,19 +1,24 @@
 use std::{
     cell::RefCell,
-    fmt::Debug,
-    iter,
+    collections::VecDeque,
+    fmt::{Debug, Write},
     rc::{Rc, Weak},
+    time::Duration,
 };
 
+use anyhow::anyhow;
 use log::info;
 use wgpu_text::{
     BrushBuilder, TextBrush,
-    glyph_brush::{Layout, Section, Text, ab_glyph::FontRef},
+    glyph_brush::{
+        BuiltInLineBreaker, HorizontalAlign, Layout, Section, Text, VerticalAlign,
+        ab_glyph::FontRef,
+    },
 };
 
 use crate::{
     ReadOnlyString,
-    component::{ComponentHandle, ComponentStoreHandle},
+    component::{ComponentHandle, ComponentStore},
     graphics::lowlevel::WgpuRenderer,
 };
 
@@ -21,6 +26,7 @@ pub struct DebugRenderer {
     pub enabled: bool,
     brush: TextBrush<FontRef<'static>>,
     stats: Vec<Weak<DebugStatistic>>,
+    visible_categories: [bool; StatCategory::ALL.len()],
     wgpu: ComponentHandle<WgpuRenderer>,
 }
 
@@ -29,48 +35,148 @@ impl Debug for DebugRenderer {
         f.debug_struct("DebugRenderer")
             .field("enabled", &self.enabled)
             .field("stats_count", &self.stats.len())
+            .field("visible_categories", &self.visible_categories)
             .finish()
     }
 }
 
+/// The font used by the debug renderer unless another one is given.
+pub const DEFAULT_DEBUG_FONT: &[u8] = include_bytes!("../../FiraCode-Regular.ttf");
+
+/// The color of the category headers in the overlay.
+const HEADER_COLOR: [f32; 4] = [1.0, 0.85, 0.3, 1.0];
+/// The color of statistics that don't set their own.
+const DEFAULT_STAT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
 /// A type alias for a reference-counted debug statistic.
 pub type DebugProvider = Rc<DebugStatistic>;
 
 impl DebugRenderer {
-    /// Creates a new debug renderer.
-    pub fn new(state: &ComponentStoreHandle) -> anyhow::Result<DebugRenderer> {
+    /// Creates a new debug renderer using the bundled font.
+    pub fn new(state: &ComponentStore) -> anyhow::Result<DebugRenderer> {
+        Self::with_font(state, DEFAULT_DEBUG_FONT)
+    }
+
+    /// Creates a new debug renderer drawing text with the given TrueType or OpenType font.
+    ///
+    /// Returns an error if the font data can't be parsed.
+    pub fn with_font(state: &ComponentStore, font: &'static [u8]) -> anyhow::Result<DebugRenderer> {
         let wgpu = state.get::<WgpuRenderer>();
         let (render_width, render_height) = wgpu.dimensions();
         let render_format = wgpu.config.get().format;
+        let brush = BrushBuilder::using_font_bytes(font)
+            .map_err(|e| anyhow!("invalid debug font: {e}"))?
+            .build(&wgpu.device, render_width, render_height, render_format);
         Ok(Self {
-            brush: BrushBuilder::using_font_bytes(include_bytes!("../../FiraCode-Regular.ttf"))
-                .expect("failed to create debug brush")
-                .build(&wgpu.device, render_width, render_height, render_format),
+            brush,
             enabled: false,
             stats: Vec::new(),
+            visible_categories: [true; StatCategory::ALL.len()],
             wgpu: state.handle_for::<WgpuRenderer>(),
         })
     }
 
-    /// Adds a new debug statistic to be displayed.
+    /// Adds a new debug statistic to be displayed, in the performance category.
     pub fn add_statistic(
         &mut self,
         label: impl Into<ReadOnlyString>,
         initial_value: impl Into<String>,
     ) -> Rc<DebugStatistic> {
-        let stat = Rc::new(DebugStatistic::new(label, initial_value));
+        self.add_statistic_in(StatCategory::Performance, label, initial_value)
+    }
+
+    /// Adds a new debug statistic to be displayed in the given category.
+    pub fn add_statistic_in(
+        &mut self,
+        category: StatCategory,
+        label: impl Into<ReadOnlyString>,
+        initial_value: impl Into<String>,
+    ) -> Rc<DebugStatistic> {
+        self.add_statistic_with(DebugStatistic::new(label, initial_value).with_category(category))
+    }
+
+    /// Adds an already configured debug statistic to be displayed, e.g. one with its own color or
+    /// anchor.
+    pub fn add_statistic_with(&mut self, stat: DebugStatistic) -> Rc<DebugStatistic> {
+        let stat = Rc::new(stat);
         self.stats.push(Rc::downgrade(&stat.clone()));
         info!("Added debug statistic: {}", stat.label);
         stat
     }
 
-    /// Renders the debug statistics on the screen.
+    /// Returns true if statistics in the given category are shown.
+    pub fn is_category_visible(&self, category: StatCategory) -> bool {
+        self.visible_categories[category as usize]
+    }
+
+    /// Shows or hides the statistics in the given category.
+    pub fn set_category_visible(&mut self, category: StatCategory, visible: bool) {
+        self.visible_categories[category as usize] = visible;
+    }
+
+    /// Toggles whether the statistics in the given category are shown.
+    pub fn toggle_category(&mut self, category: StatCategory) {
+        let visible = !self.is_category_visible(category);
+        info!(
+            "Debug category {:?} {}",
+            category,
+            if visible { "shown" } else { "hidden" }
+        );
+        self.set_category_visible(category, visible);
+    }
+
+    /// Returns the live statistics in the given category, in the order they were added.
+    pub fn stats_in(&self, category: StatCategory) -> Vec<Rc<DebugStatistic>> {
+        self.stats
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter(|stat| stat.category == category)
+            .collect()
+    }
+
+    /// Returns the live statistics in visible categories, in the order they were added.
+    pub fn visible_stats(&self) -> Vec<Rc<DebugStatistic>> {
+        self.stats
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter(|stat| self.is_category_visible(stat.category))
+            .collect()
+    }
+
+    /// Returns the live statistics in visible categories, grouped by category in
+    /// `StatCategory::ALL` order. Within a group they keep the order they were added in, and
+    /// categories without statistics are left out.
+    pub fn grouped_stats(&self) -> Vec<(StatCategory, Vec<Rc<DebugStatistic>>)> {
+        let stats = self.visible_stats();
+        StatCategory::ALL
+            .into_iter()
+            .map(|category| {
+                let in_category = stats
+                    .iter()
+                    .filter(|stat| stat.category == category)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (category, in_category)
+            })
+            .filter(|(_, stats)| !stats.is_empty())
+            .collect()
+    }
+
+    /// Lays out the visible statistics as a text section per screen anchor, for a surface of the
+    /// given size.
+    pub fn sections(&self, screen_size: (u32, u32)) -> Vec<DebugTextSection> {
+        DebugTextSection::layout(&self.grouped_stats(), screen_size)
+    }
+
+    /// Renders the debug statistics on the screen, each in its anchor's corner and grouped by
+    /// category.
     pub fn render(&mut self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
         if !self.enabled {
             return;
         }
 
         let wgpu = self.wgpu.get();
+        let sections = self.sections(wgpu.dimensions());
 
         let mut pass = wgpu.render_pass(
             Some("Debug Renderer Pass"),
@@ -80,46 +186,22 @@ impl DebugRenderer {
             wgpu::LoadOp::Load,
         );
 
-        let mut section = Section {
-            screen_position: (0.0, 0.0),
-            bounds: (f32::INFINITY, f32::INFINITY),
-            layout: Layout::default_wrap(),
-            text: vec![],
-        };
-
-        // So i'll be the first to admit that this is a massive hack.
-        // Im sure theres a better way to do this, but for now, this works.
-        // We leak the boxes so that the Text objects can hold references to them.
-        // Then after drawing, we reclaim the boxes and drop them.
-        let mut leaked_boxes: Vec<&'static str> = Vec::new();
-
-        for stat_weak in &self.stats {
-            let stat = match stat_weak.upgrade() {
-                Some(s) => s,
-                None => continue,
-            };
-
-            let text = Box::leak(
-                format!("{}: {}\n", stat.label, stat.value.borrow().as_str()).into_boxed_str(),
-            );
-            leaked_boxes.push(text);
-
-            section
-                .text
-                .push(Text::new(text).with_color([1.0, 1.0, 1.0, 1.0]));
-        }
-
         self.brush
-            .queue(&wgpu.device, &wgpu.queue, iter::once(section))
+            .queue(
+                &wgpu.device,
+                &wgpu.queue,
+                sections.iter().map(DebugTextSection::to_section),
+            )
             .expect("failed to queue debug text");
 
         self.brush.draw(&mut pass);
+    }
 
-        for text in leaked_boxes {
-            unsafe {
-                let _ = Box::from_raw(text as *const str as *mut str);
-            }
-        }
+    /// Resizes the text brush to match the new surface size.
+    pub fn resize(&mut self, new_size: (u32, u32)) {
+        let wgpu = self.wgpu.get();
+        self.brush
+            .resize_view(new_size.0 as f32, new_size.1 as f32, &wgpu.queue);
     }
 
     /// Toggles the debug renderer on or off.
@@ -132,26 +214,234 @@ impl DebugRenderer {
     }
 }
 
+/// A group of debug statistics that can be shown or hidden together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatCategory {
+    /// Frame timings and other performance counters.
+    Performance = 0,
+    /// Information about the loaded world.
+    World = 1,
+    /// GPU resources and memory.
+    Gpu = 2,
+    /// The camera's position and orientation.
+    Camera = 3,
+}
+
+impl StatCategory {
+    /// Every category, in display order.
+    pub const ALL: [StatCategory; 4] = [
+        StatCategory::Performance,
+        StatCategory::World,
+        StatCategory::Gpu,
+        StatCategory::Camera,
+    ];
+
+    /// Returns the name the category's section is headed with in the overlay.
+    pub fn name(self) -> &'static str {
+        match self {
+            StatCategory::Performance => "Performance",
+            StatCategory::World => "World",
+            StatCategory::Gpu => "GPU",
+            StatCategory::Camera => "Camera",
+        }
+    }
+
+    /// Returns the category at the given index into `ALL`, if there is one.
+    pub fn from_index(index: usize) -> Option<StatCategory> {
+        Self::ALL.get(index).copied()
+    }
+}
+
+/// The corner of the screen a debug statistic is drawn in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ScreenAnchor {
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl ScreenAnchor {
+    /// Every anchor, in the order their sections are drawn.
+    pub const ALL: [ScreenAnchor; 4] = [
+        ScreenAnchor::TopLeft,
+        ScreenAnchor::TopRight,
+        ScreenAnchor::BottomLeft,
+        ScreenAnchor::BottomRight,
+    ];
+
+    /// Returns the position of the anchor's corner on a surface of the given size.
+    pub fn screen_position(self, screen_size: (u32, u32)) -> (f32, f32) {
+        let (width, height) = (screen_size.0 as f32, screen_size.1 as f32);
+        match self {
+            ScreenAnchor::TopLeft => (0.0, 0.0),
+            ScreenAnchor::TopRight => (width, 0.0),
+            ScreenAnchor::BottomLeft => (0.0, height),
+            ScreenAnchor::BottomRight => (width, height),
+        }
+    }
+
+    /// Returns the text layout that aligns a section to the anchor's corner.
+    fn layout(self) -> Layout<BuiltInLineBreaker> {
+        let (h_align, v_align) = match self {
+            ScreenAnchor::TopLeft => (HorizontalAlign::Left, VerticalAlign::Top),
+            ScreenAnchor::TopRight => (HorizontalAlign::Right, VerticalAlign::Top),
+            ScreenAnchor::BottomLeft => (HorizontalAlign::Left, VerticalAlign::Bottom),
+            ScreenAnchor::BottomRight => (HorizontalAlign::Right, VerticalAlign::Bottom),
+        };
+        Layout::default_wrap().h_align(h_align).v_align(v_align)
+    }
+}
+
+/// The text of the debug overlay drawn in one corner of the screen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugTextSection {
+    pub anchor: ScreenAnchor,
+    pub screen_position: (f32, f32),
+    /// The lines of text and their colors, each ending in a newline.
+    pub lines: Vec<(String, [f32; 4])>,
+}
+
+impl DebugTextSection {
+    /// Lays out grouped statistics as a section per anchor that has any. Each section lists its
+    /// statistics under a header per category, with a blank line between categories.
+    pub fn layout(
+        groups: &[(StatCategory, Vec<Rc<DebugStatistic>>)],
+        screen_size: (u32, u32),
+    ) -> Vec<DebugTextSection> {
+        ScreenAnchor::ALL
+            .into_iter()
+            .filter_map(|anchor| {
+                let mut lines = Vec::new();
+                for (category, stats) in groups {
+                    let mut stats = stats.iter().filter(|stat| stat.anchor == anchor).peekable();
+                    if stats.peek().is_none() {
+                        continue;
+                    }
+                    // A blank line between categories.
+                    let separator = if lines.is_empty() { "" } else { "\n" };
+                    lines.push((format!("{}{}\n", separator, category.name()), HEADER_COLOR));
+                    for stat in stats {
+                        stat.push_lines(1, &mut lines);
+                    }
+                }
+                (!lines.is_empty()).then(|| DebugTextSection {
+                    anchor,
+                    screen_position: anchor.screen_position(screen_size),
+                    lines,
+                })
+            })
+            .collect()
+    }
+
+    /// Converts the section into one the text brush can queue.
+    fn to_section(&self) -> Section<'_> {
+        Section {
+            screen_position: self.screen_position,
+            bounds: (f32::INFINITY, f32::INFINITY),
+            layout: self.anchor.layout(),
+            text: self
+                .lines
+                .iter()
+                .map(|(line, color)| Text::new(line).with_color(*color))
+                .collect(),
+        }
+    }
+}
+
 /// A structure representing a debug statistic to be displayed.
 pub struct DebugStatistic {
     pub label: ReadOnlyString,
     pub value: RefCell<String>,
+    pub category: StatCategory,
+    /// The text color, or `None` for the default white.
+    pub color: Option<[f32; 4]>,
+    pub anchor: ScreenAnchor,
+    /// Statistics shown indented under this one, in the order they were added.
+    children: RefCell<Vec<Rc<DebugStatistic>>>,
 }
 
 impl Debug for DebugStatistic {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("DebugStatistic")
             .field("label", &self.label)
+            .field("category", &self.category)
+            .field("color", &self.color)
+            .field("anchor", &self.anchor)
+            .field("children", &self.children.borrow().len())
             .finish()
     }
 }
 
 impl DebugStatistic {
-    /// Creates a new debug statistic with the given label and initial value.
+    /// Creates a new debug statistic with the given label and initial value, in the performance category.
     pub fn new(label: impl Into<ReadOnlyString>, initial_value: impl Into<String>) -> Self {
         Self {
             label: label.into(),
             value: RefCell::new(initial_value.into()),
+            category: StatCategory::Performance,
+            color: None,
+            anchor: ScreenAnchor::TopLeft,
+            children: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Moves the statistic into the given category.
+    pub fn with_category(mut self, category: StatCategory) -> Self {
+        self.category = category;
+        self
+    }
+
+    /// Draws the statistic in the given color.
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Draws the statistic in the given corner of the screen.
+    pub fn with_anchor(mut self, anchor: ScreenAnchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Adds a statistic shown indented under this one, in the same category and corner.
+    ///
+    /// Children are drawn as long as their parent is registered with the debug renderer, and
+    /// can have children of their own.
+    pub fn add_child(
+        &self,
+        label: impl Into<ReadOnlyString>,
+        initial_value: impl Into<String>,
+    ) -> Rc<DebugStatistic> {
+        let mut child = DebugStatistic::new(label, initial_value)
+            .with_category(self.category)
+            .with_anchor(self.anchor);
+        child.color = self.color;
+        let child = Rc::new(child);
+        self.children.borrow_mut().push(child.clone());
+        child
+    }
+
+    /// Returns the statistics shown under this one.
+    pub fn children(&self) -> Vec<Rc<DebugStatistic>> {
+        self.children.borrow().clone()
+    }
+
+    /// Adds the lines for this statistic and its children, indented by `depth` levels.
+    fn push_lines(&self, depth: usize, lines: &mut Vec<(String, [f32; 4])>) {
+        lines.push((
+            format!(
+                "{:indent$}{}: {}\n",
+                "",
+                self.label,
+                self.value.borrow().as_str(),
+                indent = depth * 2
+            ),
+            self.color.unwrap_or(DEFAULT_STAT_COLOR),
+        ));
+        for child in self.children.borrow().iter() {
+            child.push_lines(depth + 1, lines);
         }
     }
 
@@ -159,4 +449,187 @@ impl DebugStatistic {
     pub fn update_value(&self, new_value: impl ToString) {
         *self.value.borrow_mut() = new_value.to_string();
     }
+
+    /// Updates the value of the debug statistic with a duration, formatted in milliseconds.
+    pub fn update_duration(&self, duration: Duration) {
+        let mut value = self.value.borrow_mut();
+        value.clear();
+        let _ = write!(value, "{:.2} ms", duration.as_secs_f64() * 1000.0);
+    }
+}
+
+/// A rolling record of the last frame times, drawn as a bar graph in the debug overlay like
+/// Minecraft's.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugGraph {
+    /// The recorded frame times, oldest first.
+    samples: VecDeque<Duration>,
+    capacity: usize,
+}
+
+impl DebugGraph {
+    /// The number of frames kept by default.
+    pub const DEFAULT_CAPACITY: usize = 240;
+    /// Frames up to this long are drawn green: 60 frames per second.
+    pub const TARGET_FRAME_TIME: Duration = Duration::from_nanos(16_666_667);
+    /// Frames up to this long are drawn yellow, and longer ones red: 30 frames per second.
+    pub const SLOW_FRAME_TIME: Duration = Duration::from_nanos(33_333_333);
+
+    /// Creates an empty graph keeping the last `capacity` frames.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Records a frame, dropping the oldest one if the graph is full.
+    pub fn push(&mut self, frame_time: Duration) {
+        if self.capacity == 0 {
+            return;
+        }
+        while self.samples.len() >= self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(frame_time);
+    }
+
+    /// Returns the recorded frame times, oldest first.
+    pub fn samples(&self) -> impl ExactSizeIterator<Item = Duration> + '_ {
+        self.samples.iter().copied()
+    }
+
+    /// Returns how many frames the graph keeps.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Changes how many frames the graph keeps, dropping the oldest ones if it has too many.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.samples.len() > capacity {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Returns the color a frame's bar is drawn in: green if it met 60 frames per second,
+    /// yellow if it met 30, and red otherwise.
+    pub fn color(frame_time: Duration) -> [f32; 4] {
+        if frame_time <= Self::TARGET_FRAME_TIME {
+            [0.2, 0.9, 0.2, 1.0]
+        } else if frame_time <= Self::SLOW_FRAME_TIME {
+            [1.0, 0.85, 0.2, 1.0]
+        } else {
+            [1.0, 0.25, 0.2, 1.0]
+        }
+    }
+}
+
+impl Default for DebugGraph {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tiny font with a handful of glyphs, from the ttf-parser test suite.
+    const DEMO_FONT: &[u8] = include_bytes!("../tests/fonts/demo.ttf");
+
+    fn state() -> ComponentStore {
+        let mut state = ComponentStore::new();
+        WgpuRenderer::attach_noop(&mut state, (64, 64));
+        state.finish_initialization();
+        state
+    }
+
+    #[test]
+    fn alternate_font() {
+        let state = state();
+        let renderer = DebugRenderer::with_font(&state, DEMO_FONT);
+        assert!(renderer.is_ok(), "{:?}", renderer.err());
+    }
+
+    #[test]
+    fn invalid_font_is_an_error() {
+        let state = state();
+        assert!(DebugRenderer::with_font(&state, b"not a font").is_err());
+    }
+
+    fn labels(stats: &[Rc<DebugStatistic>]) -> Vec<&str> {
+        stats.iter().map(|stat| &*stat.label).collect()
+    }
+
+    #[test]
+    fn filters_stats_by_category() {
+        let state = state();
+        let mut renderer = DebugRenderer::new(&state).unwrap();
+        let _fps = renderer.add_statistic("FPS", "60");
+        let _chunks = renderer.add_statistic_in(StatCategory::World, "Chunks", "0");
+        let _position = renderer.add_statistic_in(StatCategory::Camera, "Position", "0, 0, 0");
+        let _blocks = renderer.add_statistic_in(StatCategory::World, "Blocks", "0");
+
+        assert_eq!(
+            labels(&renderer.stats_in(StatCategory::World)),
+            ["Chunks", "Blocks"]
+        );
+        assert!(renderer.stats_in(StatCategory::Gpu).is_empty());
+        // Everything is shown by default.
+        assert_eq!(renderer.visible_stats().len(), 4);
+
+        renderer.toggle_category(StatCategory::World);
+        assert!(!renderer.is_category_visible(StatCategory::World));
+        assert_eq!(labels(&renderer.visible_stats()), ["FPS", "Position"]);
+        let grouped = renderer.grouped_stats();
+        assert_eq!(
+            grouped
+                .iter()
+                .map(|(category, _)| *category)
+                .collect::<Vec<_>>(),
+            [StatCategory::Performance, StatCategory::Camera]
+        );
+    }
+
+    #[test]
+    fn dropped_stats_disappear() {
+        let state = state();
+        let mut renderer = DebugRenderer::new(&state).unwrap();
+        let chunks = renderer.add_statistic_in(StatCategory::World, "Chunks", "0");
+        assert_eq!(renderer.stats_in(StatCategory::World).len(), 1);
+        drop(chunks);
+        assert!(renderer.stats_in(StatCategory::World).is_empty());
+    }
+
+    #[test]
+    fn anchored_stats_get_their_own_sections() {
+        let state = state();
+        let mut renderer = DebugRenderer::new(&state).unwrap();
+        let _fps = renderer.add_statistic("FPS", "60");
+        let red = [1.0, 0.0, 0.0, 1.0];
+        let _warning = renderer.add_statistic_with(
+            DebugStatistic::new("Warning", "slow frame")
+                .with_color(red)
+                .with_anchor(ScreenAnchor::TopRight),
+        );
+
+        let sections = renderer.sections((800, 600));
+        assert_eq!(sections.len(), 2);
+        let (top_left, top_right) = (&sections[0], &sections[1]);
+        assert_eq!(top_left.anchor, ScreenAnchor::TopLeft);
+        assert_eq!(top_left.screen_position, (0.0, 0.0));
+        assert_eq!(top_right.anchor, ScreenAnchor::TopRight);
+        assert_eq!(top_right.screen_position, (800.0, 0.0));
+
+        // The default stat keeps the white text, indented under its category header.
+        assert_eq!(
+            top_left.lines[1],
+            ("  FPS: 60\n".to_string(), DEFAULT_STAT_COLOR)
+        );
+        assert_eq!(
+            top_right.lines[1],
+            ("  Warning: slow frame\n".to_string(), red)
+        );
+    }
 }
@@ -0,0 +1,238 @@
+//! Named texture atlases, so different pipelines can sample different sets of textures.
+//!
+//! Each atlas is a [`TextureCollection`] registered under a name, e.g. "blocks", "items" or "ui".
+//! A pipeline declares which atlas it samples by creating an [`AtlasBinding`] for its name, which
+//! owns the atlas's bind group and rebuilds it whenever the atlas changes.
+
+use std::collections::HashMap;
+
+use anyhow::Context;
+use log::{info, warn};
+
+use crate::{
+    ReadOnlyString,
+    graphics::textures::{TextureCollection, TextureQuality},
+    resource::Resource,
+};
+
+/// The texture atlases known to the renderer, by name.
+#[derive(Default)]
+pub struct TextureAtlases {
+    atlases: HashMap<ReadOnlyString, Resource<TextureCollection>>,
+}
+
+impl TextureAtlases {
+    /// Creates an empty set of atlases.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `collection` under `name`, returning the shared atlas.
+    ///
+    /// If an atlas with that name already exists, its contents are replaced instead, so bindings
+    /// made against it pick up the new textures.
+    pub fn register(
+        &mut self,
+        name: impl Into<ReadOnlyString>,
+        collection: TextureCollection,
+    ) -> Resource<TextureCollection> {
+        let name = name.into();
+        if let Some(existing) = self.atlases.get(&name) {
+            existing.get_mut().replace_with(collection);
+            return existing.clone();
+        }
+        info!("Registered texture atlas {}", name);
+        let atlas = Resource::new(collection);
+        self.atlases.insert(name, atlas.clone());
+        atlas
+    }
+
+    /// Returns the atlas registered under `name`, if any.
+    pub fn get(&self, name: &str) -> Option<Resource<TextureCollection>> {
+        self.atlases.get(name).cloned()
+    }
+
+    /// Returns true if an atlas is registered under `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.atlases.contains_key(name)
+    }
+
+    /// Returns the names of every registered atlas, in no particular order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.atlases.keys().map(|name| &**name)
+    }
+
+    /// Returns the number of registered atlases.
+    pub fn len(&self) -> usize {
+        self.atlases.len()
+    }
+
+    /// Returns true if no atlases are registered.
+    pub fn is_empty(&self) -> bool {
+        self.atlases.is_empty()
+    }
+
+    /// Advances the animated textures of every atlas by `delta_time` seconds.
+    pub fn advance(&self, delta_time: f32) {
+        for atlas in self.atlases.values() {
+            atlas.get_mut().advance(delta_time);
+        }
+    }
+
+    /// Changes the texture quality of every atlas.
+    pub fn set_texture_quality(&mut self, quality: TextureQuality) {
+        for atlas in self.atlases.values() {
+            atlas.get_mut().set_texture_quality(quality);
+        }
+    }
+
+    /// Creates a binding to the atlas registered under `name`, with the sampler at
+    /// `sampler_binding` and the texture array at `texture_binding`.
+    ///
+    /// Returns an error if no atlas is registered under `name`, or if the atlas is empty.
+    pub fn bind(
+        &self,
+        name: &str,
+        sampler_binding: u32,
+        texture_binding: u32,
+    ) -> anyhow::Result<AtlasBinding> {
+        let atlas = self
+            .get(name)
+            .with_context(|| format!("No texture atlas named {name}"))?;
+        AtlasBinding::new(name, atlas, sampler_binding, texture_binding)
+    }
+}
+
+/// A pipeline's bind group for one texture atlas.
+///
+/// Call `refresh` once per frame, e.g. in the pipeline's `update`. The bind group is rebuilt when
+/// the atlas's revision changes. The layout doesn't depend on the textures, so pipelines built
+/// against it stay valid.
+pub struct AtlasBinding {
+    name: ReadOnlyString,
+    atlas: Resource<TextureCollection>,
+    sampler_binding: u32,
+    texture_binding: u32,
+    layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    revision: u64,
+}
+
+impl AtlasBinding {
+    fn new(
+        name: &str,
+        atlas: Resource<TextureCollection>,
+        sampler_binding: u32,
+        texture_binding: u32,
+    ) -> anyhow::Result<Self> {
+        let mut collection = atlas.get_mut();
+        let (layout, bind_group) = collection.gpu_texture()?.layout_and_bind_group(
+            Some(name),
+            sampler_binding,
+            texture_binding,
+        );
+        let revision = collection.revision();
+        drop(collection);
+        Ok(Self {
+            name: name.into(),
+            atlas,
+            sampler_binding,
+            texture_binding,
+            layout,
+            bind_group,
+            revision,
+        })
+    }
+
+    /// Returns the name of the bound atlas.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the bound atlas.
+    pub fn atlas(&self) -> &Resource<TextureCollection> {
+        &self.atlas
+    }
+
+    /// Returns the bind group layout, for building the pipeline.
+    pub fn layout(&self) -> &wgpu::BindGroupLayout {
+        &self.layout
+    }
+
+    /// Returns the bind group, for setting in a render pass.
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    /// Rebuilds the bind group if the atlas changed since it was made. Returns true if it was
+    /// rebuilt.
+    ///
+    /// If the atlas can't be uploaded, e.g. because it was replaced with an empty collection, the
+    /// old bind group is kept and a warning is logged.
+    pub fn refresh(&mut self) -> bool {
+        let mut collection = self.atlas.get_mut();
+        if collection.revision() == self.revision {
+            return false;
+        }
+        self.revision = collection.revision();
+        let texture = match collection.gpu_texture() {
+            Ok(texture) => texture,
+            Err(e) => {
+                warn!(
+                    "Keeping old bind group for texture atlas {}: {e}",
+                    self.name
+                );
+                return false;
+            }
+        };
+        self.bind_group = texture.bind_group(
+            Some(&self.name),
+            &self.layout,
+            self.sampler_binding,
+            self.texture_binding,
+        );
+        info!("Rebuilt bind group for texture atlas {}", self.name);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{component::ComponentStore, graphics::lowlevel::WgpuRenderer};
+
+    fn collection(state: &ComponentStore, name: &str, size: u32) -> TextureCollection {
+        let mut collection = TextureCollection::new(state, Some(name.to_string()), (size, size));
+        collection.push_invalid_texture();
+        collection
+    }
+
+    #[test]
+    fn pipelines_bind_their_own_atlas() {
+        let mut state = ComponentStore::new();
+        WgpuRenderer::attach_noop(&mut state, (64, 64));
+        state.finish_initialization();
+
+        let mut atlases = TextureAtlases::new();
+        atlases.register("blocks", collection(&state, "blocks", 16));
+        atlases.register("items", collection(&state, "items", 32));
+        assert_eq!(atlases.len(), 2);
+        assert!(atlases.bind("entities", 0, 1).is_err());
+
+        // E.g. the solid pipeline samples blocks and an item pipeline samples items.
+        let mut solid = atlases.bind("blocks", 0, 1).unwrap();
+        let mut items = atlases.bind("items", 0, 1).unwrap();
+        assert_eq!(solid.name(), "blocks");
+        assert_eq!(solid.atlas().get().dimensions(), (16, 16));
+        assert_eq!(items.atlas().get().dimensions(), (32, 32));
+        assert_ne!(solid.bind_group(), items.bind_group());
+
+        // Replacing one atlas only rebuilds the bindings to it.
+        let items_group = items.bind_group().clone();
+        let replaced = atlases.register("items", collection(&state, "items", 32));
+        assert!(std::rc::Rc::ptr_eq(&replaced.inner, &items.atlas().inner));
+        assert!(!solid.refresh());
+        assert!(items.refresh());
+        assert_ne!(items.bind_group(), &items_group);
+    }
+}
@@ -1,18 +1,26 @@
 use std::f32::consts;
 
+use anyhow::bail;
 use glam::{Mat4, Vec3, Vec4};
 
+use crate::graphics::frustum::Frustum;
+
 #[derive(Clone, Debug)]
 pub struct Camera {
     projection: Mat4,
     view: Mat4,
+    fov_y: f32,
+    aspect_ratio: f32,
+    z_near: f32,
+    z_far: f32,
     pub yaw: f32,
     pub pitch: f32,
     pub position: Vec3,
     direction_vector: Vec3,
 }
 
-const FOV_Y_RADS: f32 = consts::FRAC_PI_2;
+/// The default vertical field of view, in radians.
+pub const FOV_Y_RADS: f32 = consts::FRAC_PI_2;
 
 #[rustfmt::skip]
 pub const OPENGL_TO_WGPU_MATRIX: Mat4= Mat4::from_cols(
@@ -31,6 +39,10 @@ impl Camera {
         Self {
             projection,
             view,
+            fov_y: FOV_Y_RADS,
+            aspect_ratio,
+            z_near,
+            z_far,
             yaw: 0.0,
             pitch: 0.0,
             position: Vec3::ZERO,
@@ -88,6 +100,51 @@ impl Camera {
         self.view = Mat4::look_at_rh(position, target, Vec3::Y);
     }
 
+    /// Sets the vertical field of view, in radians.
+    pub fn set_fov(&mut self, fov_y: f32) {
+        self.fov_y = fov_y;
+        self.update_projection();
+    }
+
+    /// Returns the vertical field of view, in radians.
+    pub fn fov(&self) -> f32 {
+        self.fov_y
+    }
+
+    /// Sets the width to height ratio of the view, e.g. after the window was resized.
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: f32) {
+        self.aspect_ratio = aspect_ratio;
+        self.update_projection();
+    }
+
+    /// Returns the width to height ratio of the view.
+    pub fn aspect_ratio(&self) -> f32 {
+        self.aspect_ratio
+    }
+
+    /// Sets the near and far clip planes and rebuilds the projection.
+    ///
+    /// Fails without changing anything unless `0 < near < far`.
+    pub fn set_clip_planes(&mut self, z_near: f32, z_far: f32) -> anyhow::Result<()> {
+        if !(z_near > 0.0 && z_near < z_far) {
+            bail!("Invalid clip planes: near {z_near}, far {z_far}");
+        }
+        self.z_near = z_near;
+        self.z_far = z_far;
+        self.update_projection();
+        Ok(())
+    }
+
+    /// Returns the near and far clip planes.
+    pub fn clip_planes(&self) -> (f32, f32) {
+        (self.z_near, self.z_far)
+    }
+
+    fn update_projection(&mut self) {
+        self.projection =
+            Mat4::perspective_rh(self.fov_y, self.aspect_ratio, self.z_near, self.z_far);
+    }
+
     /// Returns the projection matrix of the camera.
     pub fn projection(&self) -> Mat4 {
         self.projection
@@ -107,4 +164,72 @@ impl Camera {
     pub fn projection_view_matrix(&self) -> Mat4 {
         OPENGL_TO_WGPU_MATRIX * self.projection * self.view
     }
+
+    /// Returns the volume the camera can see, for culling.
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(self.projection_view_matrix())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Projects a point straight ahead of the camera at `distance`, returning its depth.
+    fn depth_at(camera: &Camera, distance: f32) -> f32 {
+        camera
+            .projection()
+            .project_point3(Vec3::new(0.0, 0.0, -distance))
+            .z
+    }
+
+    #[test]
+    fn clip_planes_update_the_projection() {
+        let mut camera = Camera::new(1.5, 0.1, 100.0);
+        assert!((depth_at(&camera, 100.0) - 1.0).abs() < 1e-5);
+
+        camera.set_clip_planes(1.0, 50.0).unwrap();
+        assert_eq!(camera.clip_planes(), (1.0, 50.0));
+        assert_eq!(
+            camera.projection(),
+            Mat4::perspective_rh(FOV_Y_RADS, 1.5, 1.0, 50.0)
+        );
+        assert!(depth_at(&camera, 1.0).abs() < 1e-5);
+        assert!((depth_at(&camera, 50.0) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn invalid_clip_planes_are_rejected() {
+        let mut camera = Camera::new(1.5, 0.1, 100.0);
+        let projection = camera.projection();
+        assert!(camera.set_clip_planes(0.0, 10.0).is_err());
+        assert!(camera.set_clip_planes(-1.0, 10.0).is_err());
+        assert!(camera.set_clip_planes(10.0, 10.0).is_err());
+        assert!(camera.set_clip_planes(20.0, 10.0).is_err());
+        assert_eq!(camera.clip_planes(), (0.1, 100.0));
+        assert_eq!(camera.projection(), projection);
+    }
+
+    /// Returns the height on screen, in normalized device coordinates, of a 1 block tall object
+    /// 10 blocks ahead of the camera.
+    fn projected_height(camera: &Camera) -> f32 {
+        let projection = camera.projection();
+        let top = projection.project_point3(Vec3::new(0.0, 0.5, -10.0));
+        let bottom = projection.project_point3(Vec3::new(0.0, -0.5, -10.0));
+        top.y - bottom.y
+    }
+
+    #[test]
+    fn narrower_fov_makes_objects_larger() {
+        let mut camera = Camera::new(16.0 / 9.0, 0.1, 100.0);
+        let wide = projected_height(&camera);
+        camera.set_fov(30f32.to_radians());
+        let zoomed = projected_height(&camera);
+        assert!(zoomed > wide * 2.0, "{} vs {}", zoomed, wide);
+
+        // Resizing keeps the zoomed field of view.
+        camera.set_aspect_ratio(4.0 / 3.0);
+        assert_eq!(camera.fov(), 30f32.to_radians());
+        assert!((projected_height(&camera) - zoomed).abs() < 1e-5);
+    }
 }
@@ -0,0 +1,103 @@
+//! View frustum culling.
+
+use glam::{Mat4, Vec3, Vec4, Vec4Swizzles};
+
+/// The volume a camera can see, bounded by six planes.
+///
+/// Used to skip drawing anything that lies entirely off screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// The left, right, bottom, top, near and far planes as `(normal, distance)`, with normals
+    /// pointing into the frustum. A point `p` is on the inside of a plane if
+    /// `normal.dot(p) + distance >= 0`.
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extracts the frustum from a combined projection and view matrix, like
+    /// `Camera::projection_view_matrix`.
+    ///
+    /// The matrix must map depth to wgpu's 0 to 1 range.
+    pub fn from_matrix(matrix: Mat4) -> Self {
+        let (x, y, z, w) = (matrix.row(0), matrix.row(1), matrix.row(2), matrix.row(3));
+        let planes = [w + x, w - x, w + y, w - y, z, w - z].map(|plane| {
+            let length = plane.xyz().length();
+            if length > 0.0 { plane / length } else { plane }
+        });
+        Self { planes }
+    }
+
+    /// Returns the six planes of the frustum. See the field docs for their layout.
+    pub fn planes(&self) -> &[Vec4; 6] {
+        &self.planes
+    }
+
+    /// Returns true if the point is inside the frustum.
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.xyz().dot(point) + plane.w >= 0.0)
+    }
+
+    /// Returns true if any part of the axis aligned box between `min` and `max` may be inside
+    /// the frustum.
+    ///
+    /// This is conservative: a box near a corner of the frustum can be reported as visible even
+    /// though it's just outside, but a visible box is never reported as hidden.
+    pub fn intersects_aabb(&self, min: Vec3, max: Vec3) -> bool {
+        self.planes.iter().all(|plane| {
+            // The corner of the box furthest along the plane's normal. If even that is outside,
+            // the whole box is.
+            let normal = plane.xyz();
+            let corner = Vec3::select(normal.cmpge(Vec3::ZERO), max, min);
+            normal.dot(corner) + plane.w >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A camera at the origin looking down -Z with a 90 degree field of view, seeing from 0.1 to
+    /// 100 blocks away.
+    fn frustum() -> Frustum {
+        let projection = Mat4::perspective_rh(90f32.to_radians(), 1.0, 0.1, 100.0);
+        let view = Mat4::look_at_rh(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y);
+        Frustum::from_matrix(projection * view)
+    }
+
+    fn unit_box(center: Vec3) -> (Vec3, Vec3) {
+        (center - Vec3::splat(0.5), center + Vec3::splat(0.5))
+    }
+
+    #[test]
+    fn boxes_against_each_plane() {
+        let frustum = frustum();
+        let visible = |(min, max): (Vec3, Vec3)| frustum.intersects_aabb(min, max);
+
+        assert!(visible(unit_box(Vec3::new(0.0, 0.0, -10.0))));
+        // Behind the camera, past the far plane, and off each side.
+        assert!(!visible(unit_box(Vec3::new(0.0, 0.0, 10.0))));
+        assert!(!visible(unit_box(Vec3::new(0.0, 0.0, -200.0))));
+        assert!(!visible(unit_box(Vec3::new(-20.0, 0.0, -10.0))));
+        assert!(!visible(unit_box(Vec3::new(20.0, 0.0, -10.0))));
+        assert!(!visible(unit_box(Vec3::new(0.0, -20.0, -10.0))));
+        assert!(!visible(unit_box(Vec3::new(0.0, 20.0, -10.0))));
+
+        // Boxes partly inside, straddling a side plane or the far plane, are kept.
+        assert!(visible(unit_box(Vec3::new(10.2, 0.0, -10.0))));
+        assert!(visible(unit_box(Vec3::new(0.0, 0.0, -100.2))));
+        // So is a box around the camera, which has no corner inside.
+        assert!(visible((Vec3::splat(-500.0), Vec3::splat(500.0))));
+    }
+
+    #[test]
+    fn points_inside_and_out() {
+        let frustum = frustum();
+        assert!(frustum.contains_point(Vec3::new(0.0, 0.0, -1.0)));
+        assert!(frustum.contains_point(Vec3::new(4.9, -4.9, -5.0)));
+        assert!(!frustum.contains_point(Vec3::new(5.1, 0.0, -5.0)));
+        assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, -0.05)));
+    }
+}
@@ -34,6 +34,35 @@ impl Image {
         Ok(Self { image, pixel_bytes })
     }
 
+    /// Creates an Image from raw RGBA8 pixels, row by row.
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> anyhow::Result<Self> {
+        let pixel_bytes: Arc<[u8]> = Arc::from(pixels);
+        let image = ImageBuffer::from_raw(width, height, pixel_bytes.clone()).ok_or(anyhow!(
+            "{} bytes is the wrong size for a {}x{} RGBA image",
+            pixel_bytes.len(),
+            width,
+            height
+        ))?;
+
+        Ok(Self { image, pixel_bytes })
+    }
+
+    /// Returns a copy of the image scaled to the given dimensions with nearest neighbor
+    /// filtering, which keeps pixel art crisp.
+    pub fn resized(&self, dimensions: (u32, u32)) -> Self {
+        if dimensions == self.dimensions() {
+            return self.clone();
+        }
+        let resized = image::imageops::resize(
+            &self.image,
+            dimensions.0,
+            dimensions.1,
+            image::imageops::FilterType::Nearest,
+        );
+        Self::from_rgba(dimensions.0, dimensions.1, resized.into_raw())
+            .expect("resized image has the requested dimensions")
+    }
+
     /// Returns the dimensions of the image as (width, height).
     pub fn dimensions(&self) -> (u32, u32) {
         (self.image.width(), self.image.height())
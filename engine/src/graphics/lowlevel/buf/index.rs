@@ -1,14 +1,16 @@
-use std::ops::RangeBounds;
+use std::{cell::Cell, ops::RangeBounds};
 
 use bytemuck::{Pod, Zeroable};
 
+use super::pool::write_padded;
+
 #[derive(Debug, Clone)]
 pub struct IndexBuffer<T>
 where
     T: IndexLayout,
 {
     buffer: wgpu::Buffer,
-    count: usize,
+    count: Cell<usize>,
     _marker: std::marker::PhantomData<T>,
 }
 
@@ -21,11 +23,29 @@ impl<T: IndexLayout> IndexBuffer<T> {
     pub unsafe fn from_raw_parts(buffer: wgpu::Buffer, count: usize) -> Self {
         Self {
             buffer,
-            count,
+            count: Cell::new(count),
             _marker: std::marker::PhantomData,
         }
     }
 
+    /// Replaces the contents of the buffer with `data`, if it fits.
+    ///
+    /// Returns false, leaving the buffer unchanged, if `data` is bigger than the buffer; it has to
+    /// be replaced with a bigger one instead.
+    pub fn update(&self, queue: &wgpu::Queue, data: &[T]) -> bool {
+        if std::mem::size_of_val(data) as u64 > self.buffer.size() {
+            return false;
+        }
+        write_padded(queue, &self.buffer, bytemuck::cast_slice(data));
+        self.count.set(data.len());
+        true
+    }
+
+    /// Takes the underlying wgpu::Buffer, e.g. to give it back to a `BufferPool`.
+    pub fn into_buffer(self) -> wgpu::Buffer {
+        self.buffer
+    }
+
     /// Returns the underlying wgpu::Buffer.
     pub fn buffer(&self) -> &wgpu::Buffer {
         &self.buffer
@@ -33,7 +53,12 @@ impl<T: IndexLayout> IndexBuffer<T> {
 
     /// Returns the number of indices in the buffer.
     pub fn count(&self) -> usize {
-        self.count
+        self.count.get()
+    }
+
+    /// Returns true if the buffer holds no indices.
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
     }
 
     ///
@@ -59,3 +84,137 @@ impl IndexLayout for u16 {
 impl IndexLayout for u32 {
     const FORMAT: wgpu::IndexFormat = wgpu::IndexFormat::Uint32;
 }
+
+/// A list of indices that starts out 16 bit and switches to 32 bit once an index doesn't fit.
+///
+/// Most meshes have fewer than 65536 vertices and can use the smaller format, but dense ones
+/// overflow it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Indices {
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+impl Indices {
+    /// Creates an empty list of 16 bit indices.
+    pub fn new() -> Self {
+        Self::U16(Vec::new())
+    }
+
+    /// Appends an index, upgrading the list to 32 bit if it doesn't fit in 16.
+    pub fn push(&mut self, index: u32) {
+        match self {
+            Self::U16(indices) => match u16::try_from(index) {
+                Ok(index) => indices.push(index),
+                Err(_) => {
+                    let mut upgraded = indices.iter().map(|&i| i as u32).collect::<Vec<_>>();
+                    upgraded.push(index);
+                    *self = Self::U32(upgraded);
+                }
+            },
+            Self::U32(indices) => indices.push(index),
+        }
+    }
+
+    /// Returns the number of indices.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::U16(indices) => indices.len(),
+            Self::U32(indices) => indices.len(),
+        }
+    }
+
+    /// Returns true if there are no indices.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the indices, widened to `u32`.
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        let (narrow, wide) = match self {
+            Self::U16(indices) => (indices.as_slice(), &[][..]),
+            Self::U32(indices) => (&[][..], indices.as_slice()),
+        };
+        narrow.iter().map(|&i| i as u32).chain(wide.iter().copied())
+    }
+
+    /// Returns the format the indices are stored in.
+    pub fn format(&self) -> wgpu::IndexFormat {
+        match self {
+            Self::U16(_) => u16::FORMAT,
+            Self::U32(_) => u32::FORMAT,
+        }
+    }
+
+    /// Returns the indices as bytes, in their current format.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::U16(indices) => bytemuck::cast_slice(indices),
+            Self::U32(indices) => bytemuck::cast_slice(indices),
+        }
+    }
+}
+
+impl Default for Indices {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An index buffer in whichever format its `Indices` were in.
+#[derive(Debug, Clone)]
+pub enum AnyIndexBuffer {
+    U16(IndexBuffer<u16>),
+    U32(IndexBuffer<u32>),
+}
+
+impl AnyIndexBuffer {
+    /// Returns the underlying wgpu::Buffer.
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        match self {
+            Self::U16(buffer) => buffer.buffer(),
+            Self::U32(buffer) => buffer.buffer(),
+        }
+    }
+
+    /// Returns the number of indices in the buffer.
+    pub fn count(&self) -> usize {
+        match self {
+            Self::U16(buffer) => buffer.count(),
+            Self::U32(buffer) => buffer.count(),
+        }
+    }
+
+    /// Returns true if the buffer holds no indices.
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
+    }
+
+    /// Returns the format of the indices, to bind the buffer with.
+    pub fn format(&self) -> wgpu::IndexFormat {
+        match self {
+            Self::U16(_) => u16::FORMAT,
+            Self::U32(_) => u32::FORMAT,
+        }
+    }
+
+    /// Replaces the contents of the buffer with `indices`, if they fit and are in the same
+    /// format.
+    ///
+    /// Returns false, leaving the buffer unchanged, otherwise.
+    pub fn update(&self, queue: &wgpu::Queue, indices: &Indices) -> bool {
+        match (self, indices) {
+            (Self::U16(buffer), Indices::U16(data)) => buffer.update(queue, data),
+            (Self::U32(buffer), Indices::U32(data)) => buffer.update(queue, data),
+            _ => false,
+        }
+    }
+
+    /// Takes the underlying wgpu::Buffer, e.g. to give it back to a `BufferPool`.
+    pub fn into_buffer(self) -> wgpu::Buffer {
+        match self {
+            Self::U16(buffer) => buffer.into_buffer(),
+            Self::U32(buffer) => buffer.into_buffer(),
+        }
+    }
+}
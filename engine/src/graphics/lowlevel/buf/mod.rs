@@ -2,7 +2,10 @@ mod vertex;
 pub use vertex::*;
 
 mod index;
-pub use index::{IndexBuffer, IndexLayout};
+pub use index::{AnyIndexBuffer, IndexBuffer, IndexLayout, Indices};
+
+mod pool;
+pub use pool::{BufferPool, BufferPoolStats};
 
 mod uniform;
 pub use uniform::UniformBuffer;
@@ -0,0 +1,167 @@
+use std::cell::{Cell, RefCell};
+
+/// Recycles GPU buffers, so geometry that's rebuilt often doesn't allocate a new buffer every time.
+///
+/// Buffers given back with `release` are kept, up to `MAX_FREE` of them, and handed out again by
+/// `acquire` for any data that fits in them with the same usage. A new buffer is only allocated
+/// when none fits.
+#[derive(Debug, Default)]
+pub struct BufferPool {
+    free: RefCell<Vec<wgpu::Buffer>>,
+    stats: Cell<BufferPoolStats>,
+}
+
+/// How often a [`BufferPool`] could reuse a buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BufferPoolStats {
+    /// Buffers written in place or taken from the pool.
+    pub reused: u64,
+    /// Buffers that had to be allocated because none fit.
+    pub allocated: u64,
+}
+
+impl BufferPool {
+    /// The most buffers the pool keeps; more are dropped when released.
+    pub const MAX_FREE: usize = 64;
+
+    /// Creates an empty pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a buffer with the given usage holding `data`, reusing the smallest free buffer it
+    /// fits in if there is one.
+    ///
+    /// `usage` must include `COPY_DST` so the data can be written.
+    pub fn acquire(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        usage: wgpu::BufferUsages,
+        data: &[u8],
+        label: Option<&str>,
+    ) -> wgpu::Buffer {
+        let size = padded_len(data.len()).max(wgpu::COPY_BUFFER_ALIGNMENT);
+        let mut free = self.free.borrow_mut();
+        let fit = free
+            .iter()
+            .enumerate()
+            .filter(|(_, buffer)| buffer.usage() == usage && buffer.size() >= size)
+            .min_by_key(|(_, buffer)| buffer.size())
+            .map(|(i, _)| i);
+
+        let buffer = match fit {
+            Some(i) => {
+                self.record(|stats| stats.reused += 1);
+                free.swap_remove(i)
+            }
+            None => {
+                self.record(|stats| stats.allocated += 1);
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label,
+                    // Rounded up so a buffer that grows a little at a time isn't reallocated
+                    // every time.
+                    size: size.next_power_of_two(),
+                    usage,
+                    mapped_at_creation: false,
+                })
+            }
+        };
+        drop(free);
+        write_padded(queue, &buffer, data);
+        buffer
+    }
+
+    /// Gives a buffer back to the pool for reuse.
+    pub fn release(&self, buffer: wgpu::Buffer) {
+        let mut free = self.free.borrow_mut();
+        if free.len() < Self::MAX_FREE {
+            free.push(buffer);
+        }
+    }
+
+    /// Counts a buffer that was written in place instead of being reallocated.
+    pub fn record_reuse(&self) {
+        self.record(|stats| stats.reused += 1);
+    }
+
+    /// Returns how often buffers were reused.
+    pub fn stats(&self) -> BufferPoolStats {
+        self.stats.get()
+    }
+
+    /// Returns the number of buffers waiting to be reused.
+    pub fn free_count(&self) -> usize {
+        self.free.borrow().len()
+    }
+
+    fn record(&self, f: impl FnOnce(&mut BufferPoolStats)) {
+        let mut stats = self.stats.get();
+        f(&mut stats);
+        self.stats.set(stats);
+    }
+}
+
+/// Rounds a length in bytes up to the alignment buffer writes need.
+fn padded_len(len: usize) -> u64 {
+    (len as u64).next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT)
+}
+
+/// Writes `data` to the start of `buffer`, padding it with zeros to the alignment buffer writes
+/// need, e.g. for an odd number of 16 bit indices.
+pub(crate) fn write_padded(queue: &wgpu::Queue, buffer: &wgpu::Buffer, data: &[u8]) {
+    if data.is_empty() {
+        return;
+    }
+    if data.len() as u64 == padded_len(data.len()) {
+        queue.write_buffer(buffer, 0, data);
+    } else {
+        let mut padded = data.to_vec();
+        padded.resize(padded_len(data.len()) as usize, 0);
+        queue.write_buffer(buffer, 0, &padded);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        component::ComponentStore,
+        graphics::lowlevel::{WgpuRenderer, buf::Indices},
+    };
+
+    fn indices(count: u32) -> Indices {
+        let mut indices = Indices::new();
+        for i in 0..count {
+            indices.push(i);
+        }
+        indices
+    }
+
+    #[test]
+    fn smaller_updates_reuse_the_buffer() {
+        let mut state = ComponentStore::new();
+        WgpuRenderer::attach_noop(&mut state, (64, 64));
+        state.finish_initialization();
+        let wgpu = state.get::<WgpuRenderer>();
+
+        let mut buffer = wgpu.pooled_index_buffer(&indices(12), None);
+        let original = buffer.buffer().clone();
+        let stats = wgpu.buffer_pool().stats();
+        assert_eq!((stats.reused, stats.allocated), (0, 1));
+
+        wgpu.update_index_buffer(&mut buffer, &indices(6), None);
+        assert_eq!(*buffer.buffer(), original);
+        assert_eq!(buffer.count(), 6);
+        assert_eq!(wgpu.buffer_pool().stats().reused, 1);
+
+        // Growing past it swaps in a bigger buffer, and the old one goes back to the pool.
+        wgpu.update_index_buffer(&mut buffer, &indices(600), None);
+        assert_ne!(*buffer.buffer(), original);
+        assert_eq!(wgpu.buffer_pool().stats().allocated, 2);
+        assert_eq!(wgpu.buffer_pool().free_count(), 1);
+
+        let recycled = wgpu.pooled_index_buffer(&indices(3), None);
+        assert_eq!(*recycled.buffer(), original);
+        assert_eq!(wgpu.buffer_pool().free_count(), 0);
+    }
+}
@@ -1,13 +1,18 @@
-use std::{marker::PhantomData, ops::RangeBounds};
+use std::{cell::Cell, marker::PhantomData, ops::RangeBounds};
 
 use bytemuck::{Pod, Zeroable};
 use wgpu::VertexBufferLayout;
+
+use super::pool::write_padded;
+
 #[derive(Debug, Clone)]
 pub struct VertexBuffer<T>
 where
     T: VertexLayout,
 {
     buffer: wgpu::Buffer,
+    /// The number of vertices written, which can be less than the buffer holds.
+    count: Cell<usize>,
     _marker: PhantomData<T>,
 }
 
@@ -24,12 +29,43 @@ where
     /// # Safety
     /// The caller must ensure that the provided buffer is valid for the type T.
     pub unsafe fn from_raw_parts(buffer: wgpu::Buffer) -> Self {
+        let count = (buffer.size() as usize) / std::mem::size_of::<T>();
+        // Safety: forwarded to the caller.
+        unsafe { Self::from_raw_parts_with_count(buffer, count) }
+    }
+
+    /// Creates a new WgpuBuffer from a wgpu::Buffer holding `count` vertices at its start, like a
+    /// buffer from a `BufferPool` that's bigger than its contents.
+    ///
+    /// # Safety
+    /// The caller must ensure that the provided buffer is valid for the type T and holds at least
+    /// `count` of them.
+    pub unsafe fn from_raw_parts_with_count(buffer: wgpu::Buffer, count: usize) -> Self {
         Self {
             buffer,
+            count: Cell::new(count),
             _marker: PhantomData,
         }
     }
 
+    /// Replaces the contents of the buffer with `data`, if it fits.
+    ///
+    /// Returns false, leaving the buffer unchanged, if `data` is bigger than the buffer; it has to
+    /// be replaced with a bigger one instead.
+    pub fn update(&self, queue: &wgpu::Queue, data: &[T]) -> bool {
+        if std::mem::size_of_val(data) as u64 > self.buffer.size() {
+            return false;
+        }
+        write_padded(queue, &self.buffer, bytemuck::cast_slice(data));
+        self.count.set(data.len());
+        true
+    }
+
+    /// Takes the underlying wgpu::Buffer, e.g. to give it back to a `BufferPool`.
+    pub fn into_buffer(self) -> wgpu::Buffer {
+        self.buffer
+    }
+
     /// Returns the layout of the vertex buffer.
     pub fn layout(&self) -> VertexBufferLayout<'static> {
         T::LAYOUT
@@ -42,7 +78,12 @@ where
 
     /// Returns the number of vertices in the buffer.
     pub fn count(&self) -> usize {
-        (self.buffer.size() as usize) / std::mem::size_of::<T>()
+        self.count.get()
+    }
+
+    /// Returns true if the buffer holds no vertices.
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
     }
 
     /// Sets the vertex buffer on the given render pass at the specified slot and range.
@@ -16,26 +16,24 @@ pub struct DepthTexture {
 impl DepthTexture {
     pub const TEXTURE_FORMAT: TextureFormat = TextureFormat::Depth32Float;
     pub fn new(state: &ComponentStore) -> Self {
+        // The handle can't be used until the state is fully initialized, which it isn't yet when
+        // the game creates its depth texture.
         let wgpu = state.get::<WgpuRenderer>();
-        let config = wgpu.config.get();
-        let size = wgpu::Extent3d {
-            width: config.width,
-            height: config.height,
-            depth_or_array_layers: 1,
-        };
+        Self::create(&wgpu, state.handle_for(), wgpu.dimensions())
+    }
 
-        let desc = wgpu::TextureDescriptor {
-            label: Some("Depth Texture"),
-            size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: Self::TEXTURE_FORMAT,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
-        };
+    /// Creates a depth texture with an explicit size, e.g. for rendering to an offscreen target.
+    pub fn with_size(wgpu_handle: ComponentHandle<WgpuRenderer>, size: (u32, u32)) -> Self {
+        let wgpu = wgpu_handle.get();
+        Self::create(&wgpu, wgpu_handle.clone(), size)
+    }
 
-        let texture = wgpu.device.create_texture(&desc);
+    fn create(
+        wgpu: &WgpuRenderer,
+        wgpu_handle: ComponentHandle<WgpuRenderer>,
+        size: (u32, u32),
+    ) -> Self {
+        let texture = Self::create_texture(wgpu, size);
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         let sampler = wgpu.comparing_sampler(CompareFunction::LessEqual);
@@ -44,16 +42,14 @@ impl DepthTexture {
             texture,
             view,
             sampler,
-            wgpu_handle: state.handle_for(),
+            wgpu_handle,
         }
     }
 
-    pub fn resize(&mut self) {
-        let wgpu = self.wgpu_handle.get();
-        let config = wgpu.config.get();
+    fn create_texture(wgpu: &WgpuRenderer, size: (u32, u32)) -> wgpu::Texture {
         let size = wgpu::Extent3d {
-            width: config.width,
-            height: config.height,
+            width: size.0,
+            height: size.1,
             depth_or_array_layers: 1,
         };
 
@@ -68,7 +64,12 @@ impl DepthTexture {
             view_formats: &[],
         };
 
-        self.texture = wgpu.device.create_texture(&desc);
+        wgpu.device.create_texture(&desc)
+    }
+
+    pub fn resize(&mut self) {
+        let wgpu = self.wgpu_handle.get();
+        self.texture = Self::create_texture(&wgpu, wgpu.dimensions());
         self.view = self
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
@@ -84,7 +85,32 @@ impl DepthTexture {
         }
     }
 
+    /// Returns the depth state of render bundles executed in passes using `attachment`.
+    pub fn bundle_state(&self) -> wgpu::RenderBundleDepthStencil {
+        wgpu::RenderBundleDepthStencil {
+            format: Self::TEXTURE_FORMAT,
+            depth_read_only: false,
+            stencil_read_only: true,
+        }
+    }
+
+    /// Returns a depth attachment that keeps the existing depth values.
+    ///
+    /// The depth buffer is cleared once per frame by the clear pipeline, so this is what
+    /// regular pipelines should use.
     pub fn attachment(&self) -> wgpu::RenderPassDepthStencilAttachment<'_> {
+        wgpu::RenderPassDepthStencilAttachment {
+            view: &self.view,
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }
+    }
+
+    /// Returns a depth attachment that resets every depth value to the far plane.
+    pub fn clear_attachment(&self) -> wgpu::RenderPassDepthStencilAttachment<'_> {
         wgpu::RenderPassDepthStencilAttachment {
             view: &self.view,
             depth_ops: Some(wgpu::Operations {
@@ -0,0 +1,144 @@
+use std::{fmt::Debug, sync::Arc};
+
+use crate::graphics::lowlevel::{WgpuRenderer, shader::ShaderProgram};
+
+/// The parts of a GPU device that pipeline creation needs.
+///
+/// [`WgpuRenderer`] implements this for the real device and surface. With the `noop` feature,
+/// [`NoopDevice`] implements it without a GPU, so pipelines can be built in tests.
+pub trait RenderDevice: Debug {
+    /// Returns the wgpu device.
+    fn device(&self) -> &wgpu::Device;
+    /// Returns the wgpu queue.
+    fn queue(&self) -> &wgpu::Queue;
+    /// Returns the texture format of the surface being rendered to.
+    fn surface_format(&self) -> wgpu::TextureFormat;
+    /// Returns the dimensions of the surface being rendered to.
+    fn dimensions(&self) -> (u32, u32);
+    /// Returns the layout of the bind group holding the `TimeUniform`.
+    fn time_bind_group_layout(&self) -> &wgpu::BindGroupLayout;
+
+    /// Loads a shader module from WGSL source code.
+    fn load_shader(
+        &self,
+        shader_source: &str,
+        label: Option<&str>,
+        vs_entry: Option<&str>,
+        fs_entry: Option<&str>,
+    ) -> ShaderProgram {
+        let module = self
+            .device()
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label,
+                source: wgpu::ShaderSource::Wgsl(shader_source.into()),
+            });
+
+        ShaderProgram::from_raw_parts(module, vs_entry.map(Arc::from), fs_entry.map(Arc::from))
+    }
+
+    /// Loads a shader module from WGSL source code, returning the compile errors instead of
+    /// raising them as uncaptured device errors.
+    fn try_load_shader(
+        &self,
+        shader_source: &str,
+        label: Option<&str>,
+        vs_entry: Option<&str>,
+        fs_entry: Option<&str>,
+    ) -> anyhow::Result<ShaderProgram> {
+        let scope = self
+            .device()
+            .push_error_scope(wgpu::ErrorFilter::Validation);
+        let shader = self.load_shader(shader_source, label, vs_entry, fs_entry);
+        if let Some(error) = smol::block_on(scope.pop()) {
+            anyhow::bail!(
+                "Failed to compile shader {}: {}",
+                label.unwrap_or("?"),
+                error
+            );
+        }
+        Ok(shader)
+    }
+}
+
+impl RenderDevice for WgpuRenderer {
+    fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    fn surface_format(&self) -> wgpu::TextureFormat {
+        self.config.get().format
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        WgpuRenderer::dimensions(self)
+    }
+
+    fn time_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        WgpuRenderer::time_bind_group_layout(self)
+    }
+}
+
+/// A [`RenderDevice`] backed by wgpu's no-op backend.
+///
+/// Resources can be created and validated, but nothing is ever executed.
+#[cfg(any(test, feature = "noop"))]
+#[derive(Debug)]
+pub struct NoopDevice {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    format: wgpu::TextureFormat,
+    dimensions: (u32, u32),
+    time_layout: wgpu::BindGroupLayout,
+}
+
+#[cfg(any(test, feature = "noop"))]
+impl NoopDevice {
+    /// Creates a no-op device pretending to render to a `Rgba8UnormSrgb` surface of the given size.
+    pub fn new(dimensions: (u32, u32)) -> Self {
+        let (device, queue) = wgpu::Device::noop(&wgpu::DeviceDescriptor {
+            label: Some("noop device"),
+            ..Default::default()
+        });
+        let time_layout = crate::graphics::time::TimeUniform::create_bind_group_layout(&device);
+        Self {
+            device,
+            queue,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            dimensions,
+            time_layout,
+        }
+    }
+
+    /// Sets the surface format reported to pipelines.
+    pub fn with_surface_format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+#[cfg(any(test, feature = "noop"))]
+impl RenderDevice for NoopDevice {
+    fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    fn surface_format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+
+    fn time_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.time_layout
+    }
+}
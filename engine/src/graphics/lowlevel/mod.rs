@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::cell::Cell;
 
 use anyhow::Context;
 use bytemuck::Pod;
@@ -6,7 +6,7 @@ use log::debug;
 use wgpu::{
     self as w, Color, CommandBuffer, CommandEncoder, CompareFunction, Device, DeviceDescriptor,
     Instance, InstanceDescriptor, Origin3d, PowerPreference, PresentMode, Queue, RenderPass,
-    RequestAdapterOptions, StoreOp, Surface, SurfaceConfiguration, SurfaceTexture, TextureAspect,
+    RequestAdapterOptions, Surface, SurfaceConfiguration, SurfaceTexture, TextureAspect,
     TextureView, util::DeviceExt,
 };
 
@@ -14,16 +14,22 @@ use crate::{
     ReadOnly,
     component::{ComponentStore, ComponentStoreHandle},
     graphics::lowlevel::{
-        buf::{IndexBuffer, IndexLayout, UniformBuffer, VertexBuffer, VertexLayout},
+        buf::{
+            AnyIndexBuffer, BufferPool, IndexBuffer, IndexLayout, Indices, UniformBuffer,
+            VertexBuffer, VertexLayout,
+        },
         shader::ShaderProgram,
         texture::Texture,
     },
+    graphics::pipeline::pass::PassBuilder,
+    graphics::time::TimeUniform,
     resource::Resource,
     window::GlfwWindow,
 };
 
 pub mod buf;
 pub mod depth;
+pub mod device;
 pub mod pipeline;
 pub mod shader;
 pub mod texture;
@@ -31,11 +37,17 @@ pub mod texture;
 #[derive(Debug)]
 pub struct WgpuRenderer {
     pub instance: Instance,
-    pub surface: Surface<'static>,
+    /// The window surface, or `None` for a headless renderer that only draws offscreen.
+    pub surface: Option<Surface<'static>>,
     pub device: Device,
     pub queue: Queue,
     pub config: Resource<SurfaceConfiguration>,
     pub default_sampler: Option<wgpu::Sampler>,
+    time_layout: wgpu::BindGroupLayout,
+    time_buffer: wgpu::Buffer,
+    time_bind_group: wgpu::BindGroup,
+    buffer_pool: BufferPool,
+    submissions: Cell<u64>,
     state: ComponentStoreHandle,
 }
 
@@ -91,6 +103,62 @@ impl WgpuRenderer {
 
         surface.configure(&device, &config);
 
+        Self::insert(state, instance, Some(surface), device, queue, config);
+        Ok(())
+    }
+
+    /// Attaches a headless WGPU renderer on wgpu's no-op backend to the given state, pretending
+    /// to render to a `Rgba8UnormSrgb` surface of the given size.
+    ///
+    /// Resources can be created and commands recorded and submitted, but nothing is ever
+    /// executed, so tests can drive pipelines without a GPU or window.
+    #[cfg(any(test, feature = "noop"))]
+    pub fn attach_noop(state: &mut ComponentStore, size: (u32, u32)) {
+        let instance = Instance::new(&InstanceDescriptor {
+            backends: wgpu::Backends::NOOP,
+            ..Default::default()
+        });
+        let (device, queue) = Device::noop(&DeviceDescriptor {
+            label: Some("noop device"),
+            ..Default::default()
+        });
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            width: size.0,
+            height: size.1,
+            present_mode: PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        Self::insert(state, instance, None, device, queue, config);
+    }
+
+    /// Creates the renderer from its device and surface, and inserts it into the state.
+    fn insert(
+        state: &mut ComponentStore,
+        instance: Instance,
+        surface: Option<Surface<'static>>,
+        device: Device,
+        queue: Queue,
+        config: SurfaceConfiguration,
+    ) {
+        let time_layout = TimeUniform::create_bind_group_layout(&device);
+        let time_buffer = device.create_buffer_init(&w::util::BufferInitDescriptor {
+            label: Some("time uniform buffer"),
+            contents: bytemuck::bytes_of(&TimeUniform::default()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let time_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("time bind group"),
+            layout: &time_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: time_buffer.as_entire_binding(),
+            }],
+        });
+
         let mut this = WgpuRenderer {
             instance,
             surface,
@@ -98,14 +166,19 @@ impl WgpuRenderer {
             queue,
             config: Resource::new(config),
             default_sampler: None,
+            time_layout,
+            time_buffer,
+            time_bind_group,
+            buffer_pool: BufferPool::new(),
+            submissions: Cell::new(0),
             state: state.handle(),
         };
 
         this.default_sampler =
             Some(this.sampler(Some("default sampler"), wgpu::AddressMode::ClampToEdge));
 
-        state.insert(this);
-        Ok(())
+        // Everything that changes after creation, like the surface config, has its own cell.
+        state.insert_immutable(this);
     }
 
     /// Resize the surface to the new size.
@@ -118,7 +191,31 @@ impl WgpuRenderer {
         cfg.width = new_size.0 as u32;
         cfg.height = new_size.1 as u32;
         drop(cfg);
-        self.surface.configure(&self.device, &self.config.get());
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.config.get());
+        }
+    }
+
+    /// Turns vsync on or off, reconfiguring the surface.
+    ///
+    /// Without vsync, frames are presented as soon as they're ready, which may tear.
+    pub fn set_vsync(&self, enabled: bool) {
+        self.config.get_mut().present_mode = if enabled {
+            PresentMode::Fifo
+        } else {
+            PresentMode::AutoNoVsync
+        };
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.config.get());
+        }
+    }
+
+    /// Returns true if presentation waits for vertical blank.
+    pub fn vsync(&self) -> bool {
+        matches!(
+            self.config.get().present_mode,
+            PresentMode::Fifo | PresentMode::FifoRelaxed | PresentMode::AutoVsync
+        )
     }
 
     /// Creates a command encoder.
@@ -127,6 +224,28 @@ impl WgpuRenderer {
             .create_command_encoder(&wgpu::CommandEncoderDescriptor { label })
     }
 
+    /// Creates an encoder for a render bundle drawing into the surface format, with an optional
+    /// depth attachment.
+    ///
+    /// Bundles record draws once so they can be replayed every frame with
+    /// `RenderPass::execute_bundles`, skipping the per-draw encoding cost. They suit geometry that
+    /// rarely changes, like chunk meshes.
+    pub fn render_bundle_encoder<'a>(
+        &self,
+        label: Option<&str>,
+        depth_stencil: Option<wgpu::RenderBundleDepthStencil>,
+    ) -> wgpu::RenderBundleEncoder<'a> {
+        let format = self.config.get().format;
+        self.device
+            .create_render_bundle_encoder(&wgpu::RenderBundleEncoderDescriptor {
+                label,
+                color_formats: &[Some(format)],
+                depth_stencil,
+                sample_count: 1,
+                multiview: None,
+            })
+    }
+
     /// Creates a buffer with the given usage and data.
     pub fn vertex_buffer<T>(&self, data: &[T], label: Option<&str>) -> VertexBuffer<T>
     where
@@ -161,6 +280,98 @@ impl WgpuRenderer {
         unsafe { IndexBuffer::from_raw_parts(buffer, data.len()) }
     }
 
+    /// Creates an index buffer in the format the indices are stored in.
+    pub fn any_index_buffer(&self, indices: &Indices, label: Option<&str>) -> AnyIndexBuffer {
+        match indices {
+            Indices::U16(data) => AnyIndexBuffer::U16(self.index_buffer(data, label)),
+            Indices::U32(data) => AnyIndexBuffer::U32(self.index_buffer(data, label)),
+        }
+    }
+
+    /// Returns the pool that buffers for frequently rebuilt geometry are recycled through.
+    pub fn buffer_pool(&self) -> &BufferPool {
+        &self.buffer_pool
+    }
+
+    /// Creates a vertex buffer like `vertex_buffer`, but reusing a buffer from the pool if one
+    /// fits. Give it back with `recycle_buffer` once it's no longer needed.
+    pub fn pooled_vertex_buffer<T>(&self, data: &[T], label: Option<&str>) -> VertexBuffer<T>
+    where
+        T: VertexLayout,
+    {
+        let buffer = self.buffer_pool.acquire(
+            &self.device,
+            &self.queue,
+            wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            bytemuck::cast_slice(data),
+            label,
+        );
+        // Safety: The buffer starts with `data`, a slice of T.
+        unsafe { VertexBuffer::from_raw_parts_with_count(buffer, data.len()) }
+    }
+
+    /// Creates an index buffer like `any_index_buffer`, but reusing a buffer from the pool if one
+    /// fits. Give it back with `recycle_buffer` once it's no longer needed.
+    pub fn pooled_index_buffer(&self, indices: &Indices, label: Option<&str>) -> AnyIndexBuffer {
+        let buffer = self.buffer_pool.acquire(
+            &self.device,
+            &self.queue,
+            wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+            indices.as_bytes(),
+            label,
+        );
+        // Safety: The buffer starts with the indices, in the format they're stored in.
+        unsafe {
+            match indices {
+                Indices::U16(data) => {
+                    AnyIndexBuffer::U16(IndexBuffer::from_raw_parts(buffer, data.len()))
+                }
+                Indices::U32(data) => {
+                    AnyIndexBuffer::U32(IndexBuffer::from_raw_parts(buffer, data.len()))
+                }
+            }
+        }
+    }
+
+    /// Replaces the contents of a vertex buffer, writing them in place if they fit and otherwise
+    /// swapping in a bigger buffer from the pool.
+    pub fn update_vertex_buffer<T>(
+        &self,
+        buffer: &mut VertexBuffer<T>,
+        data: &[T],
+        label: Option<&str>,
+    ) where
+        T: VertexLayout,
+    {
+        if buffer.update(&self.queue, data) {
+            self.buffer_pool.record_reuse();
+        } else {
+            let old = std::mem::replace(buffer, self.pooled_vertex_buffer(data, label));
+            self.recycle_buffer(old.into_buffer());
+        }
+    }
+
+    /// Replaces the contents of an index buffer, writing them in place if they fit in the same
+    /// format and otherwise swapping in another buffer from the pool.
+    pub fn update_index_buffer(
+        &self,
+        buffer: &mut AnyIndexBuffer,
+        indices: &Indices,
+        label: Option<&str>,
+    ) {
+        if buffer.update(&self.queue, indices) {
+            self.buffer_pool.record_reuse();
+        } else {
+            let old = std::mem::replace(buffer, self.pooled_index_buffer(indices, label));
+            self.recycle_buffer(old.into_buffer());
+        }
+    }
+
+    /// Gives a buffer that's no longer needed back to the pool.
+    pub fn recycle_buffer(&self, buffer: wgpu::Buffer) {
+        self.buffer_pool.release(buffer);
+    }
+
     pub fn uniform_buffer<T>(&self, data: &T, label: Option<&str>) -> UniformBuffer<T>
     where
         T: Pod,
@@ -177,24 +388,6 @@ impl WgpuRenderer {
         unsafe { UniformBuffer::from_raw_parts(buffer, self.state.clone()) }
     }
 
-    /// Loads a shader module from WGSL source code.
-    pub fn load_shader(
-        &self,
-        shader_source: &str,
-        label: Option<&str>,
-        vs_entry: Option<&str>,
-        fs_entry: Option<&str>,
-    ) -> ShaderProgram {
-        let module = self
-            .device
-            .create_shader_module(wgpu::ShaderModuleDescriptor {
-                label,
-                source: wgpu::ShaderSource::Wgsl(shader_source.into()),
-            });
-
-        ShaderProgram::from_raw_parts(module, vs_entry.map(Arc::from), fs_entry.map(Arc::from))
-    }
-
     /// Creates a texture with the given descriptor.
     pub fn create_texture(&self, desc: &wgpu::TextureDescriptor) -> wgpu::Texture {
         self.device.create_texture(desc)
@@ -210,6 +403,21 @@ impl WgpuRenderer {
         usage: wgpu::TextureUsages,
         dims: (u32, u32),
         image: &[ReadOnly<u8>],
+    ) -> Texture {
+        let sampler = self.default_sampler.clone().expect("no default sampler!");
+        self.texture_with_sampler(label, format, usage, dims, image, sampler)
+    }
+
+    /// Creates a texture like [`WgpuRenderer::texture`], but sampled with the given sampler
+    /// instead of the default one.
+    pub fn texture_with_sampler(
+        &self,
+        label: Option<&str>,
+        format: wgpu::TextureFormat,
+        usage: wgpu::TextureUsages,
+        dims: (u32, u32),
+        image: &[ReadOnly<u8>],
+        sampler: wgpu::Sampler,
     ) -> Texture {
         assert!(!image.is_empty(), "Image slice must not be empty");
         let (width, height) = dims;
@@ -243,7 +451,8 @@ impl WgpuRenderer {
                 view_dimension: wgpu::TextureViewDimension::D2Array,
                 // TODO: Allow this to be configurable based on texture format.
                 // Minecraft clone probably means that using a integer format is easier.
-                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                // Filterable, so linear and anisotropic samplers can be swapped in at runtime.
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
             },
             count: None,
         };
@@ -253,9 +462,15 @@ impl WgpuRenderer {
             height,
             depth_or_array_layers: 1,
         };
+        let bytes_per_row = texture::bytes_per_row(format, width);
 
         for (i, image) in image.iter().enumerate() {
             debug!("Uploading texture layer {} ({} bytes)", i, image.len());
+            assert_eq!(
+                image.len(),
+                (bytes_per_row * height) as usize,
+                "Texture layer {i} doesn't match the size of a {width}x{height} {format:?} image"
+            );
             self.queue.write_texture(
                 wgpu::TexelCopyTextureInfoBase {
                     texture: &text,
@@ -270,7 +485,7 @@ impl WgpuRenderer {
                 image.as_ref(),
                 wgpu::TexelCopyBufferLayout {
                     offset: 0,
-                    bytes_per_row: Some(4 * width),
+                    bytes_per_row: Some(bytes_per_row),
                     rows_per_image: Some(height),
                 },
                 size,
@@ -282,8 +497,6 @@ impl WgpuRenderer {
             ..Default::default()
         });
 
-        let sampler = self.default_sampler.clone().expect("no default sampler!");
-
         let sampler_layout = wgpu::BindGroupLayoutEntry {
             binding: 1,
             visibility: wgpu::ShaderStages::FRAGMENT,
@@ -495,9 +708,13 @@ impl WgpuRenderer {
     }
 
     /// Acquires the current texture view from the surface.
+    ///
+    /// Fails for a headless renderer, which has no surface.
     pub fn current_view(&self) -> anyhow::Result<(SurfaceTexture, TextureView)> {
         let frame = self
             .surface
+            .as_ref()
+            .context("Headless renderer has no surface to draw to")?
             .get_current_texture()
             .with_context(|| "Failed to acquire next swap chain texture")?;
         let view = frame
@@ -506,7 +723,10 @@ impl WgpuRenderer {
         Ok((frame, view))
     }
 
-    /// Clears the given texture view with the specified color using the provided command encoder.
+    /// Begins a render pass on `view` with the given color load op and optional depth attachment.
+    ///
+    /// Shorthand for a [`PassBuilder`] with a single color target; pipelines usually get one from
+    /// [`RenderController::pass`](crate::graphics::pipeline::controller::RenderController::pass).
     pub fn render_pass<'b>(
         &self,
         label: Option<&str>,
@@ -515,35 +735,53 @@ impl WgpuRenderer {
         depth_stencil_attachment: Option<wgpu::RenderPassDepthStencilAttachment>,
         color_load_op: wgpu::LoadOp<Color>,
     ) -> RenderPass<'b> {
-        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label,
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: color_load_op,
-                    store: StoreOp::Store,
-                },
-                depth_slice: None,
-            })],
-            depth_stencil_attachment,
-            ..Default::default()
-        })
+        let mut pass = PassBuilder::new().color_target(view).load_op(color_load_op);
+        if let Some(label) = label {
+            pass = pass.label(label);
+        }
+        if let Some(depth) = depth_stencil_attachment {
+            pass = pass.depth(depth);
+        }
+        pass.begin(encoder)
     }
 
     /// Submits a single command encoder to the queue. This is a direct wrapper around `Queue::submit`.
     pub fn submit_single(&self, encoder: CommandBuffer) {
-        self.queue.submit(std::iter::once(encoder));
+        self.submit(std::iter::once(encoder));
     }
 
     /// Submits multiple command buffers to the queue.
     pub fn submit<I: IntoIterator<Item = CommandBuffer>>(&self, bufs: I) {
+        self.submissions.set(self.submissions.get() + 1);
         self.queue.submit(bufs);
     }
 
+    /// Returns how many times command buffers were submitted through this renderer.
+    ///
+    /// A frame should only submit once; see `RenderController::render_frame`.
+    pub fn submission_count(&self) -> u64 {
+        self.submissions.get()
+    }
+
     /// Returns the current dimensions of the surface.
     pub fn dimensions(&self) -> (u32, u32) {
         let cfg = self.config.get();
         (cfg.width, cfg.height)
     }
+
+    /// Returns the layout of the time bind group, for pipelines to append to theirs.
+    pub fn time_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.time_layout
+    }
+
+    /// Returns the bind group holding the time uniform. See `TimeUniform`.
+    pub fn time_bind_group(&self) -> &wgpu::BindGroup {
+        &self.time_bind_group
+    }
+
+    /// Uploads the time uniform, e.g. after advancing it for a new frame.
+    pub fn write_time(&self, time: &TimeUniform) {
+        self.queue
+            .write_buffer(&self.time_buffer, 0, bytemuck::bytes_of(time));
+    }
 }
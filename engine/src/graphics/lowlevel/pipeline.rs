@@ -1,13 +1,13 @@
-use std::rc::Rc;
+use std::{path::Path, rc::Rc};
 
 use wgpu::{BindGroupLayout, VertexBufferLayout};
 
-use crate::graphics::lowlevel::{WgpuRenderer, buf::VertexLayout, shader::ShaderProgram};
+use crate::graphics::lowlevel::{buf::VertexLayout, device::RenderDevice, shader::ShaderProgram};
 
 /// A builder for creating render pipelines.
 #[derive(Debug)]
 pub struct PipelineBuilder<'a> {
-    wgpu: &'a WgpuRenderer,
+    wgpu: &'a dyn RenderDevice,
     label: &'a str,
     bind_group_layouts: Vec<wgpu::BindGroupLayout>,
     shader_module: Option<ShaderProgram>,
@@ -19,7 +19,7 @@ pub struct PipelineBuilder<'a> {
 
 impl<'a> PipelineBuilder<'a> {
     /// Creates a new PipelineBuilder.
-    pub fn new(wgpu: &'a WgpuRenderer, label: &'a str) -> PipelineBuilder<'a> {
+    pub fn new(wgpu: &'a dyn RenderDevice, label: &'a str) -> PipelineBuilder<'a> {
         PipelineBuilder {
             wgpu,
             label,
@@ -47,6 +47,25 @@ impl<'a> PipelineBuilder<'a> {
         self
     }
 
+    /// Sets the shader module for the pipeline, reading it from a WGSL file.
+    ///
+    /// Fails if the file can't be read or doesn't compile; see [`ShaderProgram::from_file`].
+    pub fn shader_file(
+        self,
+        path: impl AsRef<Path>,
+        vs_entry: Option<&str>,
+        fs_entry: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        let shader = ShaderProgram::from_file(self.wgpu, path, vs_entry, fs_entry)?;
+        Ok(self.shader_program(shader))
+    }
+
+    /// Sets an already compiled shader program for the pipeline.
+    pub fn shader_program(mut self, shader: ShaderProgram) -> Self {
+        self.shader_module = Some(shader);
+        self
+    }
+
     /// Adds a bind group layout to the pipeline.
     pub fn push_bind_group(mut self, layout: BindGroupLayout) -> Self {
         self.bind_group_layouts.push(layout);
@@ -68,12 +87,35 @@ impl<'a> PipelineBuilder<'a> {
         self
     }
 
+    /// Adds a color target that blends with the existing contents using the given blend state.
+    pub fn add_blended_color_target(
+        mut self,
+        format: wgpu::TextureFormat,
+        blend: wgpu::BlendState,
+    ) -> Self {
+        self.color_targets.push(Some(wgpu::ColorTargetState {
+            format,
+            blend: Some(blend),
+            write_mask: wgpu::ColorWrites::ALL,
+        }));
+        self
+    }
+
     /// Sets the primitive state for the pipeline.
     pub fn primitive_state(mut self, state: wgpu::PrimitiveState) -> Self {
         self.primitive_state = state;
         self
     }
 
+    /// Culls faces of the given orientation, treating counter-clockwise triangles as front facing.
+    ///
+    /// Pass `None` to disable culling, which is the default.
+    pub fn cull_mode(mut self, cull_mode: Option<wgpu::Face>) -> Self {
+        self.primitive_state.front_face = wgpu::FrontFace::Ccw;
+        self.primitive_state.cull_mode = cull_mode;
+        self
+    }
+
     /// Sets the depth stencil state for the pipeline.
     pub fn depth(mut self, state: wgpu::DepthStencilState) -> Self {
         self.depth_stencil = Some(state);
@@ -88,7 +130,7 @@ impl<'a> PipelineBuilder<'a> {
 
         let pipeline_layout =
             self.wgpu
-                .device
+                .device()
                 .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                     label: Some(self.label),
                     bind_group_layouts: &self.bind_group_layouts.iter().collect::<Vec<_>>(),
@@ -97,7 +139,7 @@ impl<'a> PipelineBuilder<'a> {
 
         let pipeline = self
             .wgpu
-            .device
+            .device()
             .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
                 label: Some(self.label),
                 layout: Some(&pipeline_layout),
@@ -116,6 +158,24 @@ impl<'a> PipelineBuilder<'a> {
             layout: pipeline_layout,
         }
     }
+
+    /// Builds the pipeline, returning validation errors (e.g. a missing entry point or a binding
+    /// that doesn't match the layout) instead of raising them on the device.
+    pub fn try_build(
+        self,
+        compilation_options: Option<wgpu::PipelineCompilationOptions<'_>>,
+    ) -> anyhow::Result<WgpuPipeline> {
+        let label = self.label;
+        let scope = self
+            .wgpu
+            .device()
+            .push_error_scope(wgpu::ErrorFilter::Validation);
+        let pipeline = self.build(compilation_options);
+        if let Some(error) = smol::block_on(scope.pop()) {
+            anyhow::bail!("Failed to build pipeline {}: {}", label, error);
+        }
+        Ok(pipeline)
+    }
 }
 
 pub struct WgpuPipeline {
@@ -123,3 +183,41 @@ pub struct WgpuPipeline {
     pub shader: ShaderProgram,
     pub layout: wgpu::PipelineLayout,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::lowlevel::device::NoopDevice;
+
+    const SHADER: &str = "
+        @vertex
+        fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+            return vec4<f32>(f32(index), 0.0, 0.0, 1.0);
+        }
+
+        @fragment
+        fn fs_main() -> @location(0) vec4<f32> {
+            return vec4<f32>(1.0);
+        }
+    ";
+
+    #[test]
+    fn builds_on_a_noop_device() {
+        let device = NoopDevice::new((64, 64));
+        let pipeline = PipelineBuilder::new(&device, "Test Pipeline")
+            .shader("Test Shader", SHADER, Some("vs_main"), Some("fs_main"))
+            .add_color_target(device.surface_format())
+            .try_build(None);
+        assert!(pipeline.is_ok(), "{:?}", pipeline.err());
+    }
+
+    #[test]
+    fn missing_entry_point_is_an_error() {
+        let device = NoopDevice::new((64, 64));
+        let pipeline = PipelineBuilder::new(&device, "Test Pipeline")
+            .shader("Test Shader", SHADER, Some("vs_main"), Some("fs_missing"))
+            .add_color_target(device.surface_format())
+            .try_build(None);
+        assert!(pipeline.is_err());
+    }
+}
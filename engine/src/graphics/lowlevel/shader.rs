@@ -1,8 +1,11 @@
 //! wgpu shader abstractions
 
+use std::path::Path;
+
+use anyhow::Context;
 use wgpu::VertexBufferLayout;
 
-use crate::ReadOnlyString;
+use crate::{ReadOnlyString, graphics::lowlevel::device::RenderDevice};
 
 #[derive(Clone, Debug)]
 pub struct ShaderProgram {
@@ -17,7 +20,7 @@ pub struct ShaderProgram {
 impl ShaderProgram {
     /// Creates a new ShaderProgram from the given parts.
     ///
-    /// You probably want to use [`crate::graphics::WgpuInstance::load_shader`] to create the shader module.
+    /// You probably want to use [`crate::graphics::lowlevel::device::RenderDevice::load_shader`] to create the shader module.
     pub fn from_raw_parts<'a>(
         module: wgpu::ShaderModule,
         vertex_entry_point: Option<ReadOnlyString>,
@@ -30,6 +33,27 @@ impl ShaderProgram {
         }
     }
 
+    /// Reads and compiles a WGSL shader from a file, labelled with its path.
+    ///
+    /// Unlike `RenderDevice::load_shader`, compile errors are returned rather than raised on the
+    /// device, so a typo in a shader that's being edited doesn't take the app down.
+    pub fn from_file(
+        wgpu: &dyn RenderDevice,
+        path: impl AsRef<Path>,
+        vs_entry: Option<&str>,
+        fs_entry: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read shader {}", path.display()))?;
+        wgpu.try_load_shader(
+            &source,
+            Some(&path.display().to_string()),
+            vs_entry,
+            fs_entry,
+        )
+    }
+
     /// Returns the vertex state for this shader program.
     pub fn vertex_state<'a>(
         &'a self,
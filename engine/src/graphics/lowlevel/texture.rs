@@ -5,6 +5,25 @@ use crate::{
     graphics::lowlevel::WgpuRenderer,
 };
 
+/// Returns the number of bytes in one row of texels of the given format and width.
+///
+/// Rows written through `Queue::write_texture` don't need the 256 byte alignment that buffer
+/// copies do, so this is the tightly packed size.
+///
+/// # Panics
+/// Panics for block compressed formats and formats without a single copy size, such as
+/// combined depth-stencil formats.
+pub fn bytes_per_row(format: wgpu::TextureFormat, width: u32) -> u32 {
+    assert!(
+        !format.is_compressed(),
+        "Compressed texture format {format:?} is not supported"
+    );
+    let bytes_per_texel = format
+        .block_copy_size(None)
+        .unwrap_or_else(|| panic!("Texture format {format:?} has no single copy size"));
+    bytes_per_texel * width
+}
+
 /// A structure representing a texture, its view, and its sampler.
 #[derive(Clone)]
 pub struct Texture {
@@ -129,3 +148,44 @@ impl Debug for Texture {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ReadOnly, component::ComponentStore};
+
+    #[test]
+    fn row_size_follows_the_format() {
+        use wgpu::TextureFormat::*;
+        assert_eq!(bytes_per_row(Rgba8Unorm, 16), 64);
+        assert_eq!(bytes_per_row(Rgba8UnormSrgb, 16), 64);
+        assert_eq!(bytes_per_row(R8Unorm, 16), 16);
+        assert_eq!(bytes_per_row(Rg8Unorm, 5), 10);
+        assert_eq!(bytes_per_row(Rgba16Float, 16), 128);
+        assert_eq!(bytes_per_row(Rgba32Float, 3), 48);
+    }
+
+    #[test]
+    #[should_panic(expected = "Compressed texture format")]
+    fn compressed_formats_are_rejected() {
+        bytes_per_row(wgpu::TextureFormat::Bc1RgbaUnorm, 16);
+    }
+
+    #[test]
+    fn uploads_a_wide_format() {
+        let mut state = ComponentStore::new();
+        WgpuRenderer::attach_noop(&mut state, (64, 64));
+        state.finish_initialization();
+
+        let format = wgpu::TextureFormat::Rgba16Float;
+        let layer: ReadOnly<u8> = vec![0; (bytes_per_row(format, 4) * 2) as usize].into();
+        let texture = state.get::<WgpuRenderer>().texture(
+            Some("Wide Texture"),
+            format,
+            wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            (4, 2),
+            &[layer],
+        );
+        assert_eq!(texture.texture.format(), format);
+    }
+}
@@ -1,9 +1,13 @@
+pub mod atlases;
 pub mod callback;
 pub mod camera;
+pub mod frustum;
 pub mod image;
 pub mod lowlevel;
 pub mod pipeline;
 pub mod textures;
+pub mod time;
+pub mod viewport;
 
 /// Cardinal directions in 3D space.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -47,6 +51,18 @@ impl CardinalDirection {
         }
     }
 
+    /// Returns the direction pointing the opposite way.
+    pub fn opposite(&self) -> CardinalDirection {
+        match self {
+            CardinalDirection::North => CardinalDirection::South,
+            CardinalDirection::South => CardinalDirection::North,
+            CardinalDirection::East => CardinalDirection::West,
+            CardinalDirection::West => CardinalDirection::East,
+            CardinalDirection::Up => CardinalDirection::Down,
+            CardinalDirection::Down => CardinalDirection::Up,
+        }
+    }
+
     pub fn iter() -> impl Iterator<Item = CardinalDirection> {
         [
             CardinalDirection::North,
@@ -1,14 +1,16 @@
-use std::fmt::Debug;
+use std::{any::Any, fmt::Debug};
 
-use anyhow::Context;
+use anyhow::{Context, bail};
+use log::warn;
 use wgpu::TextureView;
 
 use crate::{
     component::{ComponentHandle, ComponentStore},
     graphics::{
         lowlevel::WgpuRenderer,
-        pipeline::{RenderPipeline, UpdateRequest},
+        pipeline::{RenderPipeline, UpdateRequest, pass::PassBuilder},
     },
+    profile_span,
 };
 
 /// A trait representing a key for identifying render pipelines.
@@ -18,10 +20,31 @@ pub trait PipelineKey:
 {
 }
 
+/// How the controller records the passes of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordingMode {
+    /// Every pipeline records into one command encoder.
+    #[default]
+    Shared,
+    /// Every pipeline records into its own command encoder. The command buffers are submitted
+    /// together, in render order, so the frame still takes a single submit.
+    ///
+    /// This keeps each pipeline's commands in a separately labelled buffer, which makes them
+    /// easier to tell apart in graphics debuggers, and lets the backend process them
+    /// independently. Pipelines hold component handles that aren't `Send`, so recording still
+    /// happens on the calling thread; for expensive static geometry, record a render bundle
+    /// instead (see `WgpuRenderer::render_bundle_encoder`).
+    PerPipeline,
+}
+
 pub struct RenderController<K: PipelineKey> {
     pipelines: std::collections::HashMap<K, Box<dyn RenderPipeline<K>>>,
+    insertion_order: Vec<K>,
+    dependencies: std::collections::HashMap<K, Vec<K>>,
     render_list: Vec<K>,
+    manual_order: bool,
     render_suface: Option<(K, wgpu::TextureView)>,
+    recording_mode: RecordingMode,
     /// The WGPU renderer. Convenience access for pipelines.
     pub wgpu: ComponentHandle<WgpuRenderer>,
 }
@@ -31,15 +54,103 @@ impl<K: PipelineKey> RenderController<K> {
     pub fn new(state: &ComponentStore) -> Self {
         Self {
             pipelines: std::collections::HashMap::new(),
+            insertion_order: Vec::new(),
+            dependencies: std::collections::HashMap::new(),
             render_list: Vec::new(),
+            manual_order: false,
             render_suface: None,
+            recording_mode: RecordingMode::default(),
             wgpu: state.handle_for::<WgpuRenderer>(),
         }
     }
 
     /// Adds a render pipeline to the controller.
+    ///
+    /// Unless the order was set with `set_render_order`, pipelines render in the order they were
+    /// added, adjusted to satisfy any dependencies declared with `after`.
     pub fn add_pipeline<P: RenderPipeline<K> + 'static>(&mut self, key: K, pipeline: P) {
-        self.pipelines.insert(key, Box::new(pipeline));
+        if self
+            .pipelines
+            .insert(key.clone(), Box::new(pipeline))
+            .is_none()
+        {
+            self.insertion_order.push(key);
+        }
+        self.refresh_render_order();
+    }
+
+    /// Declares that the pipeline `key` must render after the pipeline `dependency`.
+    ///
+    /// Either pipeline may be added later. Returns an error, leaving the dependencies unchanged,
+    /// if this would create a cycle.
+    pub fn after(&mut self, key: K, dependency: K) -> anyhow::Result<()> {
+        if self.depends_on(&dependency, &key) {
+            bail!(
+                "Pipeline {:?} can't render after {:?}, as that would create a dependency cycle",
+                key,
+                dependency
+            );
+        }
+        let dependencies = self.dependencies.entry(key).or_default();
+        if !dependencies.contains(&dependency) {
+            dependencies.push(dependency);
+        }
+        self.refresh_render_order();
+        Ok(())
+    }
+
+    /// Returns true if `key` renders after `other`, directly or through other dependencies.
+    fn depends_on(&self, key: &K, other: &K) -> bool {
+        if key == other {
+            return true;
+        }
+        let mut stack = vec![key];
+        let mut visited = std::collections::HashSet::new();
+        while let Some(current) = stack.pop() {
+            if !visited.insert(current) {
+                continue;
+            }
+            for dependency in self.dependencies.get(current).into_iter().flatten() {
+                if dependency == other {
+                    return true;
+                }
+                stack.push(dependency);
+            }
+        }
+        false
+    }
+
+    /// Sorts the added pipelines so every pipeline comes after its dependencies.
+    ///
+    /// Pipelines that don't depend on each other keep their insertion order. Dependencies on
+    /// pipelines that haven't been added are ignored.
+    pub fn sorted_render_order(&self) -> anyhow::Result<Vec<K>> {
+        let mut order: Vec<K> = Vec::with_capacity(self.insertion_order.len());
+        let mut remaining = self.insertion_order.clone();
+        while !remaining.is_empty() {
+            let ready = remaining.iter().position(|key| {
+                self.dependencies
+                    .get(key)
+                    .into_iter()
+                    .flatten()
+                    .all(|dependency| !remaining.contains(dependency))
+            });
+            match ready {
+                Some(index) => order.push(remaining.remove(index)),
+                None => bail!("Pipelines {:?} have cyclic dependencies", remaining),
+            }
+        }
+        Ok(order)
+    }
+
+    fn refresh_render_order(&mut self) {
+        if self.manual_order {
+            return;
+        }
+        // `after` refuses to add cycles, so sorting can't fail here.
+        self.render_list = self
+            .sorted_render_order()
+            .expect("pipeline dependencies contain a cycle");
     }
 
     /// Retrieves a mutable reference to a render pipeline by its key.
@@ -57,9 +168,71 @@ impl<K: PipelineKey> RenderController<K> {
         self.pipelines.get(key).map(|p| p.as_ref())
     }
 
-    /// Sets the render order of the pipelines. This must be set, or no pipelines will be rendered.
+    /// Retrieves a pipeline by its key, downcast to its concrete type.
+    /// Returns None if the pipeline does not exist or is of a different type.
+    pub fn get_pipeline_as<P: RenderPipeline<K>>(&self, key: &K) -> Option<&P> {
+        let pipeline: &dyn Any = self.pipelines.get(key)?.as_ref();
+        pipeline.downcast_ref::<P>()
+    }
+
+    /// Retrieves a mutable reference to a pipeline by its key, downcast to its concrete type.
+    /// Returns None if the pipeline does not exist or is of a different type.
+    pub fn get_pipeline_as_mut<P: RenderPipeline<K>>(&mut self, key: &K) -> Option<&mut P> {
+        let pipeline: &mut dyn Any = self.pipelines.get_mut(key)?.as_mut();
+        pipeline.downcast_mut::<P>()
+    }
+
+    /// Starts describing a render pass that draws on top of `target`, keeping its contents.
+    ///
+    /// This is the pass setup every pipeline but the first should use. Add a depth attachment
+    /// with [`PassBuilder::depth`] if the pipeline depth tests.
+    pub fn pass<'a>(&self, target: &'a TextureView) -> PassBuilder<'a> {
+        PassBuilder::new().color_target(target)
+    }
+
+    /// Sets the render order of the pipelines explicitly, replacing the dependency based order.
+    ///
+    /// The first pipeline should clear the target and the rest should load it. Orders that break
+    /// this are still used, but a warning is logged.
     pub fn set_render_order(&mut self, order: Vec<K>) {
         self.render_list = order;
+        self.manual_order = true;
+        self.check_clear_policy();
+    }
+
+    /// Sets how the passes of each frame are recorded.
+    pub fn set_recording_mode(&mut self, mode: RecordingMode) {
+        self.recording_mode = mode;
+    }
+
+    /// Returns how the passes of each frame are recorded.
+    pub fn recording_mode(&self) -> RecordingMode {
+        self.recording_mode
+    }
+
+    /// Returns the order the pipelines are currently rendered in.
+    pub fn render_order(&self) -> &[K] {
+        &self.render_list
+    }
+
+    /// Warns about pipelines in the render order that don't follow the clear policy.
+    fn check_clear_policy(&self) {
+        for (i, key) in self.render_list.iter().enumerate() {
+            let Some(pipeline) = self.get_pipeline(key) else {
+                continue;
+            };
+            match (i, pipeline.clears_target()) {
+                (0, false) => warn!(
+                    "First pipeline {:?} doesn't clear the target; the previous frame will show through",
+                    key
+                ),
+                (1.., true) => warn!(
+                    "Pipeline {:?} clears the target, erasing everything rendered before it",
+                    key
+                ),
+                _ => {}
+            }
+        }
     }
 
     fn handle_update_request(&mut self, source: K, request: UpdateRequest) {
@@ -72,6 +245,7 @@ impl<K: PipelineKey> RenderController<K> {
 
     /// Updates all pipelines managed by the controller.
     pub fn update_pipelines(&mut self) {
+        let _span = profile_span!("update_pipelines");
         let keys = self.pipelines.keys().cloned().collect::<Vec<K>>();
         for pipeline_key in keys {
             let pipeline = self.get_pipeline_mut(&pipeline_key).unwrap();
@@ -81,46 +255,118 @@ impl<K: PipelineKey> RenderController<K> {
         }
     }
 
+    /// Re-reads the shader files of every pipeline and swaps in the rebuilt pipelines.
+    ///
+    /// Pipelines that fail to reload log the error and keep their old pipeline, so a shader typo
+    /// doesn't crash the app. Returns the number of pipelines that failed.
+    #[cfg(feature = "hot-reload")]
+    pub fn reload_shaders(&mut self) -> usize {
+        let _span = profile_span!("reload_shaders");
+        let mut failed = 0;
+        for pipeline_key in &self.insertion_order {
+            let pipeline = self
+                .pipelines
+                .get_mut(pipeline_key)
+                .expect("inserted pipeline is missing");
+            if let Err(e) = pipeline.reload_shaders() {
+                log::error!("Failed to reload shaders of {:?}: {:#}", pipeline_key, e);
+                failed += 1;
+            }
+        }
+        failed
+    }
+
+    /// Records and submits a whole frame.
+    ///
+    /// Every pipeline renders in render order, followed by `overlay` (e.g. the debug text), which
+    /// draws on top of the swapchain texture. The commands are recorded according to the
+    /// `RecordingMode` and submitted once. Returns the surface texture, which the caller should
+    /// present.
+    pub fn render_frame(
+        &self,
+        overlay: impl FnOnce(&mut wgpu::CommandEncoder, &TextureView),
+    ) -> anyhow::Result<wgpu::SurfaceTexture> {
+        let _span = profile_span!("render_frame");
+        let (surface, view) = self
+            .wgpu
+            .get()
+            .current_view()
+            .with_context(|| "Failed to get swapchain texture")?;
+        self.submit_frame(&view, overlay)?;
+        Ok(surface)
+    }
+
+    /// Records every pipeline and `overlay` into `target` and submits them once, like
+    /// `render_frame` does for the swapchain texture.
+    pub fn submit_frame(
+        &self,
+        target: &TextureView,
+        overlay: impl FnOnce(&mut wgpu::CommandEncoder, &TextureView),
+    ) -> anyhow::Result<()> {
+        let wgpu = self.wgpu.get();
+        if self.recording_mode == RecordingMode::Shared {
+            let mut encoder = wgpu.create_encoder(Some("Frame Encoder"));
+            for pipeline_key in &self.render_list {
+                self.render_pipeline(pipeline_key, &mut encoder, target)?;
+            }
+            overlay(&mut encoder, target);
+            wgpu.submit_single(encoder.finish());
+            return Ok(());
+        }
+
+        let mut buffers = Vec::with_capacity(self.render_list.len() + 1);
+        for pipeline_key in &self.render_list {
+            let label = format!("{:?} Encoder", pipeline_key);
+            let mut encoder = wgpu.create_encoder(Some(&label));
+            self.render_pipeline(pipeline_key, &mut encoder, target)?;
+            buffers.push(encoder.finish());
+        }
+        let mut encoder = wgpu.create_encoder(Some("Overlay Encoder"));
+        overlay(&mut encoder, target);
+        buffers.push(encoder.finish());
+        wgpu.submit(buffers);
+        Ok(())
+    }
+
     /// Renders all pipelines in the order specified by `set_render_order`.
+    ///
+    /// This only records the passes into `encoder`; submitting it is up to the caller. Prefer
+    /// `render_frame`, which does both.
     pub fn render_pipelines(
         &self,
         encoder: &mut wgpu::CommandEncoder,
     ) -> anyhow::Result<(wgpu::SurfaceTexture, TextureView)> {
+        let _span = profile_span!("render_pipelines");
         let wgpu = self.wgpu.get();
         let (surf, swapchain_texture) = wgpu
             .current_view()
             .with_context(|| "Failed to get swapchain texture")?;
 
-        if let Some((ref key, ref target)) = self.render_suface {
-            self.render_with_target(encoder, &swapchain_texture, key, target)?;
-            return Ok((surf, swapchain_texture));
-        }
-
         for pipeline_key in &self.render_list {
-            let pipeline = self
-                .get_pipeline(pipeline_key)
-                .with_context(|| format!("Pipeline {:?} not found in controller", pipeline_key))?;
-            pipeline.render(self, encoder, &swapchain_texture);
+            self.render_pipeline(pipeline_key, encoder, &swapchain_texture)?;
         }
 
         Ok((surf, swapchain_texture))
     }
 
-    fn render_with_target(
+    /// Records the passes of one pipeline into `encoder`.
+    ///
+    /// If a pipeline redirected rendering with `UpdateRequest::SetRenderTarget`, every other
+    /// pipeline renders into that target instead, and the requesting pipeline renders into
+    /// `output`.
+    fn render_pipeline(
         &self,
+        pipeline_key: &K,
         encoder: &mut wgpu::CommandEncoder,
         output: &wgpu::TextureView,
-        key: &K,
-        target: &wgpu::TextureView,
     ) -> anyhow::Result<()> {
-        for pipeline_key in &self.render_list {
-            let pipeline = self
-                .get_pipeline(pipeline_key)
-                .with_context(|| format!("Pipeline {:?} not found in controller", pipeline_key))?;
-            if pipeline_key == key {
-                pipeline.render(self, encoder, output);
-            }
-            pipeline.render(self, encoder, target);
+        let pipeline = self
+            .get_pipeline(pipeline_key)
+            .with_context(|| format!("Pipeline {:?} not found in controller", pipeline_key))?;
+        match &self.render_suface {
+            Some((key, _)) if pipeline_key == key => pipeline.render(self, encoder, output),
+            Some((_, target)) => pipeline.render(self, encoder, target),
+            None => pipeline.render(self, encoder, output),
         }
         Ok(())
     }
@@ -140,3 +386,125 @@ impl<K: PipelineKey> Debug for RenderController<K> {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum Key {
+        Clear,
+        Solid,
+        Water,
+        Ui,
+    }
+
+    impl PipelineKey for Key {}
+
+    struct Empty;
+
+    impl RenderPipeline<Key> for Empty {
+        fn label(&self) -> Option<&str> {
+            None
+        }
+
+        fn update(&mut self) -> Option<UpdateRequest> {
+            None
+        }
+
+        fn render(
+            &self,
+            _controller: &RenderController<Key>,
+            _encoder: &mut wgpu::CommandEncoder,
+            _target: &TextureView,
+        ) {
+        }
+    }
+
+    fn controller(keys: &[Key]) -> RenderController<Key> {
+        let state = ComponentStore::new();
+        let mut controller = RenderController::new(&state);
+        for key in keys {
+            controller.add_pipeline(*key, Empty);
+        }
+        controller
+    }
+
+    #[test]
+    fn insertion_order_without_dependencies() {
+        let controller = controller(&[Key::Ui, Key::Clear, Key::Solid]);
+        assert_eq!(
+            controller.render_order(),
+            &[Key::Ui, Key::Clear, Key::Solid]
+        );
+    }
+
+    #[test]
+    fn dependency_chain_is_sorted() {
+        let mut controller = controller(&[Key::Ui, Key::Water, Key::Solid, Key::Clear]);
+        controller.after(Key::Ui, Key::Water).unwrap();
+        controller.after(Key::Water, Key::Solid).unwrap();
+        controller.after(Key::Solid, Key::Clear).unwrap();
+        assert_eq!(
+            controller.render_order(),
+            &[Key::Clear, Key::Solid, Key::Water, Key::Ui]
+        );
+        assert_eq!(
+            controller.sorted_render_order().unwrap(),
+            controller.render_order()
+        );
+    }
+
+    #[test]
+    fn dependencies_on_pipelines_added_later() {
+        let mut controller = controller(&[Key::Solid]);
+        controller.after(Key::Solid, Key::Clear).unwrap();
+        assert_eq!(controller.render_order(), &[Key::Solid]);
+        controller.add_pipeline(Key::Clear, Empty);
+        assert_eq!(controller.render_order(), &[Key::Clear, Key::Solid]);
+    }
+
+    #[test]
+    fn one_submit_per_frame() {
+        let mut state = ComponentStore::new();
+        WgpuRenderer::attach_noop(&mut state, (64, 64));
+        state.finish_initialization();
+        let mut controller = RenderController::new(&state);
+        for key in [Key::Clear, Key::Solid, Key::Water, Key::Ui] {
+            controller.add_pipeline(key, Empty);
+        }
+
+        let wgpu = state.get::<WgpuRenderer>();
+        let target = wgpu.texture_uninit(
+            Some("Frame Test Target"),
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+            (64, 64),
+            1,
+        );
+        for mode in [RecordingMode::Shared, RecordingMode::PerPipeline] {
+            controller.set_recording_mode(mode);
+            let before = wgpu.submission_count();
+            let mut overlay_recorded = false;
+            controller
+                .submit_frame(&target.view, |_, _| overlay_recorded = true)
+                .unwrap();
+            assert!(overlay_recorded);
+            assert_eq!(wgpu.submission_count(), before + 1, "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn cycles_are_rejected() {
+        let mut controller = controller(&[Key::Clear, Key::Solid, Key::Water]);
+        controller.after(Key::Solid, Key::Clear).unwrap();
+        controller.after(Key::Water, Key::Solid).unwrap();
+        assert!(controller.after(Key::Clear, Key::Water).is_err());
+        assert!(controller.after(Key::Clear, Key::Clear).is_err());
+        // The failed calls left the order alone.
+        assert_eq!(
+            controller.render_order(),
+            &[Key::Clear, Key::Solid, Key::Water]
+        );
+    }
+}
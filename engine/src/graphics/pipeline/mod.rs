@@ -1,14 +1,50 @@
+//! Render pipelines and the order they run in.
+//!
+//! # Clear policy
+//!
+//! Each frame, the [`RenderController`] renders every pipeline in the render order into the same
+//! target. The first pipeline in the order is the only one that clears the target, normally a
+//! [`ClearPipeline`](pipelines::clear::ClearPipeline). Every other pipeline begins its pass with
+//! `LoadOp::Load` so it draws on top of what earlier pipelines left behind.
+//!
+//! Pipelines that clear report it through [`RenderPipeline::clears_target`], and the controller
+//! warns when the render order breaks the policy. Pipelines rendering into their own offscreen
+//! targets are free to clear those however they like.
+//!
+//! Passes are best set up with [`RenderController::pass`], which loads the target as described
+//! above. See the [`pass`] module for an example pipeline.
+
+use std::any::Any;
+
 use crate::graphics::pipeline::controller::{PipelineKey, RenderController};
 
 pub mod controller;
+pub mod pass;
 pub mod pipelines;
 
 /// A trait representing a render pipeline.
-pub trait RenderPipeline<K: PipelineKey> {
+///
+/// Pipelines are `Any` so that callers can get back the concrete type through
+/// [`RenderController::get_pipeline_as`].
+pub trait RenderPipeline<K: PipelineKey>: Any {
     /// Returns the name of the pipeline.
     fn label(&self) -> Option<&str>;
+    /// Returns true if the pipeline clears its target rather than loading it.
+    ///
+    /// Only the first pipeline in the render order may clear; see the module docs.
+    fn clears_target(&self) -> bool {
+        false
+    }
     /// Updates the pipeline state.
     fn update(&mut self) -> Option<UpdateRequest>;
+    /// Rebuilds the pipeline from its shader files on disk, for editing shaders while the app is
+    /// running.
+    ///
+    /// If anything fails, the error is returned and the pipeline must keep rendering with what it
+    /// had. Pipelines without shader files on disk don't need to implement this.
+    fn reload_shaders(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
     /// Renders using the pipeline.
     fn render(
         &self,
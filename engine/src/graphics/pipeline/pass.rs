@@ -0,0 +1,301 @@
+//! Render pass setup for pipelines.
+//!
+//! [`RenderController::pass`](crate::graphics::pipeline::controller::RenderController::pass)
+//! returns a [`PassBuilder`] that already follows the clear policy, so most pipelines only have to
+//! add a label and, if they depth test, a depth attachment.
+//!
+//! A custom pipeline drawing a single triangle on top of the frame:
+//!
+//! ```no_run
+//! use engine::{
+//!     component::{ComponentHandle, ComponentStore},
+//!     graphics::{
+//!         lowlevel::{WgpuRenderer, pipeline::WgpuPipeline},
+//!         pipeline::{
+//!             RenderPipeline, UpdateRequest,
+//!             controller::{PipelineKey, RenderController},
+//!         },
+//!     },
+//! };
+//!
+//! const SHADER: &str = r#"
+//! @vertex
+//! fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+//!     let x = f32(i32(index) - 1) * 0.5;
+//!     let y = f32(i32(index & 1u) * 2 - 1) * 0.5;
+//!     return vec4<f32>(x, y, 0.0, 1.0);
+//! }
+//!
+//! @fragment
+//! fn fs_main() -> @location(0) vec4<f32> {
+//!     return vec4<f32>(1.0, 0.5, 0.0, 1.0);
+//! }
+//! "#;
+//!
+//! struct TrianglePipeline {
+//!     pipeline: WgpuPipeline,
+//! }
+//!
+//! impl TrianglePipeline {
+//!     fn new(state: &ComponentStore) -> Self {
+//!         let wgpu = state.get::<WgpuRenderer>();
+//!         let pipeline = wgpu
+//!             .pipeline_builder("Triangle Pipeline")
+//!             .shader("Triangle Shader", SHADER, Some("vs_main"), Some("fs_main"))
+//!             .add_color_target(wgpu.config.get().format)
+//!             .build(None);
+//!         Self { pipeline }
+//!     }
+//! }
+//!
+//! impl<K: PipelineKey> RenderPipeline<K> for TrianglePipeline {
+//!     fn label(&self) -> Option<&str> {
+//!         Some("Triangle Pipeline")
+//!     }
+//!
+//!     fn update(&mut self) -> Option<UpdateRequest> {
+//!         None
+//!     }
+//!
+//!     fn render(
+//!         &self,
+//!         controller: &RenderController<K>,
+//!         encoder: &mut wgpu::CommandEncoder,
+//!         target: &wgpu::TextureView,
+//!     ) {
+//!         let mut render_pass = controller
+//!             .pass(target)
+//!             .label("Triangle Pipeline Render Pass")
+//!             .begin(encoder);
+//!         render_pass.set_pipeline(&self.pipeline.pipeline);
+//!         render_pass.draw(0..3, 0..1);
+//!     }
+//! }
+//! ```
+
+use wgpu::{Color, LoadOp, Operations, StoreOp, TextureView};
+
+/// Describes a render pass: its color targets, their load and store ops, and an optional depth
+/// attachment.
+///
+/// Color targets default to loading their contents and storing the result, which is what every
+/// pipeline except the first in the render order should do.
+#[derive(Debug, Default)]
+pub struct PassBuilder<'a> {
+    label: Option<&'a str>,
+    color_attachments: Vec<Option<wgpu::RenderPassColorAttachment<'a>>>,
+    depth_stencil_attachment: Option<wgpu::RenderPassDepthStencilAttachment<'a>>,
+}
+
+impl<'a> PassBuilder<'a> {
+    /// Creates a pass with no color targets or depth attachment.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the label of the pass, shown in graphics debuggers and validation errors.
+    pub fn label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Adds a color target that keeps its existing contents.
+    pub fn color_target(self, view: &'a TextureView) -> Self {
+        self.color_target_with_ops(
+            view,
+            Operations {
+                load: LoadOp::Load,
+                store: StoreOp::Store,
+            },
+        )
+    }
+
+    /// Adds a color target that is cleared to `color` first.
+    pub fn cleared_color_target(self, view: &'a TextureView, color: Color) -> Self {
+        self.color_target_with_ops(
+            view,
+            Operations {
+                load: LoadOp::Clear(color),
+                store: StoreOp::Store,
+            },
+        )
+    }
+
+    /// Adds a color target with explicit load and store ops.
+    pub fn color_target_with_ops(mut self, view: &'a TextureView, ops: Operations<Color>) -> Self {
+        self.color_attachments
+            .push(Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops,
+                depth_slice: None,
+            }));
+        self
+    }
+
+    /// Sets the load op of every color target added so far.
+    pub fn load_op(mut self, load: LoadOp<Color>) -> Self {
+        for attachment in self.color_attachments.iter_mut().flatten() {
+            attachment.ops.load = load;
+        }
+        self
+    }
+
+    /// Sets the depth attachment, e.g. from
+    /// [`DepthTexture::attachment`](crate::graphics::lowlevel::depth::DepthTexture::attachment).
+    pub fn depth(mut self, attachment: wgpu::RenderPassDepthStencilAttachment<'a>) -> Self {
+        self.depth_stencil_attachment = Some(attachment);
+        self
+    }
+
+    /// Begins the pass on `encoder`.
+    pub fn begin<'e>(self, encoder: &'e mut wgpu::CommandEncoder) -> wgpu::RenderPass<'e> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: self.label,
+            color_attachments: &self.color_attachments,
+            depth_stencil_attachment: self.depth_stencil_attachment,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        component::ComponentStore,
+        graphics::{
+            lowlevel::{WgpuRenderer, pipeline::WgpuPipeline},
+            pipeline::{
+                RenderPipeline, UpdateRequest,
+                controller::{PipelineKey, RenderController},
+            },
+        },
+    };
+
+    const SHADER: &str = r#"
+        @vertex
+        fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+            let x = f32(i32(index) - 1) * 0.5;
+            let y = f32(i32(index & 1u) * 2 - 1) * 0.5;
+            return vec4<f32>(x, y, 0.0, 1.0);
+        }
+
+        @fragment
+        fn fs_main() -> @location(0) vec4<f32> {
+            return vec4<f32>(1.0, 0.5, 0.0, 1.0);
+        }
+    "#;
+
+    /// The pipeline from the module docs.
+    struct TrianglePipeline {
+        pipeline: WgpuPipeline,
+    }
+
+    impl TrianglePipeline {
+        fn new(state: &ComponentStore) -> Self {
+            let wgpu = state.get::<WgpuRenderer>();
+            let pipeline = wgpu
+                .pipeline_builder("Triangle Pipeline")
+                .shader("Triangle Shader", SHADER, Some("vs_main"), Some("fs_main"))
+                .add_color_target(wgpu.config.get().format)
+                .build(None);
+            Self { pipeline }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    struct Triangle;
+
+    impl PipelineKey for Triangle {}
+
+    impl<K: PipelineKey> RenderPipeline<K> for TrianglePipeline {
+        fn label(&self) -> Option<&str> {
+            Some("Triangle Pipeline")
+        }
+
+        fn update(&mut self) -> Option<UpdateRequest> {
+            None
+        }
+
+        fn render(
+            &self,
+            controller: &RenderController<K>,
+            encoder: &mut wgpu::CommandEncoder,
+            target: &wgpu::TextureView,
+        ) {
+            let mut render_pass = controller
+                .pass(target)
+                .label("Triangle Pipeline Render Pass")
+                .begin(encoder);
+            render_pass.set_pipeline(&self.pipeline.pipeline);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+
+    #[test]
+    fn custom_pipeline_draws_a_triangle() {
+        let mut state = ComponentStore::new();
+        WgpuRenderer::attach_noop(&mut state, (16, 16));
+        state.finish_initialization();
+        let mut controller = RenderController::new(&state);
+        controller.add_pipeline(Triangle, TrianglePipeline::new(&state));
+
+        let wgpu = state.get::<WgpuRenderer>();
+        let target = wgpu.texture_uninit(
+            Some("Triangle Target"),
+            wgpu.config.get().format,
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+            (16, 16),
+            1,
+        );
+        let mut encoder = wgpu.create_encoder(Some("Triangle Encoder"));
+        controller
+            .get_pipeline(&Triangle)
+            .unwrap()
+            .render(&controller, &mut encoder, &target.view);
+        wgpu.submit_single(encoder.finish());
+    }
+
+    #[test]
+    fn targets_load_unless_cleared() {
+        let mut state = ComponentStore::new();
+        WgpuRenderer::attach_noop(&mut state, (16, 16));
+        state.finish_initialization();
+        let wgpu = state.get::<WgpuRenderer>();
+        let target = wgpu.texture_uninit(
+            None,
+            wgpu.config.get().format,
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+            (16, 16),
+            1,
+        );
+
+        let ops = |pass: PassBuilder| {
+            pass.color_attachments
+                .iter()
+                .map(|attachment| attachment.as_ref().unwrap().ops)
+                .collect::<Vec<_>>()
+        };
+        let load = Operations {
+            load: LoadOp::Load,
+            store: StoreOp::Store,
+        };
+        let clear = Operations {
+            load: LoadOp::Clear(Color::BLACK),
+            store: StoreOp::Store,
+        };
+        assert_eq!(ops(PassBuilder::new().color_target(&target.view)), [load]);
+        assert_eq!(
+            ops(PassBuilder::new().cleared_color_target(&target.view, Color::BLACK)),
+            [clear]
+        );
+        assert_eq!(
+            ops(PassBuilder::new()
+                .color_target(&target.view)
+                .color_target(&target.view)
+                .load_op(LoadOp::Clear(Color::BLACK))),
+            [clear, clear]
+        );
+    }
+}
@@ -1,13 +1,45 @@
 use wgpu::{Color, LoadOp};
 
-use crate::graphics::pipeline::{RenderPipeline, controller::PipelineKey};
+use crate::{
+    component::ComponentHandle,
+    graphics::{
+        lowlevel::depth::DepthTexture,
+        pipeline::{RenderPipeline, controller::PipelineKey},
+    },
+};
 
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
-pub struct ClearPipeline(Color);
+/// Clears the render target to a solid color, and optionally resets the depth texture.
+///
+/// This is meant to be the first pipeline in the render order, so every later pipeline can load
+/// the target instead of clearing it.
+#[derive(Debug, Clone, Default)]
+pub struct ClearPipeline {
+    color: Color,
+    depth: Option<ComponentHandle<DepthTexture>>,
+}
 
 impl ClearPipeline {
     pub fn new(r: f64, g: f64, b: f64, a: f64) -> Self {
-        Self(Color { r, g, b, a })
+        Self {
+            color: Color { r, g, b, a },
+            depth: None,
+        }
+    }
+
+    /// Also clears the given depth texture every frame.
+    pub fn with_depth(mut self, depth: ComponentHandle<DepthTexture>) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    /// Returns the color the target is cleared to.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Sets the color the target is cleared to.
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
     }
 }
 
@@ -16,6 +48,10 @@ impl<K: PipelineKey> RenderPipeline<K> for ClearPipeline {
         Some("Clear Pipeline")
     }
 
+    fn clears_target(&self) -> bool {
+        true
+    }
+
     fn update(&mut self) -> Option<crate::graphics::pipeline::UpdateRequest> {
         None
     }
@@ -27,12 +63,106 @@ impl<K: PipelineKey> RenderPipeline<K> for ClearPipeline {
         target: &wgpu::TextureView,
     ) {
         let wgpu = controller.wgpu.get();
+        let depth = self.depth.as_ref().map(|depth| depth.get());
         let _render_pass_desc = wgpu.render_pass(
             Some("Clear Pipeline Render Pass"),
             encoder,
             target,
-            None,
-            LoadOp::Clear(self.0),
+            depth.as_ref().map(|depth| depth.clear_attachment()),
+            LoadOp::Clear(self.color),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        component::ComponentStore,
+        graphics::{lowlevel::WgpuRenderer, pipeline::controller::RenderController},
+    };
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    enum Key {
+        Clear,
+        Scene,
+    }
+
+    impl PipelineKey for Key {}
+
+    /// A pipeline that loads the target and draws nothing.
+    struct Scene;
+
+    impl RenderPipeline<Key> for Scene {
+        fn label(&self) -> Option<&str> {
+            Some("Scene")
+        }
+
+        fn update(&mut self) -> Option<crate::graphics::pipeline::UpdateRequest> {
+            None
+        }
+
+        fn render(
+            &self,
+            controller: &RenderController<Key>,
+            encoder: &mut wgpu::CommandEncoder,
+            target: &wgpu::TextureView,
+        ) {
+            let _pass = controller.pass(target).begin(encoder);
+        }
+    }
+
+    #[test]
+    fn clear_renders_first_and_clears() {
+        let mut state = ComponentStore::new();
+        WgpuRenderer::attach_noop(&mut state, (16, 16));
+        state.finish_initialization();
+
+        let mut controller = RenderController::new(&state);
+        // Added out of order; the dependency moves the clear to the front.
+        controller.add_pipeline(Key::Scene, Scene);
+        controller.add_pipeline(Key::Clear, ClearPipeline::new(0.1, 0.2, 0.3, 1.0));
+        controller.after(Key::Scene, Key::Clear).unwrap();
+        assert_eq!(controller.render_order(), &[Key::Clear, Key::Scene]);
+
+        let clears = controller
+            .render_order()
+            .iter()
+            .map(|key| controller.get_pipeline(key).unwrap().clears_target())
+            .collect::<Vec<_>>();
+        assert_eq!(clears, vec![true, false]);
+
+        let clear = controller
+            .get_pipeline_as::<ClearPipeline>(&Key::Clear)
+            .unwrap();
+        assert_eq!(
+            clear.color(),
+            Color {
+                r: 0.1,
+                g: 0.2,
+                b: 0.3,
+                a: 1.0
+            }
+        );
+
+        // Record the frame into an offscreen target, as there's no surface to draw to.
+        let wgpu = state.get::<WgpuRenderer>();
+        let target = wgpu.texture_uninit(
+            Some("Clear Test Target"),
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+            (16, 16),
+            1,
         );
+        let mut encoder = wgpu.create_encoder(Some("Clear Test Encoder"));
+        for key in controller.render_order() {
+            controller
+                .get_pipeline(key)
+                .unwrap()
+                .render(&controller, &mut encoder, &target.view);
+        }
+        let submissions = wgpu.submission_count();
+        wgpu.submit_single(encoder.finish());
+        assert_eq!(wgpu.submission_count(), submissions + 1);
     }
 }
@@ -1,9 +1,13 @@
 use std::collections::HashMap;
 
-use wgpu::{TextureFormat, TextureUsages};
+use log::{debug, info};
+use wgpu::{
+    AddressMode, FilterMode, MipmapFilterMode, SamplerDescriptor, TextureFormat, TextureUsages,
+};
 
 use crate::{
     ReadOnly, ReadOnlyString,
+    assets::AssetStore,
     component::{ComponentHandle, ComponentStore, ComponentStoreHandle},
     graphics::{
         image::Image,
@@ -15,12 +19,20 @@ use crate::{
 /// This is effectively a texture atlas, packing multiple textures into a single GPU texture. The difference
 /// is that this uses texture arrays instead of a single large texture, which greatly simplifies everything. The only limitation
 /// is that all textures must have the same dimensions.
+///
+/// The tile size is chosen when the collection is created, e.g. 32x32 for HD texture packs. Textures
+/// of any other size are scaled to it when added. Texture coordinates span the whole tile
+/// regardless of its size, so meshes don't depend on it.
 pub struct TextureCollection {
     textures: HashMap<String, TextureHandle>,
+    animations: Vec<Animation>,
     buf: Vec<ReadOnly<u8>>,
     gpu_texture: Option<Texture>,
     label: Option<ReadOnlyString>,
     dimensions: (u32, u32),
+    format: TextureFormat,
+    sampler: SamplerDescriptor<'static>,
+    revision: u64,
     handle: ComponentHandle<WgpuRenderer>,
 }
 
@@ -30,25 +42,233 @@ impl TextureCollection {
         label: Option<impl Into<ReadOnlyString>>,
         dimensions: (u32, u32),
     ) -> Self {
+        assert!(
+            dimensions.0 > 0 && dimensions.1 > 0,
+            "Invalid texture collection tile size {}x{}",
+            dimensions.0,
+            dimensions.1
+        );
         Self {
             textures: HashMap::new(),
+            animations: Vec::new(),
             buf: Vec::new(),
             gpu_texture: None,
             label: label.map(|l| l.into()),
             handle: state.handle_for::<WgpuRenderer>(),
             dimensions,
+            format: TextureFormat::Rgba8Unorm,
+            sampler: Self::nearest_sampler(AddressMode::ClampToEdge),
+            revision: 0,
         }
     }
 
-    /// Adds a new texture from raw RGBA8 data.
+    /// Returns the size of every texture in the collection, as (width, height).
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+
+    /// Returns the number of texture layers in the collection.
+    pub fn layer_count(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Returns a counter that changes whenever the GPU texture has to be recreated.
+    ///
+    /// Anything holding a bind group for `gpu_texture` should rebuild it when this changes.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Drops the GPU texture so it is recreated with the current contents.
+    fn invalidate(&mut self) {
+        self.gpu_texture = None;
+        self.revision += 1;
+    }
+
+    /// Replaces the contents of this collection with another collection, e.g. after reloading
+    /// textures from disk.
+    ///
+    /// The label is kept. Texture handles from `other` are valid afterwards, so users that
+    /// baked layer indices from the old contents need to refresh them if the layout changed.
+    pub fn replace_with(&mut self, other: TextureCollection) {
+        self.textures = other.textures;
+        self.animations = other.animations;
+        self.buf = other.buf;
+        self.dimensions = other.dimensions;
+        self.format = other.format;
+        self.sampler = other.sampler;
+        self.invalidate();
+    }
+
+    /// Sets the texture format of this collection's GPU texture.
+    pub fn with_format(mut self, format: TextureFormat) -> Self {
+        self.set_format(format);
+        self
+    }
+
+    /// Sets the texture format of this collection's GPU texture.
+    ///
+    /// Textures are always added as RGBA8 and converted when the GPU texture is created. The
+    /// supported formats are `Rgba8Unorm`, `Rgba8UnormSrgb` and `R8Unorm`, which keeps only
+    /// the red channel, e.g. for masks.
+    ///
+    /// # Panics
+    /// Panics if the format isn't supported.
+    pub fn set_format(&mut self, format: TextureFormat) {
+        assert!(
+            matches!(
+                format,
+                TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb | TextureFormat::R8Unorm
+            ),
+            "Unsupported texture collection format {format:?}"
+        );
+        self.format = format;
+        self.invalidate();
+    }
+
+    /// Returns the texture format of this collection's GPU texture.
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Converts RGBA8 pixels into the texel layout of the collection's format.
+    fn convert_texels(&self, rgba: &ReadOnly<u8>) -> ReadOnly<u8> {
+        match self.format {
+            TextureFormat::R8Unorm => rgba.iter().step_by(4).copied().collect(),
+            _ => rgba.clone(),
+        }
+    }
+
+    /// Sets the sampler used for this collection's GPU texture.
+    pub fn with_sampler(mut self, sampler: SamplerDescriptor<'static>) -> Self {
+        self.set_sampler(sampler);
+        self
+    }
+
+    /// Sets the sampler used for this collection's GPU texture.
+    ///
+    /// If the GPU texture was already created, it will be recreated on the next call to `gpu_texture`.
+    /// The same goes for adding textures.
+    /// Note that wgpu requires all filter modes to be linear when `anisotropy_clamp` is above 1.
+    pub fn set_sampler(&mut self, sampler: SamplerDescriptor<'static>) {
+        self.sampler = sampler;
+        self.invalidate();
+    }
+
+    /// Sets the range of mip levels the sampler may pick from.
+    ///
+    /// Raising `min` keeps distant blocks blurrier (less shimmer), lowering `max` keeps them
+    /// sharper. wgpu samplers have no LOD bias, so a bias has to be applied in the shader with
+    /// `textureSampleBias`.
+    pub fn with_lod_clamp(mut self, min: f32, max: f32) -> Self {
+        self.set_lod_clamp(min, max);
+        self
+    }
+
+    /// Sets the range of mip levels the sampler may pick from. See `with_lod_clamp`.
+    ///
+    /// Panics unless `0 <= min <= max`.
+    pub fn set_lod_clamp(&mut self, min: f32, max: f32) {
+        assert!(
+            0.0 <= min && min <= max,
+            "Invalid LOD clamp: min {min}, max {max}"
+        );
+        self.sampler.lod_min_clamp = min;
+        self.sampler.lod_max_clamp = max;
+        self.invalidate();
+    }
+
+    /// Returns the minimum and maximum LOD the sampler clamps to.
+    pub fn lod_clamp(&self) -> (f32, f32) {
+        (self.sampler.lod_min_clamp, self.sampler.lod_max_clamp)
+    }
+
+    /// Switches the sampler to the given quality preset, keeping the address mode and LOD clamp.
+    ///
+    /// Only the sampler is recreated; the texture isn't uploaded again. Users of `gpu_texture`
+    /// still need to rebuild their bind groups, which `revision` tells them.
+    pub fn set_texture_quality(&mut self, quality: TextureQuality) {
+        let (lod_min_clamp, lod_max_clamp) = self.lod_clamp();
+        self.sampler = SamplerDescriptor {
+            lod_min_clamp,
+            lod_max_clamp,
+            ..quality.sampler(self.sampler.address_mode_u)
+        };
+        if let Some(texture) = self.gpu_texture.as_mut() {
+            texture.sampler = self.handle.get().create_sampler(&SamplerDescriptor {
+                label: self.label.as_deref(),
+                ..self.sampler.clone()
+            });
+        }
+        self.revision += 1;
+        info!("Texture quality set to {}", quality);
+    }
+
+    /// Returns the quality preset matching the current sampler.
+    pub fn texture_quality(&self) -> TextureQuality {
+        TextureQuality::of(&self.sampler)
+    }
+
+    /// Returns the sampler settings used for this collection.
+    pub fn sampler_descriptor(&self) -> &SamplerDescriptor<'static> {
+        &self.sampler
+    }
+
+    /// A sampler with nearest filtering, which keeps pixel art crisp. This is the default.
+    pub fn nearest_sampler(address_mode: AddressMode) -> SamplerDescriptor<'static> {
+        SamplerDescriptor {
+            label: None,
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: MipmapFilterMode::Nearest,
+            ..Default::default()
+        }
+    }
+
+    /// A sampler with linear filtering and the given anisotropy clamp (1 disables anisotropic filtering).
+    pub fn linear_sampler(
+        address_mode: AddressMode,
+        anisotropy: u16,
+    ) -> SamplerDescriptor<'static> {
+        SamplerDescriptor {
+            label: None,
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: MipmapFilterMode::Linear,
+            anisotropy_clamp: anisotropy.max(1),
+            ..Default::default()
+        }
+    }
+
+    /// Gets the pixels of `image` scaled to the tile size of the collection.
+    fn tile_pixels(&self, image: &Image) -> ReadOnly<u8> {
+        if image.dimensions() == self.dimensions {
+            return image.pixel_bytes().clone();
+        }
+        debug!(
+            "Scaling {:?} texture to the {:?} tile size",
+            image.dimensions(),
+            self.dimensions
+        );
+        image.resized(self.dimensions).pixel_bytes().clone()
+    }
+
+    /// Adds a new texture from raw RGBA8 data, scaling it to the tile size if needed.
     pub fn add_texture(&mut self, name: &str, data: &Image) -> TextureHandle {
         let handle = TextureHandle::single(self.buf.len() as u32);
-        self.buf.push(data.pixel_bytes().clone());
+        self.buf.push(self.tile_pixels(data));
         self.textures.insert(name.to_string(), handle);
+        self.invalidate();
         handle
     }
 
-    /// Adds multiple textures from an iterator of (name, data) pairs.
+    /// Adds multiple textures under one name, scaling them to the tile size if needed.
     pub fn add_textures<'a>(
         &mut self,
         name: &str,
@@ -57,57 +277,279 @@ impl TextureCollection {
         let base = self.buf.len() as u32;
         let mut count = 0;
         for texture in textures {
-            self.buf.push(texture.pixel_bytes().clone());
+            self.buf.push(self.tile_pixels(texture));
             count += 1;
         }
         let handle = TextureHandle::new(base, count);
         self.textures.insert(name.to_string(), handle);
+        self.invalidate();
         handle
     }
 
-    pub fn push_invalid_texture(&mut self) -> TextureHandle {
-        let mut data = vec![0u8; (self.dimensions.0 * self.dimensions.1 * 4) as usize];
-        for (i, px) in data.chunks_mut(self.dimensions.0 as usize * 4).enumerate() {
-            let is_bottom_half = i >= (self.dimensions.1 as usize / 2);
-            for j in 0..(self.dimensions.0 as usize) {
-                let offset = j * 4;
-                let is_right_half = j >= (self.dimensions.0 as usize / 2);
-                if !(is_bottom_half ^ is_right_half) {
-                    px[offset + 3] = 255;
-                    continue;
+    /// Adds every image in `assets` whose name starts with `prefix`, in name order, each under its
+    /// own name. Returns the names and handles of the added textures.
+    ///
+    /// Unlike `add_texture`, images aren't scaled: if any of them doesn't match the tile size,
+    /// nothing is added and the error lists every mismatched image and the file it came from.
+    pub fn add_textures_from_store(
+        &mut self,
+        assets: &AssetStore,
+        prefix: &str,
+    ) -> anyhow::Result<Vec<(String, TextureHandle)>> {
+        let images = assets.images_with_prefix(prefix);
+        let mismatched = images
+            .iter()
+            .filter(|(_, image)| image.dimensions() != self.dimensions)
+            .map(|(name, image)| {
+                let (width, height) = image.dimensions();
+                match assets.image_source(name) {
+                    Some(path) => format!("{} ({}x{}, {})", name, width, height, path.display()),
+                    None => format!("{} ({}x{})", name, width, height),
                 }
-                px[offset] = 255;
-                px[offset + 1] = 0;
-                px[offset + 2] = 255;
-                px[offset + 3] = 255;
-            }
+            })
+            .collect::<Vec<_>>();
+        if !mismatched.is_empty() {
+            anyhow::bail!(
+                "Textures for {} must be {}x{}, but these aren't: {}",
+                self.label.as_deref().unwrap_or("<unnamed>"),
+                self.dimensions.0,
+                self.dimensions.1,
+                mismatched.join(", ")
+            );
         }
 
-        self.buf.push(data.into());
+        Ok(images
+            .into_iter()
+            .map(|(name, image)| (name.to_string(), self.add_texture(name, &image)))
+            .collect())
+    }
+
+    /// Adds an animated texture whose frames are shown in order, each for `frame_time_ms`
+    /// milliseconds, looping forever. Each frame is stored as its own layer.
+    ///
+    /// The returned handle covers every frame. Use `current_layer` to get the frame to draw, which
+    /// `advance` moves along.
+    ///
+    /// # Panics
+    /// Panics if there are no frames or `frame_time_ms` is 0.
+    pub fn add_animated_texture(
+        &mut self,
+        name: &str,
+        frames: &[Image],
+        frame_time_ms: u32,
+    ) -> TextureHandle {
+        assert!(!frames.is_empty(), "Animated texture {name} has no frames");
+        assert!(
+            frame_time_ms > 0,
+            "Animated texture {name} has a frame time of 0"
+        );
+        let handle = self.add_textures(name, frames);
+        self.animations.push(Animation {
+            handle,
+            frame_time_ms,
+            elapsed_ms: 0.0,
+            frame: 0,
+        });
+        handle
+    }
+
+    /// Advances every animated texture by `delta_time` seconds. Returns true if any of them moved
+    /// on to another frame.
+    pub fn advance(&mut self, delta_time: f32) -> bool {
+        let mut changed = false;
+        for animation in &mut self.animations {
+            changed |= animation.advance(delta_time);
+        }
+        changed
+    }
+
+    /// Returns the layer to draw for the texture, which is the current frame for animated
+    /// textures and the first layer otherwise.
+    pub fn current_layer(&self, handle: TextureHandle) -> u32 {
+        self.animation(handle.base_layer)
+            .map_or(handle.base_layer, Animation::current_layer)
+    }
+
+    /// Returns the index of the current frame of an animated texture, or `None` if the texture
+    /// isn't animated.
+    pub fn current_frame(&self, handle: TextureHandle) -> Option<u32> {
+        self.animation(handle.base_layer)
+            .map(|animation| animation.frame)
+    }
+
+    /// Returns the first layer and current frame layer of every animated texture.
+    pub fn animated_layers(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.animations
+            .iter()
+            .map(|animation| (animation.handle.base_layer, animation.current_layer()))
+    }
+
+    fn animation(&self, base_layer: u32) -> Option<&Animation> {
+        self.animations
+            .iter()
+            .find(|animation| animation.handle.base_layer == base_layer)
+    }
+
+    /// Adds the missing texture, a black and magenta checkerboard, at the collection's tile size.
+    pub fn push_invalid_texture(&mut self) -> TextureHandle {
+        let image = Self::invalid_texture(self.dimensions);
+        self.buf.push(image.pixel_bytes().clone());
+        self.invalidate();
         TextureHandle::single(self.buf.len() as u32 - 1)
     }
 
+    /// Generates the missing texture at the given size: a 2x2 checkerboard of black and magenta
+    /// quadrants, with magenta in the top right and bottom left.
+    pub fn invalid_texture(dimensions: (u32, u32)) -> Image {
+        let (width, height) = (dimensions.0 as usize, dimensions.1 as usize);
+        let mut data = vec![0u8; width * height * 4];
+        for (y, row) in data.chunks_mut(width * 4).enumerate() {
+            let is_bottom_half = y >= height / 2;
+            for (x, px) in row.chunks_mut(4).enumerate() {
+                let is_right_half = x >= width / 2;
+                let color = if is_bottom_half ^ is_right_half {
+                    [255, 0, 255, 255]
+                } else {
+                    [0, 0, 0, 255]
+                };
+                px.copy_from_slice(&color);
+            }
+        }
+        Image::from_rgba(dimensions.0, dimensions.1, data)
+            .expect("buffer matches the texture dimensions")
+    }
+
     /// Retrieves a texture handle by name.
     pub fn get_texture(&self, name: &str) -> Option<&TextureHandle> {
         self.textures.get(name)
     }
 
     /// Returns the GPU texture, creating it if it doesn't exist.
-    pub fn gpu_texture(&mut self) -> Texture {
-        if self.gpu_texture.is_some() {
-            return self.gpu_texture.as_ref().unwrap().clone();
+    ///
+    /// Returns an error if no textures were added, as an empty texture array can't be created.
+    /// Collections that may end up empty can call `push_invalid_texture` first.
+    pub fn gpu_texture(&mut self) -> anyhow::Result<Texture> {
+        if let Some(texture) = &self.gpu_texture {
+            return Ok(texture.clone());
+        }
+        if self.buf.is_empty() {
+            anyhow::bail!(
+                "Texture collection {} has no textures; add at least one before using it",
+                self.label.as_deref().unwrap_or("<unnamed>")
+            );
         }
 
-        let texture = self.handle.get().texture(
+        let wgpu = self.handle.get();
+        let sampler = wgpu.create_sampler(&SamplerDescriptor {
+            label: self.label.as_deref(),
+            ..self.sampler.clone()
+        });
+        let layers = self
+            .buf
+            .iter()
+            .map(|rgba| self.convert_texels(rgba))
+            .collect::<Vec<_>>();
+        let texture = wgpu.texture_with_sampler(
             self.label.as_deref(),
-            TextureFormat::Rgba8Unorm,
+            self.format,
             TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
             self.dimensions,
-            &self.buf,
+            &layers,
+            sampler,
         );
+        drop(wgpu);
 
         self.gpu_texture = Some(texture);
-        self.gpu_texture.as_ref().unwrap().clone()
+        Ok(self.gpu_texture.as_ref().unwrap().clone())
+    }
+}
+
+/// The playback state of an animated texture.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Animation {
+    /// The layers of the frames.
+    handle: TextureHandle,
+    frame_time_ms: u32,
+    /// Time spent on the current frame, in milliseconds.
+    elapsed_ms: f64,
+    /// The index of the current frame.
+    frame: u32,
+}
+
+impl Animation {
+    /// Moves the animation along by `delta_time` seconds. Returns true if the frame changed.
+    fn advance(&mut self, delta_time: f32) -> bool {
+        self.elapsed_ms += delta_time as f64 * 1000.0;
+        let frame_time = self.frame_time_ms as f64;
+        if self.elapsed_ms < frame_time {
+            return false;
+        }
+        // A long frame can skip several animation frames at once.
+        let passed = (self.elapsed_ms / frame_time).floor();
+        self.elapsed_ms -= passed * frame_time;
+        let previous = self.frame;
+        self.frame = ((self.frame as u64 + passed as u64) % self.handle.count as u64) as u32;
+        self.frame != previous
+    }
+
+    fn current_layer(&self) -> u32 {
+        self.handle.layer(self.frame)
+    }
+}
+
+/// Sampler presets for a texture collection, from the sharpest and cheapest to the smoothest.
+///
+/// Collections are created without mipmaps, so `Trilinear` only differs from `Nearest` in
+/// filtering within a mip level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextureQuality {
+    /// Nearest filtering, keeping pixel art crisp.
+    #[default]
+    Nearest,
+    /// Linear filtering between texels and mip levels.
+    Trilinear,
+    /// Trilinear filtering with the given anisotropy clamp.
+    Anisotropic(u16),
+}
+
+impl TextureQuality {
+    /// Returns the sampler for this preset.
+    pub fn sampler(self, address_mode: AddressMode) -> SamplerDescriptor<'static> {
+        match self {
+            TextureQuality::Nearest => TextureCollection::nearest_sampler(address_mode),
+            TextureQuality::Trilinear => TextureCollection::linear_sampler(address_mode, 1),
+            TextureQuality::Anisotropic(clamp) => {
+                TextureCollection::linear_sampler(address_mode, clamp)
+            }
+        }
+    }
+
+    /// Returns the preset closest to the given sampler.
+    pub fn of(sampler: &SamplerDescriptor) -> TextureQuality {
+        match (sampler.mag_filter, sampler.anisotropy_clamp) {
+            (FilterMode::Nearest, _) => TextureQuality::Nearest,
+            (FilterMode::Linear, 0..=1) => TextureQuality::Trilinear,
+            (FilterMode::Linear, clamp) => TextureQuality::Anisotropic(clamp),
+        }
+    }
+
+    /// Returns the next preset, wrapping around to `Nearest`. Handy for cycling with a key.
+    pub fn next(self) -> TextureQuality {
+        match self {
+            TextureQuality::Nearest => TextureQuality::Trilinear,
+            TextureQuality::Trilinear => TextureQuality::Anisotropic(16),
+            TextureQuality::Anisotropic(_) => TextureQuality::Nearest,
+        }
+    }
+}
+
+impl std::fmt::Display for TextureQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextureQuality::Nearest => write!(f, "Nearest"),
+            TextureQuality::Trilinear => write!(f, "Trilinear"),
+            TextureQuality::Anisotropic(clamp) => write!(f, "Anisotropic {}x", clamp),
+        }
     }
 }
 
@@ -149,3 +591,174 @@ impl TextureHandle {
         self.base_layer + index
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collections_keep_their_own_sampler() {
+        let state = ComponentStore::new();
+        let blocks = TextureCollection::new(&state, Some("Blocks"), (16, 16));
+        let ui = TextureCollection::new(&state, Some("UI"), (16, 16))
+            .with_sampler(TextureCollection::linear_sampler(AddressMode::Repeat, 8));
+
+        let blocks = blocks.sampler_descriptor();
+        assert_eq!(blocks.mag_filter, FilterMode::Nearest);
+        assert_eq!(blocks.mipmap_filter, MipmapFilterMode::Nearest);
+        assert_eq!(blocks.address_mode_u, AddressMode::ClampToEdge);
+        assert_eq!(blocks.anisotropy_clamp, 1);
+
+        let ui = ui.sampler_descriptor();
+        assert_eq!(ui.mag_filter, FilterMode::Linear);
+        assert_eq!(ui.min_filter, FilterMode::Linear);
+        assert_eq!(ui.mipmap_filter, MipmapFilterMode::Linear);
+        assert_eq!(ui.address_mode_u, AddressMode::Repeat);
+        assert_eq!(ui.anisotropy_clamp, 8);
+    }
+
+    #[test]
+    fn r8_collection_uploads_one_byte_per_texel() {
+        let mut state = ComponentStore::new();
+        WgpuRenderer::attach_noop(&mut state, (64, 64));
+        state.finish_initialization();
+
+        // Not square, so a stride computed from the height would be caught too.
+        let (width, height) = (16, 8);
+        let mask =
+            Image::from_rgba(width, height, vec![200; (width * height * 4) as usize]).unwrap();
+        let mut collection = TextureCollection::new(&state, Some("Masks"), (width, height))
+            .with_format(TextureFormat::R8Unorm);
+        collection.add_texture("mask", &mask);
+
+        let layer = collection.convert_texels(&collection.buf[0]);
+        assert_eq!(layer.len(), (width * height) as usize);
+        assert!(layer.iter().all(|texel| *texel == 200));
+
+        let texture = collection.gpu_texture().unwrap();
+        assert_eq!(texture.texture.format(), TextureFormat::R8Unorm);
+        let size = texture.texture.size();
+        assert_eq!((size.width, size.height), (width, height));
+    }
+
+    #[test]
+    fn hd_collection_scales_the_invalid_texture() {
+        let mut state = ComponentStore::new();
+        WgpuRenderer::attach_noop(&mut state, (64, 64));
+        state.finish_initialization();
+
+        let mut collection = TextureCollection::new(&state, Some("HD Blocks"), (32, 32));
+        let invalid = collection.push_invalid_texture();
+        // A 16x16 texture is scaled up to the tile size.
+        let small = Image::from_rgba(16, 16, vec![255; 16 * 16 * 4]).unwrap();
+        collection.add_texture("small", &small);
+        assert_eq!(collection.dimensions(), (32, 32));
+        assert_eq!(collection.layer_count(), 2);
+
+        let layer = &collection.buf[invalid.base_layer as usize];
+        assert_eq!(layer.len(), 32 * 32 * 4);
+        let pixel = |x: usize, y: usize| &layer[(y * 32 + x) * 4..][..4];
+        // Each quadrant is 16x16, so the checkerboard flips at the middle of the tile.
+        assert_eq!(pixel(15, 15), [0, 0, 0, 255]);
+        assert_eq!(pixel(16, 15), [255, 0, 255, 255]);
+        assert_eq!(pixel(15, 16), [255, 0, 255, 255]);
+        assert_eq!(pixel(31, 31), [0, 0, 0, 255]);
+        assert_eq!(collection.buf[1].len(), 32 * 32 * 4);
+
+        let size = collection.gpu_texture().unwrap().texture.size();
+        assert_eq!(
+            (size.width, size.height, size.depth_or_array_layers),
+            (32, 32, 2)
+        );
+    }
+
+    #[test]
+    fn switching_quality_replaces_the_active_sampler() {
+        let mut state = ComponentStore::new();
+        WgpuRenderer::attach_noop(&mut state, (64, 64));
+        state.finish_initialization();
+
+        let mut blocks = TextureCollection::new(&state, Some("Blocks"), (16, 16));
+        blocks.push_invalid_texture();
+        let before = blocks.gpu_texture().unwrap();
+        let revision = blocks.revision();
+        assert_eq!(blocks.texture_quality(), TextureQuality::Nearest);
+
+        blocks.set_texture_quality(TextureQuality::Anisotropic(16));
+        assert_eq!(blocks.texture_quality(), TextureQuality::Anisotropic(16));
+        let sampler = blocks.sampler_descriptor();
+        assert_eq!(sampler.mag_filter, FilterMode::Linear);
+        assert_eq!(sampler.anisotropy_clamp, 16);
+        assert!(blocks.revision() > revision);
+
+        // The sampler is replaced, but the texture isn't uploaded again.
+        let after = blocks.gpu_texture().unwrap();
+        assert_ne!(after.sampler, before.sampler);
+        assert_eq!(after.texture, before.texture);
+    }
+
+    #[test]
+    fn empty_collection_errors() {
+        let mut state = ComponentStore::new();
+        WgpuRenderer::attach_noop(&mut state, (64, 64));
+        state.finish_initialization();
+
+        let mut collection = TextureCollection::new(&state, Some("Empty"), (16, 16));
+        let error = collection.gpu_texture().unwrap_err().to_string();
+        assert!(error.contains("Empty"), "{}", error);
+        assert!(error.contains("no textures"), "{}", error);
+
+        // Adding a texture fixes it.
+        collection.push_invalid_texture();
+        assert!(collection.gpu_texture().is_ok());
+    }
+
+    #[test]
+    fn animations_advance_and_wrap() {
+        let state = ComponentStore::new();
+        let mut collection = TextureCollection::new(&state, Some("Blocks"), (16, 16));
+        collection.push_invalid_texture();
+        let frames = (0..3)
+            .map(|i| Image::from_rgba(16, 16, vec![i * 80; 16 * 16 * 4]).unwrap())
+            .collect::<Vec<_>>();
+        let water = collection.add_animated_texture("water", &frames, 100);
+        assert_eq!(water.count, 3);
+        assert_eq!(collection.current_layer(water), water.base_layer);
+
+        // Half a frame.
+        assert!(!collection.advance(0.05));
+        assert_eq!(collection.current_frame(water), Some(0));
+
+        assert!(collection.advance(0.05));
+        assert_eq!(collection.current_frame(water), Some(1));
+        assert_eq!(collection.current_layer(water), water.base_layer + 1);
+
+        assert!(collection.advance(0.1));
+        assert_eq!(collection.current_layer(water), water.base_layer + 2);
+
+        // The last frame wraps back to the first.
+        assert!(collection.advance(0.1));
+        assert_eq!(collection.current_layer(water), water.base_layer);
+    }
+
+    #[test]
+    fn sampler_carries_the_lod_clamp() {
+        let state = ComponentStore::new();
+        let mut blocks =
+            TextureCollection::new(&state, Some("Blocks"), (16, 16)).with_lod_clamp(1.0, 4.0);
+        assert_eq!(blocks.lod_clamp(), (1.0, 4.0));
+        let sampler = blocks.sampler_descriptor();
+        assert_eq!((sampler.lod_min_clamp, sampler.lod_max_clamp), (1.0, 4.0));
+
+        // Changing the quality keeps the clamp.
+        blocks.set_texture_quality(TextureQuality::Trilinear);
+        assert_eq!(blocks.lod_clamp(), (1.0, 4.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid LOD clamp")]
+    fn inverted_lod_clamp_panics() {
+        let state = ComponentStore::new();
+        TextureCollection::new(&state, Some("Blocks"), (16, 16)).with_lod_clamp(4.0, 1.0);
+    }
+}
@@ -0,0 +1,89 @@
+//! A global time uniform for animating shaders.
+
+/// The time uniform, laid out for shaders as:
+///
+/// ```wgsl
+/// struct Time {
+///     seconds: f32,
+///     delta: f32,
+///     frame: u32,
+/// }
+/// ```
+///
+/// Bound through `WgpuRenderer::time_bind_group`, with the layout from
+/// `RenderDevice::time_bind_group_layout`. It's advanced once per frame.
+#[derive(Debug, Clone, Copy, PartialEq, Default, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct TimeUniform {
+    /// Seconds since the game started, wrapped to `TimeUniform::PERIOD`.
+    pub seconds: f32,
+    /// Seconds the last frame took.
+    pub delta: f32,
+    /// Frames since the game started, wrapping on overflow.
+    pub frame: u32,
+    _padding: u32,
+}
+
+impl TimeUniform {
+    /// The period `seconds` wraps at.
+    ///
+    /// An `f32` counting up forever loses precision, so animations would start to stutter in
+    /// long sessions. Wrapping keeps it within a millisecond; animations that shouldn't jump at
+    /// the wrap need a period that divides this one.
+    pub const PERIOD: f32 = 1024.0;
+
+    /// Moves the time forward by one frame that took `delta` seconds.
+    pub fn advance(&mut self, delta: f32) {
+        self.seconds = (self.seconds + delta) % Self::PERIOD;
+        self.delta = delta;
+        self.frame = self.frame.wrapping_add(1);
+    }
+
+    /// Creates the layout of the bind group holding the time uniform at binding 0.
+    pub fn create_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("time bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advances_by_the_frame_delta() {
+        let mut time = TimeUniform::default();
+        for delta in [1.0 / 60.0, 1.0 / 30.0, 0.25] {
+            let before = time.seconds;
+            time.advance(delta);
+            assert!((time.seconds - before - delta).abs() < 1e-6);
+            assert_eq!(time.delta, delta);
+        }
+        assert_eq!(time.frame, 3);
+    }
+
+    #[test]
+    fn wraps_without_losing_precision() {
+        let mut time = TimeUniform::default();
+        // Ten hours at 60 frames per second.
+        for _ in 0..10 * 60 * 60 * 60 {
+            time.advance(1.0 / 60.0);
+        }
+        assert!(time.seconds < TimeUniform::PERIOD);
+        let before = time.seconds;
+        time.advance(1.0 / 60.0);
+        let step = (time.seconds - before).rem_euclid(TimeUniform::PERIOD);
+        assert!((step - 1.0 / 60.0).abs() < 1e-3, "{}", step);
+    }
+}
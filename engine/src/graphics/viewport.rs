@@ -0,0 +1,113 @@
+/// A rectangle of a render target that a pass draws into, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Viewport {
+    /// Returns a viewport covering the whole target.
+    pub fn full(size: (u32, u32)) -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            width: size.0 as f32,
+            height: size.1 as f32,
+        }
+    }
+
+    /// Returns the largest centered viewport of the target with the given aspect ratio
+    /// (width / height), leaving black bars on the remaining sides.
+    ///
+    /// With no target aspect the viewport covers the whole target and the image is stretched.
+    pub fn letterbox(size: (u32, u32), target_aspect: Option<f32>) -> Self {
+        let full = Self::full(size);
+        let Some(target_aspect) = target_aspect else {
+            return full;
+        };
+        if full.width <= 0.0 || full.height <= 0.0 || target_aspect <= 0.0 {
+            return full;
+        }
+
+        let surface_aspect = full.width / full.height;
+        if surface_aspect > target_aspect {
+            // The surface is wider than the target, so bars go on the left and right.
+            let width = full.height * target_aspect;
+            Self {
+                x: (full.width - width) / 2.0,
+                width,
+                ..full
+            }
+        } else {
+            // The surface is taller than the target, so bars go on the top and bottom.
+            let height = full.width / target_aspect;
+            Self {
+                y: (full.height - height) / 2.0,
+                height,
+                ..full
+            }
+        }
+    }
+
+    /// Restricts the render pass to this viewport.
+    pub fn apply(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        render_pass.set_viewport(self.x, self.y, self.width, self.height, 0.0, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_surface_gets_side_bars() {
+        let viewport = Viewport::letterbox((1920, 1080), Some(4.0 / 3.0));
+        assert_eq!(
+            viewport,
+            Viewport {
+                x: 240.0,
+                y: 0.0,
+                width: 1440.0,
+                height: 1080.0
+            }
+        );
+    }
+
+    #[test]
+    fn tall_surface_gets_top_and_bottom_bars() {
+        let viewport = Viewport::letterbox((800, 800), Some(2.0));
+        assert_eq!(
+            viewport,
+            Viewport {
+                x: 0.0,
+                y: 200.0,
+                width: 800.0,
+                height: 400.0
+            }
+        );
+    }
+
+    #[test]
+    fn stretches_by_default() {
+        assert_eq!(
+            Viewport::letterbox((1920, 1080), None),
+            Viewport::full((1920, 1080))
+        );
+        // A matching aspect needs no bars.
+        assert_eq!(
+            Viewport::letterbox((1920, 1080), Some(16.0 / 9.0)),
+            Viewport::full((1920, 1080))
+        );
+        // A minimized window or a nonsensical aspect falls back to the full target.
+        assert_eq!(
+            Viewport::letterbox((0, 0), Some(1.0)),
+            Viewport::full((0, 0))
+        );
+        assert_eq!(
+            Viewport::letterbox((800, 600), Some(0.0)),
+            Viewport::full((800, 600))
+        );
+    }
+}
@@ -1,7 +1,6 @@
-use std::fmt::Debug;
+use std::{cell::Cell, fmt::Debug, rc::Rc};
 
 use glam::{Mat4, Vec2, Vec3, vec2};
-use glfw::Key;
 use log::info;
 
 use crate::{
@@ -9,20 +8,38 @@ use crate::{
     debug::{DebugProvider, DebugRenderer},
     graphics::{
         callback::TargetHandle,
-        camera::Camera,
+        camera::{Camera, FOV_Y_RADS},
+        frustum::Frustum,
         lowlevel::{WgpuRenderer, buf::UniformBuffer},
     },
+    input::{
+        input_map::{Action, InputMap},
+        keyboard::Keyboard,
+    },
     window::GlfwWindow,
 };
 
+/// How fast the camera moves by default, in blocks per second.
+pub const DEFAULT_MOVE_SPEED: f32 = 10.0;
+
 #[derive(Clone)]
 pub struct CameraController {
     pub pos: Vec3,
     /// Pitch and yaw rotation.
     pub rot: Vec2,
     camera: Camera,
+    /// Field of view when not zoomed, in radians.
+    pub base_fov: f32,
+    /// Field of view while fully zoomed, in radians.
+    pub zoom_fov: f32,
+    /// Degrees turned per pixel of mouse movement, before zoom scaling.
+    pub sensitivity: f32,
+    /// How fast the camera moves, in blocks per second.
+    move_speed: f32,
+    zooming: bool,
     uniform: UniformBuffer<Mat4>,
     callback_handle: Option<TargetHandle<(f64, f64)>>,
+    focus_handle: Option<TargetHandle<bool>>,
     wgpu_handle: ComponentHandle<WgpuRenderer>,
 }
 
@@ -43,27 +60,35 @@ impl CameraController {
         let camera = Camera::new(
             width as f32 / height as f32,
             0.1,
-            16.0 * 32.0, // TODO: render distance setting? i think this is in world units
+            16.0 * 32.0, // Games should call `set_view_distance` with their render distance.
         );
 
         let uniform = wgpu.uniform_buffer(&camera.projection_view_matrix(), Some("Camera Uniform"));
         CameraController {
             wgpu_handle: state.handle_for::<WgpuRenderer>(),
             camera,
+            base_fov: FOV_Y_RADS,
+            zoom_fov: FOV_Y_RADS / 3.0,
+            sensitivity: 0.1,
+            move_speed: DEFAULT_MOVE_SPEED,
+            zooming: false,
             uniform,
             pos: Vec3::ZERO,
             callback_handle: None,
+            focus_handle: None,
             rot: Vec2::ZERO,
         }
     }
 
     pub fn process_rot(&mut self, direction: Vec2) {
-        let sensitivity = 0.1;
+        // Scale sensitivity with the zoom so aiming stays precise while zoomed in.
+        let sensitivity = self.sensitivity * self.camera.fov() / self.base_fov;
         self.rot.x += direction.x * sensitivity;
         self.rot.y += direction.y * sensitivity;
 
-        // Clamp the pitch to avoid flipping
+        // Clamp the pitch to avoid flipping, and keep the yaw small so it doesn't lose precision
         self.rot.y = self.rot.y.clamp(-89.0, 89.0);
+        self.rot.x = wrap_yaw(self.rot.x);
 
         let yaw_radians = self.rot.x.to_radians();
         let pitch_radians = self.rot.y.to_radians();
@@ -72,6 +97,42 @@ impl CameraController {
         self.camera.pos(self.pos);
     }
 
+    /// Sets the yaw and pitch of the camera, in degrees, and writes the new matrix.
+    pub fn set_rotation(&mut self, rot: Vec2) {
+        self.rot = rot;
+        self.process_rot(Vec2::ZERO);
+        self.flush();
+    }
+
+    /// Sets the near and far clip planes of the camera, and writes the new matrix.
+    pub fn set_clip_planes(&mut self, near: f32, far: f32) -> anyhow::Result<()> {
+        self.camera.set_clip_planes(near, far)?;
+        self.flush();
+        Ok(())
+    }
+
+    /// Sets how far the camera can see, in world units, keeping the current near plane.
+    pub fn set_view_distance(&mut self, distance: f32) -> anyhow::Result<()> {
+        let (near, _) = self.camera.clip_planes();
+        self.set_clip_planes(near, distance)
+    }
+
+    /// Matches the camera's aspect ratio to a window of the given size, keeping the field of view.
+    ///
+    /// Sizes with a zero dimension, e.g. from a minimized window, are ignored.
+    pub fn resize(&mut self, (width, height): (i32, i32)) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        self.camera.set_aspect_ratio(width as f32 / height as f32);
+        self.flush();
+    }
+
+    /// Returns the near and far clip planes of the camera.
+    pub fn clip_planes(&self) -> (f32, f32) {
+        self.camera.clip_planes()
+    }
+
     /// Returns a clone of the camera's uniform buffer.
     pub fn uniform(&self) -> UniformBuffer<Mat4> {
         self.uniform.clone()
@@ -138,14 +199,14 @@ impl CameraController {
     pub fn register_callback(this: ComponentHandle<CameraController>, window: &GlfwWindow) {
         let closure_camera = this.clone();
         let mut last = Vec2::ZERO;
-        let mut first_mouse = true;
+        let first_mouse = Rc::new(Cell::new(true));
+        let closure_first_mouse = first_mouse.clone();
         let handle = window.register_mouse_pos_callback(Some("camera"), move |(x, y)| {
             let container = closure_camera.clone();
             let mut camera = container.get_mut();
             let pos = vec2(x as f32, y as f32);
-            if first_mouse {
+            if closure_first_mouse.replace(false) {
                 last = pos;
-                first_mouse = false;
                 return;
             }
 
@@ -158,32 +219,105 @@ impl CameraController {
             camera.process_rot(offset);
         });
 
-        this.get_mut().callback_handle = Some(handle);
+        // The cursor moves freely while the window is unfocused, so start over when focus returns
+        // instead of turning by however far it moved.
+        let focus_handle = window.register_focus_callback(Some("camera"), move |focused| {
+            if focused {
+                first_mouse.set(true);
+            }
+        });
+
+        let mut camera = this.get_mut();
+        camera.callback_handle = Some(handle);
+        camera.focus_handle = Some(focus_handle);
     }
 
-    pub fn update_camera(&mut self, keyboard: &crate::input::keyboard::Keyboard, delta_time: f64) {
-        let speed = 10.0 * delta_time as f32;
+    /// Returns how fast the camera moves, in blocks per second.
+    pub fn move_speed(&self) -> f32 {
+        self.move_speed
+    }
+
+    /// Sets how fast the camera moves, in blocks per second.
+    pub fn set_move_speed(&mut self, move_speed: f32) {
+        self.move_speed = move_speed;
+    }
+
+    /// Moves and zooms the camera with the keys bound in `input_map`.
+    ///
+    /// `delta_time` is the time since the last update in seconds, so the camera covers the same
+    /// distance at any frame rate.
+    pub fn update_camera(&mut self, keyboard: &Keyboard, input_map: &InputMap, delta_time: f64) {
+        let speed = self.move_speed * delta_time as f32;
+        let direction = self.movement_direction(keyboard, input_map);
+        self.update_position(|c| c + direction * speed);
+        self.update_view(keyboard, input_map, delta_time);
+    }
+
+    /// Returns the direction the held movement keys point in, relative to where the camera looks.
+    ///
+    /// Moving forward follows the pitch of the camera, and opposite keys cancel out. The result
+    /// isn't normalized, so moving diagonally is faster.
+    pub fn movement_direction(&self, keyboard: &Keyboard, input_map: &InputMap) -> Vec3 {
         let front = self.front();
-        if keyboard.is_key_held(Key::W) {
-            let front = self.front();
-            self.update_position(|c| c + front * speed);
+        let right = front.cross(Vec3::Y).normalize();
+        let mut direction = Vec3::ZERO;
+        if input_map.is_action_held(keyboard, Action::MoveForward) {
+            direction += front;
         }
-        if keyboard.is_key_held(Key::S) {
-            let front = self.front();
-            self.update_position(|c| c - front * speed);
+        if input_map.is_action_held(keyboard, Action::MoveBack) {
+            direction -= front;
         }
-        if keyboard.is_key_held(Key::A) {
-            let right = front.cross(Vec3::Y).normalize();
-            self.update_position(|c| c - right * speed);
+        if input_map.is_action_held(keyboard, Action::StrafeLeft) {
+            direction -= right;
         }
-        if keyboard.is_key_held(Key::D) {
-            let right = front.cross(Vec3::Y).normalize();
-            self.update_position(|c| c + right * speed);
+        if input_map.is_action_held(keyboard, Action::StrafeRight) {
+            direction += right;
         }
+        direction
+    }
+
+    /// Zooms the camera with the keys bound in `input_map` and writes the new matrix, without
+    /// moving it. Used when something else, like a player's body, decides where the camera is.
+    pub fn update_view(&mut self, keyboard: &Keyboard, input_map: &InputMap, delta_time: f64) {
+        self.zooming = input_map.is_action_pressed(keyboard, Action::Zoom)
+            || input_map.is_action_held(keyboard, Action::Zoom);
+        self.update_zoom(delta_time);
 
         self.flush();
     }
 
+    /// Moves the field of view towards the zoomed or unzoomed FOV.
+    fn update_zoom(&mut self, delta_time: f64) {
+        const ZOOM_SPEED: f32 = 12.0;
+        let target = if self.zooming {
+            self.zoom_fov
+        } else {
+            self.base_fov
+        };
+        let current = self.camera.fov();
+        if current == target {
+            return;
+        }
+
+        // Exponential smoothing, so the transition feels the same at any frame rate.
+        let t = 1.0 - (-ZOOM_SPEED * delta_time as f32).exp();
+        let mut fov = current + (target - current) * t;
+        if (target - fov).abs() < 1e-4 {
+            fov = target;
+        }
+        self.camera.set_fov(fov);
+    }
+
+    /// Returns true if the zoom key is currently held.
+    pub fn is_zooming(&self) -> bool {
+        self.zooming
+    }
+
+    /// Returns the current field of view, in radians.
+    pub fn fov(&self) -> f32 {
+        self.camera.fov()
+    }
+
     pub fn front(&self) -> Vec3 {
         self.camera.front()
     }
@@ -199,4 +333,132 @@ impl CameraController {
     pub fn position(&self) -> Vec3 {
         self.pos
     }
+
+    /// Returns the volume the camera can currently see, so pipelines can skip drawing anything
+    /// outside it.
+    pub fn frustum(&self) -> Frustum {
+        self.camera.frustum()
+    }
+}
+
+/// Wraps a yaw angle in degrees into the range [-180, 180).
+pub fn wrap_yaw(yaw: f32) -> f32 {
+    let wrapped = (yaw + 180.0).rem_euclid(360.0) - 180.0;
+    // `rem_euclid` can round up to the modulus for tiny negative inputs.
+    if wrapped >= 180.0 { -180.0 } else { wrapped }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn controller() -> (ComponentStore, CameraController) {
+        let mut state = ComponentStore::new();
+        WgpuRenderer::attach_noop(&mut state, (64, 64));
+        state.finish_initialization();
+        let controller = CameraController::new(&state);
+        (state, controller)
+    }
+
+    fn controller_zoomed() -> (ComponentStore, CameraController) {
+        let (state, mut controller) = controller();
+        let input_map = InputMap::new();
+        let mut keyboard = Keyboard::new();
+        keyboard.press_key(input_map.key(Action::Zoom));
+        for _ in 0..120 {
+            controller.update_view(&keyboard, &input_map, 1.0 / 60.0);
+        }
+        (state, controller)
+    }
+
+    #[test]
+    fn holding_zoom_narrows_the_fov_and_releasing_restores_it() {
+        let (_state, mut controller) = controller();
+        let input_map = InputMap::new();
+        let mut keyboard = Keyboard::new();
+        let base = controller.fov();
+
+        keyboard.press_key(input_map.key(Action::Zoom));
+        controller.update_view(&keyboard, &input_map, 1.0 / 60.0);
+        assert!(controller.is_zooming());
+        let first = controller.fov();
+        assert!(first < base);
+        assert!(first > controller.zoom_fov);
+
+        keyboard.update_keys();
+        for _ in 0..120 {
+            controller.update_view(&keyboard, &input_map, 1.0 / 60.0);
+        }
+        assert_eq!(controller.fov(), controller.zoom_fov);
+
+        keyboard.release_key(input_map.key(Action::Zoom));
+        controller.update_view(&keyboard, &input_map, 1.0 / 60.0);
+        assert!(!controller.is_zooming());
+        assert!(controller.fov() > controller.zoom_fov);
+        for _ in 0..120 {
+            controller.update_view(&keyboard, &input_map, 1.0 / 60.0);
+        }
+        assert_eq!(controller.fov(), base);
+    }
+
+    #[test]
+    fn zoom_scales_mouse_sensitivity() {
+        let (_state, mut controller) = controller();
+        controller.process_rot(Vec2::new(10.0, 0.0));
+        let unzoomed = controller.rot.x;
+
+        let (_state, mut zoomed) = controller_zoomed();
+        zoomed.process_rot(Vec2::new(10.0, 0.0));
+        assert!((zoomed.rot.x - unzoomed / 3.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn movement_is_frame_rate_independent() {
+        let input_map = InputMap::new();
+        let mut keyboard = Keyboard::new();
+        keyboard.press_key(input_map.key(Action::MoveForward));
+        keyboard.press_key(input_map.key(Action::StrafeRight));
+        keyboard.update_keys();
+
+        // One simulated second at each frame rate.
+        let end_position = |fps: u32| {
+            let (_state, mut controller) = controller();
+            controller.set_move_speed(8.0);
+            for _ in 0..fps {
+                controller.update_camera(&keyboard, &input_map, 1.0 / fps as f64);
+            }
+            controller.position()
+        };
+        let at_60 = end_position(60);
+        let at_30 = end_position(30);
+        assert!(at_60.abs_diff_eq(at_30, 1e-3), "{} vs {}", at_60, at_30);
+        assert!(at_60.length() > 1.0);
+    }
+
+    #[test]
+    fn yaw_wraps_into_range() {
+        assert_eq!(wrap_yaw(0.0), 0.0);
+        assert_eq!(wrap_yaw(180.0), -180.0);
+        assert_eq!(wrap_yaw(-180.0), -180.0);
+        assert_eq!(wrap_yaw(190.0), -170.0);
+        assert_eq!(wrap_yaw(-190.0), 170.0);
+        assert_eq!(wrap_yaw(720.0 + 45.0), 45.0);
+        assert!((-180.0..180.0).contains(&wrap_yaw(-1e-7)));
+    }
+
+    #[test]
+    fn spinning_keeps_the_yaw_small_and_the_front_stable() {
+        let (_state, mut controller) = controller();
+        controller.set_rotation(Vec2::new(30.0, 10.0));
+        let front = controller.front();
+
+        // A long session of turning the same way, ten full turns per step.
+        for _ in 0..10_000 {
+            controller.rot.x += 3600.0;
+            controller.process_rot(Vec2::ZERO);
+            assert!((-180.0..180.0).contains(&controller.rot.x));
+        }
+        assert!((controller.rot.x - 30.0).abs() < 1e-3);
+        assert!(controller.front().abs_diff_eq(front, 1e-4));
+    }
 }
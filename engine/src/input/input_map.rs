@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use glfw::Key;
+
+use crate::input::keyboard::Keyboard;
+
+/// Something the player can do by pressing a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveForward,
+    MoveBack,
+    StrafeLeft,
+    StrafeRight,
+    Zoom,
+    ToggleDebug,
+    Pause,
+    CycleTextureQuality,
+    ReloadShaders,
+    Jump,
+    ToggleFly,
+    ToggleFullscreen,
+    ToggleHud,
+}
+
+impl Action {
+    /// Every action, in declaration order.
+    pub const ALL: [Action; 13] = [
+        Action::MoveForward,
+        Action::MoveBack,
+        Action::StrafeLeft,
+        Action::StrafeRight,
+        Action::Zoom,
+        Action::ToggleDebug,
+        Action::Pause,
+        Action::CycleTextureQuality,
+        Action::ReloadShaders,
+        Action::Jump,
+        Action::ToggleFly,
+        Action::ToggleFullscreen,
+        Action::ToggleHud,
+    ];
+
+    /// The key the action is bound to by default.
+    pub fn default_key(self) -> Key {
+        match self {
+            Action::MoveForward => Key::W,
+            Action::MoveBack => Key::S,
+            Action::StrafeLeft => Key::A,
+            Action::StrafeRight => Key::D,
+            Action::Zoom => Key::C,
+            Action::ToggleDebug => Key::F3,
+            Action::Pause => Key::Escape,
+            Action::CycleTextureQuality => Key::F4,
+            Action::ReloadShaders => Key::F5,
+            Action::Jump => Key::Space,
+            Action::ToggleFly => Key::F,
+            Action::ToggleFullscreen => Key::F11,
+            Action::ToggleHud => Key::F1,
+        }
+    }
+}
+
+/// Maps actions to the keys they're bound to, so controls can be rebound.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputMap {
+    bindings: HashMap<Action, Key>,
+}
+
+impl InputMap {
+    /// Creates a map with every action bound to its default key.
+    pub fn new() -> Self {
+        Self {
+            bindings: Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_key()))
+                .collect(),
+        }
+    }
+
+    /// Returns the key the action is bound to.
+    pub fn key(&self, action: Action) -> Key {
+        self.bindings
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| action.default_key())
+    }
+
+    /// Binds the action to a new key, returning the key it was bound to before.
+    pub fn rebind(&mut self, action: Action, key: Key) -> Key {
+        self.bindings
+            .insert(action, key)
+            .unwrap_or_else(|| action.default_key())
+    }
+
+    /// Returns the action bound to the key, if any. If several actions share the key, the first
+    /// in `Action::ALL` wins.
+    pub fn action_for(&self, key: Key) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|action| self.key(*action) == key)
+    }
+
+    /// Returns true if the action's key was pressed this frame.
+    pub fn is_action_pressed(&self, keyboard: &Keyboard, action: Action) -> bool {
+        keyboard.is_key_pressed(self.key(action))
+    }
+
+    /// Returns true if the action's key is being held down.
+    pub fn is_action_held(&self, keyboard: &Keyboard, action: Action) -> bool {
+        keyboard.is_key_held(self.key(action))
+    }
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebound_actions_follow_the_new_key() {
+        let mut input_map = InputMap::default();
+        assert_eq!(input_map.rebind(Action::MoveForward, Key::Up), Key::W);
+        assert_eq!(input_map.key(Action::MoveForward), Key::Up);
+
+        let mut keyboard = Keyboard::new();
+        keyboard.press_key(Key::W);
+        keyboard.update_keys();
+        assert!(!input_map.is_action_held(&keyboard, Action::MoveForward));
+
+        keyboard.press_key(Key::Up);
+        assert!(input_map.is_action_pressed(&keyboard, Action::MoveForward));
+        keyboard.update_keys();
+        assert!(input_map.is_action_held(&keyboard, Action::MoveForward));
+        keyboard.release_key(Key::Up);
+        assert!(!input_map.is_action_held(&keyboard, Action::MoveForward));
+    }
+
+    #[test]
+    fn defaults_match_the_original_keys() {
+        let input_map = InputMap::new();
+        assert_eq!(input_map.key(Action::MoveForward), Key::W);
+        assert_eq!(input_map.key(Action::ToggleDebug), Key::F3);
+        assert_eq!(input_map.action_for(Key::Escape), Some(Action::Pause));
+        assert_eq!(input_map.action_for(Key::Q), None);
+    }
+}
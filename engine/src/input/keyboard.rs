@@ -53,6 +53,16 @@ impl Keyboard {
         self.set_key_state(key, KeyState::Released);
     }
 
+    /// Releases every key that is currently down, e.g. when the window loses focus and the
+    /// release events would never arrive.
+    pub fn release_all(&mut self) {
+        for state in self.states.values_mut() {
+            if matches!(*state, KeyState::Pressed | KeyState::Held) {
+                *state = KeyState::Released;
+            }
+        }
+    }
+
     pub fn update_keys(&mut self) {
         for state in self.states.values_mut() {
             if *state == KeyState::Pressed {
@@ -63,3 +73,31 @@ impl Keyboard {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blur_releases_held_keys() {
+        let mut keyboard = Keyboard::new();
+        keyboard.press_key(Key::W);
+        keyboard.update_keys();
+        keyboard.press_key(Key::Space);
+        keyboard.press_key(Key::A);
+        keyboard.release_key(Key::A);
+        keyboard.update_keys();
+        assert!(keyboard.is_key_held(Key::W));
+        assert!(keyboard.is_key_held(Key::Space));
+
+        keyboard.release_all();
+        assert_eq!(keyboard.get_key_state(Key::W), Some(KeyState::Released));
+        assert_eq!(keyboard.get_key_state(Key::Space), Some(KeyState::Released));
+        // Keys already up stay up.
+        assert_eq!(keyboard.get_key_state(Key::A), Some(KeyState::Up));
+
+        keyboard.update_keys();
+        assert!(!keyboard.is_key_held(Key::W));
+        assert_eq!(keyboard.get_key_state(Key::W), Some(KeyState::Up));
+    }
+}
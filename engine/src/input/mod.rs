@@ -1,2 +1,4 @@
 pub mod camera;
+pub mod input_map;
 pub mod keyboard;
+pub mod mouse;
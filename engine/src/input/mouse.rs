@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use glfw::MouseButton;
+
+use crate::input::keyboard::KeyState;
+
+/// Tracks the state of the mouse buttons and the scroll wheel, like `Keyboard` does for keys.
+#[derive(Debug)]
+pub struct Mouse {
+    states: HashMap<MouseButton, KeyState>,
+    /// Vertical scroll since the last `update_buttons`, positive when scrolling up.
+    scroll: f32,
+}
+
+impl Mouse {
+    pub fn new() -> Self {
+        Self {
+            states: HashMap::new(),
+            scroll: 0.0,
+        }
+    }
+
+    pub fn set_button_state(&mut self, button: MouseButton, state: KeyState) {
+        self.states.insert(button, state);
+    }
+
+    pub fn get_button_state(&self, button: MouseButton) -> Option<KeyState> {
+        self.states.get(&button).copied()
+    }
+
+    /// Returns true if the button was pressed this frame.
+    pub fn is_button_pressed(&self, button: MouseButton) -> bool {
+        matches!(self.get_button_state(button), Some(KeyState::Pressed))
+    }
+
+    /// Returns true if the button is currently being held down.
+    pub fn is_button_held(&self, button: MouseButton) -> bool {
+        matches!(self.get_button_state(button), Some(KeyState::Held))
+    }
+
+    /// Returns true if the button was released this frame.
+    pub fn is_button_released(&self, button: MouseButton) -> bool {
+        matches!(self.get_button_state(button), Some(KeyState::Released))
+    }
+
+    pub fn press_button(&mut self, button: MouseButton) {
+        self.set_button_state(button, KeyState::Pressed);
+    }
+
+    pub fn release_button(&mut self, button: MouseButton) {
+        self.set_button_state(button, KeyState::Released);
+    }
+
+    /// Adds a scroll event. Several events in one frame add up.
+    pub fn scroll(&mut self, delta: f32) {
+        self.scroll += delta;
+    }
+
+    /// Returns how far the wheel was scrolled this frame, positive when scrolling up.
+    pub fn scroll_delta(&self) -> f32 {
+        self.scroll
+    }
+
+    /// Releases every button that is currently down, e.g. when the window loses focus and the
+    /// release events would never arrive.
+    pub fn release_all(&mut self) {
+        for state in self.states.values_mut() {
+            if matches!(*state, KeyState::Pressed | KeyState::Held) {
+                *state = KeyState::Released;
+            }
+        }
+    }
+
+    /// Advances the button states to the next frame and resets the scroll delta.
+    pub fn update_buttons(&mut self) {
+        for state in self.states.values_mut() {
+            if *state == KeyState::Pressed {
+                *state = KeyState::Held;
+            } else if *state == KeyState::Released {
+                *state = KeyState::Up;
+            }
+        }
+        self.scroll = 0.0;
+    }
+}
+
+impl Default for Mouse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buttons_advance_each_frame() {
+        let mut mouse = Mouse::default();
+        mouse.press_button(MouseButton::Button1);
+        assert!(mouse.is_button_pressed(MouseButton::Button1));
+        mouse.update_buttons();
+        assert!(mouse.is_button_held(MouseButton::Button1));
+        mouse.release_button(MouseButton::Button1);
+        assert!(mouse.is_button_released(MouseButton::Button1));
+        mouse.update_buttons();
+        assert_eq!(
+            mouse.get_button_state(MouseButton::Button1),
+            Some(KeyState::Up)
+        );
+        assert_eq!(mouse.get_button_state(MouseButton::Button2), None);
+    }
+
+    #[test]
+    fn scroll_adds_up_within_a_frame() {
+        let mut mouse = Mouse::new();
+        mouse.scroll(1.0);
+        mouse.scroll(2.0);
+        mouse.scroll(-0.5);
+        assert_eq!(mouse.scroll_delta(), 2.5);
+        mouse.update_buttons();
+        assert_eq!(mouse.scroll_delta(), 0.0);
+    }
+}
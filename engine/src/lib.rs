@@ -11,10 +11,13 @@ pub type FloatPosition = Vec3;
 
 // keep these
 pub mod assets;
+pub mod audio;
 pub mod component;
 pub mod debug;
 pub mod graphics;
 pub mod input;
+pub mod profiling;
+pub mod tick;
 pub mod window;
 
 // TODO: REMOVE
@@ -0,0 +1,61 @@
+//! Optional `tracing` instrumentation for hot paths.
+//!
+//! With the `tracing` feature enabled, [`profile_span!`](crate::profile_span) enters a
+//! `tracing` span that lasts until the returned guard is dropped. Without it, the macro
+//! expands to a zero-sized guard and the arguments are never evaluated, so regular `log`
+//! output is unaffected and there is no runtime cost.
+//!
+//! To collect the spans, build with the feature and install a subscriber before the game starts:
+//!
+//! ```toml
+//! [dependencies]
+//! engine = { path = "engine", features = ["tracing"] }
+//! tracing-subscriber = "0.3"
+//! ```
+//!
+//! ```ignore
+//! use tracing_subscriber::fmt::format::FmtSpan;
+//!
+//! // `log` records are forwarded to the subscriber, so `info!` and friends keep working.
+//! tracing_subscriber::fmt()
+//!     .with_span_events(FmtSpan::CLOSE)
+//!     .init();
+//!
+//! fn mesh_chunk() {
+//!     let _span = engine::profile_span!("mesh_chunk");
+//!     // ...
+//! }
+//! ```
+//!
+//! The quackcraft binary does this itself when built with `--features tracing`.
+
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub use tracing;
+
+/// A span guard that does nothing. Used when the `tracing` feature is disabled.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopSpan;
+
+/// Enters a profiling span named `$name` until the returned guard is dropped.
+///
+/// Extra arguments are passed through as `tracing` fields, e.g.
+/// `profile_span!("mesh_chunk", position = ?pos)`.
+#[cfg(feature = "tracing")]
+#[macro_export]
+macro_rules! profile_span {
+    ($name: literal $(, $($fields: tt)*)?) => {
+        $crate::profiling::tracing::info_span!($name $(, $($fields)*)?).entered()
+    };
+}
+
+/// Enters a profiling span named `$name` until the returned guard is dropped.
+///
+/// The `tracing` feature is disabled, so this does nothing.
+#[cfg(not(feature = "tracing"))]
+#[macro_export]
+macro_rules! profile_span {
+    ($name: literal $(, $($fields: tt)*)?) => {
+        $crate::profiling::NoopSpan
+    };
+}
@@ -0,0 +1,184 @@
+//! Fixed rate logic ticks, decoupled from the frame rate.
+//!
+//! Simulation like fluid flow and block updates should run at the same speed no matter how fast
+//! frames are drawn. A [`TickScheduler`] accumulates frame time and runs its registered
+//! [`System`]s once for every whole tick that elapsed, 20 times a second by default.
+
+use log::warn;
+
+use crate::{
+    component::{ComponentStore, ComponentStoreHandle},
+    profile_span,
+};
+
+/// The default number of ticks per second, the same as Minecraft.
+pub const DEFAULT_TICK_RATE: f64 = 20.0;
+
+/// The most ticks a single `advance` runs. Time past this is dropped, so a long stall (e.g. a
+/// breakpoint or a dragged window) doesn't make the game spend the next frames catching up.
+pub const MAX_TICKS_PER_ADVANCE: u32 = 10;
+
+/// Logic run once per tick.
+pub trait System {
+    /// Returns the name of the system, for logging.
+    fn label(&self) -> Option<&str> {
+        None
+    }
+    /// Runs one tick. `tick` counts up from 0 and identifies the tick being run.
+    fn tick(&mut self, state: &ComponentStoreHandle, tick: u64);
+}
+
+impl<F: FnMut(&ComponentStoreHandle, u64)> System for F {
+    fn tick(&mut self, state: &ComponentStoreHandle, tick: u64) {
+        self(state, tick)
+    }
+}
+
+/// Runs registered systems at a fixed tick rate.
+///
+/// Systems get the component store, so they can read and change any component but the scheduler
+/// itself, which is borrowed while they run.
+pub struct TickScheduler {
+    systems: Vec<Box<dyn System>>,
+    tick_rate: f64,
+    accumulator: f64,
+    tick: u64,
+    state: ComponentStoreHandle,
+}
+
+impl TickScheduler {
+    /// Creates a scheduler running at `DEFAULT_TICK_RATE` with no systems.
+    pub fn new(state: &ComponentStore) -> Self {
+        Self {
+            systems: Vec::new(),
+            tick_rate: DEFAULT_TICK_RATE,
+            accumulator: 0.0,
+            tick: 0,
+            state: state.handle(),
+        }
+    }
+
+    /// Sets the number of ticks per second.
+    pub fn with_tick_rate(mut self, tick_rate: f64) -> Self {
+        self.set_tick_rate(tick_rate);
+        self
+    }
+
+    /// Sets the number of ticks per second. Time already accumulated towards the next tick is
+    /// kept.
+    pub fn set_tick_rate(&mut self, tick_rate: f64) {
+        assert!(
+            tick_rate.is_finite() && tick_rate > 0.0,
+            "Invalid tick rate {tick_rate}"
+        );
+        self.tick_rate = tick_rate;
+    }
+
+    /// Returns the number of ticks per second.
+    pub fn tick_rate(&self) -> f64 {
+        self.tick_rate
+    }
+
+    /// Returns the length of a tick, in seconds.
+    pub fn tick_length(&self) -> f64 {
+        1.0 / self.tick_rate
+    }
+
+    /// Returns how many ticks have run so far.
+    pub fn tick_count(&self) -> u64 {
+        self.tick
+    }
+
+    /// Sets how many ticks have run, e.g. when resuming a saved game.
+    pub fn set_tick_count(&mut self, tick: u64) {
+        self.tick = tick;
+    }
+
+    /// Returns how far into the next tick the accumulated time is, from 0.0 to 1.0. Useful for
+    /// interpolating between ticks when rendering.
+    pub fn partial_tick(&self) -> f64 {
+        self.accumulator / self.tick_length()
+    }
+
+    /// Registers a system, run after the already registered ones every tick.
+    pub fn add_system(&mut self, system: impl System + 'static) {
+        self.systems.push(Box::new(system));
+    }
+
+    /// Returns the number of registered systems.
+    pub fn system_count(&self) -> usize {
+        self.systems.len()
+    }
+
+    /// Adds `delta_time` seconds of frame time and runs every tick that elapsed. Returns how many
+    /// ticks were run.
+    pub fn advance(&mut self, delta_time: f64) -> u32 {
+        let _span = profile_span!("advance_ticks");
+        self.accumulator += delta_time.max(0.0);
+        let tick_length = self.tick_length();
+        let mut ran = 0;
+        while self.accumulator >= tick_length {
+            if ran == MAX_TICKS_PER_ADVANCE {
+                warn!(
+                    "Skipping {:.0} ticks to catch up",
+                    (self.accumulator / tick_length).floor()
+                );
+                self.accumulator %= tick_length;
+                break;
+            }
+            self.accumulator -= tick_length;
+            self.run_tick();
+            ran += 1;
+        }
+        ran
+    }
+
+    /// Runs a single tick immediately, regardless of the accumulated time.
+    pub fn run_tick(&mut self) {
+        for system in &mut self.systems {
+            let _span = profile_span!("tick_system", system = system.label().unwrap_or("?"));
+            system.tick(&self.state, self.tick);
+        }
+        self.tick += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    #[test]
+    fn ticks_follow_simulated_time() {
+        let state = ComponentStore::new();
+        let mut scheduler = TickScheduler::new(&state);
+        let ticks = Rc::new(RefCell::new(Vec::new()));
+        let recorded = ticks.clone();
+        scheduler
+            .add_system(move |_: &ComponentStoreHandle, tick| recorded.borrow_mut().push(tick));
+
+        // Ten seconds at 60 frames per second is 200 ticks at 20 ticks per second.
+        let ran = (0..600).map(|_| scheduler.advance(1.0 / 60.0)).sum::<u32>();
+        // Summing 1/60 rounds a little, so the last tick may still be accumulating.
+        assert!((199..=200).contains(&ran), "{}", ran);
+        assert_eq!(scheduler.tick_count(), ran as u64);
+        assert_eq!(*ticks.borrow(), (0..ran as u64).collect::<Vec<_>>());
+
+        // Frames longer than a tick run several ticks at once.
+        let before = scheduler.tick_count();
+        let ran = scheduler.advance(0.25) as u64;
+        assert!((5..=6).contains(&ran));
+        assert_eq!(scheduler.tick_count(), before + ran);
+    }
+
+    #[test]
+    fn long_stalls_are_capped() {
+        let state = ComponentStore::new();
+        let mut scheduler = TickScheduler::new(&state).with_tick_rate(10.0);
+        assert_eq!(scheduler.advance(60.0), MAX_TICKS_PER_ADVANCE);
+        // The rest of the stall was dropped rather than caught up on.
+        assert!(scheduler.partial_tick() < 1.0);
+        assert_eq!(scheduler.advance(0.0), 0);
+    }
+}
@@ -1,12 +1,29 @@
+use std::{cell::Cell, rc::Rc};
+
 use glfw::{Context, Glfw, GlfwReceiver, PWindow};
 use log::*;
 use wgpu::{Surface, SurfaceTargetUnsafe};
 
 use crate::{
     ReadOnlyString,
-    graphics::callback::{Proxy, TargetHandle},
+    graphics::{
+        callback::{Proxy, TargetHandle},
+        image::Image,
+    },
 };
 
+/// How the window covers the screen. See `GlfwWindow::set_fullscreen`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FullscreenMode {
+    /// A normal window with decorations.
+    #[default]
+    Windowed,
+    /// An undecorated window covering its monitor at the monitor's current resolution.
+    Borderless,
+    /// Exclusive fullscreen, taking over the monitor in its current video mode.
+    Exclusive,
+}
+
 #[derive(Debug)]
 pub struct GlfwWindow {
     glfw: Glfw,
@@ -14,6 +31,12 @@ pub struct GlfwWindow {
     pub window: PWindow,
     pub event_receiver: GlfwReceiver<(f64, glfw::WindowEvent)>,
     pub mouse_pos_proxy: Proxy<(f64, f64)>,
+    /// Invoked with `true` when the window gains focus and `false` when it loses it.
+    pub focus_proxy: Proxy<bool>,
+    focused: Rc<Cell<bool>>,
+    fullscreen: FullscreenMode,
+    /// The position and size to restore when going back to windowed mode.
+    windowed_rect: (i32, i32, u32, u32),
 }
 
 impl GlfwWindow {
@@ -26,6 +49,8 @@ impl GlfwWindow {
             .ok_or_else(|| anyhow::anyhow!("Failed to create GLFW window"))?;
 
         window.set_key_polling(true);
+        window.set_mouse_button_polling(true);
+        window.set_scroll_polling(true);
         window.make_current();
 
         let proxy = Proxy::new();
@@ -36,9 +61,39 @@ impl GlfwWindow {
             proxy.invoke((x, y));
         });
 
+        let focus_proxy = Proxy::new();
+        let focused = Rc::new(Cell::new(window.is_focused()));
+
+        // While the window is out of focus, mouse movement is ignored and the cursor is released,
+        // so alt-tabbing doesn't spin the camera. The cursor mode is restored on focus.
+        let closure_proxy = focus_proxy.clone();
+        let closure_mouse_proxy = proxy.clone();
+        let closure_focused = focused.clone();
+        let mut restore_mode = None;
+        window.set_focus_callback(move |window, is_focused| {
+            debug!("Window focus changed: {}", is_focused);
+            closure_focused.set(is_focused);
+            if is_focused {
+                closure_mouse_proxy.unsuspend();
+                if let Some(mode) = restore_mode.take() {
+                    window.set_cursor_mode(mode);
+                }
+            } else {
+                closure_mouse_proxy.suspend();
+                restore_mode = Some(window.get_cursor_mode());
+                window.set_cursor_mode(glfw::CursorMode::Normal);
+            }
+            closure_proxy.invoke(is_focused);
+        });
+
+        let (x, y) = window.get_pos();
         Ok(GlfwWindow {
             glfw,
+            fullscreen: FullscreenMode::Windowed,
+            windowed_rect: (x, y, width, height),
             mouse_pos_proxy: proxy,
+            focus_proxy,
+            focused,
             window,
             event_receiver,
         })
@@ -52,12 +107,122 @@ impl GlfwWindow {
         self.glfw.poll_events();
     }
 
+    /// Returns true if the window has input focus.
+    pub fn is_focused(&self) -> bool {
+        self.focused.get()
+    }
+
+    #[must_use = "The returned TargetHandle must be kept alive to keep the callback registered."]
+    pub fn register_focus_callback<F>(
+        &self,
+        label: Option<impl Into<ReadOnlyString>>,
+        callback: F,
+    ) -> TargetHandle<bool>
+    where
+        F: FnMut(bool) + 'static,
+    {
+        self.focus_proxy
+            .add_target(callback, label.map(|l| l.into()))
+    }
+
+    /// Sets the text in the window's title bar.
+    pub fn set_title(&mut self, title: &str) {
+        self.window.set_title(title);
+    }
+
+    /// Sets the window's icon. Several resolutions of the same icon can be given, and the OS
+    /// picks the one closest to the size it needs.
+    ///
+    /// Fails if no images are given, or any of them is empty.
+    pub fn set_icon(&mut self, images: &[Image]) -> anyhow::Result<()> {
+        anyhow::ensure!(!images.is_empty(), "No images given for the window icon");
+        let icons = images
+            .iter()
+            .map(icon_pixels)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.window.set_icon_from_pixels(icons);
+        Ok(())
+    }
+
     /// Gets the current size of the window.
     pub fn size(&self) -> (u32, u32) {
         let (width, height) = self.window.get_size();
         (width as u32, height as u32)
     }
 
+    /// Returns how the window currently covers the screen.
+    pub fn fullscreen(&self) -> FullscreenMode {
+        self.fullscreen
+    }
+
+    /// Switches the window to the given fullscreen mode, returning its new size.
+    ///
+    /// Fullscreen modes use the monitor the window is mostly on. The windowed position and size
+    /// are remembered when leaving windowed mode and restored when going back to it. If no
+    /// monitor can be found, the window stays windowed.
+    ///
+    /// The surface isn't resized here; the caller has to resize it, and anything sized to it, to
+    /// the returned size.
+    pub fn set_fullscreen(&mut self, mode: FullscreenMode) -> (u32, u32) {
+        let (x, y) = self.window.get_pos();
+        let (width, height) = self.size();
+        if mode == self.fullscreen {
+            return (width, height);
+        }
+        if self.fullscreen == FullscreenMode::Windowed {
+            self.windowed_rect = (x, y, width, height);
+        }
+
+        let window = &mut self.window;
+        let windowed_rect = self.windowed_rect;
+        let (applied, size) = self.glfw.with_connected_monitors(|_, monitors| {
+            let target = current_monitor((x, y, width, height), monitors)
+                .map(|index| &*monitors[index])
+                .and_then(|monitor| Some((monitor, monitor.get_video_mode()?)));
+            match (mode, target) {
+                (FullscreenMode::Borderless, Some((monitor, video))) => {
+                    let (monitor_x, monitor_y) = monitor.get_pos();
+                    window.set_decorated(false);
+                    window.set_monitor(
+                        glfw::WindowMode::Windowed,
+                        monitor_x,
+                        monitor_y,
+                        video.width,
+                        video.height,
+                        None,
+                    );
+                    (mode, (video.width, video.height))
+                }
+                (FullscreenMode::Exclusive, Some((monitor, video))) => {
+                    window.set_monitor(
+                        glfw::WindowMode::FullScreen(monitor),
+                        0,
+                        0,
+                        video.width,
+                        video.height,
+                        Some(video.refresh_rate),
+                    );
+                    (mode, (video.width, video.height))
+                }
+                (mode, target) => {
+                    if mode != FullscreenMode::Windowed && target.is_none() {
+                        warn!(
+                            "No monitor found for {:?} fullscreen, staying windowed",
+                            mode
+                        );
+                    }
+                    let (x, y, width, height) = windowed_rect;
+                    window.set_decorated(true);
+                    window.set_monitor(glfw::WindowMode::Windowed, x, y, width, height, None);
+                    (FullscreenMode::Windowed, (width, height))
+                }
+            }
+        });
+        debug!("Switched to {:?} at {:?}", applied, size);
+        self.fullscreen = applied;
+        size
+    }
+
     /// Sets the mouse cursor mode.
     pub fn set_mouse_mode(&self, mode: glfw::CursorMode) {
         // TODO: Now that `window` isn't in an Arc, we can call the actual safe method.
@@ -115,6 +280,81 @@ impl GlfwWindow {
     }
 }
 
+/// Converts an image into the pixels of a window icon.
+///
+/// GLFW reads icons as RGBA8 bytes, row by row from the top left, which is the layout of
+/// `Image::pixel_bytes`; each pixel's four bytes are packed into a `u32` in memory order.
+pub fn icon_pixels(image: &Image) -> anyhow::Result<glfw::PixelImage> {
+    let (width, height) = image.dimensions();
+    anyhow::ensure!(
+        width > 0 && height > 0,
+        "Window icons can't be empty, but the image is {}x{}",
+        width,
+        height
+    );
+    let bytes = image.pixel_bytes();
+    anyhow::ensure!(
+        bytes.len() == width as usize * height as usize * 4,
+        "Window icons must be RGBA8, but the {}x{} image has {} bytes",
+        width,
+        height,
+        bytes.len()
+    );
+    let pixels = bytes
+        .chunks_exact(4)
+        .map(|pixel| u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]))
+        .collect();
+    Ok(glfw::PixelImage {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Returns the index of the monitor covering the most of the given window rectangle, or the
+/// first monitor if the window is on none of them.
+fn current_monitor(
+    (x, y, width, height): (i32, i32, u32, u32),
+    monitors: &[&mut glfw::Monitor],
+) -> Option<usize> {
+    let overlap = |start: i32, length: u32, other_start: i32, other_length: u32| {
+        let end = (start + length as i32).min(other_start + other_length as i32);
+        (end - start.max(other_start)).max(0) as i64
+    };
+    // `max_by_key` keeps the last of equal elements, so going backwards prefers the first
+    // monitor, which is the primary one.
+    monitors
+        .iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|(_, monitor)| {
+            let Some(video) = monitor.get_video_mode() else {
+                return -1;
+            };
+            let (monitor_x, monitor_y) = monitor.get_pos();
+            overlap(x, width, monitor_x, video.width) * overlap(y, height, monitor_y, video.height)
+        })
+        .map(|(index, _)| index)
+}
+
 fn handle_glfw_error(error: glfw::Error, description: String) {
     error!("GLFW error {:?}: {}", error, description);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icon_pixels_pack_one_u32_per_pixel() {
+        let bytes = (0..3 * 2 * 4).collect::<Vec<u8>>();
+        let image = Image::from_rgba(3, 2, bytes).unwrap();
+        let icon = icon_pixels(&image).unwrap();
+        assert_eq!((icon.width, icon.height), (3, 2));
+        assert_eq!(icon.pixels.len(), 6);
+        assert_eq!(icon.pixels[1].to_ne_bytes(), [4, 5, 6, 7]);
+
+        let empty = Image::from_rgba(0, 0, Vec::new()).unwrap();
+        assert!(icon_pixels(&empty).is_err());
+    }
+}
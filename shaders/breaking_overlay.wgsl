@@ -0,0 +1,51 @@
+/// Block breaking overlay shader. Draws a destroy stage texture over the targeted block.
+
+struct DrawData {
+    /// Clip space position.
+    @builtin(position) clip_position: vec4<f32>,
+    /// Texture coordinates.
+    @location(0) tex_coord: vec2<f32>,
+    /// Destroy stage, the index into `destroy_stages`.
+    @location(1) stage: u32,
+}
+
+struct OverlayVertex {
+    /// Position of the vertex in world space.
+    @location(0) position: vec3<f32>,
+    /// Texture coordinates.
+    @location(1) tex_coord: vec2<f32>,
+    /// Destroy stage, the index into `destroy_stages`.
+    @location(2) stage: u32,
+}
+
+@group(0) @binding(0) // Camera uniform buffer
+var<uniform> camera: mat4x4<f32>;
+
+@vertex
+fn vs(vertex: OverlayVertex) -> DrawData {
+    var draw: DrawData;
+    draw.tex_coord = vertex.tex_coord;
+    draw.stage = vertex.stage;
+    draw.clip_position = camera * vec4<f32>(vertex.position, 1.0);
+    return draw;
+}
+
+@group(1) @binding(0) // Destroy stage texture array
+var destroy_stages: texture_2d_array<f32>;
+@group(1) @binding(1) // Destroy stage sampler
+var sampler_destroy: sampler;
+
+@fragment
+fn fs(in: DrawData) -> @location(0) vec4<f32> {
+    let color = textureSample(
+        destroy_stages,
+        sampler_destroy,
+        // Flipping the texture coordinate vertically, same as the chunk shader
+        vec2<f32>(in.tex_coord.x, 1.0 - in.tex_coord.y),
+        in.stage,
+    );
+    if (color.a == 0.0) {
+        discard;
+    }
+    return color;
+}
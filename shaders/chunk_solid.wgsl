@@ -8,6 +8,8 @@ struct DrawData {
     @location(0) tex_coord: vec2<f32>,
     /// Texture ID for the block type. Specifically, the index into `block_textures`.
     @location(1) texture_id: u32,
+    /// How much light reaches the vertex after ambient occlusion, from 0 to 1.
+    @location(2) light: f32,
 }
 
 /// Vertex shader
@@ -19,11 +21,37 @@ struct ChunkData {
     @location(1) tex_coord: vec2<f32>,
     /// Texture ID for the block type. Specifically, the index into `block_textures`.
     @location(2) texture_id: u32,
+    /// Ambient occlusion level, from 0 (tucked into a corner) to 3 (unoccluded).
+    @location(3) ao: u32,
+    /// The direction the face points in, as an index into `NORMALS`.
+    @location(4) normal: u32,
+    /// Block light level in front of the face, from 0 (dark) to 15 (fully lit).
+    @location(5) light: u32,
 }
 
+struct Lighting {
+    /// Direction towards the sun, with the ambient level in `w`.
+    sun: vec4<f32>,
+    /// Brightness of each face direction before the sun, in `NORMALS` order.
+    face_shade: array<vec4<f32>, 2>,
+}
+
+/// Face normals, in `CardinalDirection` order.
+const NORMALS = array<vec3<f32>, 6>(
+    vec3<f32>(1.0, 0.0, 0.0),
+    vec3<f32>(-1.0, 0.0, 0.0),
+    vec3<f32>(0.0, 1.0, 0.0),
+    vec3<f32>(0.0, -1.0, 0.0),
+    vec3<f32>(0.0, 0.0, 1.0),
+    vec3<f32>(0.0, 0.0, -1.0),
+);
+
 @group(0) @binding(0) // Camera uniform buffer
 var<uniform> camera: mat4x4<f32>;
 
+@group(2) @binding(0) // Lighting uniform buffer
+var<uniform> lighting: Lighting;
+
 @vertex
 fn vs(
     chunk: ChunkData,
@@ -31,6 +59,13 @@ fn vs(
     var draw: DrawData;
     draw.tex_coord = chunk.tex_coord;
     draw.texture_id = chunk.texture_id;
+    let normal = min(chunk.normal, 5u);
+    let shade = lighting.face_shade[normal / 4u][normal % 4u];
+    let diffuse = max(dot(NORMALS[normal], lighting.sun.xyz), 0.0);
+    let sun = shade * (lighting.sun.w + (1.0 - lighting.sun.w) * diffuse);
+    // Each light level is 80% as bright as the one above it, like light falling off with distance.
+    let block_light = pow(0.8, f32(15u - min(chunk.light, 15u)));
+    draw.light = (0.4 + 0.2 * f32(chunk.ao)) * sun * block_light;
     draw.clip_position = camera * vec4<f32>(chunk.position, 1.0);
     return draw;
 }
@@ -44,13 +79,18 @@ var sampler_block: sampler;
 
 @fragment
 fn fs(in: DrawData) -> @location(0) vec4<f32> {
-    return textureSample(
+    let color = textureSample(
         block_textures,
         sampler_block,
         // Flipping the texture coordinate vertically
         vec2<f32>(in.tex_coord.x, 1.0 - in.tex_coord.y),
         in.texture_id,
     );
+    // Cut out the see-through parts of blocks like leaves.
+    if color.a < 0.5 {
+        discard;
+    }
+    return vec4<f32>(color.rgb * in.light, color.a);
 }
 
 
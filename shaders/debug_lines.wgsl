@@ -0,0 +1,31 @@
+/// Debug line shader. Draws flat colored lines in world space.
+
+struct DrawData {
+    /// Clip space position.
+    @builtin(position) clip_position: vec4<f32>,
+    /// Line color.
+    @location(0) color: vec4<f32>,
+}
+
+struct LineVertex {
+    /// Position of the vertex in world space.
+    @location(0) position: vec3<f32>,
+    /// Line color.
+    @location(1) color: vec4<f32>,
+}
+
+@group(0) @binding(0) // Camera uniform buffer
+var<uniform> camera: mat4x4<f32>;
+
+@vertex
+fn vs(vertex: LineVertex) -> DrawData {
+    var draw: DrawData;
+    draw.color = vertex.color;
+    draw.clip_position = camera * vec4<f32>(vertex.position, 1.0);
+    return draw;
+}
+
+@fragment
+fn fs(in: DrawData) -> @location(0) vec4<f32> {
+    return in.color;
+}
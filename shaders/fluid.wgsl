@@ -0,0 +1,56 @@
+/// Fluid surface shader. Draws translucent water with a scrolling ripple pattern.
+
+struct DrawData {
+    /// Clip space position.
+    @builtin(position) clip_position: vec4<f32>,
+    /// Scrolled texture coordinates.
+    @location(0) tex_coord: vec2<f32>,
+}
+
+struct FluidVertex {
+    /// Position of the vertex in world space.
+    @location(0) position: vec3<f32>,
+    /// Texture coordinates.
+    @location(1) tex_coord: vec2<f32>,
+}
+
+@group(0) @binding(0) // Camera uniform buffer
+var<uniform> camera: mat4x4<f32>;
+
+struct Time {
+    /// Seconds since the game started, wrapping every 1024 seconds.
+    seconds: f32,
+    /// Seconds the last frame took.
+    delta: f32,
+    /// Frames since the game started.
+    frame: u32,
+}
+
+@group(1) @binding(0) // The time of the frame being drawn
+var<uniform> time: Time;
+
+/// How fast the surface scrolls, in texture repeats per second. Whole repeats fit in the time's
+/// wrap period, so the surface doesn't jump when it wraps.
+const SCROLL_SPEED: vec2<f32> = vec2<f32>(0.0625, 0.125);
+/// How fast the ripples move, in radians per second. One cycle takes 8 seconds, which also
+/// divides the wrap period.
+const RIPPLE_SPEED: f32 = 0.78539816;
+const WATER_COLOR: vec3<f32> = vec3<f32>(0.2, 0.4, 0.85);
+const WATER_ALPHA: f32 = 0.65;
+
+@vertex
+fn vs(vertex: FluidVertex) -> DrawData {
+    var draw: DrawData;
+    draw.tex_coord = vertex.tex_coord + SCROLL_SPEED * time.seconds;
+    draw.clip_position = camera * vec4<f32>(vertex.position, 1.0);
+    return draw;
+}
+
+@fragment
+fn fs(in: DrawData) -> @location(0) vec4<f32> {
+    let uv = in.tex_coord * 6.2831853;
+    let phase = RIPPLE_SPEED * time.seconds;
+    let ripple = sin(uv.x * 2.0 + phase) * sin(uv.y * 3.0 - phase * 0.5);
+    let color = WATER_COLOR * (0.9 + 0.1 * ripple);
+    return vec4<f32>(color, WATER_ALPHA);
+}
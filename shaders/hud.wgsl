@@ -0,0 +1,37 @@
+/// HUD shader. Draws each instance as a flat colored screen-space quad.
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+struct QuadInstance {
+    /// Center of the quad in clip space.
+    @location(0) center: vec2<f32>,
+    /// Half the quad's width and height in clip space.
+    @location(1) half_extent: vec2<f32>,
+    @location(2) color: vec4<f32>,
+}
+
+@vertex
+fn vs(@builtin(vertex_index) index: u32, quad: QuadInstance) -> VertexOutput {
+    // Two triangles covering the unit square, like the post-processing triangle but bounded.
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+    );
+    let corner = corners[index] * 2.0 - 1.0;
+    var out: VertexOutput;
+    out.position = vec4<f32>(quad.center + corner * quad.half_extent, 0.0, 1.0);
+    out.color = quad.color;
+    return out;
+}
+
+@fragment
+fn fs(input: VertexOutput) -> @location(0) vec4<f32> {
+    return input.color;
+}
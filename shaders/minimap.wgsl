@@ -0,0 +1,35 @@
+/// Minimap overlay shader. Draws the top-down block map as a quad in the corner of the screen.
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+struct VertexInput {
+    /// Position of the vertex in clip space.
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+}
+
+@vertex
+fn vs(input: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(input.position, 0.0, 1.0);
+    out.uv = input.uv;
+    return out;
+}
+
+@group(0) @binding(0) // Minimap texture, one layer
+var minimap_texture: texture_2d_array<f32>;
+@group(0) @binding(1)
+var sampler_minimap: sampler;
+
+@fragment
+fn fs(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(minimap_texture, sampler_minimap, input.uv, 0);
+    // Columns without any blocks are left see-through.
+    if (color.a == 0.0) {
+        discard;
+    }
+    return color;
+}
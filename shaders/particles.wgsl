@@ -0,0 +1,87 @@
+/// Particle shader. Draws block fragments as camera facing quads, one instance per particle.
+
+struct DrawData {
+    /// Clip space position.
+    @builtin(position) clip_position: vec4<f32>,
+    /// Texture coordinates.
+    @location(0) tex_coord: vec2<f32>,
+    /// Texture ID for the block type. Specifically, the index into `block_textures`.
+    @location(1) texture_id: u32,
+    /// Opacity of the particle.
+    @location(2) alpha: f32,
+}
+
+struct ParticleInstance {
+    /// Center of the particle in world space.
+    @location(0) position: vec3<f32>,
+    /// Width of the quad, in blocks.
+    @location(1) size: f32,
+    /// Offset of the particle's piece within the block texture.
+    @location(2) uv_offset: vec2<f32>,
+    /// Texture ID for the block type.
+    @location(3) texture_id: u32,
+    /// Opacity of the particle.
+    @location(4) alpha: f32,
+}
+
+struct Billboard {
+    /// Camera right vector.
+    right: vec4<f32>,
+    /// Camera up vector.
+    up: vec4<f32>,
+}
+
+@group(0) @binding(0) // Camera uniform buffer
+var<uniform> camera: mat4x4<f32>;
+
+@group(1) @binding(0) // Block texture array
+var block_textures: texture_2d_array<f32>;
+@group(1) @binding(1) // Block texture sampler
+var sampler_block: sampler;
+
+@group(2) @binding(0) // Billboard basis
+var<uniform> billboard: Billboard;
+
+/// The fraction of the block texture shown on a single particle.
+const UV_SCALE: f32 = 0.25;
+
+/// Quad corners, as two triangles.
+const CORNERS: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+    vec2<f32>(0.0, 0.0),
+    vec2<f32>(1.0, 0.0),
+    vec2<f32>(1.0, 1.0),
+    vec2<f32>(1.0, 1.0),
+    vec2<f32>(0.0, 1.0),
+    vec2<f32>(0.0, 0.0),
+);
+
+@vertex
+fn vs(@builtin(vertex_index) index: u32, particle: ParticleInstance) -> DrawData {
+    let corner = CORNERS[index];
+    let offset = (corner - vec2<f32>(0.5, 0.5)) * particle.size;
+    let position = particle.position
+        + billboard.right.xyz * offset.x
+        + billboard.up.xyz * offset.y;
+
+    var draw: DrawData;
+    draw.tex_coord = particle.uv_offset + corner * UV_SCALE;
+    draw.texture_id = particle.texture_id;
+    draw.alpha = particle.alpha;
+    draw.clip_position = camera * vec4<f32>(position, 1.0);
+    return draw;
+}
+
+@fragment
+fn fs(in: DrawData) -> @location(0) vec4<f32> {
+    let color = textureSample(
+        block_textures,
+        sampler_block,
+        // Flipping the texture coordinate vertically
+        vec2<f32>(in.tex_coord.x, 1.0 - in.tex_coord.y),
+        in.texture_id,
+    );
+    if color.a < 0.1 {
+        discard;
+    }
+    return vec4<f32>(color.rgb, color.a * in.alpha);
+}
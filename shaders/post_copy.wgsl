@@ -0,0 +1,6 @@
+// Copies the input unchanged.
+
+@fragment
+fn fs(input: VertexOutput) -> @location(0) vec4<f32> {
+    return sample_input(input.uv);
+}
@@ -0,0 +1,11 @@
+// Gamma correction.
+
+/// The gamma the colors are corrected for, in `x`.
+@group(2) @binding(0)
+var<uniform> gamma: vec4<f32>;
+
+@fragment
+fn fs(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_input(input.uv);
+    return vec4<f32>(pow(color.rgb, vec3<f32>(1.0 / gamma.x)), color.a);
+}
@@ -0,0 +1,17 @@
+// Film grain: noise that changes every frame.
+
+/// How far the noise moves each color, in `x`.
+@group(2) @binding(0)
+var<uniform> grain: vec4<f32>;
+
+fn hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(12.9898, 78.233))) * 43758.5453);
+}
+
+@fragment
+fn fs(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_input(input.uv);
+    // Offsetting by the time moves the noise every frame, instead of leaving a fixed pattern.
+    let noise = hash(input.position.xy + fract(time.seconds) * 1000.0) - 0.5;
+    return vec4<f32>(color.rgb + noise * grain.x, color.a);
+}
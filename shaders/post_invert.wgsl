@@ -0,0 +1,7 @@
+// Inverts the colors.
+
+@fragment
+fn fs(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_input(input.uv);
+    return vec4<f32>(1.0 - color.rgb, color.a);
+}
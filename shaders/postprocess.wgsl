@@ -1,30 +1,40 @@
-// Post-processing shader that simply samples from a fullscreen texture.
-
+// Shared part of every post-processing effect. The effect's own source is appended to this, and
+// only has to provide the `fs` fragment entry point.
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) uv: vec2<f32>,
 };
 
-struct VertexInput {
-    @location(0) position: vec2<f32>,
-    @location(1) uv: vec2<f32>,
-}
-
 @vertex
-fn vs(input: VertexInput) -> VertexOutput {
+fn vs(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // One triangle big enough to cover the whole screen.
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
     var out: VertexOutput;
-    out.position = vec4<f32>(input.position, 0.0, 1.0);
-    out.uv = input.uv;
+    out.position = vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = vec2<f32>(corner.x, 1.0 - corner.y);
     return out;
 }
 
+/// The output of the previous effect, or the rendered scene for the first one.
 @group(0) @binding(0)
-var fullscreen_texture: texture_2d_array<f32>;
+var input_texture: texture_2d<f32>;
 @group(0) @binding(1)
-var sampler_fullscreen: sampler;
+var input_sampler: sampler;
 
-@fragment
-fn fs(input: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(fullscreen_texture, sampler_fullscreen, input.uv, 0);
+fn sample_input(uv: vec2<f32>) -> vec4<f32> {
+    return textureSample(input_texture, input_sampler, uv);
 }
+
+struct Time {
+    /// Seconds since the game started, wrapping every 1024 seconds.
+    seconds: f32,
+    /// Seconds the last frame took.
+    delta: f32,
+    /// Frames since the game started.
+    frame: u32,
+}
+
+/// The time of the frame being drawn.
+@group(1) @binding(0)
+var<uniform> time: Time;
@@ -0,0 +1,50 @@
+/// Sky shader. Fills the screen with a gradient from the horizon to the zenith.
+
+struct Sky {
+    /// The camera's right vector, scaled by the horizontal extent of the view.
+    right: vec4<f32>,
+    /// The camera's up vector, scaled by the vertical extent of the view.
+    up: vec4<f32>,
+    /// The direction the camera is looking in.
+    front: vec4<f32>,
+    /// Color straight up.
+    top: vec4<f32>,
+    /// Color at and below the horizon.
+    bottom: vec4<f32>,
+    /// Direction towards the sun, with how strongly it tints the sky in `w`.
+    sun: vec4<f32>,
+}
+
+struct DrawData {
+    /// Clip space position.
+    @builtin(position) clip_position: vec4<f32>,
+    /// Position on the screen, from -1 to 1.
+    @location(0) screen: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> sky: Sky;
+
+@vertex
+fn vs(@builtin(vertex_index) index: u32) -> DrawData {
+    // One triangle big enough to cover the whole screen.
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var draw: DrawData;
+    draw.screen = corner * 2.0 - 1.0;
+    draw.clip_position = vec4<f32>(draw.screen, 1.0, 1.0);
+    return draw;
+}
+
+@fragment
+fn fs(in: DrawData) -> @location(0) vec4<f32> {
+    let direction = normalize(sky.front.xyz + in.screen.x * sky.right.xyz + in.screen.y * sky.up.xyz);
+    let height = clamp(direction.y, 0.0, 1.0);
+    var color = mix(sky.bottom.rgb, sky.top.rgb, sqrt(height));
+
+    let facing = max(dot(direction, sky.sun.xyz), 0.0);
+    // A bright disc for the sun itself, and a wide glow around it.
+    let sun = vec3<f32>(1.0, 0.95, 0.8) * pow(facing, 512.0) + vec3<f32>(1.0, 0.8, 0.6) * pow(facing, 8.0) * 0.3;
+    color += sun * sky.sun.w;
+
+    return vec4<f32>(color, 1.0);
+}
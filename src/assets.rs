@@ -1,11 +1,21 @@
 use engine::{
     assets::AssetStore,
     component::ComponentStore,
-    graphics::{lowlevel::WgpuRenderer, textures::TextureCollection},
+    graphics::{atlases::TextureAtlases, lowlevel::WgpuRenderer, textures::TextureCollection},
 };
 use log::info;
 
-use crate::{include_minecraft_texture, render::block_textures::BlockTextureAtlas, world::Block};
+use crate::{
+    include_minecraft_texture, render::block_textures::BlockTextureAtlas, world::BlockRegistry,
+};
+
+/// The width and height of block textures, in pixels. Textures of other sizes are scaled to it.
+pub const BLOCK_TILE_SIZE: u32 = 16;
+
+/// The name of the texture atlas holding block textures.
+pub const BLOCK_ATLAS: &str = "blocks";
+/// The name of the texture atlas holding the destroy stages drawn over blocks being mined.
+pub const DESTROY_STAGE_ATLAS: &str = "destroy_stages";
 
 pub struct BlockTextureState {
     pub textures: TextureCollection,
@@ -15,35 +25,82 @@ pub struct BlockTextureState {
 pub fn init_asset_store(
     components: &ComponentStore,
     wgpu: &WgpuRenderer,
-) -> (TextureCollection, BlockTextureAtlas, AssetStore) {
+) -> (TextureAtlases, BlockTextureAtlas, AssetStore) {
     let mut a = AssetStore::new();
 
-    a.add_image("dirt", include_minecraft_texture!("block/dirt"))
-        .unwrap();
-    a.add_image(
-        "grass_block_side",
-        include_minecraft_texture!("block/grass_block_side"),
-    )
-    .unwrap();
-    a.add_image(
-        "grass_block_top",
-        include_minecraft_texture!("block/grass_block_top"),
-    )
-    .unwrap();
-    a.add_image("stone", include_minecraft_texture!("block/stone"))
-        .unwrap();
-    a.add_image("oak_wood", include_minecraft_texture!("block/oak_log"))
-        .unwrap();
-    a.add_image(
-        "oak_log_top",
-        include_minecraft_texture!("block/oak_log_top"),
-    )
-    .unwrap();
-    a.add_image("oak_leaves", include_minecraft_texture!("block/oak_leaves"))
-        .unwrap();
+    let destroy_stages: [&[u8]; 10] = [
+        include_minecraft_texture!("block/destroy_stage_0"),
+        include_minecraft_texture!("block/destroy_stage_1"),
+        include_minecraft_texture!("block/destroy_stage_2"),
+        include_minecraft_texture!("block/destroy_stage_3"),
+        include_minecraft_texture!("block/destroy_stage_4"),
+        include_minecraft_texture!("block/destroy_stage_5"),
+        include_minecraft_texture!("block/destroy_stage_6"),
+        include_minecraft_texture!("block/destroy_stage_7"),
+        include_minecraft_texture!("block/destroy_stage_8"),
+        include_minecraft_texture!("block/destroy_stage_9"),
+    ];
+    let destroy_stage_names = (0..destroy_stages.len())
+        .map(|i| format!("destroy_stage_{i}"))
+        .collect::<Vec<_>>();
+
+    let mut images: Vec<(&str, &[u8])> = vec![
+        ("dirt", include_minecraft_texture!("block/dirt")),
+        (
+            "grass_block_side",
+            include_minecraft_texture!("block/grass_block_side"),
+        ),
+        (
+            "grass_block_top",
+            include_minecraft_texture!("block/grass_block_top"),
+        ),
+        ("stone", include_minecraft_texture!("block/stone")),
+        ("oak_wood", include_minecraft_texture!("block/oak_log")),
+        (
+            "oak_log_top",
+            include_minecraft_texture!("block/oak_log_top"),
+        ),
+        ("oak_leaves", include_minecraft_texture!("block/oak_leaves")),
+    ];
+    images.extend(
+        destroy_stage_names
+            .iter()
+            .map(String::as_str)
+            .zip(destroy_stages),
+    );
+
+    a.add_images_parallel(&images).unwrap();
 
     let (texture_collection, atlas) = init_texture_collection(components, wgpu, &a);
-    (texture_collection, atlas, a)
+    let mut atlases = TextureAtlases::new();
+    atlases.register(BLOCK_ATLAS, texture_collection);
+    atlases.register(
+        DESTROY_STAGE_ATLAS,
+        init_destroy_stage_collection(components, &destroy_stage_names, &a),
+    );
+    (atlases, atlas, a)
+}
+
+fn init_destroy_stage_collection(
+    components: &ComponentStore,
+    names: &[String],
+    asset_store: &AssetStore,
+) -> TextureCollection {
+    let mut textures = TextureCollection::new(
+        components,
+        Some("Destroy Stages"),
+        (BLOCK_TILE_SIZE, BLOCK_TILE_SIZE),
+    );
+    let stages = names
+        .iter()
+        .map(|name| {
+            asset_store
+                .get_image(name)
+                .expect("missing destroy stage texture")
+        })
+        .collect::<Vec<_>>();
+    textures.add_textures("destroy_stages", &stages);
+    textures
 }
 
 fn init_texture_collection(
@@ -51,8 +108,14 @@ fn init_texture_collection(
     wgpu: &WgpuRenderer,
     asset_store: &AssetStore,
 ) -> (TextureCollection, BlockTextureAtlas) {
-    let mut texture_collection =
-        TextureCollection::new(components, Some("Block Texture Atlas"), (16, 16));
+    let mut texture_collection = TextureCollection::new(
+        components,
+        Some("Block Texture Atlas"),
+        (BLOCK_TILE_SIZE, BLOCK_TILE_SIZE),
+    );
+
+    // Layer 0 is the missing texture, which null handles and out of range indices point to.
+    texture_collection.push_invalid_texture();
 
     let dirt = asset_store.get_image("dirt").unwrap();
 
@@ -86,12 +149,9 @@ fn init_texture_collection(
     );
 
     let mut atlas = BlockTextureAtlas::new();
+    atlas.set_layer_count(texture_collection.layer_count() as u32);
 
-    atlas.set_texture_handle(Block::Dirt, dirt_handle);
-    atlas.set_texture_handle(Block::Grass, grass_handle);
-    atlas.set_texture_handle(Block::Stone, stone_handle);
-    atlas.set_texture_handle(Block::OakWood, oak_wood_handle);
-    atlas.set_texture_handle(Block::OakLeaves, oak_leaves_handle);
+    atlas.assign_textures(&components.get::<BlockRegistry>(), &texture_collection);
 
     (texture_collection, atlas)
 }
@@ -1,9 +1,22 @@
 use std::ops::Neg;
 
+use glam::Vec3;
+
 use crate::world::chunk::CHUNK_SIZE;
 
 /// A position in block coordinates.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    bytemuck::Pod,
+    bytemuck::Zeroable,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 #[repr(C)]
 pub struct BlockPosition(pub i64, pub i64, pub i64);
 
@@ -22,10 +35,16 @@ impl BlockPosition {
     }
     /// Wrap the block position within chunk bounds.
     pub fn chunk_normalize(&self) -> Self {
+        self.normalize_in(CHUNK_SIZE)
+    }
+
+    /// Wrap the block position within the bounds of a chunk of the given size.
+    pub fn normalize_in(&self, size: usize) -> Self {
+        let size = size as i64;
         Self(
-            self.0.rem_euclid(CHUNK_SIZE as i64),
-            self.1.rem_euclid(CHUNK_SIZE as i64),
-            self.2.rem_euclid(CHUNK_SIZE as i64),
+            self.0.rem_euclid(size),
+            self.1.rem_euclid(size),
+            self.2.rem_euclid(size),
         )
     }
 
@@ -34,6 +53,40 @@ impl BlockPosition {
         *self - self.chunk_normalize()
     }
 
+    /// Converts a world block position into the position of the chunk containing it.
+    pub fn to_chunk_position(&self) -> Self {
+        self.to_chunk_position_in(CHUNK_SIZE)
+    }
+
+    /// Converts a world block position into the position of the containing chunk of the given size.
+    pub fn to_chunk_position_in(&self, size: usize) -> Self {
+        let size = size as i64;
+        Self(
+            self.0.div_euclid(size),
+            self.1.div_euclid(size),
+            self.2.div_euclid(size),
+        )
+    }
+
+    /// Converts a chunk position into the world position of the chunk's minimum corner block.
+    pub fn chunk_origin(&self) -> Self {
+        self.chunk_origin_in(CHUNK_SIZE)
+    }
+
+    /// Converts a position of a chunk of the given size into the world position of its minimum corner block.
+    pub fn chunk_origin_in(&self, size: usize) -> Self {
+        *self * Self::new(size as i64, size as i64, size as i64)
+    }
+
+    /// Returns the world-space bounding box of the chunk at this chunk position, as (min, max) corners.
+    ///
+    /// Every chunk is a full `CHUNK_SIZE` cube, so stacked chunks simply have adjacent boxes.
+    pub fn world_aabb(&self) -> (Vec3, Vec3) {
+        let origin = self.chunk_origin();
+        let min = Vec3::new(origin.0 as f32, origin.1 as f32, origin.2 as f32);
+        (min, min + Vec3::splat(CHUNK_SIZE as f32))
+    }
+
     /// Offsets the block position by one unit in the direction of the cardinal direction.
     pub fn offset(&self, dir: engine::graphics::CardinalDirection) -> Self {
         let (dx, dy, dz) = dir.normal_i64();
@@ -95,3 +148,28 @@ impl Neg for BlockPosition {
 pub fn bp(x: i64, y: i64, z: i64) -> BlockPosition {
     BlockPosition::new(x, y, z)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aabb_of_negative_chunk() {
+        let chunk = bp(-1, 0, -2);
+        assert_eq!(chunk.chunk_origin(), bp(-16, 0, -32));
+        assert_eq!(
+            chunk.world_aabb(),
+            (Vec3::new(-16.0, 0.0, -32.0), Vec3::new(0.0, 16.0, -16.0))
+        );
+        // The block at the chunk's far corner maps back to it.
+        assert_eq!(bp(-1, 15, -17).to_chunk_position(), chunk);
+    }
+
+    #[test]
+    fn stacked_chunks_have_adjacent_boxes() {
+        let (_, lower_max) = bp(0, 0, 0).world_aabb();
+        let (upper_min, upper_max) = bp(0, 1, 0).world_aabb();
+        assert_eq!(lower_max.y, upper_min.y);
+        assert_eq!(upper_max.y, 32.0);
+    }
+}
@@ -1,30 +1,76 @@
 use std::{
     any::{Any, TypeId},
     marker, mem,
+    path::Path,
 };
 
+use anyhow::Context;
 use engine::{
+    audio::Audio,
     component::ComponentStore,
+    debug::{DebugGraph, DebugProvider, DebugRenderer, StatCategory},
     graphics::{
-        lowlevel::{WgpuRenderer, depth::DepthTexture},
+        atlases::TextureAtlases,
+        camera::Camera,
+        lowlevel::{WgpuRenderer, depth::DepthTexture, texture::Texture},
         pipeline::{controller::RenderController, pipelines::clear::ClearPipeline},
+        time::TimeUniform,
     },
-    input::{camera::CameraController, keyboard::Keyboard},
-    window,
+    input::{
+        camera::CameraController,
+        input_map::{Action as InputAction, InputMap},
+        keyboard::Keyboard,
+        mouse::Mouse,
+    },
+    profile_span,
+    tick::TickScheduler,
+    window::{self, FullscreenMode},
 };
 use glam::Vec3;
-use glfw::{Action, WindowEvent};
-use log::info;
+use glfw::{Action, Key, WindowEvent};
+use log::{debug, info};
 
 use crate::{
-    render::{RenderPipelines, pipelines::solid::SolidGeometryPipeline},
-    world::ActiveWorld,
+    assets::BLOCK_ATLAS,
+    menu::MenuState,
+    physics::Player,
+    render::{
+        RenderPipelines,
+        block_textures::BlockTextureAtlas,
+        lighting::Lighting,
+        pipelines::{
+            breaking::{BreakingOverlayPipeline, BreakingProgress},
+            debug_lines::{DebugLines, DebugLinesPipeline},
+            fluid::FluidPipeline,
+            frame_graph::FrameGraphPipeline,
+            hud::{Hud, HudPass},
+            minimap::MinimapPipeline,
+            particles::{ParticleSystem, ParticlesPipeline},
+            postprocess::PostProcessChain,
+            skybox::SkyboxPass,
+            solid::SolidGeometryPipeline,
+        },
+    },
+    settings::{SETTINGS_FILE, Settings},
+    targeting::Targeting,
+    timings::{FrameTimings, TitleFps, end_span},
+    world::{
+        ActiveWorld, Block, BlockRegistry, BlockUpdates, ChunkLoader, PerlinTerrainGenerator,
+        StreamingWorld,
+        random_tick::{RandomTicks, decay_covered_grass},
+    },
 };
 
 pub mod assets;
 pub mod coords;
+pub mod menu;
 pub mod mesh;
+pub mod physics;
 pub mod render;
+pub mod save;
+pub mod settings;
+pub mod targeting;
+pub mod timings;
 pub mod world;
 
 /// A position in the world, in chunk coordinates.
@@ -32,13 +78,22 @@ pub type BlockPosition = coords::BlockPosition;
 /// A position in the world, in chunk coordinates.
 pub type ChunkPosition = coords::BlockPosition;
 
+/// The seed the world is generated from.
+pub const WORLD_SEED: u64 = 0x51_5541_434b;
+/// How many chunks the world extends from the origin in every direction.
+pub const WORLD_RADIUS: i64 = 2;
+
+/// Corner positions and texture coordinates of each face of a unit cube, indexed by `CardinalDirection`.
+///
+/// Corners are wound counter-clockwise when looking at the face from outside the cube, so
+/// triangles built with `FACE_INDICES` are front facing under `wgpu::FrontFace::Ccw`.
 pub const FACE_TABLE: [[([f32; 3], [f32; 2]); 4]; 6] = [
     // +X
     [
-        ([1.0, 0.0, 1.0], [0.0, 0.0]),
-        ([1.0, 1.0, 1.0], [0.0, 1.0]),
-        ([1.0, 1.0, 0.0], [1.0, 1.0]),
         ([1.0, 0.0, 0.0], [1.0, 0.0]),
+        ([1.0, 1.0, 0.0], [1.0, 1.0]),
+        ([1.0, 1.0, 1.0], [0.0, 1.0]),
+        ([1.0, 0.0, 1.0], [0.0, 0.0]),
     ],
     // -X
     [
@@ -56,10 +111,10 @@ pub const FACE_TABLE: [[([f32; 3], [f32; 2]); 4]; 6] = [
     ],
     // -Y
     [
-        ([0.0, 0.0, 1.0], [0.0, 1.0]),
-        ([1.0, 0.0, 1.0], [1.0, 1.0]),
-        ([1.0, 0.0, 0.0], [1.0, 0.0]),
         ([0.0, 0.0, 0.0], [0.0, 0.0]),
+        ([1.0, 0.0, 0.0], [1.0, 0.0]),
+        ([1.0, 0.0, 1.0], [1.0, 1.0]),
+        ([0.0, 0.0, 1.0], [0.0, 1.0]),
     ],
     // +Z
     [
@@ -70,25 +125,42 @@ pub const FACE_TABLE: [[([f32; 3], [f32; 2]); 4]; 6] = [
     ],
     // -Z
     [
-        ([1.0, 0.0, 0.0], [1.0, 0.0]),
-        ([1.0, 1.0, 0.0], [1.0, 1.0]),
-        ([0.0, 1.0, 0.0], [0.0, 1.0]),
         ([0.0, 0.0, 0.0], [0.0, 0.0]),
+        ([0.0, 1.0, 0.0], [0.0, 1.0]),
+        ([1.0, 1.0, 0.0], [1.0, 1.0]),
+        ([1.0, 0.0, 0.0], [1.0, 0.0]),
     ],
 ];
 
 pub const FACE_INDICES: [u16; 6] = [0, 1, 2, 2, 3, 0];
 
+/// The window title, without the frame rate.
+pub const WINDOW_TITLE: &str = "Quackcraft";
+
 pub struct Game {
     component_db: ComponentStore,
+    timings: FrameTimings,
+    title: TitleFps,
+    texture_quality: DebugProvider,
+    buffer_pool: DebugProvider,
+    loaded_chunks: DebugProvider,
 }
 
 impl Game {
     pub fn new() -> anyhow::Result<Self> {
+        Self::with_blocks(BlockRegistry::new())
+    }
+
+    /// Creates the game with the given blocks, e.g. the built-in blocks plus some registered at
+    /// startup.
+    pub fn with_blocks(blocks: BlockRegistry) -> anyhow::Result<Self> {
         let mut state = ComponentStore::new();
+        state.insert(blocks);
         state.insert(Keyboard::new());
-        let window = window::GlfwWindow::new(800, 600, "Minecraft Clone")
-            .expect("Failed to create GLFW window");
+        state.insert(Mouse::new());
+        state.insert(InputMap::new());
+        let window =
+            window::GlfwWindow::new(800, 600, WINDOW_TITLE).expect("Failed to create GLFW window");
         smol::block_on(WgpuRenderer::attach_to(&mut state, &window))?;
         window.set_mouse_mode(glfw::CursorMode::Disabled);
         state.insert(window);
@@ -96,7 +168,8 @@ impl Game {
         let camera = CameraController::new(&state);
         let camera_handle = state.insert(camera);
 
-        let world = world::World::test(&state.handle());
+        let generator = PerlinTerrainGenerator::new(WORLD_SEED);
+        let world = world::World::generate(&generator, WORLD_RADIUS, &state.handle());
         let active_world = ActiveWorld::with_world(world);
         state.insert(active_world);
 
@@ -106,29 +179,119 @@ impl Game {
         let depth_texture = DepthTexture::new(&state);
         state.insert(depth_texture);
 
-        let (tc, atlas, assets) = assets::init_asset_store(&state, &state.get());
+        let (atlases, atlas, assets) = assets::init_asset_store(&state, &state.get());
         state.insert(assets);
-        state.insert(tc);
+        state.insert(atlases);
         state.insert(atlas);
+        let loader = ChunkLoader::new(
+            generator.clone(),
+            state.get::<BlockTextureAtlas>().clone(),
+            state.get::<BlockRegistry>().clone(),
+        );
+        state.insert(StreamingWorld::new(loader, world::RENDER_DISTANCE));
+
+        state.insert(Audio::default());
+        state.insert(BreakingProgress::default());
+        state.insert(DebugLines::new());
+        state.insert(DebugGraph::default());
+        state.insert(Hud::new());
+        state.insert(Lighting::default());
+        state.insert(TimeUniform::default());
+        state.insert(ParticleSystem::new());
+        state.insert(MenuState::new());
+        state.insert(Targeting::new());
+        let mut ticks = TickScheduler::new(&state);
+        let mut block_updates = BlockUpdates::new();
+        block_updates.on_neighbor_changed(Block::Grass, |world, update| {
+            decay_covered_grass(world, update.position, Block::Grass)
+        });
+        ticks.add_system(block_updates);
+        ticks.add_system(RandomTicks::new(decay_covered_grass));
+        state.insert(ticks);
+        state.insert(Settings::load_or_default(SETTINGS_FILE));
+
+        let debug = DebugRenderer::new(&state)?;
+        state.insert(debug);
 
         state.finish_initialization();
 
+        let timings = FrameTimings::new(&mut state.get_mut::<DebugRenderer>());
+        let texture_quality = state.get_mut::<DebugRenderer>().add_statistic_in(
+            StatCategory::Gpu,
+            "Texture quality",
+            state
+                .get::<TextureAtlases>()
+                .get(BLOCK_ATLAS)
+                .expect("block atlas not registered")
+                .get()
+                .texture_quality()
+                .to_string(),
+        );
+        let buffer_pool = state.get_mut::<DebugRenderer>().add_statistic_in(
+            StatCategory::Gpu,
+            "Buffer pool",
+            "0 reused, 0 allocated",
+        );
+        let loaded_chunks = state.get_mut::<DebugRenderer>().add_statistic_in(
+            StatCategory::World,
+            "Loaded chunks",
+            state
+                .get::<ActiveWorld>()
+                .get_world()
+                .map_or(0, |world| world.chunk_count())
+                .to_string(),
+        );
+
         let mut renderer = state.get_mut::<RenderController<RenderPipelines>>();
         renderer.add_pipeline(
             RenderPipelines::Clear,
-            ClearPipeline::new(1.0, 0.0, 0.5, 1.0),
+            ClearPipeline::new(1.0, 0.0, 0.5, 1.0).with_depth(state.handle_for()),
         );
+        renderer.add_pipeline(RenderPipelines::Sky, SkyboxPass::new(&state));
 
-        let solid_pipeline = SolidGeometryPipeline::new(&state);
+        let solid_pipeline = SolidGeometryPipeline::with_progress(&state, |done, total| {
+            debug!("Meshed chunk {}/{}", done, total);
+        });
         renderer.add_pipeline(RenderPipelines::SolidGeometry, solid_pipeline);
+        renderer.add_pipeline(RenderPipelines::Fluid, FluidPipeline::new(&state));
 
-        renderer.set_render_order(vec![RenderPipelines::Clear, RenderPipelines::SolidGeometry]);
+        renderer.add_pipeline(
+            RenderPipelines::BreakingOverlay,
+            BreakingOverlayPipeline::new(&state),
+        );
+        renderer.add_pipeline(RenderPipelines::Particles, ParticlesPipeline::new(&state));
+        renderer.add_pipeline(RenderPipelines::DebugLines, DebugLinesPipeline::new(&state));
+        renderer.add_pipeline(RenderPipelines::Minimap, MinimapPipeline::new(&state));
+        renderer.add_pipeline(RenderPipelines::FrameGraph, FrameGraphPipeline::new(&state));
+        renderer.add_pipeline(RenderPipelines::Hud, HudPass::new(&state));
+        renderer.add_pipeline(RenderPipelines::PostProcess, PostProcessChain::new(&state));
+
+        renderer.after(RenderPipelines::Sky, RenderPipelines::Clear)?;
+        renderer.after(RenderPipelines::SolidGeometry, RenderPipelines::Sky)?;
+        for overlay in [
+            RenderPipelines::Fluid,
+            RenderPipelines::BreakingOverlay,
+            RenderPipelines::Particles,
+            RenderPipelines::DebugLines,
+            RenderPipelines::Minimap,
+            RenderPipelines::FrameGraph,
+            RenderPipelines::Hud,
+        ] {
+            renderer.after(overlay, RenderPipelines::SolidGeometry)?;
+            renderer.after(RenderPipelines::PostProcess, overlay)?;
+        }
 
         let mut camera = state.get_mut::<CameraController>();
-        camera.pos = glam::Vec3::new(30.0, 32.0, 30.0);
+        // Start a little above the ground, wherever the terrain puts it.
+        let spawn_height = generator.surface_height(30, 30) as f32 + 8.0;
+        camera.pos = glam::Vec3::new(30.0, spawn_height, 30.0);
         camera.look_at(Vec3::ZERO);
+        let player = Player::new(camera.pos - Vec3::Y * physics::EYE_HEIGHT);
 
         drop_all!(renderer, camera);
+        state.insert(player);
+
+        state.get::<Settings>().apply(&state)?;
 
         CameraController::register_callback(camera_handle, &state.get::<window::GlfwWindow>());
 
@@ -136,6 +299,11 @@ impl Game {
 
         Ok(Self {
             component_db: state,
+            timings,
+            title: TitleFps::new(WINDOW_TITLE),
+            texture_quality,
+            buffer_pool,
+            loaded_chunks,
         })
     }
 
@@ -146,7 +314,10 @@ impl Game {
     /// Returns `Ok(())` if the update was successful, or `Err(None)` if the game should exit,
     /// or `Err(Some(error))` if an error occurred.
     pub fn update(&mut self, delta_time: f64) -> Option<()> {
-        let _ = delta_time;
+        let frame_time = std::time::Duration::from_secs_f64(delta_time);
+        self.timings.frame.update_duration(frame_time);
+        self.component_db.get_mut::<DebugGraph>().push(frame_time);
+        let span = std::time::Instant::now();
 
         let mut window = self.component_db.get_mut::<window::GlfwWindow>();
         if window.should_close() {
@@ -155,8 +326,9 @@ impl Game {
 
         let mut keyboard = self.component_db.get_mut::<Keyboard>();
         keyboard.update_keys();
-        let wgpu = self.component_db.get::<WgpuRenderer>();
-
+        let mut mouse = self.component_db.get_mut::<Mouse>();
+        mouse.update_buttons();
+        let input_map = self.component_db.get::<InputMap>();
         window.poll_events();
 
         while let Some((_, event)) = window.event_receiver.receive() {
@@ -164,8 +336,80 @@ impl Game {
                 WindowEvent::Close => {
                     return None;
                 }
-                WindowEvent::Size(x, y) => {
-                    wgpu.resize((x, y));
+                // Minimizing the window reports a zero size, which the surface can't take.
+                WindowEvent::Size(x, y) if x > 0 && y > 0 => {
+                    self.resize((x, y));
+                }
+                WindowEvent::Key(key, _, Action::Press, _)
+                    if key == input_map.key(InputAction::ToggleFullscreen) =>
+                {
+                    let mode = match window.fullscreen() {
+                        FullscreenMode::Windowed => FullscreenMode::Borderless,
+                        _ => FullscreenMode::Windowed,
+                    };
+                    let (width, height) = window.set_fullscreen(mode);
+                    if width > 0 && height > 0 {
+                        self.resize((width as i32, height as i32));
+                    }
+                    keyboard.press_key(key);
+                }
+                WindowEvent::Key(key, _, Action::Press, _)
+                    if key == input_map.key(InputAction::Pause) =>
+                {
+                    self.component_db
+                        .get_mut::<MenuState>()
+                        .handle_escape(&window);
+                    keyboard.press_key(key);
+                }
+                WindowEvent::Key(key, _, Action::Press, _)
+                    if key == input_map.key(InputAction::ToggleDebug) =>
+                {
+                    self.component_db.get_mut::<DebugRenderer>().toggle();
+                    keyboard.press_key(key);
+                }
+                WindowEvent::Key(key, _, Action::Press, _)
+                    if key == input_map.key(InputAction::ToggleHud) =>
+                {
+                    self.component_db.get_mut::<Hud>().toggle();
+                    keyboard.press_key(key);
+                }
+                WindowEvent::Key(key, _, Action::Press, _)
+                    if key == input_map.key(InputAction::CycleTextureQuality) =>
+                {
+                    let mut atlases = self.component_db.get_mut::<TextureAtlases>();
+                    let quality = atlases
+                        .get(BLOCK_ATLAS)
+                        .expect("block atlas not registered")
+                        .get()
+                        .texture_quality()
+                        .next();
+                    atlases.set_texture_quality(quality);
+                    self.texture_quality.update_value(quality);
+                    keyboard.press_key(key);
+                }
+                #[cfg(feature = "hot-reload")]
+                WindowEvent::Key(key, _, Action::Press, _)
+                    if key == input_map.key(InputAction::ReloadShaders) =>
+                {
+                    let failed = self
+                        .component_db
+                        .get_mut::<RenderController<RenderPipelines>>()
+                        .reload_shaders();
+                    if failed == 0 {
+                        info!("Reloaded shaders");
+                    }
+                    keyboard.press_key(key);
+                }
+                WindowEvent::Key(key, _, Action::Press, _)
+                    if self.component_db.get::<DebugRenderer>().enabled
+                        && debug_category_for_key(key).is_some() =>
+                {
+                    // While the overlay is open, the number keys toggle its categories.
+                    let category = debug_category_for_key(key).expect("checked by the guard");
+                    self.component_db
+                        .get_mut::<DebugRenderer>()
+                        .toggle_category(category);
+                    keyboard.press_key(key);
                 }
                 WindowEvent::Key(key, _, Action::Press, _) => {
                     info!("Key pressed: {:?}", key);
@@ -176,38 +420,266 @@ impl Game {
                     info!("Key released: {:?}", key);
                     keyboard.release_key(key);
                 }
+                WindowEvent::MouseButton(button, Action::Press, _) => {
+                    mouse.press_button(button);
+                }
+                WindowEvent::MouseButton(button, Action::Release, _) => {
+                    mouse.release_button(button);
+                }
+                WindowEvent::Scroll(_, y) => {
+                    mouse.scroll(y as f32);
+                }
                 _ => {}
             }
         }
 
-        // Update the camera
+        // Release events never arrive for keys let go while the window is unfocused.
+        let focused = window.is_focused();
+        if !focused {
+            keyboard.release_all();
+            mouse.release_all();
+        }
+
+        let span = end_span(span, &self.timings.input);
+
+        // Update the camera, unless the game is paused or in the background
         let mut camera = self.component_db.get_mut::<CameraController>();
-        camera.update_camera(&keyboard, delta_time);
+        let mut player = self.component_db.get_mut::<Player>();
+        let paused = self.component_db.get::<MenuState>().is_paused();
+        if focused && !paused {
+            if input_map.is_action_pressed(&keyboard, InputAction::ToggleFly) {
+                player.toggle_flying();
+            }
+            if player.flying {
+                camera.update_camera(&keyboard, &input_map, delta_time);
+                player.set_eye_position(camera.position());
+            } else {
+                // Walking ignores the pitch, so looking down doesn't slow the player.
+                let direction = camera.movement_direction(&keyboard, &input_map).with_y(0.0);
+                player.walk(direction.normalize_or_zero() * physics::WALK_SPEED);
+                if input_map.is_action_held(&keyboard, InputAction::Jump) {
+                    player.jump();
+                }
+                camera.update_view(&keyboard, &input_map, delta_time);
+            }
+        }
+
+        // Move the player through the world, and keep the camera at their eyes
+        if !paused && !player.flying {
+            if let Some(world) = self.component_db.get::<ActiveWorld>().get_world() {
+                physics::step(world, &mut player, delta_time as f32);
+            }
+            let eye = player.eye_position();
+            camera.update_position(|_| eye);
+            camera.flush();
+        }
+        drop(player);
+
+        // Find the targeted block once, for everything that needs it this frame
+        if let Some(world) = self.component_db.get::<ActiveWorld>().get_world() {
+            self.component_db.get_mut::<Targeting>().update(
+                world,
+                camera.position(),
+                camera.front(),
+            );
+        }
+
+        drop_all!(window, keyboard, mouse, input_map, camera);
 
-        drop_all!(window, keyboard, camera);
+        self.component_db
+            .get_mut::<ParticleSystem>()
+            .update(delta_time as f32);
 
+        // Move animated textures along, and point block faces at their current frames
+        let atlases = self.component_db.get::<TextureAtlases>();
+        atlases.advance(delta_time as f32);
+        if let Some(blocks) = atlases.get(BLOCK_ATLAS) {
+            self.component_db
+                .get_mut::<BlockTextureAtlas>()
+                .sync_animations(&blocks.get());
+        }
+        drop(atlases);
+
+        // Advance the clock shaders animate with
+        let mut time = self.component_db.get_mut::<TimeUniform>();
+        time.advance(delta_time as f32);
+        self.component_db.get::<WgpuRenderer>().write_time(&time);
+        drop(time);
+
+        // Run the world's logic ticks, which keep a fixed rate regardless of the frame rate
+        if !self.component_db.get::<MenuState>().is_paused() {
+            self.component_db
+                .get_mut::<TickScheduler>()
+                .advance(delta_time);
+        }
+
+        let span = end_span(span, &self.timings.update);
+
+        self.stream_chunks();
         let mut renderer = self
             .component_db
             .get_mut::<RenderController<RenderPipelines>>();
 
         renderer.update_pipelines();
+        end_span(span, &self.timings.upload);
         Some(())
     }
 
+    /// Loads and unloads chunks around the camera, and uploads or drops their meshes.
+    fn stream_chunks(&self) {
+        let camera = self.component_db.get::<CameraController>().pos;
+        let mut active_world = self.component_db.get_mut::<ActiveWorld>();
+        let mut streaming = self.component_db.get_mut::<StreamingWorld>();
+        let streamed = streaming.update(camera, &mut active_world);
+        if self.component_db.get::<DebugRenderer>().enabled {
+            let loaded = active_world
+                .get_world()
+                .map_or(0, |world| world.chunk_count());
+            self.loaded_chunks.update_value(format!(
+                "{} ({} loading, distance {})",
+                loaded,
+                streaming.pending(),
+                streaming.render_distance()
+            ));
+        }
+        drop_all!(active_world, streaming);
+        if streamed.loaded.is_empty() && streamed.unloaded.is_empty() {
+            return;
+        }
+
+        let mut renderer = self
+            .component_db
+            .get_mut::<RenderController<RenderPipelines>>();
+        if let Some(fluid) = renderer.get_pipeline_as_mut::<FluidPipeline>(&RenderPipelines::Fluid)
+        {
+            fluid.unload_chunks(&streamed.unloaded);
+            for chunk in &streamed.loaded {
+                fluid.queue_chunk(chunk.position);
+            }
+        }
+        let Some(solid) =
+            renderer.get_pipeline_as_mut::<SolidGeometryPipeline>(&RenderPipelines::SolidGeometry)
+        else {
+            return;
+        };
+        solid.unload_chunks(&streamed.unloaded);
+        for chunk in streamed.loaded {
+            solid.insert_chunk_mesh(chunk.position, chunk.mesh);
+        }
+    }
+
+    /// Resizes the surface, and everything sized to it, to the window's new size.
+    fn resize(&self, size: (i32, i32)) {
+        resize_surface(&self.component_db, size);
+    }
+
+    /// Sets the window title, which the frame rate is appended to.
+    pub fn set_window_title(&mut self, title: impl Into<String>) {
+        self.title.set_base(title);
+    }
+
+    /// Returns the current settings.
+    pub fn settings(&self) -> Settings {
+        self.component_db.get::<Settings>().clone()
+    }
+
+    /// Changes the settings, applies them, and saves them to `SETTINGS_FILE`.
+    pub fn update_settings(&mut self, f: impl FnOnce(&mut Settings)) -> anyhow::Result<()> {
+        let mut settings = self.component_db.get_mut::<Settings>();
+        f(&mut settings);
+        settings.apply(&self.component_db)?;
+        settings.save(SETTINGS_FILE)
+    }
+
+    /// Saves the world, camera, world time and settings to the directory at `path`.
+    pub fn save_game(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        save::save_game(&self.component_db, path)
+    }
+
+    /// Loads a save written by `save_game`, replacing the current world, camera, world time and
+    /// settings.
+    pub fn load_game(&mut self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        save::load_game(&self.component_db, path)
+    }
+
+    /// Renders the world from the given camera into an offscreen texture, e.g. for thumbnails.
+    pub fn render_to_texture(&self, camera: &Camera, size: (u32, u32)) -> anyhow::Result<Texture> {
+        let renderer = self.component_db.get::<RenderController<RenderPipelines>>();
+        let solid = renderer
+            .get_pipeline_as::<SolidGeometryPipeline>(&RenderPipelines::SolidGeometry)
+            .context("Solid geometry pipeline not found")?;
+        Ok(solid.render_to_texture(camera, size))
+    }
+
     /// Renders a frame. Returns the time taken to render the frame, in milliseconds.
     pub fn render(&mut self) -> anyhow::Result<()> {
+        let _span = profile_span!("render");
         let renderer = self
             .component_db
             .get_mut::<RenderController<RenderPipelines>>();
-        let wgpu = self.component_db.get::<WgpuRenderer>();
-        let mut encoder = wgpu.create_encoder(Some("Main Render Encoder"));
-        let (view, _texture) = renderer.render_pipelines(&mut encoder)?;
-        wgpu.submit_single(encoder.finish());
-        view.present();
+        let submissions = self.component_db.get::<WgpuRenderer>().submission_count();
+        let span = std::time::Instant::now();
+        let surface = renderer.render_frame(|encoder, view| {
+            self.component_db
+                .get_mut::<DebugRenderer>()
+                .render(encoder, view);
+        })?;
+        debug_assert_eq!(
+            self.component_db.get::<WgpuRenderer>().submission_count() - submissions,
+            1,
+            "a frame should be submitted exactly once"
+        );
+        let span = end_span(span, &self.timings.encode);
+        surface.present();
+        let now = end_span(span, &self.timings.present);
+
+        if self.component_db.get::<DebugRenderer>().enabled {
+            let wgpu = self.component_db.get::<WgpuRenderer>();
+            let pool = wgpu.buffer_pool();
+            let stats = pool.stats();
+            self.buffer_pool.update_value(format!(
+                "{} reused, {} allocated, {} free",
+                stats.reused,
+                stats.allocated,
+                pool.free_count()
+            ));
+        }
+
+        // The overlay already shows the frame time, so the title only does while it's closed
+        let show_fps = self.component_db.get::<Settings>().fps_in_title
+            && !self.component_db.get::<DebugRenderer>().enabled;
+        self.title.update(
+            &mut self.component_db.get_mut::<window::GlfwWindow>(),
+            show_fps,
+            now,
+        );
         Ok(())
     }
 }
 
+/// Resizes the surface and everything drawn at its size.
+fn resize_surface(state: &ComponentStore, size: (i32, i32)) {
+    state.get::<WgpuRenderer>().resize(size);
+    // The depth attachment has to match the surface, or the next render pass fails.
+    state.get_mut::<DepthTexture>().resize();
+    state.get_mut::<CameraController>().resize(size);
+    state
+        .get_mut::<DebugRenderer>()
+        .resize((size.0 as u32, size.1 as u32));
+}
+
+/// Maps the number keys 1 to 4 to the debug overlay categories.
+fn debug_category_for_key(key: Key) -> Option<StatCategory> {
+    let index = match key {
+        Key::Num1 => 0,
+        Key::Num2 => 1,
+        Key::Num3 => 2,
+        Key::Num4 => 3,
+        _ => return None,
+    };
+    StatCategory::from_index(index)
+}
+
 pub fn run_game() -> anyhow::Result<()> {
     let game = Game::new()?;
 
@@ -247,3 +719,46 @@ macro_rules! drop_all {
         $(drop($val);)*
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{coords::bp, render::headless::headless_state};
+
+    #[test]
+    fn rendering_after_a_resize() {
+        let state = headless_state(|world| {
+            world.set_block(bp(0, 0, 0), Block::Stone);
+        });
+        let mut renderer = RenderController::<RenderPipelines>::new(&state);
+        renderer.add_pipeline(
+            RenderPipelines::Clear,
+            ClearPipeline::new(1.0, 0.0, 0.5, 1.0).with_depth(state.handle_for()),
+        );
+        renderer.add_pipeline(
+            RenderPipelines::SolidGeometry,
+            SolidGeometryPipeline::new(&state),
+        );
+        renderer.update_pipelines();
+
+        for size in [(100, 60), (32, 200)] {
+            resize_surface(&state, size);
+            let size = (size.0 as u32, size.1 as u32);
+            let depth = state.get::<DepthTexture>().texture.size();
+            assert_eq!((depth.width, depth.height), size);
+
+            // Stands in for the resized swapchain texture. The depth attachment must match it.
+            let wgpu = state.get::<WgpuRenderer>();
+            let target = wgpu.texture_uninit(
+                Some("Resize Test Target"),
+                wgpu.config.get().format,
+                wgpu::TextureUsages::RENDER_ATTACHMENT,
+                size,
+                1,
+            );
+            drop(wgpu);
+            renderer.update_pipelines();
+            renderer.submit_frame(&target.view, |_, _| {}).unwrap();
+        }
+    }
+}
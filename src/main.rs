@@ -1,6 +1,12 @@
 use quackcraft::run_game;
 
 fn main() -> anyhow::Result<()> {
+    #[cfg(not(feature = "tracing"))]
     env_logger::init();
+    // The subscriber also picks up `log` records, so it replaces env_logger.
+    #[cfg(feature = "tracing")]
+    tracing_subscriber::fmt()
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+        .init();
     run_game()
 }
@@ -0,0 +1,146 @@
+use engine::{
+    ReadOnlyString,
+    graphics::callback::{Proxy, TargetHandle},
+    window::GlfwWindow,
+};
+use glfw::CursorMode;
+use log::info;
+
+/// Emitted when the player opens or closes the menu with Escape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuEvent {
+    /// The game was paused and the menu should open.
+    Paused,
+    /// The game was resumed and the menu should close.
+    Resumed,
+}
+
+/// Tracks whether the game is paused, and lets menu systems react to Escape.
+///
+/// By default pausing frees the cursor and resuming captures it again. Menu systems that manage
+/// the cursor themselves can turn that off with `set_default_handling`.
+#[derive(Debug)]
+pub struct MenuState {
+    paused: bool,
+    default_handling: bool,
+    listeners: Proxy<MenuEvent>,
+}
+
+impl MenuState {
+    /// Creates an unpaused menu state with the default cursor handling enabled.
+    pub fn new() -> Self {
+        Self {
+            paused: false,
+            default_handling: true,
+            listeners: Proxy::new(),
+        }
+    }
+
+    /// Returns true if the game is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Returns true if pausing and resuming toggles the cursor mode.
+    pub fn default_handling(&self) -> bool {
+        self.default_handling
+    }
+
+    /// Enables or disables toggling the cursor mode when pausing and resuming.
+    pub fn set_default_handling(&mut self, enabled: bool) {
+        self.default_handling = enabled;
+    }
+
+    /// Registers a callback invoked whenever the game is paused or resumed.
+    #[must_use = "The returned TargetHandle must be kept alive to keep the callback registered."]
+    pub fn register_listener<F>(
+        &self,
+        label: Option<impl Into<ReadOnlyString>>,
+        callback: F,
+    ) -> TargetHandle<MenuEvent>
+    where
+        F: FnMut(MenuEvent) + 'static,
+    {
+        self.listeners.add_target(callback, label.map(|l| l.into()))
+    }
+
+    /// Pauses or resumes the game and notifies the listeners. Returns the event that was emitted.
+    pub fn toggle(&mut self) -> MenuEvent {
+        self.paused = !self.paused;
+        let event = if self.paused {
+            MenuEvent::Paused
+        } else {
+            MenuEvent::Resumed
+        };
+        info!("Menu event: {:?}", event);
+        self.listeners.invoke(event);
+        event
+    }
+
+    /// Handles an Escape press: toggles the pause state and, if enabled, the cursor mode.
+    pub fn handle_escape(&mut self, window: &GlfwWindow) -> MenuEvent {
+        self.handle_escape_with(|mode| window.set_mouse_mode(mode))
+    }
+
+    /// Handles an Escape press like `handle_escape`, passing the new cursor mode to
+    /// `set_cursor_mode` instead of setting it on a window.
+    fn handle_escape_with(&mut self, set_cursor_mode: impl FnOnce(CursorMode)) -> MenuEvent {
+        let event = self.toggle();
+        if self.default_handling {
+            set_cursor_mode(match event {
+                MenuEvent::Paused => CursorMode::Normal,
+                MenuEvent::Resumed => CursorMode::Disabled,
+            });
+        }
+        event
+    }
+}
+
+impl Default for MenuState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    #[test]
+    fn escape_notifies_listeners_and_toggles_the_cursor() {
+        let mut menu = MenuState::new();
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let _listener = menu.register_listener(Some("test"), {
+            let events = events.clone();
+            move |event| events.borrow_mut().push(event)
+        });
+
+        let mut cursor = None;
+        assert_eq!(
+            menu.handle_escape_with(|mode| cursor = Some(mode)),
+            MenuEvent::Paused
+        );
+        assert!(menu.is_paused());
+        assert_eq!(cursor, Some(CursorMode::Normal));
+
+        assert_eq!(
+            menu.handle_escape_with(|mode| cursor = Some(mode)),
+            MenuEvent::Resumed
+        );
+        assert!(!menu.is_paused());
+        assert_eq!(cursor, Some(CursorMode::Disabled));
+        assert_eq!(*events.borrow(), [MenuEvent::Paused, MenuEvent::Resumed]);
+    }
+
+    #[test]
+    fn cursor_is_left_alone_without_default_handling() {
+        let mut menu = MenuState::new();
+        menu.set_default_handling(false);
+        let mut cursor = None;
+        menu.handle_escape_with(|mode| cursor = Some(mode));
+        assert!(menu.is_paused());
+        assert_eq!(cursor, None);
+    }
+}
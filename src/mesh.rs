@@ -1,3 +1,5 @@
+use std::fmt::Write;
+
 use bytemuck::{Pod, Zeroable};
 
 use crate::{BlockPosition, FACE_INDICES, FACE_TABLE};
@@ -8,7 +10,7 @@ use engine::{
         CardinalDirection,
         lowlevel::{
             WgpuRenderer,
-            buf::{IndexBuffer, VertexBuffer, VertexLayout},
+            buf::{AnyIndexBuffer, Indices, VertexBuffer, VertexLayout},
         },
         textures::TextureHandle,
     },
@@ -16,7 +18,7 @@ use engine::{
 #[derive(Clone, Debug)]
 pub struct BlockMesh {
     vertices: Vec<BlockVertex>,
-    indices: Vec<u16>,
+    indices: Indices,
     face_count: usize,
 }
 
@@ -24,15 +26,15 @@ impl BlockMesh {
     pub fn empty() -> Self {
         Self {
             vertices: Vec::new(),
-            indices: Vec::new(),
+            indices: Indices::new(),
             face_count: 0,
         }
     }
 
     /// Pushes a vertex to the mesh and returns its index.
-    pub fn push_vertex(&mut self, vertex: BlockVertex) -> u16 {
+    pub fn push_vertex(&mut self, vertex: BlockVertex) -> u32 {
         self.vertices.push(vertex);
-        (self.vertices.len() - 1) as u16
+        (self.vertices.len() - 1) as u32
     }
 
     /// Emits a face for the given block position in the given direction.
@@ -59,6 +61,8 @@ impl BlockMesh {
                 position: *face,
                 tex_coord: *tex_coords,
                 texture_id: texture,
+                normal: direction as u8,
+                _padding: [0; 3],
             };
 
             face_indices[i] = self.push_vertex(vertex);
@@ -73,18 +77,20 @@ impl BlockMesh {
         &self.vertices
     }
 
-    pub fn indices(&self) -> &Vec<u16> {
+    /// Returns the indices, which are 16 bit unless the mesh has too many vertices for them.
+    pub fn indices(&self) -> &Indices {
         &self.indices
     }
 
     /// Combines another mesh into this mesh.
     pub fn combine(&mut self, other: &BlockMesh) {
-        let index_offset = self.vertices.len() as u16;
+        let index_offset = self.vertices.len() as u32;
 
         self.vertices.extend_from_slice(&other.vertices);
 
-        self.indices
-            .extend(other.indices.iter().map(|&i| i + index_offset));
+        for i in other.indices.iter() {
+            self.indices.push(i + index_offset);
+        }
 
         self.face_count += other.face_count;
     }
@@ -93,17 +99,14 @@ impl BlockMesh {
     pub fn create_buffers(
         &self,
         state: &ComponentStoreHandle,
-    ) -> (VertexBuffer<BlockVertex>, IndexBuffer<u16>) {
+    ) -> (VertexBuffer<BlockVertex>, AnyIndexBuffer) {
         let wgpu = state.get::<WgpuRenderer>();
         let vertex_buffer = wgpu.vertex_buffer::<BlockVertex>(
             bytemuck::cast_slice::<_, BlockVertex>(self.vertices()),
             Some("BlockMesh Vertex Buffer"),
         );
 
-        let index_buffer = wgpu.index_buffer::<u16>(
-            bytemuck::cast_slice::<_, u16>(self.indices()),
-            Some("BlockMesh Index Buffer"),
-        );
+        let index_buffer = wgpu.any_index_buffer(self.indices(), Some("BlockMesh Index Buffer"));
 
         (vertex_buffer, index_buffer)
     }
@@ -112,6 +115,50 @@ impl BlockMesh {
     pub fn face_count(&self) -> usize {
         self.face_count
     }
+
+    /// Exports the mesh as a Wavefront OBJ, for inspecting its geometry outside the engine.
+    pub fn export_obj(&self) -> String {
+        let mut obj = String::new();
+        write_obj_object(
+            &mut obj,
+            "mesh",
+            self.vertices
+                .iter()
+                .map(|vertex| (vertex.position, vertex.tex_coord)),
+            &self.indices,
+            0,
+        );
+        obj
+    }
+}
+
+/// Appends an object to a Wavefront OBJ, as `v`, `vt` and `f` entries. Returns the number of
+/// vertices written.
+///
+/// OBJ indices count vertices across the whole file, so `index_offset` must be the number of
+/// vertices written by the objects before this one.
+pub fn write_obj_object(
+    obj: &mut String,
+    name: &str,
+    vertices: impl IntoIterator<Item = ([f32; 3], [f32; 2])>,
+    indices: &Indices,
+    index_offset: usize,
+) -> usize {
+    // Writing to a `String` can't fail.
+    writeln!(obj, "o {}", name).unwrap();
+    let mut count = 0;
+    for ([x, y, z], [u, v]) in vertices {
+        writeln!(obj, "v {} {} {}", x, y, z).unwrap();
+        writeln!(obj, "vt {} {}", u, v).unwrap();
+        count += 1;
+    }
+    let indices = indices.iter().collect::<Vec<_>>();
+    for triangle in indices.chunks_exact(3) {
+        // Positions and texture coordinates share indices, which start at 1.
+        let [a, b, c] = [0, 1, 2].map(|i| triangle[i] as usize + index_offset + 1);
+        writeln!(obj, "f {a}/{a} {b}/{b} {c}/{c}").unwrap();
+    }
+    count
 }
 
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
@@ -120,6 +167,9 @@ pub struct BlockVertex {
     position: [f32; 3],
     tex_coord: [f32; 2],
     texture_id: u32,
+    /// The direction the vertex's face points in, as a `CardinalDirection` index.
+    normal: u8,
+    _padding: [u8; 3],
 }
 
 impl BlockVertex {
@@ -152,6 +202,7 @@ unsafe impl VertexLayout for BlockVertex {
             0 => Float32x3, // position
             1 => Float32x2, // tex_coord
             2 => Uint32,    // block type
+            3 => Uint8,     // normal
         ],
     };
 }
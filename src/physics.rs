@@ -0,0 +1,307 @@
+use glam::Vec3;
+
+use crate::{BlockPosition, coords::bp, world::World};
+
+/// An axis aligned bounding box in world space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// A box covering a single block, relative to the block's minimum corner.
+    pub const UNIT: Aabb = Aabb::new(Vec3::ZERO, Vec3::ONE);
+
+    /// Creates a box from its minimum and maximum corners.
+    pub const fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// Returns the box moved by the given offset.
+    pub fn offset(&self, by: Vec3) -> Aabb {
+        Aabb::new(self.min + by, self.max + by)
+    }
+
+    /// Returns the box stretched in the direction of `motion`, covering everything it would
+    /// pass through while moving.
+    pub fn expand_towards(&self, motion: Vec3) -> Aabb {
+        Aabb::new(
+            self.min + motion.min(Vec3::ZERO),
+            self.max + motion.max(Vec3::ZERO),
+        )
+    }
+
+    /// Returns true if the boxes overlap. Boxes that only touch don't count.
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.cmplt(other.max).all() && self.max.cmpgt(other.min).all()
+    }
+
+    /// Returns the positions of every block the box overlaps.
+    pub fn overlapping_blocks(&self) -> impl Iterator<Item = BlockPosition> {
+        let min = self.min.floor();
+        let max = self.max.ceil();
+        let (x0, y0, z0) = (min.x as i64, min.y as i64, min.z as i64);
+        let (x1, y1, z1) = (max.x as i64, max.y as i64, max.z as i64);
+        (x0..x1).flat_map(move |x| (y0..y1).flat_map(move |y| (z0..z1).map(move |z| bp(x, y, z))))
+    }
+
+    /// Shortens `motion` along `axis` so that this box stops at the face of `other`.
+    ///
+    /// Motion is only clipped if the boxes overlap on the other two axes and this box starts
+    /// outside `other` on the moving side.
+    fn clip_axis(&self, other: &Aabb, axis: usize, motion: f32) -> f32 {
+        let separated = (0..3)
+            .filter(|&a| a != axis)
+            .any(|a| self.max[a] <= other.min[a] || self.min[a] >= other.max[a]);
+        if separated {
+            return motion;
+        }
+        if motion > 0.0 && self.max[axis] <= other.min[axis] {
+            motion.min(other.min[axis] - self.max[axis])
+        } else if motion < 0.0 && self.min[axis] >= other.max[axis] {
+            motion.max(other.max[axis] - self.min[axis])
+        } else {
+            motion
+        }
+    }
+}
+
+/// Moves `body` by `motion`, stopping at any of the `obstacles`, and returns the motion that was
+/// actually applied.
+///
+/// Axes are resolved one at a time, Y first, so bodies land on floors and slide along walls.
+pub fn sweep(body: Aabb, motion: Vec3, obstacles: &[Aabb]) -> Vec3 {
+    let mut body = body;
+    let mut applied = Vec3::ZERO;
+    for axis in [1, 0, 2] {
+        let delta = obstacles.iter().fold(motion[axis], |delta, obstacle| {
+            body.clip_axis(obstacle, axis, delta)
+        });
+        let mut step = Vec3::ZERO;
+        step[axis] = delta;
+        body = body.offset(step);
+        applied[axis] = delta;
+    }
+    applied
+}
+
+/// How fast a falling player speeds up, in blocks per second squared.
+pub const GRAVITY: f32 = 32.0;
+
+/// The fastest a player can fall, in blocks per second.
+pub const TERMINAL_VELOCITY: f32 = 78.0;
+
+/// The upward speed of a jump, in blocks per second. High enough to clear a single block.
+pub const JUMP_VELOCITY: f32 = 9.0;
+
+/// How fast a player walks, in blocks per second.
+pub const WALK_SPEED: f32 = 4.3;
+
+/// The longest step simulated at once, in seconds, so a long frame doesn't launch the player.
+pub const MAX_STEP: f32 = 0.1;
+
+/// The width and depth of the player's box.
+pub const PLAYER_WIDTH: f32 = 0.6;
+
+/// The height of the player's box.
+pub const PLAYER_HEIGHT: f32 = 1.8;
+
+/// How far the player's eyes are above their feet.
+pub const EYE_HEIGHT: f32 = 1.62;
+
+/// The player's body, moved through the world by `step`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Player {
+    /// The player's box in world space.
+    pub aabb: Aabb,
+    /// The player's velocity, in blocks per second.
+    pub velocity: Vec3,
+    /// Whether the player was standing on something after the last step.
+    pub on_ground: bool,
+    /// Whether gravity and collisions are ignored, so the camera moves freely.
+    pub flying: bool,
+}
+
+impl Player {
+    /// Creates a player standing with their feet centered on `feet`.
+    pub fn new(feet: Vec3) -> Self {
+        let half = Vec3::new(PLAYER_WIDTH / 2.0, 0.0, PLAYER_WIDTH / 2.0);
+        Self {
+            aabb: Aabb::new(feet - half, feet + half + Vec3::Y * PLAYER_HEIGHT),
+            velocity: Vec3::ZERO,
+            on_ground: false,
+            flying: false,
+        }
+    }
+
+    /// Returns the point centered under the player's box.
+    pub fn feet(&self) -> Vec3 {
+        Vec3::new(
+            (self.aabb.min.x + self.aabb.max.x) / 2.0,
+            self.aabb.min.y,
+            (self.aabb.min.z + self.aabb.max.z) / 2.0,
+        )
+    }
+
+    /// Returns where the player's eyes are, for placing the camera.
+    pub fn eye_position(&self) -> Vec3 {
+        self.feet() + Vec3::Y * EYE_HEIGHT
+    }
+
+    /// Moves the player so their eyes are at `eye`, keeping their velocity.
+    pub fn set_eye_position(&mut self, eye: Vec3) {
+        let offset = eye - self.eye_position();
+        self.aabb = self.aabb.offset(offset);
+    }
+
+    /// Sets the horizontal velocity, leaving the vertical velocity to gravity and jumping.
+    pub fn walk(&mut self, velocity: Vec3) {
+        self.velocity.x = velocity.x;
+        self.velocity.z = velocity.z;
+    }
+
+    /// Jumps if the player is standing on something. Returns true if they jumped.
+    pub fn jump(&mut self) -> bool {
+        if !self.on_ground || self.flying {
+            return false;
+        }
+        self.velocity.y = JUMP_VELOCITY;
+        self.on_ground = false;
+        true
+    }
+
+    /// Switches between flying and walking. Flying stops any fall in progress.
+    pub fn toggle_flying(&mut self) {
+        self.flying = !self.flying;
+        self.velocity = Vec3::ZERO;
+        self.on_ground = false;
+    }
+}
+
+/// Moves the player by their velocity over `dt` seconds, applying gravity and stopping at solid
+/// blocks.
+///
+/// Each axis is resolved separately, so a player pushing into a wall slides along it. Velocity
+/// along any axis that hit something is dropped, and the player is on the ground if their fall
+/// was stopped. Flying players aren't moved, as the camera drives them directly.
+pub fn step(world: &World, player: &mut Player, dt: f32) {
+    if player.flying {
+        return;
+    }
+    let dt = dt.min(MAX_STEP);
+    player.velocity.y = (player.velocity.y - GRAVITY * dt).max(-TERMINAL_VELOCITY);
+
+    let motion = player.velocity * dt;
+    let applied = world.sweep_aabb(player.aabb, motion);
+    player.aabb = player.aabb.offset(applied);
+
+    for axis in 0..3 {
+        if applied[axis] != motion[axis] {
+            player.velocity[axis] = 0.0;
+        }
+    }
+    player.on_ground = motion.y < 0.0 && applied.y > motion.y;
+}
+
+#[cfg(test)]
+mod tests {
+    use engine::component::ComponentStore;
+
+    use super::*;
+    use crate::world::Block;
+
+    /// The collision box of a bottom slab, relative to its minimum corner.
+    const BOTTOM_SLAB: Aabb = Aabb::new(Vec3::ZERO, Vec3::new(1.0, 0.5, 1.0));
+
+    #[test]
+    fn falling_onto_a_bottom_slab_stops_half_a_block_up() {
+        let slab = BOTTOM_SLAB.offset(Vec3::new(2.0, 4.0, 0.0));
+        let player = Player::new(Vec3::new(2.5, 6.0, 0.5));
+
+        let applied = sweep(player.aabb, Vec3::new(0.0, -5.0, 0.0), &[slab]);
+        assert_eq!(player.aabb.offset(applied).min.y, 4.5);
+    }
+
+    #[test]
+    fn a_slab_blocks_walking_beside_it_but_not_on_top() {
+        let slab = BOTTOM_SLAB.offset(Vec3::new(2.0, 4.0, 0.0));
+        // Standing on the slab's level, the slab blocks horizontal movement.
+        let beside = Player::new(Vec3::new(1.0, 4.0, 0.5));
+        let applied = sweep(beside.aabb, Vec3::new(2.0, 0.0, 0.0), &[slab]);
+        assert!((beside.aabb.offset(applied).max.x - 2.0).abs() < 1e-5);
+
+        // Standing on top of it, the player walks across freely.
+        let above = Player::new(Vec3::new(1.0, 4.5, 0.5));
+        let motion = Vec3::new(2.0, 0.0, 0.0);
+        assert_eq!(sweep(above.aabb, motion, &[slab]), motion);
+    }
+
+    #[test]
+    fn only_solid_blocks_collide() {
+        let store = ComponentStore::new();
+        store.finish_initialization();
+        let mut world = World::empty(&store.handle());
+        world.set_block(bp(0, 2, 0), Block::Stone);
+        world.set_block(bp(0, 5, 0), Block::Water);
+
+        let mut player = Player::new(Vec3::new(0.5, 8.0, 0.5));
+        for _ in 0..100 {
+            step(&world, &mut player, 1.0 / 60.0);
+        }
+        // The player fell through the water and landed on the stone.
+        assert_eq!(player.feet().y, 3.0);
+        assert!(player.on_ground);
+        assert_eq!(player.velocity.y, 0.0);
+    }
+
+    fn world_with(blocks: impl IntoIterator<Item = BlockPosition>) -> (ComponentStore, World) {
+        let store = ComponentStore::new();
+        store.finish_initialization();
+        let mut world = World::empty(&store.handle());
+        for position in blocks {
+            world.set_block(position, Block::Stone);
+        }
+        (store, world)
+    }
+
+    #[test]
+    fn falls_onto_a_floor_and_jumps() {
+        let floor = (-2..=2).flat_map(|x| (-2..=2).map(move |z| bp(x, 0, z)));
+        let (_store, world) = world_with(floor);
+        let mut player = Player::new(Vec3::new(0.5, 10.0, 0.5));
+        assert!(!player.jump());
+
+        for _ in 0..120 {
+            step(&world, &mut player, 1.0 / 60.0);
+        }
+        assert_eq!(player.feet().y, 1.0);
+        assert!(player.on_ground);
+
+        assert!(player.jump());
+        step(&world, &mut player, 1.0 / 60.0);
+        assert!(player.feet().y > 1.0);
+        assert!(!player.on_ground);
+    }
+
+    #[test]
+    fn fast_players_stop_at_walls() {
+        // A floor, and a wall one block thick at x = 5.
+        let floor = (-2..=8).map(|x| bp(x, 0, 0));
+        let wall = (1..=3).map(|y| bp(5, y, 0));
+        let (_store, world) = world_with(floor.chain(wall));
+        let mut player = Player::new(Vec3::new(0.5, 1.0, 0.5));
+
+        // Far enough in one step to pass the wall if only the end position were checked.
+        player.walk(Vec3::new(600.0, 0.0, 0.0));
+        step(&world, &mut player, MAX_STEP);
+        assert!((player.aabb.max.x - 5.0).abs() < 1e-5, "{:?}", player.aabb);
+        assert_eq!(player.velocity.x, 0.0);
+
+        // Walking diagonally into it slides along it.
+        player.walk(Vec3::new(5.0, 0.0, 5.0));
+        step(&world, &mut player, 0.1);
+        assert!((player.aabb.max.x - 5.0).abs() < 1e-5);
+        assert!(player.feet().z > 0.5);
+    }
+}
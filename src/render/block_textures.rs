@@ -1,36 +1,134 @@
+use std::sync::Mutex;
+
 use engine::graphics::textures::{TextureCollection, TextureHandle};
 
-use crate::world::{Block, block};
+use log::warn;
+
+use crate::world::{Block, BlockRegistry, chunk::Fnv1a};
 
+#[derive(Clone)]
 pub struct BlockTextureAtlas {
-    handles: [TextureHandle; Block::MAX_DISCRIMINANT as usize + 1],
+    /// The texture of each block, indexed by ID. Blocks past the end use the missing texture.
+    handles: Vec<TextureHandle>,
+    layer_count: Option<u32>,
+    /// The first layer of each animated texture, and the layer of its current frame.
+    animated_layers: Vec<(u32, u32)>,
 }
 
 impl BlockTextureAtlas {
     /// Creates a new BlockTextureAtlas from the given TextureCollection.
     pub fn new() -> Self {
         Self {
-            handles: [TextureHandle::null(); Block::MAX_DISCRIMINANT as usize + 1],
+            handles: Vec::new(),
+            layer_count: None,
+            animated_layers: Vec::new(),
         }
     }
 
     /// Gets the texture handle for the given block.
     pub fn get_texture_handle(&self, block: Block) -> TextureHandle {
-        self.handles[block as usize]
+        self.handles
+            .get(block.id() as usize)
+            .copied()
+            .unwrap_or(TextureHandle::null())
     }
 
     /// Sets the texture handle for the given block.
     pub fn set_texture_handle(&mut self, block: Block, handle: TextureHandle) {
-        self.handles[block as usize] = handle;
+        let index = block.id() as usize;
+        if index >= self.handles.len() {
+            self.handles.resize(index + 1, TextureHandle::null());
+        }
+        self.handles[index] = handle;
+    }
+
+    /// Points every registered block at the texture named by its `texture_key` in `collection`.
+    ///
+    /// Blocks whose texture isn't in the collection keep the missing texture, and a warning is
+    /// logged.
+    pub fn assign_textures(&mut self, registry: &BlockRegistry, collection: &TextureCollection) {
+        for (block, properties) in registry.iter() {
+            let Some(key) = &properties.texture_key else {
+                continue;
+            };
+            match collection.get_texture(key) {
+                Some(handle) => self.set_texture_handle(block, *handle),
+                None => warn!(
+                    "Block {:?} uses texture {:?}, which isn't in the block atlas",
+                    properties.name, key
+                ),
+            }
+        }
+    }
+
+    /// Sets the number of layers in the texture array the atlas indexes into, e.g.
+    /// `TextureCollection::layer_count`. Indices past it are replaced with the missing texture.
+    pub fn set_layer_count(&mut self, layer_count: u32) {
+        self.layer_count = Some(layer_count);
+    }
+
+    /// Returns the number of layers indices are checked against, if set.
+    pub fn layer_count(&self) -> Option<u32> {
+        self.layer_count
+    }
+
+    /// Copies the current frame of every animated texture from the collection the atlas indexes
+    /// into, e.g. after `TextureCollection::advance`.
+    pub fn sync_animations(&mut self, collection: &TextureCollection) {
+        self.animated_layers.clear();
+        self.animated_layers.extend(collection.animated_layers());
     }
 
     /// Gets the texture index for the given block and direction.
+    ///
+    /// Animated textures resolve to their current frame, as of the last `sync_animations`.
+    ///
+    /// If the index is past the end of the texture array, layer 0 is returned instead, which is
+    /// where the missing texture lives, and a warning is logged.
     pub fn texture_index(
         &self,
         block: Block,
         direction: engine::graphics::CardinalDirection,
     ) -> u32 {
-        block.id_from(self.get_texture_handle(block), direction)
+        let index = block.id_from(self.get_texture_handle(block), direction);
+        match self.layer_count {
+            Some(layer_count) if index >= layer_count => {
+                warn_once(block, index, layer_count);
+                0
+            }
+            _ => self
+                .animated_layers
+                .iter()
+                .find(|(base, _)| *base == index)
+                .map_or(index, |(_, current)| *current),
+        }
+    }
+
+    /// Returns a checksum of the texture handles and layer count, which changes whenever a
+    /// block's texture indices may have.
+    ///
+    /// Animation frames are left out, so cached meshes aren't thrown away every frame.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = Fnv1a::new();
+        for handle in &self.handles {
+            hasher.write(&handle.base_layer.to_le_bytes());
+            hasher.write(&handle.count.to_le_bytes());
+        }
+        hasher.write(&self.layer_count.unwrap_or(u32::MAX).to_le_bytes());
+        hasher.finish()
+    }
+}
+
+/// Logs an out of range texture index, once per block.
+fn warn_once(block: Block, index: u32, layer_count: u32) {
+    static WARNED: Mutex<Vec<Block>> = Mutex::new(Vec::new());
+    let mut warned = WARNED.lock().unwrap_or_else(|e| e.into_inner());
+    if !warned.contains(&block) {
+        warned.push(block);
+        warn!(
+            "{:?} uses texture layer {} but only {} layers exist, using the missing texture",
+            block, index, layer_count
+        );
     }
 }
 
@@ -39,3 +137,31 @@ impl Default for BlockTextureAtlas {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use engine::graphics::CardinalDirection;
+
+    use super::*;
+
+    #[test]
+    fn out_of_range_layers_use_the_missing_texture() {
+        let mut atlas = BlockTextureAtlas::new();
+        atlas.set_texture_handle(Block::Dirt, TextureHandle::single(1));
+        // Grass spans layers 2 to 4, so its bottom face is past the end of a 4 layer array.
+        atlas.set_texture_handle(Block::Grass, TextureHandle::new(2, 3));
+        atlas.set_texture_handle(Block::Stone, TextureHandle::single(10));
+
+        // Without a layer count, indices aren't checked.
+        assert_eq!(atlas.texture_index(Block::Stone, CardinalDirection::Up), 10);
+
+        atlas.set_layer_count(4);
+        assert_eq!(atlas.texture_index(Block::Dirt, CardinalDirection::Up), 1);
+        assert_eq!(atlas.texture_index(Block::Grass, CardinalDirection::Up), 3);
+        assert_eq!(
+            atlas.texture_index(Block::Grass, CardinalDirection::Down),
+            0
+        );
+        assert_eq!(atlas.texture_index(Block::Stone, CardinalDirection::Up), 0);
+    }
+}
@@ -0,0 +1,131 @@
+use std::cmp::Ordering;
+
+use glam::Vec3;
+
+use crate::BlockPosition;
+
+/// How far the camera has to move, in blocks, before draws are sorted again.
+///
+/// The order only has to be roughly right to help, so resorting every frame isn't worth it.
+pub const RESORT_DISTANCE: f32 = 4.0;
+
+/// The order draws are sorted in, relative to the camera.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    /// Nearest first, so the depth test can reject hidden fragments early. Used for opaque
+    /// geometry.
+    FrontToBack,
+    /// Farthest first, so translucent surfaces blend over what's behind them.
+    BackToFront,
+}
+
+/// Compares two draws by the distance of their centers to `eye`.
+///
+/// Draws at the same distance are ordered by position, so the order is deterministic.
+pub fn compare_distance(
+    eye: Vec3,
+    (a, a_center): (BlockPosition, Vec3),
+    (b, b_center): (BlockPosition, Vec3),
+    direction: SortDirection,
+) -> Ordering {
+    let a_distance = eye.distance_squared(a_center);
+    let b_distance = eye.distance_squared(b_center);
+    let by_distance = match direction {
+        SortDirection::FrontToBack => a_distance.total_cmp(&b_distance),
+        SortDirection::BackToFront => b_distance.total_cmp(&a_distance),
+    };
+    by_distance.then_with(|| (a.0, a.1, a.2).cmp(&(b.0, b.1, b.2)))
+}
+
+/// Returns the center of the chunk at the given chunk position.
+pub fn chunk_center(chunk: BlockPosition) -> Vec3 {
+    let (min, max) = chunk.world_aabb();
+    (min + max) / 2.0
+}
+
+/// A cached, distance sorted list of draws, e.g. chunks or regions.
+#[derive(Debug)]
+pub struct DrawOrder {
+    direction: SortDirection,
+    order: Vec<BlockPosition>,
+    sorted_at: Option<Vec3>,
+}
+
+impl DrawOrder {
+    /// Creates an empty draw order sorting in the given direction.
+    pub fn new(direction: SortDirection) -> Self {
+        Self {
+            direction,
+            order: Vec::new(),
+            sorted_at: None,
+        }
+    }
+
+    /// Forces a resort on the next `update`, e.g. because draws were added or removed.
+    pub fn invalidate(&mut self) {
+        self.sorted_at = None;
+    }
+
+    /// Sorts `draws` by distance to `eye`, if the camera moved at least `RESORT_DISTANCE` since the
+    /// last sort or the order was invalidated. Returns true if the draws were sorted.
+    pub fn update(
+        &mut self,
+        eye: Vec3,
+        draws: impl IntoIterator<Item = (BlockPosition, Vec3)>,
+    ) -> bool {
+        if self
+            .sorted_at
+            .is_some_and(|sorted_at| sorted_at.distance(eye) < RESORT_DISTANCE)
+        {
+            return false;
+        }
+        let mut draws = draws.into_iter().collect::<Vec<_>>();
+        draws.sort_by(|a, b| compare_distance(eye, *a, *b, self.direction));
+        self.order = draws.into_iter().map(|(position, _)| position).collect();
+        self.sorted_at = Some(eye);
+        true
+    }
+
+    /// Returns the draws in sorted order.
+    pub fn order(&self) -> &[BlockPosition] {
+        &self.order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coords::bp;
+
+    #[test]
+    fn comparator_sorts_by_distance_to_the_eye() {
+        let eye = Vec3::new(8.0, 8.0, 8.0);
+        let near = (bp(0, 0, 0), chunk_center(bp(0, 0, 0)));
+        let far = (bp(3, 0, 0), chunk_center(bp(3, 0, 0)));
+        // The same distance from the eye as `far`, on the other side of it.
+        let mirrored = (bp(-3, 0, 0), chunk_center(bp(-3, 0, 0)));
+
+        assert_eq!(
+            compare_distance(eye, near, far, SortDirection::FrontToBack),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_distance(eye, near, far, SortDirection::BackToFront),
+            Ordering::Greater
+        );
+        // Ties are broken by position in both directions, so the order never flickers.
+        for direction in [SortDirection::FrontToBack, SortDirection::BackToFront] {
+            assert_eq!(
+                compare_distance(eye, mirrored, far, direction),
+                Ordering::Less
+            );
+        }
+
+        let mut order = DrawOrder::new(SortDirection::BackToFront);
+        assert!(order.update(eye, [near, far, mirrored]));
+        assert_eq!(order.order(), &[bp(-3, 0, 0), bp(3, 0, 0), bp(0, 0, 0)]);
+        // Small camera moves keep the cached order.
+        assert!(!order.update(eye + Vec3::X, [near]));
+        assert_eq!(order.order().len(), 3);
+    }
+}
@@ -0,0 +1,52 @@
+//! A component store set up for driving render pipelines in tests, on wgpu's no-op backend.
+
+use engine::{
+    component::ComponentStore,
+    debug::DebugRenderer,
+    graphics::{
+        lowlevel::{WgpuRenderer, depth::DepthTexture},
+        time::TimeUniform,
+    },
+    input::camera::CameraController,
+};
+
+use crate::{
+    assets::init_asset_store,
+    render::{
+        lighting::Lighting,
+        pipelines::{breaking::BreakingProgress, debug_lines::DebugLines},
+    },
+    world::{ActiveWorld, BlockRegistry, World},
+};
+
+/// The size of the pretend surface, in pixels.
+pub const SURFACE_SIZE: (u32, u32) = (64, 48);
+
+/// Creates a fully initialized store with everything the render pipelines use, and an active
+/// world filled in by `build`.
+pub fn headless_state(build: impl FnOnce(&mut World)) -> ComponentStore {
+    let mut state = ComponentStore::new();
+    state.insert(BlockRegistry::new());
+    WgpuRenderer::attach_noop(&mut state, SURFACE_SIZE);
+    let camera = CameraController::new(&state);
+    state.insert(camera);
+    state.insert(ActiveWorld::new());
+    let depth_texture = DepthTexture::new(&state);
+    state.insert(depth_texture);
+    let (atlases, atlas, assets) = init_asset_store(&state, &state.get());
+    state.insert(assets);
+    state.insert(atlases);
+    state.insert(atlas);
+    state.insert(BreakingProgress::default());
+    state.insert(DebugLines::new());
+    state.insert(Lighting::default());
+    state.insert(TimeUniform::default());
+    let debug = DebugRenderer::new(&state).expect("failed to create the debug renderer");
+    state.insert(debug);
+    state.finish_initialization();
+
+    let mut world = World::empty(&state.handle());
+    build(&mut world);
+    state.get_mut::<ActiveWorld>().set_world(world);
+    state
+}
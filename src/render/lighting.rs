@@ -0,0 +1,58 @@
+use engine::graphics::CardinalDirection;
+use glam::{Vec3, Vec4};
+
+/// How bright each face is before the sun is taken into account, in `CardinalDirection` order.
+///
+/// Tops are brightest and bottoms darkest, with the sides in between, so the shape of blocks
+/// reads even when the sun is straight overhead.
+pub const FACE_SHADE: [f32; 6] = [0.6, 0.6, 1.0, 0.5, 0.8, 0.8];
+
+/// The light the world is shaded with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lighting {
+    /// The direction towards the sun. Doesn't need to be normalized.
+    pub sun_direction: Vec3,
+    /// How much light reaches faces turned away from the sun, from 0 to 1.
+    pub ambient: f32,
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self {
+            sun_direction: Vec3::new(0.3, 1.0, 0.5),
+            ambient: 0.6,
+        }
+    }
+}
+
+impl Lighting {
+    /// Returns how brightly a face pointing in `face` is lit, from 0 to 1, ignoring ambient
+    /// occlusion. This matches what `chunk_solid.wgsl` computes.
+    pub fn brightness(&self, face: CardinalDirection) -> f32 {
+        let sun = self.sun_direction.try_normalize().unwrap_or(Vec3::Y);
+        let diffuse = face.normal().dot(sun).max(0.0);
+        let ambient = self.ambient.clamp(0.0, 1.0);
+        FACE_SHADE[face as usize] * (ambient + (1.0 - ambient) * diffuse)
+    }
+}
+
+/// The lighting, laid out for `chunk_solid.wgsl`.
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct LightingUniform {
+    /// The normalized direction towards the sun, with the ambient level in `w`.
+    sun: Vec4,
+    /// `FACE_SHADE`, packed four to a vector.
+    face_shade: [Vec4; 2],
+}
+
+impl From<&Lighting> for LightingUniform {
+    fn from(lighting: &Lighting) -> Self {
+        let sun = lighting.sun_direction.try_normalize().unwrap_or(Vec3::Y);
+        let [a, b, c, d, e, f] = FACE_SHADE;
+        Self {
+            sun: sun.extend(lighting.ambient.clamp(0.0, 1.0)),
+            face_shade: [Vec4::new(a, b, c, d), Vec4::new(e, f, 0.0, 0.0)],
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, bail};
+use engine::graphics::lowlevel::buf::Indices;
+
+use crate::{
+    BlockPosition,
+    coords::bp,
+    render::{
+        block_textures::BlockTextureAtlas,
+        pipelines::solid::{ChunkMesh, SolidBlockVertex},
+    },
+    world::{
+        BlockRegistry, Chunk,
+        chunk::{CHUNK_SIZE, Fnv1a},
+    },
+};
+
+/// Identifies mesh cache files.
+const MAGIC: &[u8; 4] = b"QCMC";
+/// Bumped whenever the file layout or the mesher's output changes, invalidating old files.
+const VERSION: u32 = 7;
+/// The size of the file header: magic, version, key, the vertex and index counts of the opaque
+/// and transparent meshes, then the byte width of each mesh's indices.
+const HEADER_LEN: usize = 4 + 4 + 8 + 4 * 4 + 2;
+
+/// Computes the key a chunk's mesh is cached under.
+///
+/// The mesh depends on more than the chunk's own blocks: faces on the chunk's border are culled
+/// against the neighboring chunks, ambient occlusion samples the blocks around its edges and
+/// corners, faces are lit by the light in front of them, and texture indices and block
+/// properties come from the atlas and registry. All of those are hashed together, so any change
+/// to them misses the cache.
+pub fn mesh_key(chunk: &Chunk, atlas: &BlockTextureAtlas, registry: &BlockRegistry) -> u64 {
+    let mut hasher = Fnv1a::new();
+    hasher.write(&chunk.content_hash().to_le_bytes());
+    hasher.write(chunk.light.as_flattened().as_flattened());
+
+    // Every block and light level in the one block thick shell around the chunk.
+    let size = CHUNK_SIZE as i64;
+    for x in -1..=size {
+        for y in -1..=size {
+            for z in -1..=size {
+                let inside = [x, y, z].iter().all(|c| (0..size).contains(c));
+                if !inside {
+                    hasher.write(&chunk.inspect_block_relative(bp(x, y, z)).id().to_le_bytes());
+                    hasher.write_u8(chunk.light_relative(bp(x, y, z)));
+                }
+            }
+        }
+    }
+
+    hasher.write(&atlas.fingerprint().to_le_bytes());
+    hasher.write(&registry.fingerprint().to_le_bytes());
+    hasher.finish()
+}
+
+/// An on-disk cache of solid chunk meshes, so static worlds don't have to be remeshed on load.
+///
+/// Each chunk's mesh is stored in its own file in the cache directory, tagged with the key from
+/// `mesh_key`. A lookup only hits if the stored key matches, so stale meshes are never used.
+#[derive(Debug, Clone)]
+pub struct MeshCache {
+    dir: PathBuf,
+}
+
+impl MeshCache {
+    /// Creates a cache storing meshes in the given directory, e.g. next to the world's files.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Returns the directory meshes are stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the path the mesh of the chunk at `position` is stored at.
+    pub fn path_for(&self, position: BlockPosition) -> PathBuf {
+        self.dir.join(format!(
+            "mesh_{}_{}_{}.bin",
+            position.0, position.1, position.2
+        ))
+    }
+
+    /// Loads the cached mesh of the chunk at `position`, if one exists with the given key.
+    pub fn load(&self, position: BlockPosition, key: u64) -> Option<ChunkMesh> {
+        let bytes = std::fs::read(self.path_for(position)).ok()?;
+        decode_mesh(&bytes, key).ok()
+    }
+
+    /// Stores the mesh of the chunk at `position` under the given key, replacing any old one.
+    pub fn store(&self, position: BlockPosition, key: u64, mesh: &ChunkMesh) -> anyhow::Result<()> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create mesh cache {}", self.dir.display()))?;
+        let path = self.path_for(position);
+        std::fs::write(&path, encode_mesh(key, mesh))
+            .with_context(|| format!("Failed to write cached mesh {}", path.display()))
+    }
+}
+
+/// Serializes a mesh and its key.
+fn encode_mesh(key: u64, mesh: &ChunkMesh) -> Vec<u8> {
+    let parts: [&[u8]; 4] = [
+        bytemuck::cast_slice(&mesh.vertices),
+        mesh.indices.as_bytes(),
+        bytemuck::cast_slice(&mesh.transparent_vertices),
+        mesh.transparent_indices.as_bytes(),
+    ];
+    let mut bytes = Vec::with_capacity(HEADER_LEN + parts.iter().map(|p| p.len()).sum::<usize>());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&key.to_le_bytes());
+    for count in [
+        mesh.vertices.len(),
+        mesh.indices.len(),
+        mesh.transparent_vertices.len(),
+        mesh.transparent_indices.len(),
+    ] {
+        bytes.extend_from_slice(&(count as u32).to_le_bytes());
+    }
+    for indices in [&mesh.indices, &mesh.transparent_indices] {
+        bytes.push(indices.format().byte_size() as u8);
+    }
+    for part in parts {
+        bytes.extend_from_slice(part);
+    }
+    bytes
+}
+
+/// Deserializes a mesh, failing if it's malformed or wasn't stored under `key`.
+fn decode_mesh(bytes: &[u8], key: u64) -> anyhow::Result<ChunkMesh> {
+    if bytes.len() < HEADER_LEN || &bytes[0..4] != MAGIC {
+        bail!("Not a mesh cache file");
+    }
+    let read_u32 = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+    if read_u32(4) != VERSION {
+        bail!("Mesh cache file has an old version");
+    }
+    if u64::from_le_bytes(bytes[8..16].try_into().unwrap()) != key {
+        bail!("Mesh cache file is stale");
+    }
+    let [index_width, transparent_index_width] = [bytes[32], bytes[33]].map(usize::from);
+    if [index_width, transparent_index_width]
+        .iter()
+        .any(|width| ![size_of::<u16>(), size_of::<u32>()].contains(width))
+    {
+        bail!("Mesh cache file has an invalid index width");
+    }
+    let vertex_len = read_u32(16) as usize * size_of::<SolidBlockVertex>();
+    let index_len = read_u32(20) as usize * index_width;
+    let transparent_vertex_len = read_u32(24) as usize * size_of::<SolidBlockVertex>();
+    let transparent_index_len = read_u32(28) as usize * transparent_index_width;
+    if bytes.len()
+        != HEADER_LEN + vertex_len + index_len + transparent_vertex_len + transparent_index_len
+    {
+        bail!("Mesh cache file is truncated");
+    }
+
+    // The data isn't necessarily aligned, so copy it out instead of casting in place.
+    let mut rest = &bytes[HEADER_LEN..];
+    let mut take = |len: usize| {
+        let (part, tail) = rest.split_at(len);
+        rest = tail;
+        part
+    };
+    let indices = |bytes: &[u8], width: usize| {
+        if width == size_of::<u16>() {
+            Indices::U16(bytemuck::pod_collect_to_vec(bytes))
+        } else {
+            Indices::U32(bytemuck::pod_collect_to_vec(bytes))
+        }
+    };
+    Ok(ChunkMesh {
+        vertices: bytemuck::pod_collect_to_vec(take(vertex_len)),
+        indices: indices(take(index_len), index_width),
+        transparent_vertices: bytemuck::pod_collect_to_vec(take(transparent_vertex_len)),
+        transparent_indices: indices(take(transparent_index_len), transparent_index_width),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use engine::graphics::textures::TextureHandle;
+
+    use super::*;
+    use crate::{render::pipelines::solid::build_mesh_for_chunk, world::Block};
+
+    fn atlas() -> BlockTextureAtlas {
+        let mut atlas = BlockTextureAtlas::new();
+        for block in crate::world::block::BUILTIN_BLOCKS {
+            atlas.set_texture_handle(block, TextureHandle::new(0, 6));
+        }
+        atlas
+    }
+
+    fn chunk() -> Chunk {
+        let mut blocks = [[[Block::Air; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+        blocks[3][3][3] = Block::Stone;
+        blocks[3][4][3] = Block::Grass;
+        blocks[7][2][9] = Block::OakLeaves;
+        Chunk::from_blocks(blocks)
+    }
+
+    /// Returns the mesh as raw bytes, for comparing meshes.
+    fn mesh_bytes(mesh: &ChunkMesh) -> Vec<u8> {
+        encode_mesh(0, mesh)
+    }
+
+    #[test]
+    fn cache_hit_matches_a_fresh_build() {
+        let (atlas, registry, chunk) = (atlas(), BlockRegistry::new(), chunk());
+        let position = bp(2, 0, -3);
+        let mesh = build_mesh_for_chunk(&atlas, &registry, &chunk, position.chunk_origin());
+        let key = mesh_key(&chunk, &atlas, &registry);
+
+        let cache =
+            MeshCache::new(std::env::temp_dir().join(format!("mesh-cache-{}", std::process::id())));
+        cache.store(position, key, &mesh).unwrap();
+        let hit = cache.load(position, key);
+        // A different key, as after an edit, misses.
+        let miss = cache.load(position, key ^ 1);
+        std::fs::remove_dir_all(cache.dir()).unwrap();
+
+        let hit = hit.expect("cached mesh wasn't found");
+        assert_eq!(mesh_bytes(&hit), mesh_bytes(&mesh));
+        assert!(!hit.transparent_indices.is_empty());
+        assert!(miss.is_none());
+    }
+
+    #[test]
+    fn key_follows_the_blocks() {
+        let (atlas, registry) = (atlas(), BlockRegistry::new());
+        let mut chunk = chunk();
+        let key = mesh_key(&chunk, &atlas, &registry);
+        assert_eq!(mesh_key(&chunk.clone(), &atlas, &registry), key);
+        chunk[(0, 0, 0)] = Block::Dirt;
+        assert_ne!(mesh_key(&chunk, &atlas, &registry), key);
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::{BlockPosition, coords::bp, world::World};
+
+/// The order chunks are meshed in.
+///
+/// Faces on a chunk's border are culled against its neighbors, so a chunk meshed before a
+/// neighbor is loaded gets faces along that border that shouldn't be there. To avoid these seams,
+/// chunks missing a neighbor are deferred while other chunks are still queued, since one of those
+/// may be the missing neighbor. Once nothing else is queued, the deferred chunks are on the edge
+/// of the loaded world and are meshed as they are. When a neighbor loads later, queue it with
+/// `push_with_neighbors` so the chunks around it are remeshed against it.
+#[derive(Debug, Default)]
+pub struct MeshQueue {
+    pending: VecDeque<BlockPosition>,
+    deferred: VecDeque<BlockPosition>,
+    queued: HashSet<BlockPosition>,
+}
+
+impl MeshQueue {
+    /// Creates an empty queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a chunk to be meshed. Returns false if it was already queued.
+    pub fn push(&mut self, chunk: BlockPosition) -> bool {
+        if !self.queued.insert(chunk) {
+            // Give deferred chunks another chance, as the chunk may have changed.
+            if let Some(index) = self.deferred.iter().position(|c| *c == chunk) {
+                self.deferred.remove(index);
+                self.pending.push_back(chunk);
+            }
+            return false;
+        }
+        self.pending.push_back(chunk);
+        true
+    }
+
+    /// Queues a chunk along with every loaded chunk around it, e.g. after the chunk was loaded or
+    /// one of its border blocks changed. Returns how many chunks were newly queued.
+    ///
+    /// Diagonal neighbors are included, since ambient occlusion along a chunk's edges and corners
+    /// samples the blocks in them.
+    pub fn push_with_neighbors(&mut self, chunk: BlockPosition, world: &World) -> usize {
+        let mut added = self.push(chunk) as usize;
+        for x in -1..=1 {
+            for y in -1..=1 {
+                for z in -1..=1 {
+                    let neighbor = chunk + bp(x, y, z);
+                    if neighbor != chunk && world.contains_chunk(neighbor) {
+                        added += self.push(neighbor) as usize;
+                    }
+                }
+            }
+        }
+        added
+    }
+
+    /// Takes the next chunk that should be meshed, deferring chunks that are missing neighbors
+    /// while others are still queued.
+    ///
+    /// Chunks that aren't loaded are returned straight away, so their meshes can be removed.
+    pub fn pop_ready(&mut self, world: &World) -> Option<BlockPosition> {
+        while let Some(chunk) = self.pending.pop_front() {
+            let complete = world
+                .chunk(chunk)
+                .is_none_or(|c| c.get().has_all_neighbors());
+            if complete {
+                self.queued.remove(&chunk);
+                return Some(chunk);
+            }
+            self.deferred.push_back(chunk);
+        }
+        let chunk = self.deferred.pop_front()?;
+        self.queued.remove(&chunk);
+        Some(chunk)
+    }
+
+    /// Returns true if `chunk` is waiting for its neighbors to load.
+    pub fn is_deferred(&self, chunk: BlockPosition) -> bool {
+        self.deferred.contains(&chunk)
+    }
+
+    /// Returns the number of queued chunks, including deferred ones.
+    pub fn len(&self) -> usize {
+        self.queued.len()
+    }
+
+    /// Returns true if the chunk is waiting to be meshed.
+    pub fn contains(&self, chunk: BlockPosition) -> bool {
+        self.queued.contains(&chunk)
+    }
+
+    /// Returns true if no chunks are queued.
+    pub fn is_empty(&self) -> bool {
+        self.queued.is_empty()
+    }
+
+    /// Removes a chunk from the queue, e.g. because it was unloaded. Returns false if it wasn't
+    /// queued.
+    pub fn remove(&mut self, chunk: BlockPosition) -> bool {
+        if !self.queued.remove(&chunk) {
+            return false;
+        }
+        self.pending.retain(|c| *c != chunk);
+        self.deferred.retain(|c| *c != chunk);
+        true
+    }
+
+    /// Removes every queued chunk.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.deferred.clear();
+        self.queued.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use engine::{component::ComponentStore, graphics::textures::TextureHandle};
+
+    use super::*;
+    use crate::{
+        render::{block_textures::BlockTextureAtlas, pipelines::solid::build_mesh_for_chunk},
+        world::{
+            Block, BlockRegistry, Chunk,
+            chunk::{CHUNK_SIZE, ChunkBlocks},
+        },
+    };
+
+    fn face_count(world: &World, chunk: BlockPosition) -> usize {
+        let registry = BlockRegistry::new();
+        let mut atlas = BlockTextureAtlas::new();
+        for block in crate::world::block::BUILTIN_BLOCKS {
+            atlas.set_texture_handle(block, TextureHandle::new(0, 6));
+        }
+        let mesh = build_mesh_for_chunk(
+            &atlas,
+            &registry,
+            &world.chunk(chunk).unwrap().get(),
+            chunk.chunk_origin(),
+        );
+        mesh.vertices.len() / 4
+    }
+
+    #[test]
+    fn chunks_missing_neighbors_wait_for_them() {
+        let store = ComponentStore::new();
+        store.finish_initialization();
+        let mut world = World::empty(&store.handle());
+        let stone: ChunkBlocks<CHUNK_SIZE> = [[[Block::Stone; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+        let edge = bp(0, 0, 0);
+        let neighbor = bp(1, 0, 0);
+        world.insert_chunk(edge, Chunk::from_blocks(stone));
+
+        let mut queue = MeshQueue::new();
+        queue.push(edge);
+        queue.push(neighbor);
+        // The neighbor isn't loaded, so it goes first to have its mesh removed, while the edge
+        // chunk waits in case its neighbors arrive.
+        assert_eq!(queue.pop_ready(&world), Some(neighbor));
+        assert!(queue.is_deferred(edge));
+        // Nothing else is queued, so the edge chunk is meshed as it is, with its border faces.
+        assert_eq!(queue.pop_ready(&world), Some(edge));
+        assert!(queue.is_empty());
+        let side = CHUNK_SIZE * CHUNK_SIZE;
+        assert_eq!(face_count(&world, edge), 6 * side);
+
+        // Loading the neighbor queues the edge chunk again, which now culls the shared border.
+        world.insert_chunk(neighbor, Chunk::from_blocks(stone));
+        queue.push_with_neighbors(neighbor, &world);
+        assert!(queue.contains(edge));
+        let mut meshed = Vec::new();
+        while let Some(chunk) = queue.pop_ready(&world) {
+            meshed.push(chunk);
+        }
+        assert!(meshed.contains(&edge));
+        assert_eq!(face_count(&world, edge), 5 * side);
+    }
+
+    #[test]
+    fn removed_chunks_are_not_meshed() {
+        let store = ComponentStore::new();
+        store.finish_initialization();
+        let mut world = World::empty(&store.handle());
+        let stone: ChunkBlocks<CHUNK_SIZE> = [[[Block::Stone; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+        let (kept, unloaded) = (bp(0, 0, 0), bp(1, 0, 0));
+        world.insert_chunk(kept, Chunk::from_blocks(stone));
+
+        let mut queue = MeshQueue::new();
+        queue.push(kept);
+        queue.push(unloaded);
+        // Queue the chunk not in the world again after it's popped, while the other one waits
+        // for it as a deferred chunk.
+        assert_eq!(queue.pop_ready(&world), Some(unloaded));
+        assert!(queue.is_deferred(kept));
+        queue.push(unloaded);
+
+        assert!(queue.remove(unloaded));
+        assert!(!queue.remove(unloaded));
+        assert!(queue.remove(kept));
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop_ready(&world), None);
+    }
+}
@@ -1,13 +1,28 @@
 use engine::graphics::pipeline::controller::PipelineKey;
 
 pub mod block_textures;
+pub mod draw_order;
+#[cfg(test)]
+pub(crate) mod headless;
+pub mod lighting;
+pub mod mesh_cache;
+pub mod mesh_queue;
 pub mod pipelines;
 
 /// A collection of render pipelines.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RenderPipelines {
     Clear,
+    Sky,
     SolidGeometry,
+    Fluid,
+    BreakingOverlay,
+    Particles,
+    DebugLines,
+    Minimap,
+    FrameGraph,
+    Hud,
+    PostProcess,
 }
 
 impl PipelineKey for RenderPipelines {}
@@ -0,0 +1,229 @@
+use engine::{
+    component::{ComponentHandle, ComponentStore},
+    graphics::{
+        CardinalDirection,
+        atlases::{AtlasBinding, TextureAtlases},
+        lowlevel::{WgpuRenderer, buf::VertexBuffer, depth::DepthTexture, pipeline::WgpuPipeline},
+        pipeline::{RenderPipeline, controller::PipelineKey},
+    },
+    input::camera::CameraController,
+};
+use glam::{Vec2, Vec3};
+use log::info;
+
+use crate::{
+    BlockPosition, FACE_INDICES, FACE_TABLE, assets::DESTROY_STAGE_ATLAS,
+    render::pipelines::solid::SolidBlockVertex,
+};
+
+/// The number of destroy stage textures.
+pub const DESTROY_STAGE_COUNT: u32 = 10;
+
+/// How far the overlay cube is pushed out from the block, to avoid z-fighting with its faces.
+const OVERLAY_INFLATE: f32 = 0.002;
+
+/// Maps a mining progress fraction to a destroy stage index.
+///
+/// Returns `None` when there is no progress yet, otherwise a stage in `0..DESTROY_STAGE_COUNT`.
+/// Progress past 1.0 stays on the last stage.
+pub fn destroy_stage(progress: f32) -> Option<u32> {
+    if progress.is_nan() || progress <= 0.0 {
+        return None;
+    }
+    let stage = (progress * DESTROY_STAGE_COUNT as f32) as u32;
+    Some(stage.min(DESTROY_STAGE_COUNT - 1))
+}
+
+/// The block currently being mined and how far along it is.
+///
+/// The breaking overlay pipeline reads this every frame.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BreakingProgress {
+    /// The block being mined, if any.
+    pub target: Option<BlockPosition>,
+    /// Mining progress, from 0.0 (just started) to 1.0 (broken).
+    pub progress: f32,
+}
+
+impl BreakingProgress {
+    /// Returns the targeted block and its destroy stage, if the overlay should be drawn.
+    pub fn stage(&self) -> Option<(BlockPosition, u32)> {
+        Some((self.target?, destroy_stage(self.progress)?))
+    }
+}
+
+/// Draws the destroy stage texture over the faces of the block being mined.
+pub struct BreakingOverlayPipeline {
+    wgpu: ComponentHandle<WgpuRenderer>,
+    progress: ComponentHandle<BreakingProgress>,
+    depth_texture: ComponentHandle<DepthTexture>,
+    textures: AtlasBinding,
+    camera_bind_group: Option<wgpu::BindGroup>,
+    pipeline: Option<WgpuPipeline>,
+    cube: Option<VertexBuffer<SolidBlockVertex>>,
+    drawn_stage: Option<(BlockPosition, u32)>,
+}
+
+impl BreakingOverlayPipeline {
+    pub fn new(csh: &ComponentStore) -> BreakingOverlayPipeline {
+        let textures = csh
+            .get::<TextureAtlases>()
+            .bind(DESTROY_STAGE_ATLAS, 1, 0)
+            .expect("failed to bind the destroy stage atlas");
+
+        let mut new = Self {
+            wgpu: csh.handle_for(),
+            progress: csh.handle_for(),
+            depth_texture: csh.handle_for(),
+            textures,
+            camera_bind_group: None,
+            pipeline: None,
+            cube: None,
+            drawn_stage: None,
+        };
+
+        new.create_pipeline(&csh.get::<CameraController>());
+
+        new
+    }
+
+    fn create_pipeline(&mut self, camera: &CameraController) {
+        let wgpu = self.wgpu.get();
+        let mut builder = wgpu
+            .pipeline_builder("Breaking Overlay Pipeline")
+            .shader(
+                "Breaking Overlay Shader",
+                include_str!("../../../shaders/breaking_overlay.wgsl"),
+                Some("vs"),
+                Some("fs"),
+            )
+            .add_vertex_layout::<SolidBlockVertex>()
+            .add_blended_color_target(wgpu.config.get().format, wgpu::BlendState::ALPHA_BLENDING);
+
+        let (camera_bind_group_layout, camera_bind_group) = camera.bind_group(0);
+        self.camera_bind_group = Some(camera_bind_group);
+        builder = builder.push_bind_group(camera_bind_group_layout);
+
+        // Test against the world's depth, but don't write so the overlay never hides anything.
+        builder = builder.depth(wgpu::DepthStencilState {
+            depth_write_enabled: false,
+            ..self.depth_texture.get().state()
+        });
+
+        builder = builder.push_bind_group(self.textures.layout().clone());
+
+        info!("Creating Breaking Overlay Pipeline: {:#?}", builder);
+
+        self.pipeline = Some(builder.build(None));
+    }
+
+    /// Builds the slightly inflated cube drawn around the target block.
+    fn build_cube(position: BlockPosition, stage: u32) -> Vec<SolidBlockVertex> {
+        let base = Vec3::new(position.0 as f32, position.1 as f32, position.2 as f32);
+        let mut vertices = Vec::with_capacity(36);
+        for direction in CardinalDirection::iter() {
+            let corner = |i: usize| {
+                let (pos, uv) = FACE_TABLE[direction as usize][i];
+                let offset = Vec3::from(pos) * (1.0 + 2.0 * OVERLAY_INFLATE) - OVERLAY_INFLATE;
+                SolidBlockVertex::new(base + offset, Vec2::from(uv), stage, direction)
+            };
+            for &i in FACE_INDICES.iter() {
+                vertices.push(corner(i as usize));
+            }
+        }
+        vertices
+    }
+}
+
+impl<K: PipelineKey> RenderPipeline<K> for BreakingOverlayPipeline {
+    fn label(&self) -> Option<&str> {
+        Some("Breaking Overlay Pipeline")
+    }
+
+    fn update(&mut self) -> Option<engine::graphics::pipeline::UpdateRequest> {
+        self.textures.refresh();
+
+        let stage = self.progress.get().stage();
+        if stage != self.drawn_stage {
+            self.drawn_stage = stage;
+            self.cube = stage.map(|(position, stage)| {
+                self.wgpu.get().vertex_buffer(
+                    &Self::build_cube(position, stage),
+                    Some("Breaking Overlay Cube"),
+                )
+            });
+        }
+        None
+    }
+
+    fn render(
+        &self,
+        controller: &engine::graphics::pipeline::controller::RenderController<K>,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+    ) {
+        let Some(cube) = &self.cube else {
+            return;
+        };
+
+        let depth_texture = self.depth_texture.get();
+        let mut render_pass = controller
+            .pass(target)
+            .label("Breaking Overlay Pipeline Render Pass")
+            .depth(depth_texture.attachment())
+            .begin(encoder);
+
+        let pipeline = self
+            .pipeline
+            .as_ref()
+            .expect("Breaking Overlay Pipeline not created");
+
+        render_pass.set_pipeline(&pipeline.pipeline);
+        if let Some(ref camera_bind_group) = self.camera_bind_group {
+            render_pass.set_bind_group(0, camera_bind_group, &[]);
+        }
+        render_pass.set_bind_group(1, self.textures.bind_group(), &[]);
+        render_pass.set_vertex_buffer(0, cube.buffer().slice(..));
+        render_pass.draw(0..cube.count() as u32, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{coords::bp, render::headless::headless_state};
+
+    #[test]
+    fn progress_maps_to_stages() {
+        assert_eq!(destroy_stage(0.0), None);
+        assert_eq!(destroy_stage(-0.5), None);
+        assert_eq!(destroy_stage(f32::NAN), None);
+        assert_eq!(destroy_stage(0.01), Some(0));
+        assert_eq!(destroy_stage(0.099), Some(0));
+        assert_eq!(destroy_stage(0.1), Some(1));
+        assert_eq!(destroy_stage(0.55), Some(5));
+        assert_eq!(destroy_stage(0.95), Some(9));
+        assert_eq!(destroy_stage(1.0), Some(9));
+        assert_eq!(destroy_stage(3.0), Some(DESTROY_STAGE_COUNT - 1));
+    }
+
+    #[test]
+    fn stage_needs_a_target() {
+        let mut progress = BreakingProgress {
+            target: None,
+            progress: 0.5,
+        };
+        assert_eq!(progress.stage(), None);
+        progress.target = Some(bp(1, 2, 3));
+        assert_eq!(progress.stage(), Some((bp(1, 2, 3), 5)));
+        progress.progress = 0.0;
+        assert_eq!(progress.stage(), None);
+    }
+
+    #[test]
+    fn overlay_pipeline_builds() {
+        let state = headless_state(|_| {});
+        let overlay = BreakingOverlayPipeline::new(&state);
+        assert!(overlay.pipeline.is_some());
+    }
+}
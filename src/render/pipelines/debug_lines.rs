@@ -0,0 +1,268 @@
+use engine::{
+    component::{ComponentHandle, ComponentStore},
+    debug::DebugRenderer,
+    graphics::{
+        lowlevel::{
+            WgpuRenderer,
+            buf::{VertexBuffer, VertexLayout},
+            depth::DepthTexture,
+            pipeline::WgpuPipeline,
+        },
+        pipeline::{RenderPipeline, controller::PipelineKey},
+    },
+    input::camera::CameraController,
+};
+use glam::Vec3;
+use log::info;
+
+/// Debug lines queued for the current frame.
+///
+/// Anything can queue lines during an update; the debug lines pipeline takes them every frame,
+/// so lines have to be re-queued each frame to stay visible.
+#[derive(Debug, Clone, Default)]
+pub struct DebugLines {
+    vertices: Vec<DebugLineVertex>,
+}
+
+impl DebugLines {
+    /// Creates an empty set of debug lines.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a line from `a` to `b`.
+    pub fn draw_line(&mut self, a: Vec3, b: Vec3, color: [f32; 4]) {
+        self.vertices.push(DebugLineVertex::new(a, color));
+        self.vertices.push(DebugLineVertex::new(b, color));
+    }
+
+    /// Queues the twelve edges of the axis aligned box between `min` and `max`.
+    pub fn draw_box(&mut self, min: Vec3, max: Vec3, color: [f32; 4]) {
+        let corner = |x: bool, y: bool, z: bool| {
+            Vec3::new(
+                if x { max.x } else { min.x },
+                if y { max.y } else { min.y },
+                if z { max.z } else { min.z },
+            )
+        };
+        for a in [false, true] {
+            for b in [false, true] {
+                self.draw_line(corner(false, a, b), corner(true, a, b), color);
+                self.draw_line(corner(a, false, b), corner(a, true, b), color);
+                self.draw_line(corner(a, b, false), corner(a, b, true), color);
+            }
+        }
+    }
+
+    /// Returns the queued line vertices, two per line.
+    pub fn vertices(&self) -> &[DebugLineVertex] {
+        &self.vertices
+    }
+
+    /// Returns true if no lines are queued.
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// Removes all queued lines.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+
+    /// Removes and returns all queued line vertices.
+    pub fn take(&mut self) -> Vec<DebugLineVertex> {
+        std::mem::take(&mut self.vertices)
+    }
+}
+
+/// Draws the queued `DebugLines` as a line list while the debug overlay is enabled.
+pub struct DebugLinesPipeline {
+    wgpu: ComponentHandle<WgpuRenderer>,
+    lines: ComponentHandle<DebugLines>,
+    debug: ComponentHandle<DebugRenderer>,
+    depth_texture: ComponentHandle<DepthTexture>,
+    camera_bind_group: Option<wgpu::BindGroup>,
+    pipeline: Option<WgpuPipeline>,
+    buffer: Option<VertexBuffer<DebugLineVertex>>,
+}
+
+impl DebugLinesPipeline {
+    pub fn new(csh: &ComponentStore) -> DebugLinesPipeline {
+        let mut new = Self {
+            wgpu: csh.handle_for(),
+            lines: csh.handle_for(),
+            debug: csh.handle_for(),
+            depth_texture: csh.handle_for(),
+            camera_bind_group: None,
+            pipeline: None,
+            buffer: None,
+        };
+
+        new.create_pipeline(&csh.get::<CameraController>());
+
+        new
+    }
+
+    fn create_pipeline(&mut self, camera: &CameraController) {
+        let wgpu = self.wgpu.get();
+        let mut builder = wgpu
+            .pipeline_builder("Debug Lines Pipeline")
+            .shader(
+                "Debug Lines Shader",
+                include_str!("../../../shaders/debug_lines.wgsl"),
+                Some("vs"),
+                Some("fs"),
+            )
+            .add_vertex_layout::<DebugLineVertex>()
+            .add_color_target(wgpu.config.get().format)
+            .primitive_state(wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                ..Default::default()
+            });
+
+        let (camera_bind_group_layout, camera_bind_group) = camera.bind_group(0);
+        self.camera_bind_group = Some(camera_bind_group);
+        builder = builder.push_bind_group(camera_bind_group_layout);
+
+        // Lines are hidden behind terrain, but don't occlude anything themselves.
+        builder = builder.depth(wgpu::DepthStencilState {
+            depth_write_enabled: false,
+            ..self.depth_texture.get().state()
+        });
+
+        info!("Creating Debug Lines Pipeline: {:#?}", builder);
+
+        self.pipeline = Some(builder.build(None));
+    }
+}
+
+impl<K: PipelineKey> RenderPipeline<K> for DebugLinesPipeline {
+    fn label(&self) -> Option<&str> {
+        Some("Debug Lines Pipeline")
+    }
+
+    fn update(&mut self) -> Option<engine::graphics::pipeline::UpdateRequest> {
+        let vertices = self.lines.get_mut().take();
+        self.buffer = if self.debug.get().enabled && !vertices.is_empty() {
+            Some(
+                self.wgpu
+                    .get()
+                    .vertex_buffer(&vertices, Some("Debug Lines Buffer")),
+            )
+        } else {
+            None
+        };
+        None
+    }
+
+    fn render(
+        &self,
+        controller: &engine::graphics::pipeline::controller::RenderController<K>,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+    ) {
+        let Some(buffer) = &self.buffer else {
+            return;
+        };
+
+        let depth_texture = self.depth_texture.get();
+        let mut render_pass = controller
+            .pass(target)
+            .label("Debug Lines Pipeline Render Pass")
+            .depth(depth_texture.attachment())
+            .begin(encoder);
+
+        let pipeline = self
+            .pipeline
+            .as_ref()
+            .expect("Debug Lines Pipeline not created");
+
+        render_pass.set_pipeline(&pipeline.pipeline);
+        if let Some(ref camera_bind_group) = self.camera_bind_group {
+            render_pass.set_bind_group(0, camera_bind_group, &[]);
+        }
+        render_pass.set_vertex_buffer(0, buffer.buffer().slice(..));
+        render_pass.draw(0..buffer.count() as u32, 0..1);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct DebugLineVertex {
+    pub position: Vec3,
+    pub color: [f32; 4],
+}
+
+impl DebugLineVertex {
+    pub fn new(position: Vec3, color: [f32; 4]) -> Self {
+        Self { position, color }
+    }
+}
+
+unsafe impl VertexLayout for DebugLineVertex {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<DebugLineVertex>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &wgpu::vertex_attr_array![
+            0 => Float32x3, // position
+            1 => Float32x4, // color
+        ],
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{RenderPipelines, headless::headless_state};
+
+    const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+    #[test]
+    fn queued_lines_produce_two_vertices_each() {
+        let mut lines = DebugLines::new();
+        assert!(lines.is_empty());
+        lines.draw_line(Vec3::ZERO, Vec3::ONE, RED);
+        assert_eq!(lines.vertices().len(), 2);
+        lines.draw_box(Vec3::ZERO, Vec3::ONE, RED);
+        assert_eq!(lines.vertices().len(), 2 + 24);
+
+        // Every box edge runs along one axis and has length 1.
+        for edge in lines.vertices()[2..].chunks(2) {
+            let delta = (edge[1].position - edge[0].position).abs();
+            assert_eq!(delta.x + delta.y + delta.z, 1.0);
+        }
+
+        assert_eq!(lines.take().len(), 26);
+        assert!(lines.is_empty());
+    }
+
+    #[test]
+    fn pipeline_takes_lines_each_frame() {
+        let state = headless_state(|_| {});
+        let mut pipeline = DebugLinesPipeline::new(&state);
+        let update = |pipeline: &mut DebugLinesPipeline| {
+            RenderPipeline::<RenderPipelines>::update(pipeline);
+        };
+
+        state.get_mut::<DebugRenderer>().enabled = true;
+        state
+            .get_mut::<DebugLines>()
+            .draw_box(Vec3::ZERO, Vec3::ONE, RED);
+        update(&mut pipeline);
+        assert_eq!(pipeline.buffer.as_ref().unwrap().count(), 24);
+        assert!(state.get::<DebugLines>().is_empty());
+
+        // Nothing was queued again, so nothing is drawn.
+        update(&mut pipeline);
+        assert!(pipeline.buffer.is_none());
+
+        // Lines are dropped while the overlay is hidden.
+        state.get_mut::<DebugRenderer>().enabled = false;
+        state
+            .get_mut::<DebugLines>()
+            .draw_line(Vec3::ZERO, Vec3::ONE, RED);
+        update(&mut pipeline);
+        assert!(pipeline.buffer.is_none());
+        assert!(state.get::<DebugLines>().is_empty());
+    }
+}
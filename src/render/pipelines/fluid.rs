@@ -0,0 +1,367 @@
+use std::collections::{HashMap, HashSet};
+
+use engine::{
+    component::{ComponentHandle, ComponentStore},
+    graphics::{
+        CardinalDirection,
+        lowlevel::{
+            WgpuRenderer,
+            buf::{AnyIndexBuffer, Indices, VertexBuffer, VertexLayout},
+            depth::DepthTexture,
+            pipeline::WgpuPipeline,
+        },
+        pipeline::{RenderPipeline, controller::PipelineKey},
+    },
+    input::camera::CameraController,
+    profile_span,
+};
+use glam::{Vec2, Vec3};
+use log::info;
+
+use crate::{
+    BlockPosition, FACE_INDICES, FACE_TABLE,
+    coords::bp,
+    render::draw_order::{DrawOrder, SortDirection, chunk_center},
+    world::{ActiveWorld, ChunkN},
+};
+
+/// How far the top face of a fluid is lowered when there is no fluid above it.
+pub const FLUID_SURFACE_DROP: f32 = 0.125;
+
+/// Builds the fluid surface mesh for a chunk.
+///
+/// Faces between two blocks of the same fluid, or against solid blocks, are skipped. Unlike
+/// solid blocks, fluids never hide the faces of other blocks.
+pub fn build_fluid_mesh_for_chunk<const S: usize>(
+    chunk: &ChunkN<S>,
+    world_pos: BlockPosition,
+) -> (Vec<FluidVertex>, Indices) {
+    let _span = profile_span!("build_fluid_mesh_for_chunk", position = ?world_pos);
+    let mut vertices = Vec::new();
+    let mut indices = Indices::new();
+
+    for x in 0..S {
+        for y in 0..S {
+            for z in 0..S {
+                let block = chunk.data[x][y][z];
+                if !block.is_fluid() {
+                    continue;
+                }
+                let local = bp(x as i64, y as i64, z as i64);
+                let surface = chunk.inspect_block(local, CardinalDirection::Up) != block;
+                let base = world_pos + local;
+
+                for face in CardinalDirection::iter() {
+                    let neighbor = chunk.inspect_block(local, face);
+                    if block.face_hidden_by(neighbor) {
+                        continue;
+                    }
+
+                    let base_index = vertices.len() as u32;
+                    for (pos, uv) in FACE_TABLE[face as usize].iter() {
+                        let mut position = Vec3::from(*pos);
+                        if surface && position.y == 1.0 {
+                            position.y -= FLUID_SURFACE_DROP;
+                        }
+                        let offset = Vec3::new(base.0 as f32, base.1 as f32, base.2 as f32);
+                        vertices.push(FluidVertex::new(position + offset, Vec2::from(*uv)));
+                    }
+                    for &index in FACE_INDICES.iter() {
+                        indices.push(base_index + index as u32);
+                    }
+                }
+            }
+        }
+    }
+
+    (vertices, indices)
+}
+
+/// Draws fluid blocks as translucent, animated surfaces after the solid geometry.
+///
+/// Only the chunks changed since the last frame are remeshed: edits come from
+/// `ActiveWorld::take_fluid_dirty_chunks`, and streamed chunks are passed to `queue_chunk` and
+/// `unload_chunks`.
+pub struct FluidPipeline {
+    chunks: HashMap<BlockPosition, (VertexBuffer<FluidVertex>, AnyIndexBuffer)>,
+    /// Chunks waiting to be remeshed.
+    pending: HashSet<BlockPosition>,
+    draw_order: DrawOrder,
+    camera: ComponentHandle<CameraController>,
+    world: ComponentHandle<ActiveWorld>,
+    wgpu: ComponentHandle<WgpuRenderer>,
+    depth_texture: ComponentHandle<DepthTexture>,
+    camera_bind_group: Option<wgpu::BindGroup>,
+    pipeline: Option<WgpuPipeline>,
+    /// The `ActiveWorld::generation` the meshes were built from.
+    world_generation: Option<u64>,
+}
+
+impl FluidPipeline {
+    pub fn new(csh: &ComponentStore) -> FluidPipeline {
+        let mut new = Self {
+            chunks: HashMap::new(),
+            pending: HashSet::new(),
+            draw_order: DrawOrder::new(SortDirection::BackToFront),
+            camera: csh.handle_for(),
+            world: csh.handle_for(),
+            wgpu: csh.handle_for(),
+            depth_texture: csh.handle_for(),
+            camera_bind_group: None,
+            pipeline: None,
+            world_generation: None,
+        };
+
+        new.create_pipeline(&csh.get::<CameraController>());
+        new.rebuild_meshes();
+
+        new
+    }
+
+    fn create_pipeline(&mut self, camera: &CameraController) {
+        let wgpu = self.wgpu.get();
+        let mut builder = wgpu
+            .pipeline_builder("Fluid Pipeline")
+            .shader(
+                "Fluid Shader",
+                include_str!("../../../shaders/fluid.wgsl"),
+                Some("vs"),
+                Some("fs"),
+            )
+            .add_vertex_layout::<FluidVertex>()
+            .add_blended_color_target(wgpu.config.get().format, wgpu::BlendState::ALPHA_BLENDING);
+
+        let (camera_bind_group_layout, camera_bind_group) = camera.bind_group(0);
+        self.camera_bind_group = Some(camera_bind_group);
+        builder = builder.push_bind_group(camera_bind_group_layout);
+
+        builder = builder.push_bind_group(wgpu.time_bind_group_layout().clone());
+
+        // Fluids are see-through, so they test against the world's depth without writing to it.
+        builder = builder.depth(wgpu::DepthStencilState {
+            depth_write_enabled: false,
+            ..self.depth_texture.get().state()
+        });
+
+        info!("Creating Fluid Pipeline: {:#?}", builder);
+
+        self.pipeline = Some(builder.build(None));
+    }
+
+    /// Queues a chunk to have its fluid remeshed, e.g. after it was streamed in.
+    pub fn queue_chunk(&mut self, chunk: BlockPosition) {
+        self.pending.insert(chunk);
+    }
+
+    /// Drops the fluid meshes of chunks removed from the world.
+    pub fn unload_chunks(&mut self, chunks: &[BlockPosition]) {
+        let wgpu = self.wgpu.get();
+        for chunk in chunks {
+            self.pending.remove(chunk);
+            if let Some((vertex_buffer, index_buffer)) = self.chunks.remove(chunk) {
+                wgpu.recycle_buffer(vertex_buffer.into_buffer());
+                wgpu.recycle_buffer(index_buffer.into_buffer());
+                self.draw_order.invalidate();
+            }
+        }
+    }
+
+    /// Rebuilds the fluid meshes of the queued and edited chunks. Every chunk is queued when the
+    /// active world is replaced.
+    fn rebuild_meshes(&mut self) {
+        let generation = self.world.get().generation();
+        if self.world_generation != Some(generation) {
+            self.world_generation = Some(generation);
+            let loaded = self.chunks.keys().copied().collect::<Vec<_>>();
+            self.unload_chunks(&loaded);
+            if let Some(world) = self.world.get().get_world() {
+                self.pending
+                    .extend(world.chunks().map(|(chunk_coord, _)| chunk_coord));
+            }
+        }
+        let mut world_ref = self.world.get_mut();
+        self.pending.extend(world_ref.take_fluid_dirty_chunks());
+        if self.pending.is_empty() {
+            return;
+        }
+        let Some(world) = world_ref.get_world() else {
+            return;
+        };
+
+        let wgpu = self.wgpu.get();
+        for chunk_coord in self.pending.drain() {
+            // The old buffers go back to the pool, so the new mesh can reuse them.
+            if let Some((vertex_buffer, index_buffer)) = self.chunks.remove(&chunk_coord) {
+                wgpu.recycle_buffer(vertex_buffer.into_buffer());
+                wgpu.recycle_buffer(index_buffer.into_buffer());
+            }
+            // The chunk may have been unloaded since it was queued.
+            let Some(chunk) = world.chunk(chunk_coord) else {
+                continue;
+            };
+            let (vertices, indices) =
+                build_fluid_mesh_for_chunk(&chunk.get(), chunk_coord.chunk_origin());
+            if indices.is_empty() {
+                continue;
+            }
+            self.chunks.insert(
+                chunk_coord,
+                (
+                    wgpu.pooled_vertex_buffer(&vertices, Some("Fluid Vertex Buffer")),
+                    wgpu.pooled_index_buffer(&indices, Some("Fluid Index Buffer")),
+                ),
+            );
+        }
+        self.draw_order.invalidate();
+    }
+}
+
+impl<K: PipelineKey> RenderPipeline<K> for FluidPipeline {
+    fn label(&self) -> Option<&str> {
+        Some("Fluid Pipeline")
+    }
+
+    fn update(&mut self) -> Option<engine::graphics::pipeline::UpdateRequest> {
+        self.rebuild_meshes();
+        let eye = self.camera.get().position();
+        self.draw_order.update(
+            eye,
+            self.chunks
+                .keys()
+                .map(|chunk| (*chunk, chunk_center(*chunk))),
+        );
+        None
+    }
+
+    fn render(
+        &self,
+        controller: &engine::graphics::pipeline::controller::RenderController<K>,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+    ) {
+        if self.chunks.is_empty() {
+            return;
+        }
+
+        let depth_texture = self.depth_texture.get();
+        let mut render_pass = controller
+            .pass(target)
+            .label("Fluid Pipeline Render Pass")
+            .depth(depth_texture.attachment())
+            .begin(encoder);
+
+        let pipeline = self.pipeline.as_ref().expect("Fluid Pipeline not created");
+
+        render_pass.set_pipeline(&pipeline.pipeline);
+        if let Some(ref camera_bind_group) = self.camera_bind_group {
+            render_pass.set_bind_group(0, camera_bind_group, &[]);
+        }
+        render_pass.set_bind_group(1, self.wgpu.get().time_bind_group(), &[]);
+        // Chunks are drawn back to front, so nearer surfaces blend over farther ones.
+        for (vertex_buffer, index_buffer) in self
+            .draw_order
+            .order()
+            .iter()
+            .filter_map(|chunk| self.chunks.get(chunk))
+            .filter(|(_, index_buffer)| !index_buffer.is_empty())
+        {
+            render_pass.set_vertex_buffer(0, vertex_buffer.buffer().slice(..));
+            render_pass.set_index_buffer(index_buffer.buffer().slice(..), index_buffer.format());
+            render_pass.draw_indexed(0..index_buffer.count() as u32, 0, 0..1);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct FluidVertex {
+    pub position: Vec3,
+    pub tex_coord: Vec2,
+}
+
+impl FluidVertex {
+    pub fn new(position: Vec3, tex_coord: Vec2) -> Self {
+        Self {
+            position,
+            tex_coord,
+        }
+    }
+}
+
+unsafe impl VertexLayout for FluidVertex {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<FluidVertex>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &wgpu::vertex_attr_array![
+            0 => Float32x3, // position
+            1 => Float32x2, // tex_coord
+        ],
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        render::{RenderPipelines, headless::headless_state},
+        world::{Block, Chunk, chunk::CHUNK_SIZE},
+    };
+
+    fn mesh_of(fluid: &[(usize, usize, usize)]) -> Vec<FluidVertex> {
+        let mut blocks = [[[Block::Air; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+        for &(x, y, z) in fluid {
+            blocks[x][y][z] = Block::Water;
+        }
+        build_fluid_mesh_for_chunk(&Chunk::from_blocks(blocks), bp(0, 0, 0)).0
+    }
+
+    #[test]
+    fn surface_top_face_is_lowered() {
+        let vertices = mesh_of(&[(4, 4, 4)]);
+        assert_eq!(vertices.len(), 6 * 4);
+        let top = 5.0 - FLUID_SURFACE_DROP;
+        assert!(vertices.iter().all(|v| v.position.y <= top));
+        assert_eq!(
+            vertices.iter().filter(|v| v.position.y == top).count(),
+            // The top face, and the upper edge of each side face.
+            4 + 4 * 2
+        );
+    }
+
+    #[test]
+    fn only_the_top_of_a_column_is_lowered() {
+        let vertices = mesh_of(&[(4, 4, 4), (4, 5, 4)]);
+        // The faces between the two blocks are culled.
+        assert_eq!(vertices.len(), 10 * 4);
+        let top = 6.0 - FLUID_SURFACE_DROP;
+        assert!(vertices.iter().all(|v| v.position.y <= top));
+        // The lower block's sides reach all the way up to the block above.
+        assert!(vertices.iter().any(|v| v.position.y == 5.0));
+    }
+
+    #[test]
+    fn edits_remesh_only_their_chunks() {
+        let state = headless_state(|world| {
+            world.set_block(bp(0, 0, 0), Block::Water);
+            world.set_block(bp(40, 0, 0), Block::Water);
+        });
+        let mut pipeline = FluidPipeline::new(&state);
+        assert_eq!(pipeline.chunks.len(), 2);
+        let far = pipeline.chunks[&bp(2, 0, 0)].0.buffer().clone();
+
+        state
+            .get_mut::<ActiveWorld>()
+            .get_world_mut()
+            .unwrap()
+            .set_block(bp(1, 0, 0), Block::Water);
+        RenderPipeline::<RenderPipelines>::update(&mut pipeline);
+        assert!(pipeline.pending.is_empty());
+        // The two blocks share a face now, so two of the twelve faces are culled.
+        assert_eq!(pipeline.chunks[&bp(0, 0, 0)].0.count(), 10 * 4);
+        // The untouched chunk keeps its buffers.
+        assert_eq!(*pipeline.chunks[&bp(2, 0, 0)].0.buffer(), far);
+
+        pipeline.unload_chunks(&[bp(2, 0, 0)]);
+        assert!(!pipeline.chunks.contains_key(&bp(2, 0, 0)));
+    }
+}
@@ -0,0 +1,195 @@
+use std::time::Duration;
+
+use engine::{
+    component::{ComponentHandle, ComponentStore},
+    debug::{DebugGraph, DebugRenderer, StatCategory},
+    graphics::{
+        lowlevel::{
+            WgpuRenderer,
+            buf::{VertexBuffer, VertexLayout},
+            pipeline::WgpuPipeline,
+        },
+        pipeline::{RenderPipeline, controller::PipelineKey},
+    },
+};
+use glam::Vec2;
+use log::info;
+
+/// Height of the frame graph overlay, in pixels. Its width is one pixel per sample.
+const GRAPH_HEIGHT: f32 = 100.0;
+/// Distance of the frame graph overlay from the screen corner, in pixels.
+const GRAPH_MARGIN: f32 = 10.0;
+/// The frame time at the top of the graph. Longer frames are cut off.
+const GRAPH_MAX_FRAME_TIME: Duration = Duration::from_nanos(50_000_000);
+
+const BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.5];
+const REFERENCE_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.6];
+
+/// Builds the quads of the frame graph for the given surface size: a background, a bar per
+/// sample from oldest to newest, and reference lines at 60 and 30 frames per second.
+pub fn graph_vertices(graph: &DebugGraph, surface_size: (u32, u32)) -> Vec<FrameGraphVertex> {
+    let (width, height) = (surface_size.0.max(1) as f32, surface_size.1.max(1) as f32);
+    // Pixels from the top left corner to clip space.
+    let clip = |x: f32, y: f32| Vec2::new(x / width * 2.0 - 1.0, 1.0 - y / height * 2.0);
+    let bottom = height - GRAPH_MARGIN;
+    let bar_height = |frame_time: Duration| {
+        (frame_time.as_secs_f32() / GRAPH_MAX_FRAME_TIME.as_secs_f32()).min(1.0) * GRAPH_HEIGHT
+    };
+
+    let mut vertices = Vec::with_capacity((graph.capacity() + 3) * 6);
+    let mut quad = |x: f32, y: f32, w: f32, h: f32, color: [f32; 4]| {
+        let (min, max) = (clip(x, y + h), clip(x + w, y));
+        vertices.extend(
+            [
+                (min.x, max.y),
+                (min.x, min.y),
+                (max.x, min.y),
+                (max.x, min.y),
+                (max.x, max.y),
+                (min.x, max.y),
+            ]
+            .map(|(x, y)| FrameGraphVertex {
+                position: Vec2::new(x, y),
+                color,
+            }),
+        );
+    };
+
+    let graph_width = graph.capacity() as f32;
+    quad(
+        GRAPH_MARGIN,
+        bottom - GRAPH_HEIGHT,
+        graph_width,
+        GRAPH_HEIGHT,
+        BACKGROUND_COLOR,
+    );
+
+    // The newest sample is always drawn at the right edge.
+    let first = GRAPH_MARGIN + (graph.capacity() - graph.samples().len()) as f32;
+    for (i, frame_time) in graph.samples().enumerate() {
+        let h = bar_height(frame_time);
+        quad(
+            first + i as f32,
+            bottom - h,
+            1.0,
+            h,
+            DebugGraph::color(frame_time),
+        );
+    }
+
+    for reference in [DebugGraph::TARGET_FRAME_TIME, DebugGraph::SLOW_FRAME_TIME] {
+        let y = bottom - bar_height(reference);
+        quad(GRAPH_MARGIN, y, graph_width, 1.0, REFERENCE_COLOR);
+    }
+
+    vertices
+}
+
+/// Draws the `DebugGraph` in the bottom left corner of the screen while the performance
+/// statistics of the debug overlay are shown.
+pub struct FrameGraphPipeline {
+    wgpu: ComponentHandle<WgpuRenderer>,
+    graph: ComponentHandle<DebugGraph>,
+    debug: ComponentHandle<DebugRenderer>,
+    pipeline: Option<WgpuPipeline>,
+    buffer: Option<VertexBuffer<FrameGraphVertex>>,
+}
+
+impl FrameGraphPipeline {
+    pub fn new(csh: &ComponentStore) -> FrameGraphPipeline {
+        let mut new = Self {
+            wgpu: csh.handle_for(),
+            graph: csh.handle_for(),
+            debug: csh.handle_for(),
+            pipeline: None,
+            buffer: None,
+        };
+
+        new.create_pipeline();
+
+        new
+    }
+
+    fn create_pipeline(&mut self) {
+        let wgpu = self.wgpu.get();
+        let builder = wgpu
+            .pipeline_builder("Frame Graph Pipeline")
+            .shader(
+                "Frame Graph Shader",
+                include_str!("../../../shaders/frame_graph.wgsl"),
+                Some("vs"),
+                Some("fs"),
+            )
+            .add_vertex_layout::<FrameGraphVertex>()
+            .add_blended_color_target(wgpu.config.get().format, wgpu::BlendState::ALPHA_BLENDING);
+
+        info!("Creating Frame Graph Pipeline: {:#?}", builder);
+
+        self.pipeline = Some(builder.build(None));
+    }
+}
+
+impl<K: PipelineKey> RenderPipeline<K> for FrameGraphPipeline {
+    fn label(&self) -> Option<&str> {
+        Some("Frame Graph Pipeline")
+    }
+
+    fn update(&mut self) -> Option<engine::graphics::pipeline::UpdateRequest> {
+        let debug = self.debug.get();
+        let graph = self.graph.get();
+        self.buffer = if debug.enabled
+            && debug.is_category_visible(StatCategory::Performance)
+            && graph.capacity() > 0
+        {
+            let wgpu = self.wgpu.get();
+            let vertices = graph_vertices(&graph, wgpu.dimensions());
+            Some(wgpu.vertex_buffer(&vertices, Some("Frame Graph Buffer")))
+        } else {
+            None
+        };
+        None
+    }
+
+    fn render(
+        &self,
+        controller: &engine::graphics::pipeline::controller::RenderController<K>,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+    ) {
+        let Some(buffer) = &self.buffer else {
+            return;
+        };
+
+        let mut render_pass = controller
+            .pass(target)
+            .label("Frame Graph Pipeline Render Pass")
+            .begin(encoder);
+
+        let pipeline = self
+            .pipeline
+            .as_ref()
+            .expect("Frame Graph Pipeline not created");
+
+        render_pass.set_pipeline(&pipeline.pipeline);
+        render_pass.set_vertex_buffer(0, buffer.buffer().slice(..));
+        render_pass.draw(0..buffer.count() as u32, 0..1);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct FrameGraphVertex {
+    pub position: Vec2,
+    pub color: [f32; 4],
+}
+
+unsafe impl VertexLayout for FrameGraphVertex {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<FrameGraphVertex>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &wgpu::vertex_attr_array![
+            0 => Float32x2, // position
+            1 => Float32x4, // color
+        ],
+    };
+}
@@ -0,0 +1,239 @@
+use engine::{
+    component::{ComponentHandle, ComponentStore},
+    graphics::{
+        lowlevel::{
+            WgpuRenderer,
+            buf::{VertexBuffer, VertexLayout},
+            pipeline::WgpuPipeline,
+        },
+        pipeline::{RenderPipeline, controller::PipelineKey},
+    },
+};
+use glam::Vec2;
+use log::info;
+
+/// Length of each crosshair arm, as a fraction of the screen height.
+const CROSSHAIR_LENGTH: f32 = 0.02;
+/// Thickness of the crosshair lines, as a fraction of the screen height.
+const CROSSHAIR_THICKNESS: f32 = 0.002;
+const CROSSHAIR_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.8];
+
+/// A flat colored rectangle on the HUD.
+///
+/// Quads are placed relative to the screen and sized relative to its height, so they scale with
+/// the window and keep their shape whatever its aspect ratio.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HudQuad {
+    /// Center of the quad, from (0, 0) at the top left of the screen to (1, 1) at the bottom
+    /// right.
+    pub center: Vec2,
+    /// Width and height of the quad, as fractions of the screen height.
+    pub size: Vec2,
+    pub color: [f32; 4],
+}
+
+impl HudQuad {
+    /// Converts the quad to clip space for a surface of the given size.
+    pub fn to_instance(&self, surface_size: (u32, u32)) -> HudInstance {
+        let (width, height) = (surface_size.0.max(1) as f32, surface_size.1.max(1) as f32);
+        HudInstance {
+            center: Vec2::new(self.center.x * 2.0 - 1.0, 1.0 - self.center.y * 2.0),
+            // A clip space unit is half the screen, and sizes are relative to its height.
+            half_extent: Vec2::new(self.size.x * height / width, self.size.y),
+            color: self.color,
+        }
+    }
+}
+
+/// What's drawn on the HUD: a crosshair, plus any quads registered by other systems.
+#[derive(Debug, Clone)]
+pub struct Hud {
+    pub visible: bool,
+    quads: Vec<(String, HudQuad)>,
+    revision: u64,
+}
+
+impl Hud {
+    /// Creates a visible HUD with just the crosshair.
+    pub fn new() -> Self {
+        let mut hud = Self {
+            visible: true,
+            quads: Vec::new(),
+            revision: 0,
+        };
+        let center = Vec2::splat(0.5);
+        hud.add_quad(
+            "crosshair horizontal",
+            HudQuad {
+                center,
+                size: Vec2::new(CROSSHAIR_LENGTH, CROSSHAIR_THICKNESS),
+                color: CROSSHAIR_COLOR,
+            },
+        );
+        hud.add_quad(
+            "crosshair vertical",
+            HudQuad {
+                center,
+                size: Vec2::new(CROSSHAIR_THICKNESS, CROSSHAIR_LENGTH),
+                color: CROSSHAIR_COLOR,
+            },
+        );
+        hud
+    }
+
+    /// Adds a quad drawn on top of the ones before it, replacing any quad with the same label.
+    pub fn add_quad(&mut self, label: impl Into<String>, quad: HudQuad) {
+        let label = label.into();
+        self.quads.retain(|(l, _)| *l != label);
+        self.quads.push((label, quad));
+        self.revision += 1;
+    }
+
+    /// Removes the quad with the given label, returning it.
+    pub fn remove_quad(&mut self, label: &str) -> Option<HudQuad> {
+        let index = self.quads.iter().position(|(l, _)| l == label)?;
+        self.revision += 1;
+        Some(self.quads.remove(index).1)
+    }
+
+    /// Returns the quads, in the order they're drawn.
+    pub fn quads(&self) -> impl Iterator<Item = &HudQuad> {
+        self.quads.iter().map(|(_, quad)| quad)
+    }
+
+    /// Returns a number that changes whenever quads are added or removed.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Shows or hides the HUD.
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        info!("HUD {}", if self.visible { "shown" } else { "hidden" });
+    }
+}
+
+impl Default for Hud {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Draws the `Hud` quads in screen space over the world.
+///
+/// Each quad is an instance; the vertex shader expands it into its corners. The instances are only
+/// rebuilt when the quads or the surface size change.
+pub struct HudPass {
+    wgpu: ComponentHandle<WgpuRenderer>,
+    hud: ComponentHandle<Hud>,
+    pipeline: Option<WgpuPipeline>,
+    instances: Option<VertexBuffer<HudInstance>>,
+    built_for: Option<(u64, (u32, u32))>,
+}
+
+impl HudPass {
+    pub fn new(csh: &ComponentStore) -> HudPass {
+        let mut new = Self {
+            wgpu: csh.handle_for(),
+            hud: csh.handle_for(),
+            pipeline: None,
+            instances: None,
+            built_for: None,
+        };
+
+        new.create_pipeline();
+
+        new
+    }
+
+    fn create_pipeline(&mut self) {
+        let wgpu = self.wgpu.get();
+        let builder = wgpu
+            .pipeline_builder("HUD Pipeline")
+            .shader(
+                "HUD Shader",
+                include_str!("../../../shaders/hud.wgsl"),
+                Some("vs"),
+                Some("fs"),
+            )
+            .add_vertex_layout::<HudInstance>()
+            .add_blended_color_target(wgpu.config.get().format, wgpu::BlendState::ALPHA_BLENDING);
+
+        info!("Creating HUD Pipeline: {:#?}", builder);
+
+        self.pipeline = Some(builder.build(None));
+    }
+}
+
+impl<K: PipelineKey> RenderPipeline<K> for HudPass {
+    fn label(&self) -> Option<&str> {
+        Some("HUD Pipeline")
+    }
+
+    fn update(&mut self) -> Option<engine::graphics::pipeline::UpdateRequest> {
+        let hud = self.hud.get();
+        let wgpu = self.wgpu.get();
+        let key = (hud.revision(), wgpu.dimensions());
+        if self.built_for == Some(key) {
+            return None;
+        }
+        self.built_for = Some(key);
+
+        let instances = hud
+            .quads()
+            .map(|quad| quad.to_instance(key.1))
+            .collect::<Vec<_>>();
+        self.instances = if instances.is_empty() {
+            None
+        } else {
+            Some(wgpu.vertex_buffer(&instances, Some("HUD Instances")))
+        };
+        None
+    }
+
+    fn render(
+        &self,
+        controller: &engine::graphics::pipeline::controller::RenderController<K>,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+    ) {
+        let Some(instances) = &self.instances else {
+            return;
+        };
+        if !self.hud.get().visible {
+            return;
+        }
+
+        let mut render_pass = controller
+            .pass(target)
+            .label("HUD Pipeline Render Pass")
+            .begin(encoder);
+
+        let pipeline = self.pipeline.as_ref().expect("HUD Pipeline not created");
+
+        render_pass.set_pipeline(&pipeline.pipeline);
+        render_pass.set_vertex_buffer(0, instances.buffer().slice(..));
+        render_pass.draw(0..6, 0..instances.count() as u32);
+    }
+}
+
+/// A `HudQuad` in clip space, as the HUD shader takes it.
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct HudInstance {
+    pub center: Vec2,
+    pub half_extent: Vec2,
+    pub color: [f32; 4],
+}
+
+unsafe impl VertexLayout for HudInstance {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<HudInstance>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Instance,
+        attributes: &wgpu::vertex_attr_array![
+            0 => Float32x2, // center
+            1 => Float32x2, // half_extent
+            2 => Float32x4, // color
+        ],
+    };
+}
@@ -0,0 +1,462 @@
+use std::collections::{HashMap, HashSet};
+
+use engine::{
+    ReadOnly,
+    component::{ComponentHandle, ComponentStore},
+    graphics::{
+        lowlevel::{
+            WgpuRenderer,
+            buf::{VertexBuffer, VertexLayout},
+            pipeline::WgpuPipeline,
+            texture::Texture,
+        },
+        pipeline::{RenderPipeline, controller::PipelineKey},
+    },
+    profile_span,
+};
+use glam::Vec2;
+use log::info;
+
+use crate::{
+    coords::bp,
+    world::{ActiveWorld, Block, ChunkColumn, World, chunk::CHUNK_SIZE},
+};
+
+/// Size of the minimap overlay, in pixels.
+const MINIMAP_SIZE: f32 = 200.0;
+/// Distance of the minimap overlay from the screen corner, in pixels.
+const MINIMAP_MARGIN: f32 = 10.0;
+
+/// The highest non-air block of every column in the loaded area.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopBlockMap {
+    /// World X and Z of the first column.
+    pub origin: (i64, i64),
+    /// Number of columns along X and Z.
+    pub size: (usize, usize),
+    /// Top blocks, row-major with X varying fastest.
+    pub blocks: Vec<Block>,
+}
+
+impl TopBlockMap {
+    /// Gets the top block of the column at the given world X and Z.
+    pub fn get(&self, x: i64, z: i64) -> Option<Block> {
+        let local_x = usize::try_from(x - self.origin.0).ok()?;
+        let local_z = usize::try_from(z - self.origin.1).ok()?;
+        if local_x >= self.size.0 || local_z >= self.size.1 {
+            return None;
+        }
+        Some(self.blocks[local_z * self.size.0 + local_x])
+    }
+
+    /// Converts the map into RGBA8 pixels using each block's map color.
+    pub fn to_pixels(&self) -> Vec<u8> {
+        self.blocks.iter().flat_map(|b| b.map_color()).collect()
+    }
+}
+
+/// Finds the highest non-air block of every column covered by loaded chunks.
+///
+/// Returns `None` if the world has no chunks.
+pub fn scan_top_blocks(world: &World) -> Option<TopBlockMap> {
+    let size = CHUNK_SIZE as i64;
+
+    let columns = world.columns().collect::<HashMap<_, _>>();
+    let (min, max) = column_bounds(columns.keys().copied())?;
+
+    let width = ((max.0 - min.0 + 1) * size) as usize;
+    let depth = ((max.1 - min.1 + 1) * size) as usize;
+    let mut blocks = vec![Block::Air; width * depth];
+
+    for ((cx, cz), column) in columns.iter() {
+        let tops = column_top_blocks(column);
+        for z in 0..CHUNK_SIZE {
+            let map_x = ((cx - min.0) * size) as usize;
+            let map_z = ((cz - min.1) * size) as usize + z;
+            let start = map_z * width + map_x;
+            blocks[start..start + CHUNK_SIZE]
+                .copy_from_slice(&tops[z * CHUNK_SIZE..(z + 1) * CHUNK_SIZE]);
+        }
+    }
+
+    let origin = bp(min.0, 0, min.1).chunk_origin();
+    Some(TopBlockMap {
+        origin: (origin.0, origin.2),
+        size: (width, depth),
+        blocks,
+    })
+}
+
+/// Finds the highest non-air block of every column of blocks in a chunk column, row-major with
+/// X varying fastest.
+pub fn column_top_blocks(column: &ChunkColumn) -> Vec<Block> {
+    // Highest chunk first.
+    let chunks = column
+        .chunks()
+        .rev()
+        .map(|(_, chunk)| chunk.get())
+        .collect::<Vec<_>>();
+    let mut tops = vec![Block::Air; CHUNK_SIZE * CHUNK_SIZE];
+    for z in 0..CHUNK_SIZE {
+        for x in 0..CHUNK_SIZE {
+            tops[z * CHUNK_SIZE + x] = chunks
+                .iter()
+                .find_map(|chunk| {
+                    (0..CHUNK_SIZE)
+                        .rev()
+                        .map(|y| chunk[(x, y, z)])
+                        .find(|b| *b != Block::Air)
+                })
+                .unwrap_or(Block::Air);
+        }
+    }
+    tops
+}
+
+/// Returns the smallest and largest chunk X and Z of the given columns.
+fn column_bounds(columns: impl Iterator<Item = (i64, i64)>) -> Option<((i64, i64), (i64, i64))> {
+    columns.fold(None, |bounds, (x, z)| match bounds {
+        None => Some(((x, z), (x, z))),
+        Some((min, max)) => Some(((min.0.min(x), min.1.min(z)), (max.0.max(x), max.1.max(z)))),
+    })
+}
+
+/// Draws a top-down map of the loaded chunks in the top right corner of the screen.
+///
+/// The map is only updated when the world's revision changes. Columns changed since the last
+/// update are rescanned and written into the existing texture; the texture is only rebuilt when
+/// the loaded area grows or shrinks, or another world is loaded.
+pub struct MinimapPipeline {
+    world: ComponentHandle<ActiveWorld>,
+    wgpu: ComponentHandle<WgpuRenderer>,
+    pipeline: Option<WgpuPipeline>,
+    texture_layout: Option<wgpu::BindGroupLayout>,
+    texture: Option<Texture>,
+    texture_bind_group: Option<wgpu::BindGroup>,
+    /// The smallest and largest chunk X and Z the texture covers.
+    bounds: Option<((i64, i64), (i64, i64))>,
+    /// The columns drawn into the texture.
+    drawn: HashSet<(i64, i64)>,
+    quad: Option<VertexBuffer<MinimapVertex>>,
+    world_revision: Option<u64>,
+    world_generation: u64,
+    surface_size: (u32, u32),
+}
+
+impl MinimapPipeline {
+    pub fn new(csh: &ComponentStore) -> MinimapPipeline {
+        let mut new = Self {
+            world: csh.handle_for(),
+            wgpu: csh.handle_for(),
+            pipeline: None,
+            texture_layout: None,
+            texture: None,
+            texture_bind_group: None,
+            bounds: None,
+            drawn: HashSet::new(),
+            quad: None,
+            world_revision: None,
+            world_generation: csh.get::<ActiveWorld>().generation(),
+            surface_size: (0, 0),
+        };
+
+        new.create_pipeline();
+        new.refresh();
+
+        new
+    }
+
+    fn create_pipeline(&mut self) {
+        let wgpu = self.wgpu.get();
+        // The layout doesn't depend on the texture contents, so build it from a placeholder.
+        let placeholder = Self::create_texture(&wgpu, (1, 1), vec![0; 4]);
+        let layout = placeholder.layout(Some("minimap texture"), 1, 0);
+
+        let builder = wgpu
+            .pipeline_builder("Minimap Pipeline")
+            .shader(
+                "Minimap Shader",
+                include_str!("../../../shaders/minimap.wgsl"),
+                Some("vs"),
+                Some("fs"),
+            )
+            .add_vertex_layout::<MinimapVertex>()
+            .add_color_target(wgpu.config.get().format)
+            .push_bind_group(layout.clone());
+
+        info!("Creating Minimap Pipeline: {:#?}", builder);
+
+        self.pipeline = Some(builder.build(None));
+        self.texture_layout = Some(layout);
+    }
+
+    fn create_texture(wgpu: &WgpuRenderer, size: (u32, u32), pixels: Vec<u8>) -> Texture {
+        wgpu.texture(
+            Some("Minimap Texture"),
+            wgpu::TextureFormat::Rgba8Unorm,
+            wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            size,
+            &[ReadOnly::from(pixels)],
+        )
+    }
+
+    /// Updates the map texture and rebuilds the overlay quad if the world or the surface size
+    /// changed.
+    fn refresh(&mut self) {
+        let wgpu = self.wgpu.get();
+
+        let surface_size = wgpu.dimensions();
+        if surface_size != self.surface_size || self.quad.is_none() {
+            self.surface_size = surface_size;
+            self.quad = Some(wgpu.vertex_buffer(
+                &MinimapVertex::corner_quad(surface_size),
+                Some("Minimap Quad"),
+            ));
+        }
+        drop(wgpu);
+
+        // Cloned so the world can stay borrowed while the texture is updated.
+        let world_handle = self.world.clone();
+        let world_ref = world_handle.get();
+        if world_ref.generation() != self.world_generation {
+            self.world_generation = world_ref.generation();
+            self.world_revision = None;
+        }
+        let Some(world) = world_ref.get_world() else {
+            return;
+        };
+        if self.world_revision == Some(world.revision()) {
+            return;
+        }
+
+        let bounds = column_bounds(world.columns().map(|(column, _)| column));
+        if self.world_revision.is_none() || bounds != self.bounds {
+            self.rebuild(world);
+        } else {
+            self.patch(world, self.world_revision.unwrap_or(0));
+        }
+        self.world_revision = Some(world.revision());
+    }
+
+    /// Rescans every column into a new texture sized to the loaded area.
+    fn rebuild(&mut self, world: &World) {
+        let _span = profile_span!("minimap_rebuild");
+        self.drawn = world.columns().map(|(column, _)| column).collect();
+        self.bounds = column_bounds(self.drawn.iter().copied());
+        let Some(map) = scan_top_blocks(world) else {
+            self.texture = None;
+            self.texture_bind_group = None;
+            return;
+        };
+
+        let texture = Self::create_texture(
+            &self.wgpu.get(),
+            (map.size.0 as u32, map.size.1 as u32),
+            map.to_pixels(),
+        );
+        self.texture_bind_group = Some(
+            texture.bind_group(
+                Some("minimap texture"),
+                self.texture_layout
+                    .as_ref()
+                    .expect("Minimap Pipeline not created"),
+                1,
+                0,
+            ),
+        );
+        self.texture = Some(texture);
+    }
+
+    /// Rescans the columns changed after `since` into the existing texture, and clears the ones
+    /// that were unloaded.
+    fn patch(&mut self, world: &World, since: u64) {
+        let _span = profile_span!("minimap_patch");
+        let unloaded = self
+            .drawn
+            .iter()
+            .copied()
+            .filter(|(x, z)| world.column(*x, *z).is_none())
+            .collect::<Vec<_>>();
+        for column in unloaded {
+            self.drawn.remove(&column);
+            self.write_column(column, &[Block::Air; CHUNK_SIZE * CHUNK_SIZE]);
+        }
+        for (position, column) in world.columns() {
+            if column.revision() > since || !self.drawn.contains(&position) {
+                self.drawn.insert(position);
+                self.write_column(position, &column_top_blocks(column));
+            }
+        }
+    }
+
+    /// Writes the top blocks of a column into its square of the texture.
+    fn write_column(&self, column: (i64, i64), tops: &[Block]) {
+        let (Some(texture), Some((min, _))) = (&self.texture, self.bounds) else {
+            return;
+        };
+        let pixels = tops.iter().flat_map(|b| b.map_color()).collect::<Vec<_>>();
+        let size = CHUNK_SIZE as u32;
+        self.wgpu.get().queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &texture.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: (column.0 - min.0) as u32 * size,
+                    y: (column.1 - min.1) as u32 * size,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &pixels,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(size * 4),
+                rows_per_image: Some(size),
+            },
+            wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
+
+impl<K: PipelineKey> RenderPipeline<K> for MinimapPipeline {
+    fn label(&self) -> Option<&str> {
+        Some("Minimap Pipeline")
+    }
+
+    fn update(&mut self) -> Option<engine::graphics::pipeline::UpdateRequest> {
+        self.refresh();
+        None
+    }
+
+    fn render(
+        &self,
+        controller: &engine::graphics::pipeline::controller::RenderController<K>,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+    ) {
+        let (Some(bind_group), Some(quad)) = (&self.texture_bind_group, &self.quad) else {
+            return;
+        };
+
+        let mut render_pass = controller
+            .pass(target)
+            .label("Minimap Pipeline Render Pass")
+            .begin(encoder);
+
+        let pipeline = self
+            .pipeline
+            .as_ref()
+            .expect("Minimap Pipeline not created");
+
+        render_pass.set_pipeline(&pipeline.pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.set_vertex_buffer(0, quad.buffer().slice(..));
+        render_pass.draw(0..quad.count() as u32, 0..1);
+    }
+}
+
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct MinimapVertex {
+    pub position: Vec2,
+    pub tex_coord: Vec2,
+}
+
+impl MinimapVertex {
+    /// Builds the two triangles of the overlay quad for the given surface size.
+    fn corner_quad(surface_size: (u32, u32)) -> [MinimapVertex; 6] {
+        let (width, height) = (surface_size.0.max(1) as f32, surface_size.1.max(1) as f32);
+        let max = Vec2::new(
+            1.0 - 2.0 * MINIMAP_MARGIN / width,
+            1.0 - 2.0 * MINIMAP_MARGIN / height,
+        );
+        let min = max - Vec2::new(2.0 * MINIMAP_SIZE / width, 2.0 * MINIMAP_SIZE / height);
+
+        let vertex = |x: f32, y: f32, u: f32, v: f32| MinimapVertex {
+            position: Vec2::new(x, y),
+            tex_coord: Vec2::new(u, v),
+        };
+        [
+            vertex(min.x, max.y, 0.0, 0.0),
+            vertex(min.x, min.y, 0.0, 1.0),
+            vertex(max.x, min.y, 1.0, 1.0),
+            vertex(max.x, min.y, 1.0, 1.0),
+            vertex(max.x, max.y, 1.0, 0.0),
+            vertex(min.x, max.y, 0.0, 0.0),
+        ]
+    }
+}
+
+unsafe impl VertexLayout for MinimapVertex {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<MinimapVertex>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &wgpu::vertex_attr_array![
+            0 => Float32x2, // position
+            1 => Float32x2, // tex_coord
+        ],
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::headless::headless_state;
+
+    #[test]
+    fn scan_finds_highest_block() {
+        let store = engine::component::ComponentStore::new();
+        store.finish_initialization();
+        let mut world = World::empty(&store.handle());
+        assert!(scan_top_blocks(&world).is_none());
+
+        world.set_block(bp(0, 0, 0), Block::Stone);
+        world.set_block(bp(0, 20, 0), Block::Grass);
+        world.set_block(bp(3, 2, 5), Block::Dirt);
+        world.set_block(bp(-1, 40, -1), Block::OakLeaves);
+        world.set_block(bp(-1, 10, -1), Block::OakWood);
+
+        let map = scan_top_blocks(&world).unwrap();
+        assert_eq!(map.origin, (-16, -16));
+        assert_eq!(map.size, (32, 32));
+        assert_eq!(map.get(0, 0), Some(Block::Grass));
+        assert_eq!(map.get(3, 5), Some(Block::Dirt));
+        assert_eq!(map.get(-1, -1), Some(Block::OakLeaves));
+        assert_eq!(map.get(1, 1), Some(Block::Air));
+        assert_eq!(map.get(16, 0), None);
+    }
+
+    #[test]
+    fn edits_patch_the_existing_texture() {
+        let state = headless_state(|world| {
+            world.set_block(bp(0, 0, 0), Block::Stone);
+            world.set_block(bp(16, 0, 0), Block::Stone);
+        });
+        let mut minimap = MinimapPipeline::new(&state);
+        let texture = minimap.texture.clone().unwrap().texture;
+        assert_eq!(minimap.drawn.len(), 2);
+
+        state
+            .get_mut::<ActiveWorld>()
+            .get_world_mut()
+            .unwrap()
+            .set_block(bp(17, 5, 3), Block::Dirt);
+        minimap.refresh();
+        assert_eq!(minimap.texture.as_ref().unwrap().texture, texture);
+
+        // Growing the loaded area needs a bigger texture.
+        state
+            .get_mut::<ActiveWorld>()
+            .get_world_mut()
+            .unwrap()
+            .set_block(bp(0, 0, 40), Block::Dirt);
+        minimap.refresh();
+        assert_ne!(minimap.texture.as_ref().unwrap().texture, texture);
+        assert_eq!(minimap.drawn.len(), 3);
+        assert_eq!(minimap.bounds, Some(((0, 0), (1, 2))));
+    }
+}
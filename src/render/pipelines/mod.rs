@@ -1 +1,10 @@
+pub mod breaking;
+pub mod debug_lines;
+pub mod fluid;
+pub mod frame_graph;
+pub mod hud;
+pub mod minimap;
+pub mod particles;
+pub mod postprocess;
+pub mod skybox;
 pub mod solid;
@@ -0,0 +1,411 @@
+use engine::{
+    component::{ComponentHandle, ComponentStore},
+    graphics::{
+        CardinalDirection,
+        atlases::{AtlasBinding, TextureAtlases},
+        lowlevel::{
+            WgpuRenderer,
+            buf::{UniformBuffer, VertexBuffer, VertexLayout},
+            depth::DepthTexture,
+            pipeline::WgpuPipeline,
+        },
+        pipeline::{RenderPipeline, controller::PipelineKey},
+    },
+    input::camera::CameraController,
+};
+use glam::{Vec2, Vec3, Vec4};
+use log::info;
+
+use crate::{assets::BLOCK_ATLAS, render::block_textures::BlockTextureAtlas, world::Block};
+
+/// The default maximum number of live particles. Emitting past this drops the new particles.
+pub const MAX_PARTICLES: usize = 2048;
+/// How long a particle lives, in seconds.
+pub const PARTICLE_LIFETIME: f32 = 1.0;
+/// Downwards acceleration applied to particles, in blocks per second squared.
+pub const PARTICLE_GRAVITY: f32 = 20.0;
+/// The width of a particle quad, in blocks.
+pub const PARTICLE_SIZE: f32 = 0.12;
+/// The fraction of the block texture shown on a single particle.
+pub const PARTICLE_UV_SCALE: f32 = 0.25;
+
+/// A single block fragment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Particle {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    /// Seconds since the particle was emitted.
+    pub age: f32,
+    /// Seconds the particle lives for.
+    pub lifetime: f32,
+    /// The block whose texture the particle shows.
+    pub block: Block,
+    /// Where in the block texture the particle's piece is taken from.
+    pub uv_offset: Vec2,
+}
+
+impl Particle {
+    /// Returns the particle's opacity, fading from 1 to 0 over its lifetime.
+    pub fn alpha(&self) -> f32 {
+        (1.0 - self.age / self.lifetime).clamp(0.0, 1.0)
+    }
+
+    /// Returns true if the particle has outlived its lifetime.
+    pub fn is_dead(&self) -> bool {
+        self.age >= self.lifetime
+    }
+}
+
+/// Simulates the particles thrown off broken blocks.
+///
+/// Particles fall with gravity and fade out over `PARTICLE_LIFETIME`. The particles pipeline
+/// draws whatever is alive after each update.
+#[derive(Debug, Clone)]
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    max_particles: usize,
+    seed: u32,
+}
+
+impl ParticleSystem {
+    /// Creates an empty particle system holding at most `MAX_PARTICLES` particles.
+    pub fn new() -> Self {
+        Self::with_max_particles(MAX_PARTICLES)
+    }
+
+    /// Creates an empty particle system holding at most `max_particles` particles.
+    pub fn with_max_particles(max_particles: usize) -> Self {
+        Self {
+            particles: Vec::new(),
+            max_particles,
+            seed: 0x9E37_79B9,
+        }
+    }
+
+    /// Returns a pseudo random number in `[0, 1)`.
+    fn random(&mut self) -> f32 {
+        // xorshift32, plenty for scattering particles.
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed >> 8) as f32 / (1 << 24) as f32
+    }
+
+    /// Emits up to `count` particles of `block` from the block at `position`.
+    ///
+    /// Returns how many particles were emitted, which is less than `count` if the cap was hit.
+    pub fn emit(&mut self, position: Vec3, block: Block, count: usize) -> usize {
+        let count = count.min(self.max_particles.saturating_sub(self.particles.len()));
+        for _ in 0..count {
+            let offset = Vec3::new(self.random(), self.random(), self.random());
+            let velocity = Vec3::new(
+                (self.random() - 0.5) * 4.0,
+                2.0 + self.random() * 3.0,
+                (self.random() - 0.5) * 4.0,
+            );
+            let uv_offset = Vec2::new(self.random(), self.random()) * (1.0 - PARTICLE_UV_SCALE);
+            let lifetime = PARTICLE_LIFETIME * (0.6 + 0.4 * self.random());
+            self.particles.push(Particle {
+                position: position + offset,
+                velocity,
+                age: 0.0,
+                lifetime,
+                block,
+                uv_offset,
+            });
+        }
+        count
+    }
+
+    /// Advances the simulation by `delta_time` seconds, removing dead particles.
+    pub fn update(&mut self, delta_time: f32) {
+        for particle in self.particles.iter_mut() {
+            particle.velocity.y -= PARTICLE_GRAVITY * delta_time;
+            particle.position += particle.velocity * delta_time;
+            particle.age += delta_time;
+        }
+        self.particles.retain(|particle| !particle.is_dead());
+    }
+
+    /// Returns the live particles.
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Returns the number of live particles.
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// Returns true if there are no live particles.
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// Removes all particles.
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+}
+
+impl Default for ParticleSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The camera's right and up vectors, used to turn particle quads towards the camera.
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct BillboardUniform {
+    right: Vec4,
+    up: Vec4,
+}
+
+impl BillboardUniform {
+    fn facing(front: Vec3) -> Self {
+        let right = front.cross(Vec3::Y).try_normalize().unwrap_or(Vec3::X);
+        let up = right.cross(front).normalize();
+        Self {
+            right: right.extend(0.0),
+            up: up.extend(0.0),
+        }
+    }
+}
+
+/// Draws the live particles as instanced, camera facing quads.
+pub struct ParticlesPipeline {
+    wgpu: ComponentHandle<WgpuRenderer>,
+    particles: ComponentHandle<ParticleSystem>,
+    camera: ComponentHandle<CameraController>,
+    atlas: ComponentHandle<BlockTextureAtlas>,
+    textures: AtlasBinding,
+    depth_texture: ComponentHandle<DepthTexture>,
+    billboard: UniformBuffer<BillboardUniform>,
+    camera_bind_group: Option<wgpu::BindGroup>,
+    billboard_bind_group: Option<wgpu::BindGroup>,
+    pipeline: Option<WgpuPipeline>,
+    instances: Option<VertexBuffer<ParticleInstance>>,
+}
+
+impl ParticlesPipeline {
+    pub fn new(csh: &ComponentStore) -> ParticlesPipeline {
+        let billboard = csh.get::<WgpuRenderer>().uniform_buffer(
+            &BillboardUniform::facing(csh.get::<CameraController>().front()),
+            Some("Particle Billboard Uniform"),
+        );
+        let mut new = Self {
+            wgpu: csh.handle_for(),
+            particles: csh.handle_for(),
+            camera: csh.handle_for(),
+            atlas: csh.handle_for(),
+            textures: csh
+                .get::<TextureAtlases>()
+                .bind(BLOCK_ATLAS, 1, 0)
+                .expect("failed to bind the block atlas"),
+            depth_texture: csh.handle_for(),
+            billboard,
+            camera_bind_group: None,
+            billboard_bind_group: None,
+            pipeline: None,
+            instances: None,
+        };
+
+        new.create_pipeline();
+
+        new
+    }
+
+    fn create_pipeline(&mut self) {
+        let wgpu = self.wgpu.get();
+        let mut builder = wgpu
+            .pipeline_builder("Particles Pipeline")
+            .shader(
+                "Particles Shader",
+                include_str!("../../../shaders/particles.wgsl"),
+                Some("vs"),
+                Some("fs"),
+            )
+            .add_vertex_layout::<ParticleInstance>()
+            .add_blended_color_target(wgpu.config.get().format, wgpu::BlendState::ALPHA_BLENDING);
+
+        let (camera_bind_group_layout, camera_bind_group) = self.camera.get().bind_group(0);
+        self.camera_bind_group = Some(camera_bind_group);
+        builder = builder.push_bind_group(camera_bind_group_layout);
+
+        builder = builder.push_bind_group(self.textures.layout().clone());
+
+        let billboard_layout = wgpu.bind_group_layout(
+            Some("particle billboard bind group layout"),
+            &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        );
+        self.billboard_bind_group = Some(wgpu.bind_group(
+            Some("particle billboard bind group"),
+            &billboard_layout,
+            &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(
+                    self.billboard.buffer().as_entire_buffer_binding(),
+                ),
+            }],
+        ));
+        builder = builder.push_bind_group(billboard_layout);
+
+        // Particles are translucent while fading, so they don't write depth.
+        builder = builder.depth(wgpu::DepthStencilState {
+            depth_write_enabled: false,
+            ..self.depth_texture.get().state()
+        });
+
+        info!("Creating Particles Pipeline: {:#?}", builder);
+
+        self.pipeline = Some(builder.build(None));
+    }
+}
+
+impl<K: PipelineKey> RenderPipeline<K> for ParticlesPipeline {
+    fn label(&self) -> Option<&str> {
+        Some("Particles Pipeline")
+    }
+
+    fn update(&mut self) -> Option<engine::graphics::pipeline::UpdateRequest> {
+        self.textures.refresh();
+
+        let particles = self.particles.get();
+        if particles.is_empty() {
+            self.instances = None;
+            return None;
+        }
+
+        let atlas = self.atlas.get();
+        let instances = particles
+            .particles()
+            .iter()
+            .map(|particle| ParticleInstance {
+                position: particle.position,
+                size: PARTICLE_SIZE,
+                uv_offset: particle.uv_offset,
+                texture_index: atlas.texture_index(particle.block, CardinalDirection::North),
+                alpha: particle.alpha(),
+            })
+            .collect::<Vec<_>>();
+        drop(particles);
+        drop(atlas);
+
+        self.billboard
+            .write(&BillboardUniform::facing(self.camera.get().front()));
+        self.instances = Some(
+            self.wgpu
+                .get()
+                .vertex_buffer(&instances, Some("Particle Instances")),
+        );
+        None
+    }
+
+    fn render(
+        &self,
+        controller: &engine::graphics::pipeline::controller::RenderController<K>,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+    ) {
+        let Some(instances) = &self.instances else {
+            return;
+        };
+
+        let depth_texture = self.depth_texture.get();
+        let mut render_pass = controller
+            .pass(target)
+            .label("Particles Pipeline Render Pass")
+            .depth(depth_texture.attachment())
+            .begin(encoder);
+
+        let pipeline = self
+            .pipeline
+            .as_ref()
+            .expect("Particles Pipeline not created");
+
+        render_pass.set_pipeline(&pipeline.pipeline);
+        if let Some(ref camera_bind_group) = self.camera_bind_group {
+            render_pass.set_bind_group(0, camera_bind_group, &[]);
+        }
+        render_pass.set_bind_group(1, self.textures.bind_group(), &[]);
+        if let Some(ref billboard_bind_group) = self.billboard_bind_group {
+            render_pass.set_bind_group(2, billboard_bind_group, &[]);
+        }
+        render_pass.set_vertex_buffer(0, instances.buffer().slice(..));
+        // Each quad's corners are generated in the vertex shader.
+        render_pass.draw(0..6, 0..instances.count() as u32);
+    }
+}
+
+/// Per particle data, stepped once per instance.
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct ParticleInstance {
+    pub position: Vec3,
+    pub size: f32,
+    pub uv_offset: Vec2,
+    pub texture_index: u32,
+    pub alpha: f32,
+}
+
+unsafe impl VertexLayout for ParticleInstance {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<ParticleInstance>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Instance,
+        attributes: &wgpu::vertex_attr_array![
+            0 => Float32x3, // position
+            1 => Float32,   // size
+            2 => Float32x2, // uv_offset
+            3 => Uint32,    // texture_index
+            4 => Float32,   // alpha
+        ],
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn particles_fall_fade_and_die() {
+        let mut system = ParticleSystem::new();
+        assert_eq!(system.emit(Vec3::new(4.0, 10.0, 4.0), Block::Stone, 16), 16);
+        assert_eq!(system.len(), 16);
+        for particle in system.particles() {
+            assert_eq!(particle.block, Block::Stone);
+            assert_eq!(particle.alpha(), 1.0);
+            // Particles start somewhere inside the broken block.
+            assert!((4.0..5.0).contains(&particle.position.x));
+        }
+
+        system.update(0.5);
+        assert_eq!(system.len(), 16);
+        for particle in system.particles() {
+            assert!(particle.alpha() < 1.0);
+            assert!(particle.velocity.y < 2.0);
+        }
+
+        // No particle outlives the longest lifetime.
+        system.update(PARTICLE_LIFETIME);
+        assert!(system.is_empty());
+    }
+
+    #[test]
+    fn emitting_stops_at_the_cap() {
+        let mut system = ParticleSystem::with_max_particles(10);
+        assert_eq!(system.emit(Vec3::ZERO, Block::Dirt, 8), 8);
+        assert_eq!(system.emit(Vec3::ZERO, Block::Dirt, 8), 2);
+        assert_eq!(system.emit(Vec3::ZERO, Block::Dirt, 8), 0);
+        assert_eq!(system.len(), 10);
+    }
+}
@@ -0,0 +1,399 @@
+use engine::{
+    component::{ComponentHandle, ComponentStore},
+    graphics::{
+        lowlevel::{
+            WgpuRenderer,
+            device::RenderDevice,
+            pipeline::{PipelineBuilder, WgpuPipeline},
+            shader::ShaderProgram,
+        },
+        pipeline::{RenderPipeline, UpdateRequest, controller::PipelineKey, pass::PassBuilder},
+    },
+};
+use log::info;
+use wgpu::util::DeviceExt;
+
+/// The part of every effect's shader that's shared: the screen covering vertex shader and the
+/// input texture bindings.
+const SHADER_PRELUDE: &str = include_str!("../../../shaders/postprocess.wgsl");
+
+/// One full screen pass of a [`PostProcessChain`], reading the output of the previous effect.
+///
+/// An effect is a fragment shader with an `fs` entry point, appended to the shared prelude in
+/// `shaders/postprocess.wgsl`, which declares `sample_input(uv)` for reading the input and the
+/// frame's `time`. Effects with a uniform buffer find it at `@group(2) @binding(0)`.
+pub struct PostEffect {
+    label: String,
+    shader: ShaderProgram,
+    pipeline: WgpuPipeline,
+    uniform: Option<(wgpu::Buffer, wgpu::BindGroup)>,
+}
+
+impl PostEffect {
+    /// Creates an effect from the source of its fragment shader.
+    ///
+    /// `input_layout` is the chain's [`PostProcessChain::input_layout`]. If `uniform` is given, a
+    /// uniform buffer is created with it as the initial contents.
+    pub fn new(
+        device: &dyn RenderDevice,
+        input_layout: &wgpu::BindGroupLayout,
+        label: &str,
+        fragment_source: &str,
+        uniform: Option<&[u8]>,
+    ) -> Self {
+        let source = format!("{}\n{}", SHADER_PRELUDE, fragment_source);
+        let shader = device.load_shader(&source, Some(label), Some("vs"), Some("fs"));
+
+        let mut builder = PipelineBuilder::new(device, label)
+            .shader_program(shader.clone())
+            .add_color_target(device.surface_format())
+            .push_bind_group(input_layout.clone())
+            .push_bind_group(device.time_bind_group_layout().clone());
+
+        let uniform = uniform.map(|contents| Self::create_uniform(device, label, contents));
+        if let Some((layout, _, _)) = &uniform {
+            builder = builder.push_bind_group(layout.clone());
+        }
+
+        info!("Creating post effect {}: {:#?}", label, builder);
+
+        Self {
+            label: label.to_string(),
+            shader,
+            pipeline: builder.build(None),
+            uniform: uniform.map(|(_, buffer, bind_group)| (buffer, bind_group)),
+        }
+    }
+
+    /// Creates a uniform buffer holding `contents`, with its bind group and layout.
+    fn create_uniform(
+        device: &dyn RenderDevice,
+        label: &str,
+        contents: &[u8],
+    ) -> (wgpu::BindGroupLayout, wgpu::Buffer, wgpu::BindGroup) {
+        let layout = device
+            .device()
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("post effect uniform bind group layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        let buffer = device
+            .device()
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+        let bind_group = device
+            .device()
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("post effect uniform bind group"),
+                layout: &layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+            });
+        (layout, buffer, bind_group)
+    }
+
+    /// Copies the input unchanged. This is what an empty chain draws.
+    pub fn copy(device: &dyn RenderDevice, input_layout: &wgpu::BindGroupLayout) -> Self {
+        Self::new(
+            device,
+            input_layout,
+            "Copy Post Effect",
+            include_str!("../../../shaders/post_copy.wgsl"),
+            None,
+        )
+    }
+
+    /// Raises the colors to the power of `1 / gamma`.
+    pub fn gamma_correction(
+        device: &dyn RenderDevice,
+        input_layout: &wgpu::BindGroupLayout,
+        gamma: f32,
+    ) -> Self {
+        Self::new(
+            device,
+            input_layout,
+            "Gamma Correction Post Effect",
+            include_str!("../../../shaders/post_gamma.wgsl"),
+            Some(bytemuck::bytes_of(&[gamma, 0.0, 0.0, 0.0])),
+        )
+    }
+
+    /// Adds animated noise, like film grain. `strength` is how far the noise moves each color,
+    /// from 0 to 1.
+    pub fn film_grain(
+        device: &dyn RenderDevice,
+        input_layout: &wgpu::BindGroupLayout,
+        strength: f32,
+    ) -> Self {
+        Self::new(
+            device,
+            input_layout,
+            "Film Grain Post Effect",
+            include_str!("../../../shaders/post_grain.wgsl"),
+            Some(bytemuck::bytes_of(&[strength, 0.0, 0.0, 0.0])),
+        )
+    }
+
+    /// Inverts the colors.
+    pub fn invert(device: &dyn RenderDevice, input_layout: &wgpu::BindGroupLayout) -> Self {
+        Self::new(
+            device,
+            input_layout,
+            "Invert Post Effect",
+            include_str!("../../../shaders/post_invert.wgsl"),
+            None,
+        )
+    }
+
+    /// Returns the name of the effect.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Returns the effect's shader.
+    pub fn shader(&self) -> &ShaderProgram {
+        &self.shader
+    }
+
+    /// Overwrites the start of the effect's uniform buffer. Does nothing if the effect has none.
+    pub fn write_uniform(&self, device: &dyn RenderDevice, data: &[u8]) {
+        if let Some((buffer, _)) = &self.uniform {
+            device.queue().write_buffer(buffer, 0, data);
+        }
+    }
+
+    fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        input: &wgpu::BindGroup,
+        time: &wgpu::BindGroup,
+        output: &wgpu::TextureView,
+    ) {
+        // Every pixel is overwritten, so there's nothing worth loading.
+        let mut render_pass = PassBuilder::new()
+            .label(&self.label)
+            .cleared_color_target(output, wgpu::Color::BLACK)
+            .begin(encoder);
+        render_pass.set_pipeline(&self.pipeline.pipeline);
+        render_pass.set_bind_group(0, input, &[]);
+        render_pass.set_bind_group(1, time, &[]);
+        if let Some((_, bind_group)) = &self.uniform {
+            render_pass.set_bind_group(2, bind_group, &[]);
+        }
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// The two offscreen textures the chain ping-pongs between, and their input bind groups.
+struct Targets {
+    size: (u32, u32),
+    views: [wgpu::TextureView; 2],
+    bind_groups: [wgpu::BindGroup; 2],
+}
+
+/// Runs a list of full screen effects over the rendered scene before it reaches the swapchain.
+///
+/// The chain redirects every other pipeline into an offscreen texture. Each effect reads the
+/// output of the one before it, alternating between two offscreen textures, and the last effect
+/// writes to the swapchain. An empty chain copies the scene across unchanged. Add it last in the
+/// render order.
+pub struct PostProcessChain {
+    wgpu: ComponentHandle<WgpuRenderer>,
+    input_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    effects: Vec<PostEffect>,
+    copy: PostEffect,
+    targets: Option<Targets>,
+}
+
+impl PostProcessChain {
+    pub fn new(csh: &ComponentStore) -> PostProcessChain {
+        let wgpu = csh.get::<WgpuRenderer>();
+        let input_layout = Self::create_input_layout(&*wgpu);
+        let sampler = wgpu.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post Process Sampler"),
+            ..Default::default()
+        });
+        let copy = PostEffect::copy(&*wgpu, &input_layout);
+        drop(wgpu);
+
+        Self {
+            wgpu: csh.handle_for(),
+            input_layout,
+            sampler,
+            effects: Vec::new(),
+            copy,
+            targets: None,
+        }
+    }
+
+    /// Creates the layout of the bind group effects read their input through.
+    pub fn create_input_layout(device: &dyn RenderDevice) -> wgpu::BindGroupLayout {
+        device
+            .device()
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("post process input bind group layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            })
+    }
+
+    /// Returns the layout effects for this chain are built with.
+    pub fn input_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.input_layout
+    }
+
+    /// Adds an effect to the end of the chain.
+    pub fn push(&mut self, effect: PostEffect) {
+        self.effects.push(effect);
+    }
+
+    /// Removes the first effect with the given label, returning it.
+    pub fn remove(&mut self, label: &str) -> Option<PostEffect> {
+        let index = self.effects.iter().position(|e| e.label() == label)?;
+        Some(self.effects.remove(index))
+    }
+
+    /// Removes every effect, so the scene is drawn unchanged.
+    pub fn clear(&mut self) {
+        self.effects.clear();
+    }
+
+    /// Returns the effects, in the order they run.
+    pub fn effects(&self) -> &[PostEffect] {
+        &self.effects
+    }
+
+    /// Returns the effects, in the order they run, for changing their uniforms or order.
+    pub fn effects_mut(&mut self) -> &mut Vec<PostEffect> {
+        &mut self.effects
+    }
+
+    /// Creates the offscreen textures for the given surface size.
+    fn create_targets(&self, wgpu: &WgpuRenderer, size: (u32, u32)) -> Targets {
+        let create = |i: usize| {
+            let texture = wgpu.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(&format!("Post Process Target {}", i)),
+                size: wgpu::Extent3d {
+                    width: size.0,
+                    height: size.1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu.surface_format(),
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let bind_group = wgpu.bind_group(
+                Some("post process input bind group"),
+                &self.input_layout,
+                &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            );
+            (view, bind_group)
+        };
+        let (first_view, first_bind_group) = create(0);
+        let (second_view, second_bind_group) = create(1);
+        Targets {
+            size,
+            views: [first_view, second_view],
+            bind_groups: [first_bind_group, second_bind_group],
+        }
+    }
+}
+
+impl<K: PipelineKey> RenderPipeline<K> for PostProcessChain {
+    fn label(&self) -> Option<&str> {
+        Some("Post Process Chain")
+    }
+
+    fn update(&mut self) -> Option<UpdateRequest> {
+        let wgpu = self.wgpu.get();
+        let size = wgpu.dimensions();
+        if self.targets.as_ref().is_some_and(|t| t.size == size) {
+            return None;
+        }
+        // The surface was resized (or this is the first frame), so the scene texture has to be
+        // recreated and every other pipeline pointed at the new one.
+        let targets = self.create_targets(&wgpu, size);
+        let scene = targets.views[0].clone();
+        drop(wgpu);
+        self.targets = Some(targets);
+        Some(UpdateRequest::SetRenderTarget(scene))
+    }
+
+    fn render(
+        &self,
+        _controller: &engine::graphics::pipeline::controller::RenderController<K>,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+    ) {
+        let Some(targets) = &self.targets else {
+            return;
+        };
+        let wgpu = self.wgpu.get();
+
+        let effects = if self.effects.is_empty() {
+            std::slice::from_ref(&self.copy)
+        } else {
+            &self.effects[..]
+        };
+        // The scene is in the first texture; each effect writes to the other one, and the last
+        // writes to the swapchain.
+        for (i, effect) in effects.iter().enumerate() {
+            let output = if i + 1 == effects.len() {
+                target
+            } else {
+                &targets.views[(i + 1) % 2]
+            };
+            effect.render(
+                encoder,
+                &targets.bind_groups[i % 2],
+                wgpu.time_bind_group(),
+                output,
+            );
+        }
+    }
+}
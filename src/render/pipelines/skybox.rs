@@ -0,0 +1,237 @@
+use engine::{
+    component::{ComponentHandle, ComponentStore},
+    graphics::{
+        lowlevel::{
+            WgpuRenderer,
+            buf::UniformBuffer,
+            device::RenderDevice,
+            pipeline::{PipelineBuilder, WgpuPipeline},
+        },
+        pipeline::{RenderPipeline, controller::PipelineKey},
+    },
+    input::camera::CameraController,
+};
+use glam::{Vec3, Vec4};
+use log::info;
+
+/// The colors of the sky gradient.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkyColor {
+    /// The color straight up.
+    pub top: [f32; 3],
+    /// The color at and below the horizon.
+    pub bottom: [f32; 3],
+}
+
+impl Default for SkyColor {
+    fn default() -> Self {
+        Self {
+            top: [0.22, 0.42, 0.82],
+            bottom: [0.68, 0.8, 0.95],
+        }
+    }
+}
+
+/// The sky colors and camera basis, laid out for `skybox.wgsl`.
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct SkyUniform {
+    right: Vec4,
+    up: Vec4,
+    front: Vec4,
+    top: Vec4,
+    bottom: Vec4,
+    sun: Vec4,
+}
+
+impl SkyUniform {
+    fn new(
+        camera: &CameraController,
+        aspect_ratio: f32,
+        sky: SkyColor,
+        sun_direction: Option<Vec3>,
+    ) -> Self {
+        let front = camera.front();
+        let right = front.cross(Vec3::Y).try_normalize().unwrap_or(Vec3::X);
+        let up = right.cross(front).normalize();
+        let half_height = (camera.fov() / 2.0).tan();
+        let sun = match sun_direction.and_then(Vec3::try_normalize) {
+            Some(direction) => direction.extend(1.0),
+            None => Vec4::ZERO,
+        };
+        Self {
+            right: (right * half_height * aspect_ratio).extend(0.0),
+            up: (up * half_height).extend(0.0),
+            front: front.extend(0.0),
+            top: Vec3::from(sky.top).extend(1.0),
+            bottom: Vec3::from(sky.bottom).extend(1.0),
+            sun,
+        }
+    }
+}
+
+/// Fills the background with a gradient from the horizon to the zenith, optionally tinted
+/// towards the sun.
+///
+/// The sky is drawn over the whole target without a depth attachment, so it belongs right after
+/// the clear and before any geometry.
+pub struct SkyboxPass {
+    wgpu: ComponentHandle<WgpuRenderer>,
+    camera: ComponentHandle<CameraController>,
+    sky: SkyColor,
+    sun_direction: Option<Vec3>,
+    uniform: UniformBuffer<SkyUniform>,
+    bind_group: wgpu::BindGroup,
+    pipeline: WgpuPipeline,
+}
+
+impl SkyboxPass {
+    pub fn new(csh: &ComponentStore) -> SkyboxPass {
+        let wgpu = csh.get::<WgpuRenderer>();
+        let sky = SkyColor::default();
+        let uniform = wgpu.uniform_buffer(
+            &SkyUniform::new(&csh.get::<CameraController>(), 1.0, sky, None),
+            Some("Sky Uniform"),
+        );
+        let layout = Self::bind_group_layout(&*wgpu);
+        let bind_group = wgpu.bind_group(
+            Some("sky bind group"),
+            &layout,
+            &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(
+                    uniform.buffer().as_entire_buffer_binding(),
+                ),
+            }],
+        );
+        let pipeline = Self::build_pipeline(&*wgpu, &layout);
+        drop(wgpu);
+
+        Self {
+            wgpu: csh.handle_for(),
+            camera: csh.handle_for(),
+            sky,
+            sun_direction: None,
+            uniform,
+            bind_group,
+            pipeline,
+        }
+    }
+
+    /// Creates the layout of the sky uniform's bind group.
+    pub fn bind_group_layout(device: &dyn RenderDevice) -> wgpu::BindGroupLayout {
+        device
+            .device()
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("sky bind group layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            })
+    }
+
+    /// Builds the sky pipeline for the device's surface format.
+    pub fn build_pipeline(
+        device: &dyn RenderDevice,
+        layout: &wgpu::BindGroupLayout,
+    ) -> WgpuPipeline {
+        let builder = PipelineBuilder::new(device, "Skybox Pipeline")
+            .shader(
+                "Skybox Shader",
+                include_str!("../../../shaders/skybox.wgsl"),
+                Some("vs"),
+                Some("fs"),
+            )
+            .add_color_target(device.surface_format())
+            .push_bind_group(layout.clone());
+
+        info!("Creating Skybox Pipeline: {:#?}", builder);
+
+        builder.build(None)
+    }
+
+    /// Returns the colors of the sky gradient.
+    pub fn sky_color(&self) -> SkyColor {
+        self.sky
+    }
+
+    /// Sets the colors of the sky gradient.
+    pub fn set_sky_color(&mut self, sky: SkyColor) {
+        self.sky = sky;
+    }
+
+    /// Returns the direction towards the sun, if the sky is tinted towards it.
+    pub fn sun_direction(&self) -> Option<Vec3> {
+        self.sun_direction
+    }
+
+    /// Sets the direction towards the sun, or `None` for an even sky with no sun.
+    pub fn set_sun_direction(&mut self, direction: Option<Vec3>) {
+        self.sun_direction = direction;
+    }
+}
+
+impl<K: PipelineKey> RenderPipeline<K> for SkyboxPass {
+    fn label(&self) -> Option<&str> {
+        Some("Skybox Pipeline")
+    }
+
+    fn update(&mut self) -> Option<engine::graphics::pipeline::UpdateRequest> {
+        let (width, height) = self.wgpu.get().dimensions();
+        let aspect_ratio = width.max(1) as f32 / height.max(1) as f32;
+        self.uniform.write(&SkyUniform::new(
+            &self.camera.get(),
+            aspect_ratio,
+            self.sky,
+            self.sun_direction,
+        ));
+        None
+    }
+
+    fn render(
+        &self,
+        controller: &engine::graphics::pipeline::controller::RenderController<K>,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+    ) {
+        let mut render_pass = controller
+            .pass(target)
+            .label("Skybox Pipeline Render Pass")
+            .begin(encoder);
+
+        render_pass.set_pipeline(&self.pipeline.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        // The screen covering triangle is generated in the vertex shader.
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::RenderPipelines;
+
+    #[test]
+    fn builds_from_a_minimal_store() {
+        let mut state = ComponentStore::new();
+        WgpuRenderer::attach_noop(&mut state, (64, 64));
+        let camera = CameraController::new(&state);
+        state.insert(camera);
+        state.finish_initialization();
+
+        let mut sky = SkyboxPass::new(&state);
+        sky.set_sun_direction(Some(Vec3::Y));
+        assert_eq!(
+            RenderPipeline::<RenderPipelines>::label(&sky),
+            Some("Skybox Pipeline")
+        );
+        assert!(RenderPipeline::<RenderPipelines>::update(&mut sky).is_none());
+    }
+}
@@ -1,151 +1,831 @@
-use std::{collections::HashMap, path::Component};
+use std::{
+    collections::{HashMap, HashSet},
+    path::Component,
+};
 
 use engine::{
     component::{ComponentHandle, ComponentStore, ComponentStoreHandle},
+    debug::{DebugProvider, DebugRenderer, StatCategory},
     graphics::{
         CardinalDirection,
+        atlases::{AtlasBinding, TextureAtlases},
         camera::Camera,
+        frustum::Frustum,
         lowlevel::{
             WgpuRenderer,
-            buf::{IndexBuffer, VertexBuffer, VertexLayout},
+            buf::{AnyIndexBuffer, Indices, UniformBuffer, VertexBuffer, VertexLayout},
             depth::DepthTexture,
+            device::RenderDevice,
             pipeline::WgpuPipeline,
+            shader::ShaderProgram,
+            texture::Texture,
         },
         pipeline::{RenderPipeline, controller::PipelineKey},
-        textures::TextureCollection,
     },
     input::camera::CameraController,
+    profile_span,
 };
 use glam::{Vec2, Vec3};
-use log::info;
+use log::{info, warn};
+use wgpu::util::RenderEncoder;
 
 use crate::{
     BlockPosition, FACE_INDICES, FACE_TABLE,
+    assets::BLOCK_ATLAS,
     coords::bp,
-    render::block_textures::BlockTextureAtlas,
-    world::{ActiveWorld, Block, Chunk},
+    render::{
+        block_textures::BlockTextureAtlas,
+        draw_order::{DrawOrder, SortDirection, chunk_center},
+        lighting::{Lighting, LightingUniform},
+        mesh_cache::{MeshCache, mesh_key},
+        mesh_queue::MeshQueue,
+        pipelines::debug_lines::DebugLines,
+    },
+    world::{ActiveWorld, Block, BlockRegistry, ChunkN, chunk::CHUNK_SIZE},
 };
 
+/// How many queued chunks are meshed each frame while the initial world streams in.
+pub const CHUNKS_MESHED_PER_FRAME: usize = 8;
+
+/// How many edited chunks are re-meshed each frame. An edit on a chunk corner dirties four
+/// chunks, so this keeps a few edits a frame from lagging behind.
+pub const DIRTY_CHUNKS_PER_FRAME: usize = 16;
+
+/// The color of the bounds of chunks in a region that's drawn.
+const VISIBLE_CHUNK_COLOR: [f32; 4] = [0.2, 1.0, 0.2, 1.0];
+/// The color of the bounds of chunks in a region that's culled.
+const CULLED_CHUNK_COLOR: [f32; 4] = [1.0, 0.2, 0.2, 1.0];
+/// The color of the bounds of chunks waiting to be meshed.
+const DIRTY_CHUNK_COLOR: [f32; 4] = [1.0, 0.9, 0.1, 1.0];
+
+/// The width and depth of a region, in chunks.
+///
+/// The chunks of a region share one vertex and index buffer and are drawn with a single call.
+/// Bigger regions mean fewer draw calls, but more work to rebuild a region when a chunk changes.
+pub const REGION_SIZE: i64 = 4;
+
+/// Returns the position of the region containing the given chunk.
+///
+/// Regions group `REGION_SIZE` x `REGION_SIZE` chunks horizontally; each vertical layer of
+/// chunks is its own region.
+pub fn region_of(chunk: BlockPosition) -> BlockPosition {
+    bp(
+        chunk.0.div_euclid(REGION_SIZE),
+        chunk.1,
+        chunk.2.div_euclid(REGION_SIZE),
+    )
+}
+
+/// Returns the center of the given region, in world space.
+pub fn region_center(region: BlockPosition) -> Vec3 {
+    let size = (REGION_SIZE as usize * CHUNK_SIZE) as f32;
+    let height = CHUNK_SIZE as f32;
+    Vec3::new(
+        (region.0 as f32 + 0.5) * size,
+        (region.1 as f32 + 0.5) * height,
+        (region.2 as f32 + 0.5) * size,
+    )
+}
+
+/// Returns the minimum and maximum corners of the given region, in world space.
+pub fn region_bounds(region: BlockPosition) -> (Vec3, Vec3) {
+    let size = (REGION_SIZE as usize * CHUNK_SIZE) as f32;
+    let height = CHUNK_SIZE as f32;
+    let min = Vec3::new(
+        region.0 as f32 * size,
+        region.1 as f32 * height,
+        region.2 as f32 * size,
+    );
+    (min, min + Vec3::new(size, height, size))
+}
+
+/// Returns the positions of all chunks that belong to the given region.
+pub fn region_chunks(region: BlockPosition) -> impl Iterator<Item = BlockPosition> {
+    (0..REGION_SIZE).flat_map(move |dx| {
+        (0..REGION_SIZE).map(move |dz| {
+            bp(
+                region.0 * REGION_SIZE + dx,
+                region.1,
+                region.2 * REGION_SIZE + dz,
+            )
+        })
+    })
+}
+
+/// Merges chunk meshes into one region mesh, offsetting each chunk's indices past the vertices
+/// before it. The merged mesh uses 32 bit indices, since a region can exceed 65536 vertices.
+pub fn merge_region_meshes<'a>(
+    meshes: impl IntoIterator<Item = (&'a [SolidBlockVertex], &'a Indices)>,
+) -> (Vec<SolidBlockVertex>, Vec<u32>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for (chunk_vertices, chunk_indices) in meshes {
+        let base = vertices.len() as u32;
+        vertices.extend_from_slice(chunk_vertices);
+        indices.extend(chunk_indices.iter().map(|index| base + index));
+    }
+    (vertices, indices)
+}
+
+/// Draws the solid blocks of the world.
+///
+/// Chunks aren't meshed when the pipeline is created. Instead they are queued and meshed a few
+/// at a time in `update`, so the window is interactive straight away and chunks pop in over the
+/// first frames. The trade-off is that the world is incomplete for a short while; call
+/// `create_initial_chunks` to mesh everything up front instead. See `MeshQueue` for the order
+/// chunks are meshed in.
 pub struct SolidGeometryPipeline {
-    chunks: HashMap<BlockPosition, ChunkSolidRenderData>,
+    chunk_meshes: HashMap<BlockPosition, ChunkMesh>,
+    regions: HashMap<BlockPosition, RegionSolidRenderData>,
+    draw_order: DrawOrder,
+    visible_regions: Vec<BlockPosition>,
+    transparent_chunks: HashMap<BlockPosition, ChunkTransparentRenderData>,
+    transparent_order: DrawOrder,
+    visible_transparent: Vec<BlockPosition>,
+    chunks_drawn: DebugProvider,
+    debug: ComponentHandle<DebugRenderer>,
+    debug_lines: ComponentHandle<DebugLines>,
+    pending: MeshQueue,
+    pending_total: usize,
+    progress: Option<Box<dyn FnMut(usize, usize)>>,
+    mesh_cache: Option<MeshCache>,
     world: ComponentHandle<ActiveWorld>,
     wgpu: ComponentHandle<WgpuRenderer>,
     camera: ComponentHandle<CameraController>,
     atlas: ComponentHandle<BlockTextureAtlas>,
-    textures: ComponentHandle<TextureCollection>,
+    registry: ComponentHandle<BlockRegistry>,
+    textures: AtlasBinding,
     depth_texture: ComponentHandle<DepthTexture>,
-    textures_bind_group: Option<wgpu::BindGroup>,
     camera_bind_group: Option<wgpu::BindGroup>,
+    camera_bind_group_layout: Option<wgpu::BindGroupLayout>,
+    lighting: ComponentHandle<Lighting>,
+    lighting_uniform: UniformBuffer<LightingUniform>,
+    lighting_bind_group_layout: wgpu::BindGroupLayout,
+    lighting_bind_group: wgpu::BindGroup,
     pipeline: Option<WgpuPipeline>,
+    transparent_pipeline: Option<WgpuPipeline>,
+    bundles_recorded: u64,
+    world_generation: u64,
 }
 
 impl SolidGeometryPipeline {
     pub fn new(csh: &ComponentStore) -> SolidGeometryPipeline {
+        Self::create(csh, None)
+    }
+
+    /// Creates the pipeline, calling `progress(done, total)` after each queued chunk is meshed.
+    pub fn with_progress(
+        csh: &ComponentStore,
+        progress: impl FnMut(usize, usize) + 'static,
+    ) -> SolidGeometryPipeline {
+        Self::create(csh, Some(Box::new(progress)))
+    }
+
+    fn create(
+        csh: &ComponentStore,
+        progress: Option<Box<dyn FnMut(usize, usize)>>,
+    ) -> SolidGeometryPipeline {
+        let (lighting_uniform, lighting_bind_group_layout, lighting_bind_group) =
+            Self::create_lighting(&csh.get(), &csh.get());
         let mut new = Self {
             world: csh.handle_for(),
             wgpu: csh.handle_for(),
             camera: csh.handle_for(),
             depth_texture: csh.handle_for(),
             atlas: csh.handle_for(),
-            textures: csh.handle_for(),
-            chunks: HashMap::new(),
+            registry: csh.handle_for(),
+            bundles_recorded: 0,
+            world_generation: csh.get::<ActiveWorld>().generation(),
+            textures: csh
+                .get::<TextureAtlases>()
+                .bind(BLOCK_ATLAS, 1, 0)
+                .expect("failed to bind the block atlas"),
+            chunk_meshes: HashMap::new(),
+            regions: HashMap::new(),
+            draw_order: DrawOrder::new(SortDirection::FrontToBack),
+            visible_regions: Vec::new(),
+            transparent_chunks: HashMap::new(),
+            transparent_order: DrawOrder::new(SortDirection::BackToFront),
+            visible_transparent: Vec::new(),
+            chunks_drawn: csh.get_mut::<DebugRenderer>().add_statistic_in(
+                StatCategory::World,
+                "Chunks Drawn",
+                "0 / 0",
+            ),
+            debug: csh.handle_for(),
+            debug_lines: csh.handle_for(),
+            pending: MeshQueue::new(),
+            pending_total: 0,
+            progress,
+            mesh_cache: None,
             camera_bind_group: None,
-            textures_bind_group: None,
+            camera_bind_group_layout: None,
+            lighting: csh.handle_for(),
+            lighting_uniform,
+            lighting_bind_group_layout,
+            lighting_bind_group,
             pipeline: None,
+            transparent_pipeline: None,
         };
 
         new.create_pipeline();
-        new.create_initial_chunks();
+        new.queue_initial_chunks();
 
         new
     }
 
-    fn create_pipeline(&mut self) {
-        let wgpu = self.wgpu.get();
-        let mut textures = self.textures.get_mut();
-        let mut builder = wgpu
-            .pipeline_builder("Solid Geometry Pipeline")
-            .shader(
-                "Solid Geometry Shader",
-                include_str!("../../../shaders/chunk_solid.wgsl"),
-                Some("vs"),
-                Some("fs"),
-            )
-            .add_vertex_layout::<SolidBlockVertex>();
-        builder = builder.add_color_target(wgpu.config.get().format);
+    /// Creates the lighting uniform and its bind group.
+    fn create_lighting(
+        wgpu: &WgpuRenderer,
+        lighting: &Lighting,
+    ) -> (
+        UniformBuffer<LightingUniform>,
+        wgpu::BindGroupLayout,
+        wgpu::BindGroup,
+    ) {
+        let uniform =
+            wgpu.uniform_buffer(&LightingUniform::from(lighting), Some("Lighting Uniform"));
+        let layout = wgpu.bind_group_layout(
+            Some("lighting bind group layout"),
+            &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        );
+        let bind_group = wgpu.bind_group(
+            Some("lighting bind group"),
+            &layout,
+            &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(
+                    uniform.buffer().as_entire_buffer_binding(),
+                ),
+            }],
+        );
+        (uniform, layout, bind_group)
+    }
 
+    fn create_pipeline(&mut self) {
         let camera = self.camera.get();
         let (camera_bind_group_layout, camera_bind_group) = camera.bind_group(0);
         self.camera_bind_group = Some(camera_bind_group);
-        builder = builder.push_bind_group(camera_bind_group_layout);
+        self.camera_bind_group_layout = Some(camera_bind_group_layout);
+        drop(camera);
+
+        let shader = self.wgpu.get().load_shader(
+            include_str!("../../../shaders/chunk_solid.wgsl"),
+            Some("Solid Geometry Shader"),
+            Some("vs"),
+            Some("fs"),
+        );
+        let (pipeline, transparent_pipeline) = self
+            .build_pipelines(&shader)
+            .expect("built-in solid geometry shader is invalid");
+        self.pipeline = Some(pipeline);
+        self.transparent_pipeline = Some(transparent_pipeline);
+    }
+
+    /// Builds the opaque and transparent pipelines from the given shader.
+    fn build_pipelines(
+        &self,
+        shader: &ShaderProgram,
+    ) -> anyhow::Result<(WgpuPipeline, WgpuPipeline)> {
+        let pipeline = self.build_pipeline(
+            "Solid Geometry Pipeline",
+            shader,
+            wgpu::BlendState::REPLACE,
+            true,
+        )?;
+        // Transparent faces blend over what's behind them, and don't write depth so transparent
+        // faces further back aren't rejected by nearer ones.
+        let transparent_pipeline = self.build_pipeline(
+            "Solid Geometry Transparent Pipeline",
+            shader,
+            wgpu::BlendState::ALPHA_BLENDING,
+            false,
+        )?;
+        Ok((pipeline, transparent_pipeline))
+    }
+
+    fn build_pipeline(
+        &self,
+        label: &str,
+        shader: &ShaderProgram,
+        blend: wgpu::BlendState,
+        depth_write_enabled: bool,
+    ) -> anyhow::Result<WgpuPipeline> {
+        let wgpu = self.wgpu.get();
+        let mut builder = wgpu
+            .pipeline_builder(label)
+            .shader_program(shader.clone())
+            .add_vertex_layout::<SolidBlockVertex>()
+            // Faces are only ever seen from outside, so skip the ones facing away.
+            .cull_mode(Some(wgpu::Face::Back));
+        builder = builder.add_blended_color_target(wgpu.config.get().format, blend);
+
+        builder = builder.push_bind_group(
+            self.camera_bind_group_layout
+                .clone()
+                .expect("camera bind group layout not created"),
+        );
 
         let depth_texture = self.depth_texture.get();
-        builder = builder.depth(depth_texture.state());
+        builder = builder.depth(wgpu::DepthStencilState {
+            depth_write_enabled,
+            ..depth_texture.state()
+        });
+
+        builder = builder.push_bind_group(self.textures.layout().clone());
+        builder = builder.push_bind_group(self.lighting_bind_group_layout.clone());
 
-        let block_texture = textures.gpu_texture();
+        info!("Creating {}: {:#?}", label, builder);
 
-        let (blocks_bind_layout, blocks_bind_group) =
-            block_texture.layout_and_bind_group(Some("block textures"), 1, 0);
-        self.textures_bind_group = Some(blocks_bind_group);
+        builder.try_build(None)
+    }
+
+    /// Queues every chunk in the world to be meshed over the next frames.
+    pub fn queue_initial_chunks(&mut self) {
+        let world_ref = self.world.get();
+        let world = world_ref.get_world().expect("no world present");
+        for (chunk, _) in world.chunks() {
+            self.pending_total += self.pending.push(chunk) as usize;
+        }
+    }
 
-        builder = builder.push_bind_group(blocks_bind_layout);
+    /// Drops every mesh and queues the chunks of the active world to be meshed again, e.g. after
+    /// the world was replaced by loading a save.
+    pub fn reload_world(&mut self) {
+        let wgpu = self.wgpu.get();
+        self.chunk_meshes.clear();
+        for (_, render_data) in self.regions.drain() {
+            render_data.recycle(&wgpu);
+        }
+        self.draw_order.invalidate();
+        self.visible_regions.clear();
+        for (_, render_data) in self.transparent_chunks.drain() {
+            render_data.recycle(&wgpu);
+        }
+        drop(wgpu);
+        self.visible_transparent.clear();
+        self.transparent_order.invalidate();
+        self.pending.clear();
+        self.pending_total = 0;
+        let world_ref = self.world.get();
+        self.world_generation = world_ref.generation();
+        if world_ref.get_world().is_some() {
+            drop(world_ref);
+            self.queue_initial_chunks();
+        }
+    }
+
+    /// Queues a newly loaded or changed chunk to be meshed, along with its loaded neighbors so
+    /// their borders are culled against it.
+    pub fn queue_chunk(&mut self, chunk: BlockPosition) {
+        let world_ref = self.world.get();
+        let Some(world) = world_ref.get_world() else {
+            return;
+        };
+        self.pending_total += self.pending.push_with_neighbors(chunk, world);
+    }
 
-        info!("Creating Solid Geometry Pipeline: {:#?}", builder);
+    /// Uploads a mesh built elsewhere for `chunk`, e.g. by a `ChunkLoader`, replacing its current
+    /// mesh and rebuilding its region.
+    ///
+    /// The mesh is drawn as it is until the chunk is next remeshed.
+    pub fn insert_chunk_mesh(&mut self, chunk: BlockPosition, mesh: ChunkMesh) {
+        self.chunk_meshes.insert(chunk, mesh);
+        self.rebuild_transparent_chunk(chunk);
+        self.rebuild_region(region_of(chunk));
+    }
 
-        self.pipeline = Some(builder.build(None));
+    /// Drops the meshes and buffers of chunks removed from the world, rebuilding the regions they
+    /// were in. Chunks still waiting to be meshed are taken off the queue.
+    pub fn unload_chunks(&mut self, chunks: &[BlockPosition]) {
+        let mut dirty_regions = HashSet::new();
+        for &chunk in chunks {
+            if self.pending.remove(chunk) {
+                self.pending_total -= 1;
+            }
+            if self.chunk_meshes.remove(&chunk).is_some() {
+                dirty_regions.insert(region_of(chunk));
+            }
+            self.rebuild_transparent_chunk(chunk);
+        }
+        for region in dirty_regions {
+            self.rebuild_region(region);
+        }
     }
 
-    /// Creates initial chunk render data for all chunks in the world.
+    /// Creates initial chunk render data for all chunks in the world, blocking until it's done.
     pub fn create_initial_chunks(&mut self) {
+        let _span = profile_span!("create_initial_chunks");
+        self.pending.clear();
+        self.pending_total = 0;
+        self.queue_initial_chunks();
+        self.mesh_pending_chunks(usize::MAX);
+    }
+
+    /// Returns the number of chunks still waiting to be meshed.
+    pub fn pending_chunks(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Sets the on-disk cache meshes are loaded from and stored to, or `None` to always remesh.
+    ///
+    /// Only chunks meshed after this call use the cache, so set it before queueing chunks.
+    pub fn set_mesh_cache(&mut self, cache: Option<MeshCache>) {
+        self.mesh_cache = cache;
+    }
+
+    /// Returns the on-disk mesh cache, if any.
+    pub fn mesh_cache(&self) -> Option<&MeshCache> {
+        self.mesh_cache.as_ref()
+    }
+
+    /// Meshes up to `limit` queued chunks. Returns how many were meshed.
+    pub fn mesh_pending_chunks(&mut self, limit: usize) -> usize {
+        let _span = profile_span!("mesh_pending_chunks");
         let world_ref = self.world.get();
-        let world = world_ref.get_world().expect("no world present");
+        let Some(world) = world_ref.get_world() else {
+            return 0;
+        };
+        let mut chunks = Vec::new();
+        while chunks.len() < limit {
+            let Some(chunk_coord) = self.pending.pop_ready(world) else {
+                break;
+            };
+            chunks.push(chunk_coord);
+        }
+        drop(world_ref);
+
+        let meshed = chunks.len();
+        self.mesh_chunks(&chunks);
+        if let Some(progress) = self.progress.as_mut() {
+            let done = self.pending_total - self.pending.len();
+            for done in (done + 1).saturating_sub(meshed)..=done {
+                progress(done, self.pending_total);
+            }
+        }
+
+        if meshed > 0 && self.pending.is_empty() {
+            let (vertex_count, index_count) =
+                self.regions.values().fold((0, 0), |(v, i), region| {
+                    (
+                        v + region.vertex_buffer.count(),
+                        i + region.index_buffer.count(),
+                    )
+                });
+            info!(
+                "Created solid geometry for {} chunks in {} regions ({} vertices, {} indices)",
+                self.chunk_meshes.len(),
+                self.regions.len(),
+                vertex_count,
+                index_count
+            );
+            self.pending_total = 0;
+        }
+
+        meshed
+    }
+
+    /// Re-meshes up to `limit` chunks from the active world's dirty queue, returning how many
+    /// were re-meshed.
+    ///
+    /// Only the dirty chunks and the regions containing them are rebuilt; every other chunk keeps
+    /// its buffers.
+    pub fn remesh_dirty_chunks(&mut self, limit: usize) -> usize {
+        let _span = profile_span!("remesh_dirty_chunks");
+        let chunks = self.world.get_mut().pop_dirty_chunks(limit);
+        self.mesh_chunks(&chunks);
+        chunks.len()
+    }
+
+    /// Meshes the given chunks, dropping the meshes of chunks that are no longer loaded, and
+    /// rebuilds the regions containing them.
+    fn mesh_chunks(&mut self, chunks: &[BlockPosition]) {
+        if chunks.is_empty() {
+            return;
+        }
+        let world_ref = self.world.get();
+        let Some(world) = world_ref.get_world() else {
+            return;
+        };
         let atlas = self.atlas.get();
+        let registry = self.registry.get();
 
-        let mut vertex_count = 0;
-        let mut index_count = 0;
+        let mut dirty_regions = HashSet::new();
+        for &chunk_coord in chunks {
+            // The chunk may have been unloaded since it was queued.
+            if let Some(chunk_res) = world.chunk(chunk_coord) {
+                let chunk = chunk_res.get();
+                let mesh = match &self.mesh_cache {
+                    Some(cache) => {
+                        let key = mesh_key(&chunk, &atlas, &registry);
+                        cache.load(chunk_coord, key).unwrap_or_else(|| {
+                            let mesh = build_mesh_for_chunk(
+                                &atlas,
+                                &registry,
+                                &chunk,
+                                chunk_coord.chunk_origin(),
+                            );
+                            if let Err(e) = cache.store(chunk_coord, key, &mesh) {
+                                warn!("{:#}", e);
+                            }
+                            mesh
+                        })
+                    }
+                    None => {
+                        build_mesh_for_chunk(&atlas, &registry, &chunk, chunk_coord.chunk_origin())
+                    }
+                };
+                self.chunk_meshes.insert(chunk_coord, mesh);
+            } else {
+                self.chunk_meshes.remove(&chunk_coord);
+            }
+            dirty_regions.insert(region_of(chunk_coord));
+        }
 
-        for (chunk_coord, chunk_res) in world.chunks.iter() {
-            let chunk = chunk_res.get();
-            let render_data =
-                ChunkSolidRenderData::from_chunk(self.wgpu.clone(), &atlas, &chunk, *chunk_coord);
-            vertex_count += render_data.vertex_buffer.count();
-            index_count += render_data.index_buffer.count();
-            self.chunks.insert(*chunk_coord, render_data);
+        drop((atlas, registry));
+        drop(world_ref);
+        for &chunk in chunks {
+            self.rebuild_transparent_chunk(chunk);
         }
+        for region in dirty_regions {
+            self.rebuild_region(region);
+        }
+    }
 
-        info!(
-            "Created solid geometry for {} chunks ({} vertices, {} indices)",
-            self.chunks.len(),
-            vertex_count,
-            index_count
+    /// Rebuilds the shared buffers of a region from the meshes of its chunks.
+    fn rebuild_region(&mut self, region: BlockPosition) {
+        let _span = profile_span!("rebuild_region", region = ?region);
+        let (vertices, indices) = merge_region_meshes(
+            region_chunks(region)
+                .filter_map(|chunk| self.chunk_meshes.get(&chunk))
+                .map(|mesh| (mesh.vertices.as_slice(), &mesh.indices)),
         );
+        let wgpu = self.wgpu.get();
+        let old = self.regions.remove(&region);
+        if indices.is_empty() {
+            if let Some(old) = old {
+                old.recycle(&wgpu);
+                self.draw_order.invalidate();
+            }
+            return;
+        }
+
+        let indices = Indices::U32(indices);
+        let vertex_label = format!("Region Solid Vertex Buffer {:?}", region);
+        let index_label = format!("Region Solid Index Buffer {:?}", region);
+        // The region's old buffers are written in place when the new geometry fits.
+        let mut render_data = match old {
+            Some(mut old) => {
+                wgpu.update_vertex_buffer(&mut old.vertex_buffer, &vertices, Some(&vertex_label));
+                wgpu.update_index_buffer(&mut old.index_buffer, &indices, Some(&index_label));
+                old.bundle = None;
+                old
+            }
+            None => {
+                self.draw_order.invalidate();
+                RegionSolidRenderData {
+                    vertex_buffer: wgpu.pooled_vertex_buffer(&vertices, Some(&vertex_label)),
+                    index_buffer: wgpu.pooled_index_buffer(&indices, Some(&index_label)),
+                    bundle: None,
+                }
+            }
+        };
+        drop(wgpu);
+        render_data.bundle = Some(self.record_bundle(region, &render_data));
+        self.bundles_recorded += 1;
+        self.regions.insert(region, render_data);
     }
-}
 
-impl<K: PipelineKey> RenderPipeline<K> for SolidGeometryPipeline {
-    fn label(&self) -> Option<&str> {
-        Some("Solid Geometry Pipeline")
+    /// Rebuilds the buffers holding the transparent faces of a chunk from its mesh.
+    ///
+    /// Transparent faces are kept per chunk rather than per region, so they can be sorted back to
+    /// front at a finer grain.
+    fn rebuild_transparent_chunk(&mut self, chunk: BlockPosition) {
+        let Some(mesh) = self
+            .chunk_meshes
+            .get(&chunk)
+            .filter(|mesh| !mesh.transparent_indices.is_empty())
+        else {
+            if let Some(old) = self.transparent_chunks.remove(&chunk) {
+                old.recycle(&self.wgpu.get());
+                self.transparent_order.invalidate();
+            }
+            return;
+        };
+
+        let wgpu = self.wgpu.get();
+        let vertex_label = format!("Chunk Transparent Vertex Buffer {:?}", chunk);
+        let index_label = format!("Chunk Transparent Index Buffer {:?}", chunk);
+        match self.transparent_chunks.get_mut(&chunk) {
+            Some(render_data) => {
+                wgpu.update_vertex_buffer(
+                    &mut render_data.vertex_buffer,
+                    &mesh.transparent_vertices,
+                    Some(&vertex_label),
+                );
+                wgpu.update_index_buffer(
+                    &mut render_data.index_buffer,
+                    &mesh.transparent_indices,
+                    Some(&index_label),
+                );
+            }
+            None => {
+                let render_data = ChunkTransparentRenderData {
+                    vertex_buffer: wgpu
+                        .pooled_vertex_buffer(&mesh.transparent_vertices, Some(&vertex_label)),
+                    index_buffer: wgpu
+                        .pooled_index_buffer(&mesh.transparent_indices, Some(&index_label)),
+                };
+                self.transparent_chunks.insert(chunk, render_data);
+                self.transparent_order.invalidate();
+            }
+        }
     }
 
-    fn update(&mut self) -> Option<engine::graphics::pipeline::UpdateRequest> {
-        /// TODO: Chunk updates will be done with a queue system to update only changed chunks.
-        None
+    /// Returns the number of chunks with transparent faces to draw.
+    pub fn transparent_chunk_count(&self) -> usize {
+        self.transparent_chunks.len()
     }
 
-    fn render(
+    /// Records a render bundle setting the pipeline and its bind groups and drawing `render_data`
+    /// with the main camera.
+    ///
+    /// Region geometry rarely changes, so each region's bundle is recorded once when the region
+    /// is rebuilt and replayed every frame after.
+    fn record_bundle(
         &self,
-        controller: &engine::graphics::pipeline::controller::RenderController<K>,
+        region: BlockPosition,
+        render_data: &RegionSolidRenderData,
+    ) -> wgpu::RenderBundle {
+        let _span = profile_span!("solid_record_bundle", region = ?region);
+        let wgpu = self.wgpu.get();
+        let label = format!("Region Solid Bundle {:?}", region);
+        let mut encoder =
+            wgpu.render_bundle_encoder(Some(&label), Some(self.depth_texture.get().bundle_state()));
+        let pipeline = self
+            .pipeline
+            .as_ref()
+            .expect("Solid Geometry Pipeline not created");
+        let camera_bind_group = self
+            .camera_bind_group
+            .as_ref()
+            .expect("Solid Geometry Pipeline not created");
+
+        encoder.set_pipeline(&pipeline.pipeline);
+        encoder.set_bind_group(0, camera_bind_group, &[]);
+        encoder.set_bind_group(1, self.textures.bind_group(), &[]);
+        encoder.set_bind_group(2, &self.lighting_bind_group, &[]);
+        render_data.draw(&mut encoder);
+        encoder.finish(&wgpu::RenderBundleDescriptor {
+            label: Some(&label),
+        })
+    }
+
+    /// Records the bundles of every region again, e.g. because the bind groups baked into them
+    /// changed.
+    fn rerecord_bundles(&mut self) {
+        let regions = self.regions.keys().copied().collect::<Vec<_>>();
+        for region in regions {
+            let bundle = self.record_bundle(region, &self.regions[&region]);
+            self.regions
+                .get_mut(&region)
+                .expect("region was just listed")
+                .bundle = Some(bundle);
+            self.bundles_recorded += 1;
+        }
+    }
+
+    /// Returns how many region bundles have been recorded so far.
+    ///
+    /// Bundles are only recorded when a region is rebuilt after a remesh, or when the textures are
+    /// rebound, so this stays constant while nothing changes.
+    pub fn bundles_recorded(&self) -> u64 {
+        self.bundles_recorded
+    }
+
+    /// Renders the world from the given camera into a new offscreen texture of the given size.
+    ///
+    /// Only chunks that have been meshed so far are drawn.
+    /// The texture uses the surface format and can be sampled or copied from afterwards.
+    /// This submits its own command buffer, so it can be called outside of the normal frame.
+    pub fn render_to_texture(&self, camera: &Camera, size: (u32, u32)) -> Texture {
+        let _span = profile_span!("solid_render_to_texture");
+        let wgpu = self.wgpu.get();
+        let texture = wgpu.texture_uninit(
+            Some("Solid Geometry Offscreen Target"),
+            wgpu.config.get().format,
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            size,
+            1,
+        );
+        // The texture's own view is an array view, which can't be used as an attachment.
+        let target = texture.texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2),
+            ..Default::default()
+        });
+        let depth_texture = DepthTexture::with_size(self.wgpu.clone(), size);
+
+        let uniform = wgpu.uniform_buffer(
+            &camera.projection_view_matrix(),
+            Some("Offscreen Camera Uniform"),
+        );
+        let camera_bind_group = wgpu.bind_group(
+            Some("offscreen camera bind group"),
+            self.camera_bind_group_layout
+                .as_ref()
+                .expect("Solid Geometry Pipeline not created"),
+            &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(
+                    uniform.buffer().as_entire_buffer_binding(),
+                ),
+            }],
+        );
+
+        // The cached order and culling follow the main camera, so redo them for this one.
+        let frustum = camera.frustum();
+        let mut draw_order = DrawOrder::new(SortDirection::FrontToBack);
+        draw_order.update(
+            camera.position,
+            self.regions
+                .keys()
+                .filter(|region| {
+                    let (min, max) = region_bounds(**region);
+                    frustum.intersects_aabb(min, max)
+                })
+                .map(|region| (*region, region_center(*region))),
+        );
+
+        let mut transparent_order = DrawOrder::new(SortDirection::BackToFront);
+        transparent_order.update(
+            camera.position,
+            self.transparent_chunks
+                .keys()
+                .filter(|chunk| {
+                    let (min, max) = chunk.world_aabb();
+                    frustum.intersects_aabb(min, max)
+                })
+                .map(|chunk| (*chunk, chunk_center(*chunk))),
+        );
+
+        let mut encoder = wgpu.create_encoder(Some("Solid Geometry Offscreen Encoder"));
+        self.draw(
+            &wgpu,
+            &mut encoder,
+            &target,
+            depth_texture.clear_attachment(),
+            &camera_bind_group,
+            wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+            draw_order.order(),
+        );
+        {
+            let mut render_pass = wgpu.render_pass(
+                Some("Solid Geometry Transparent Render Pass"),
+                &mut encoder,
+                &target,
+                Some(depth_texture.attachment()),
+                wgpu::LoadOp::Load,
+            );
+            self.draw_transparent(
+                &mut render_pass,
+                &camera_bind_group,
+                transparent_order.order(),
+            );
+        }
+        wgpu.submit_single(encoder.finish());
+
+        texture
+    }
+
+    /// Records a render pass drawing the regions in `order` into the given target.
+    #[allow(clippy::too_many_arguments)]
+    fn draw(
+        &self,
+        wgpu: &WgpuRenderer,
         encoder: &mut wgpu::CommandEncoder,
         target: &wgpu::TextureView,
+        depth: wgpu::RenderPassDepthStencilAttachment<'_>,
+        camera_bind_group: &wgpu::BindGroup,
+        color_load_op: wgpu::LoadOp<wgpu::Color>,
+        order: &[BlockPosition],
     ) {
-        let wgpu = controller.wgpu.get();
-        let depth_texture = self.depth_texture.get();
         let mut render_pass_desc = wgpu.render_pass(
             Some("Solid Geometry Pipeline Render Pass"),
             encoder,
             target,
-            Some(depth_texture.attachment()),
-            wgpu::LoadOp::Load,
+            Some(depth),
+            color_load_op,
         );
 
         let pipeline = self
@@ -154,74 +834,324 @@ impl<K: PipelineKey> RenderPipeline<K> for SolidGeometryPipeline {
             .expect("Solid Geometry Pipeline not created");
 
         render_pass_desc.set_pipeline(&pipeline.pipeline);
-        if let Some(ref camera_bind_group) = self.camera_bind_group {
-            render_pass_desc.set_bind_group(0, camera_bind_group, &[]);
+        render_pass_desc.set_bind_group(0, camera_bind_group, &[]);
+        render_pass_desc.set_bind_group(1, self.textures.bind_group(), &[]);
+        render_pass_desc.set_bind_group(2, &self.lighting_bind_group, &[]);
+
+        // Regions are drawn front to back, so the depth test rejects hidden fragments early.
+        for region in order {
+            if let Some(region_render_data) = self.regions.get(region) {
+                region_render_data.draw(&mut render_pass_desc);
+            }
         }
-        if let Some(ref textures_bind_group) = self.textures_bind_group {
-            render_pass_desc.set_bind_group(1, textures_bind_group, &[]);
+    }
+}
+
+impl SolidGeometryPipeline {
+    /// Keeps only the regions and transparent chunks at least partly inside `frustum` for drawing,
+    /// in draw order, and updates the drawn chunks statistic.
+    fn cull(&mut self, frustum: &Frustum) {
+        let _span = profile_span!("solid_cull");
+        self.visible_regions.clear();
+        self.visible_regions
+            .extend(self.draw_order.order().iter().copied().filter(|region| {
+                let (min, max) = region_bounds(*region);
+                frustum.intersects_aabb(min, max)
+            }));
+        self.visible_transparent.clear();
+        self.visible_transparent
+            .extend(
+                self.transparent_order
+                    .order()
+                    .iter()
+                    .copied()
+                    .filter(|chunk| {
+                        let (min, max) = chunk.world_aabb();
+                        frustum.intersects_aabb(min, max)
+                    }),
+            );
+
+        let drawn = self
+            .visible_regions
+            .iter()
+            .flat_map(|region| region_chunks(*region))
+            .filter(|chunk| self.chunk_meshes.contains_key(chunk))
+            .count();
+        self.chunks_drawn
+            .update_value(format!("{} / {}", drawn, self.chunk_meshes.len()));
+    }
+
+    /// Queues the bounds of every chunk with geometry as debug lines, colored by whether the chunk
+    /// is drawn, culled or waiting to be meshed.
+    ///
+    /// Only done while the debug overlay shows the world category, so it costs nothing otherwise.
+    fn draw_chunk_bounds(&self) {
+        let debug = self.debug.get();
+        if !debug.enabled || !debug.is_category_visible(StatCategory::World) {
+            return;
         }
+        drop(debug);
 
-        for chunk_render_data in self.chunks.values() {
-            chunk_render_data.draw(&mut render_pass_desc);
+        let _span = profile_span!("draw_chunk_bounds");
+        let world = self.world.get();
+        let visible = self.visible_regions.iter().collect::<HashSet<_>>();
+        let mut lines = self.debug_lines.get_mut();
+        for (&chunk, mesh) in &self.chunk_meshes {
+            let dirty = self.pending.contains(chunk) || world.is_chunk_dirty(chunk);
+            // Air chunks would bury the interesting ones in boxes.
+            if !dirty && mesh.vertices.is_empty() && mesh.transparent_vertices.is_empty() {
+                continue;
+            }
+            let color = if dirty {
+                DIRTY_CHUNK_COLOR
+            } else if visible.contains(&region_of(chunk)) {
+                VISIBLE_CHUNK_COLOR
+            } else {
+                CULLED_CHUNK_COLOR
+            };
+            let (min, max) = chunk.world_aabb();
+            lines.draw_box(min, max, color);
         }
     }
-}
 
-struct ChunkSolidRenderData {
-    vertex_buffer: VertexBuffer<SolidBlockVertex>,
-    index_buffer: IndexBuffer<u16>,
+    /// Draws the transparent faces of the chunks in `order` into a render pass.
+    fn draw_transparent(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        camera_bind_group: &wgpu::BindGroup,
+        order: &[BlockPosition],
+    ) {
+        let pipeline = self
+            .transparent_pipeline
+            .as_ref()
+            .expect("Solid Geometry Transparent Pipeline not created");
+        render_pass.set_pipeline(&pipeline.pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_bind_group(1, self.textures.bind_group(), &[]);
+        render_pass.set_bind_group(2, &self.lighting_bind_group, &[]);
+
+        // Chunks are drawn back to front, so nearer faces blend over farther ones.
+        for chunk in order {
+            if let Some(render_data) = self.transparent_chunks.get(chunk) {
+                render_data.draw(render_pass);
+            }
+        }
+    }
 }
 
-impl ChunkSolidRenderData {
-    pub fn from_chunk(
-        wgpu: ComponentHandle<WgpuRenderer>,
-        atlas: &BlockTextureAtlas,
-        chunk: &Chunk,
-        chunk_coord: BlockPosition,
-    ) -> Self {
-        let (vertices, indices) = build_mesh_for_chunk(atlas, chunk, chunk_coord * bp(16, 16, 16));
-        let wgpu = wgpu.get();
-        let vertex_buffer = wgpu.vertex_buffer(
-            &vertices,
-            Some(&format!("Chunk Solid Vertex Buffer {:?}", chunk_coord)),
+impl<K: PipelineKey> RenderPipeline<K> for SolidGeometryPipeline {
+    fn label(&self) -> Option<&str> {
+        Some("Solid Geometry Pipeline")
+    }
+
+    fn reload_shaders(&mut self) -> anyhow::Result<()> {
+        let shader = ShaderProgram::from_file(
+            &*self.wgpu.get(),
+            concat!(env!("CARGO_MANIFEST_DIR"), "/shaders/chunk_solid.wgsl"),
+            Some("vs"),
+            Some("fs"),
+        )?;
+        let (pipeline, transparent_pipeline) = self.build_pipelines(&shader)?;
+        self.pipeline = Some(pipeline);
+        self.transparent_pipeline = Some(transparent_pipeline);
+        // The region bundles were recorded with the old pipelines.
+        self.rerecord_bundles();
+        Ok(())
+    }
+
+    fn update(&mut self) -> Option<engine::graphics::pipeline::UpdateRequest> {
+        if self.world.get().generation() != self.world_generation {
+            self.reload_world();
+        }
+        if self.textures.refresh() {
+            self.rerecord_bundles();
+        }
+        // The bundles read the uniform when they're replayed, so they don't need recording again.
+        self.lighting_uniform
+            .write(&LightingUniform::from(&*self.lighting.get()));
+        // Edits go first, so they show up even while the world is still streaming in.
+        self.remesh_dirty_chunks(DIRTY_CHUNKS_PER_FRAME);
+        self.mesh_pending_chunks(CHUNKS_MESHED_PER_FRAME);
+        let camera = self.camera.get();
+        let eye = camera.position();
+        let frustum = camera.frustum();
+        drop(camera);
+        self.draw_order.update(
+            eye,
+            self.regions
+                .keys()
+                .map(|region| (*region, region_center(*region))),
         );
-        let index_buffer = wgpu.index_buffer(
-            &indices,
-            Some(&format!("Chunk Solid Index Buffer {:?}", chunk_coord)),
+        self.transparent_order.update(
+            eye,
+            self.transparent_chunks
+                .keys()
+                .map(|chunk| (*chunk, chunk_center(*chunk))),
         );
-        Self {
-            vertex_buffer,
-            index_buffer,
+        self.cull(&frustum);
+        self.draw_chunk_bounds();
+        None
+    }
+
+    fn render(
+        &self,
+        controller: &engine::graphics::pipeline::controller::RenderController<K>,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+    ) {
+        let _span = profile_span!("solid_geometry_render");
+        let depth_texture = self.depth_texture.get();
+        let mut render_pass = controller
+            .pass(target)
+            .label("Solid Geometry Pipeline Render Pass")
+            .depth(depth_texture.attachment())
+            .begin(encoder);
+        // Regions are drawn front to back, so the depth test rejects hidden fragments early.
+        render_pass.execute_bundles(
+            self.visible_regions
+                .iter()
+                .filter_map(|region| self.regions.get(region)?.bundle.as_ref()),
+        );
+        drop(render_pass);
+
+        if self.visible_transparent.is_empty() {
+            return;
+        }
+        let mut render_pass = controller
+            .pass(target)
+            .label("Solid Geometry Transparent Render Pass")
+            .depth(depth_texture.attachment())
+            .begin(encoder);
+        self.draw_transparent(
+            &mut render_pass,
+            self.camera_bind_group
+                .as_ref()
+                .expect("Solid Geometry Pipeline not created"),
+            &self.visible_transparent,
+        );
+    }
+}
+
+/// The CPU side mesh of a chunk, kept so its region can be rebuilt without remeshing it.
+///
+/// Indices are 16 bit unless the mesh has too many vertices for them.
+#[derive(Clone, Default)]
+pub struct ChunkMesh {
+    /// Faces of opaque blocks, merged into the region's buffers.
+    pub vertices: Vec<SolidBlockVertex>,
+    pub indices: Indices,
+    /// Faces of transparent blocks like leaves, drawn in a separate blended pass.
+    pub transparent_vertices: Vec<SolidBlockVertex>,
+    pub transparent_indices: Indices,
+}
+
+/// The shared buffers holding the solid geometry of every chunk in a region, and the bundle
+/// drawing them.
+struct RegionSolidRenderData {
+    vertex_buffer: VertexBuffer<SolidBlockVertex>,
+    index_buffer: AnyIndexBuffer,
+    bundle: Option<wgpu::RenderBundle>,
+}
+
+impl RegionSolidRenderData {
+    /// Gives the region's buffers back to the renderer's buffer pool.
+    fn recycle(self, wgpu: &WgpuRenderer) {
+        wgpu.recycle_buffer(self.vertex_buffer.into_buffer());
+        wgpu.recycle_buffer(self.index_buffer.into_buffer());
+    }
+
+    /// Returns true if the region has no geometry to draw.
+    pub fn is_empty(&self) -> bool {
+        self.index_buffer.is_empty() || self.vertex_buffer.is_empty()
+    }
+
+    /// Draws the region's solid geometry, into a render pass or a render bundle. Empty regions are
+    /// skipped, as some backends warn about zero-count draws.
+    pub fn draw<'a>(&'a self, render_pass: &mut impl RenderEncoder<'a>) {
+        if self.is_empty() {
+            return;
         }
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.buffer().slice(..));
+        render_pass.set_index_buffer(
+            self.index_buffer.buffer().slice(..),
+            self.index_buffer.format(),
+        );
+        render_pass.draw_indexed(0..self.index_buffer.count() as u32, 0, 0..1);
     }
+}
 
-    /// Draws the chunk's solid geometry.
-    pub fn draw<'a>(&self, render_pass: &mut wgpu::RenderPass<'a>) {
+/// The buffers holding the transparent faces of a chunk.
+struct ChunkTransparentRenderData {
+    vertex_buffer: VertexBuffer<SolidBlockVertex>,
+    index_buffer: AnyIndexBuffer,
+}
+
+impl ChunkTransparentRenderData {
+    /// Gives the chunk's buffers back to the renderer's buffer pool.
+    fn recycle(self, wgpu: &WgpuRenderer) {
+        wgpu.recycle_buffer(self.vertex_buffer.into_buffer());
+        wgpu.recycle_buffer(self.index_buffer.into_buffer());
+    }
+
+    /// Draws the chunk's transparent faces.
+    fn draw(&self, render_pass: &mut wgpu::RenderPass<'_>) {
         render_pass.set_vertex_buffer(0, self.vertex_buffer.buffer().slice(..));
         render_pass.set_index_buffer(
             self.index_buffer.buffer().slice(..),
-            wgpu::IndexFormat::Uint16,
+            self.index_buffer.format(),
         );
         render_pass.draw_indexed(0..self.index_buffer.count() as u32, 0, 0..1);
     }
 }
 
+/// The ambient occlusion level of a vertex with nothing around it.
+pub const AO_UNOCCLUDED: u32 = 3;
+
 #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 #[repr(C)]
 pub struct SolidBlockVertex {
     pub position: Vec3,
     pub tex_coord: Vec2,
     pub texture_index: u32,
+    /// How exposed the vertex is, from 0 (tucked into a corner) to `AO_UNOCCLUDED`.
+    pub ao: u32,
+    /// The direction the vertex's face points in, as a `CardinalDirection` index.
+    pub normal: u8,
+    /// The light level in front of the vertex's face, from 0 to `BlockRegistry::MAX_LIGHT`.
+    pub light: u8,
+    _padding: [u8; 2],
 }
 
 impl SolidBlockVertex {
-    pub fn new(position: Vec3, tex_coord: Vec2, texture_index: u32) -> Self {
+    /// Creates an unoccluded, fully lit vertex on a face pointing in `face`.
+    pub fn new(
+        position: Vec3,
+        tex_coord: Vec2,
+        texture_index: u32,
+        face: CardinalDirection,
+    ) -> Self {
         Self {
             position,
             tex_coord,
             texture_index,
+            ao: AO_UNOCCLUDED,
+            normal: face as u8,
+            light: BlockRegistry::MAX_LIGHT,
+            _padding: [0; 2],
         }
     }
+
+    /// Sets the ambient occlusion level of the vertex.
+    pub fn with_ao(mut self, ao: u32) -> Self {
+        self.ao = ao;
+        self
+    }
+
+    /// Sets the light level of the vertex.
+    pub fn with_light(mut self, light: u8) -> Self {
+        self.light = light;
+        self
+    }
 }
 
 unsafe impl VertexLayout for SolidBlockVertex {
@@ -232,54 +1162,127 @@ unsafe impl VertexLayout for SolidBlockVertex {
             0 => Float32x3, // position
             1 => Float32x2, // tex_coord
             2 => Uint32,    // texture_index
+            3 => Uint32,    // ao
+            4 => Uint8,     // normal
+            5 => Uint8,     // light
         ],
     };
 }
 
-/// TODO: This function in the future can be made to return (BufferState<SolidBlockVertex>, BufferState<TransparentBlockVertex>)
-fn build_mesh_for_chunk(
+/// Builds the solid geometry of a chunk, offset to `world_pos`.
+///
+/// Faces hidden by their neighbor, as decided by `BlockRegistry::face_hidden_by`, are skipped.
+/// Faces of transparent blocks go into the mesh's transparent buffers, so opaque geometry can be
+/// drawn without blending.
+pub fn build_mesh_for_chunk<const S: usize>(
     atlas: &BlockTextureAtlas,
-    chunk: &Chunk,
+    registry: &BlockRegistry,
+    chunk: &ChunkN<S>,
     world_pos: BlockPosition,
-) -> (Vec<SolidBlockVertex>, Vec<u16>) {
-    let mut vertices = Vec::new();
-    let mut indices = Vec::new();
+) -> ChunkMesh {
+    let _span = profile_span!("build_mesh_for_chunk", position = ?world_pos);
+    let mut mesh = ChunkMesh::default();
 
-    for x in 0..16 {
-        for y in 0..16 {
-            for z in 0..16 {
+    for x in 0..S {
+        for y in 0..S {
+            for z in 0..S {
                 let block = chunk.data[x][y][z];
-                if block.is_solid() {
+                if registry.is_cube(block) {
+                    let (vertices, indices) = if registry.is_transparent(block) {
+                        (
+                            &mut mesh.transparent_vertices,
+                            &mut mesh.transparent_indices,
+                        )
+                    } else {
+                        (&mut mesh.vertices, &mut mesh.indices)
+                    };
                     mesh_block_at(
                         block,
+                        registry,
                         chunk,
                         world_pos,
                         bp(x as i64, y as i64, z as i64),
                         atlas,
-                        &mut vertices,
-                        &mut indices,
+                        vertices,
+                        indices,
                     );
                 }
             }
         }
     }
 
-    (vertices, indices)
+    mesh
+}
+/// Returns the ambient occlusion level of a vertex from whether the two blocks along its edges
+/// and the block in its corner are opaque.
+///
+/// Two opaque sides fully occlude the vertex, whatever is in the corner.
+pub fn vertex_ao(side_a: bool, side_b: bool, corner: bool) -> u32 {
+    if side_a && side_b {
+        0
+    } else {
+        AO_UNOCCLUDED - (side_a as u32 + side_b as u32 + corner as u32)
+    }
 }
 
-fn mesh_block_at(
+/// Returns the indices of a face whose corners have the given ambient occlusion levels.
+///
+/// A quad is split into two triangles along a diagonal, and its shading is interpolated across
+/// each triangle separately. Splitting along the diagonal whose corners are darker keeps the
+/// gradient symmetric, instead of smearing one dark corner across the whole face.
+pub fn face_indices(ao: [u32; 4]) -> [u16; 6] {
+    if ao[0] + ao[2] > ao[1] + ao[3] {
+        [1, 2, 3, 3, 0, 1]
+    } else {
+        FACE_INDICES
+    }
+}
+
+/// Returns the ambient occlusion level of each corner of a block's face, in `FACE_TABLE` order.
+fn face_ao<const S: usize>(
+    registry: &BlockRegistry,
+    chunk: &ChunkN<S>,
+    chunk_pos: BlockPosition,
+    face: CardinalDirection,
+) -> [u32; 4] {
+    let normal = face.normal();
+    let front = chunk_pos + bp(normal.x as i64, normal.y as i64, normal.z as i64);
+    let occludes =
+        |offset: Vec3| {
+            registry.is_opaque(chunk.inspect_block_relative(
+                front + bp(offset.x as i64, offset.y as i64, offset.z as i64),
+            ))
+        };
+    FACE_TABLE[face as usize].map(|(corner, _)| {
+        // Which way the corner lies from the face's center, along each axis of the face.
+        let toward = (Vec3::from(corner) * 2.0 - Vec3::ONE) * (Vec3::ONE - normal.abs());
+        let axes = toward.to_array().map(|c| c != 0.0);
+        let (a, b) = match axes {
+            [false, _, _] => (Vec3::new(0.0, toward.y, 0.0), Vec3::new(0.0, 0.0, toward.z)),
+            [_, false, _] => (Vec3::new(toward.x, 0.0, 0.0), Vec3::new(0.0, 0.0, toward.z)),
+            _ => (Vec3::new(toward.x, 0.0, 0.0), Vec3::new(0.0, toward.y, 0.0)),
+        };
+        vertex_ao(occludes(a), occludes(b), occludes(a + b))
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn mesh_block_at<const S: usize>(
     block: Block,
-    chunk: &Chunk,
+    registry: &BlockRegistry,
+    chunk: &ChunkN<S>,
     chunk_world_pos: BlockPosition,
     chunk_pos: BlockPosition,
     atlas: &BlockTextureAtlas,
     vertices: &mut Vec<SolidBlockVertex>,
-    indices: &mut Vec<u16>,
+    indices: &mut Indices,
 ) {
     let mut push_face = |face: CardinalDirection| {
-        let base_index = vertices.len() as u16;
+        let base_index = vertices.len() as u32;
         let world_pos = chunk_pos + chunk_world_pos;
-        for (pos, uv) in FACE_TABLE[face as usize].iter() {
+        let ao = face_ao(registry, chunk, chunk_pos, face);
+        let light = chunk.light_relative(chunk_pos.offset(face));
+        for ((pos, uv), ao) in FACE_TABLE[face as usize].iter().zip(ao) {
             let world_pos = Vec3::new(
                 world_pos.0 as f32 + pos[0],
                 world_pos.1 as f32 + pos[1],
@@ -290,19 +1293,460 @@ fn mesh_block_at(
                 world_pos,
                 Vec2::new(uv[0], uv[1]),
                 atlas.texture_index(block, face),
-            );
+                face,
+            )
+            .with_ao(ao)
+            .with_light(light);
             vertices.push(vertex);
         }
-        for &index in FACE_INDICES.iter() {
-            indices.push(base_index + index);
+        for index in face_indices(ao) {
+            indices.push(base_index + index as u32);
         }
     };
 
-    let chunk_rel = chunk_pos.chunk_normalize();
+    let chunk_rel = chunk_pos.normalize_in(S);
 
     for face in CardinalDirection::iter() {
-        if !chunk.inspect_block(chunk_rel, face).is_solid() {
+        if !registry.face_hidden_by(block, chunk.inspect_block(chunk_rel, face)) {
             push_face(face);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use engine::graphics::textures::{TextureCollection, TextureHandle};
+
+    use super::*;
+    use crate::{
+        render::{RenderPipelines, headless::headless_state, lighting::FACE_SHADE},
+        world::{BlockProperties, Chunk},
+    };
+
+    #[test]
+    fn render_single_block_to_texture() {
+        let state = headless_state(|world| {
+            world.set_block(bp(0, 0, 0), Block::Stone);
+        });
+        let mut pipeline = SolidGeometryPipeline::new(&state);
+        assert_eq!(pipeline.pending_chunks(), 1);
+        pipeline.mesh_pending_chunks(usize::MAX);
+        assert_eq!(pipeline.pending_chunks(), 0);
+        assert_eq!(pipeline.chunk_meshes.len(), 1);
+
+        let mut camera = Camera::new(1.0, 0.1, 100.0);
+        camera.position = Vec3::new(3.0, 3.0, 3.0);
+        camera.look_at(Vec3::splat(0.5));
+        let submissions = state.get::<WgpuRenderer>().submission_count();
+        let texture = pipeline.render_to_texture(&camera, (8, 4));
+
+        let size = texture.texture.size();
+        assert_eq!((size.width, size.height), (8, 4));
+        assert!(
+            texture
+                .texture
+                .usage()
+                .contains(wgpu::TextureUsages::COPY_SRC)
+        );
+        assert_eq!(
+            state.get::<WgpuRenderer>().submission_count(),
+            submissions + 1
+        );
+    }
+
+    #[test]
+    fn empty_region_draw_is_a_no_op() {
+        let state = headless_state(|_| {});
+        let wgpu = state.get::<WgpuRenderer>();
+        let region = |vertices: &[SolidBlockVertex], indices: Vec<u32>| RegionSolidRenderData {
+            vertex_buffer: wgpu.vertex_buffer(vertices, None),
+            index_buffer: wgpu.any_index_buffer(&Indices::U32(indices), None),
+            bundle: None,
+        };
+        // Records `region` into a bundle without a pipeline set, which only succeeds if nothing
+        // is drawn.
+        let records = |region: &RegionSolidRenderData| {
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let mut encoder = wgpu.render_bundle_encoder(None, None);
+                region.draw(&mut encoder);
+                encoder.finish(&wgpu::RenderBundleDescriptor { label: None });
+            }))
+            .is_ok()
+        };
+
+        let empty = region(&[], Vec::new());
+        assert!(empty.is_empty());
+        assert!(records(&empty));
+
+        let vertices = [bytemuck::Zeroable::zeroed(); 3];
+        let triangle = region(&vertices, vec![0, 1, 2]);
+        assert!(!triangle.is_empty());
+        assert!(!records(&triangle));
+    }
+
+    #[test]
+    fn meshed_region_records_a_bundle() {
+        let state = headless_state(|world| {
+            world.set_block(bp(0, 0, 0), Block::Stone);
+        });
+        state.get_mut::<ActiveWorld>().pop_dirty_chunks(usize::MAX);
+        let mut pipeline = SolidGeometryPipeline::new(&state);
+        assert_eq!(pipeline.bundles_recorded(), 0);
+        pipeline.mesh_pending_chunks(usize::MAX);
+
+        let region = &pipeline.regions[&region_of(bp(0, 0, 0))];
+        assert!(region.bundle.is_some());
+        assert_eq!(pipeline.bundles_recorded(), 1);
+
+        // The bundle replays into an offscreen pass without recording the region again.
+        let mut camera = Camera::new(1.0, 0.1, 100.0);
+        camera.position = Vec3::new(3.0, 3.0, 3.0);
+        camera.look_at(Vec3::splat(0.5));
+        pipeline.render_to_texture(&camera, (4, 4));
+        assert_eq!(pipeline.bundles_recorded(), 1);
+    }
+
+    #[test]
+    fn bundles_are_only_recorded_on_remesh() {
+        // Two blocks in different regions.
+        let state = headless_state(|world| {
+            world.set_block(bp(0, 0, 0), Block::Stone);
+            world.set_block(bp(100, 0, 0), Block::Stone);
+        });
+        state.get_mut::<ActiveWorld>().pop_dirty_chunks(usize::MAX);
+        let mut pipeline = SolidGeometryPipeline::new(&state);
+        pipeline.mesh_pending_chunks(usize::MAX);
+        assert_eq!(pipeline.regions.len(), 2);
+        let recorded = pipeline.bundles_recorded();
+        assert_eq!(recorded, 2);
+
+        for _ in 0..3 {
+            RenderPipeline::<RenderPipelines>::update(&mut pipeline);
+        }
+        assert_eq!(pipeline.bundles_recorded(), recorded);
+
+        // Editing one region only records that region's bundle again.
+        state
+            .get_mut::<ActiveWorld>()
+            .get_world_mut()
+            .unwrap()
+            .set_block(bp(1, 0, 0), Block::Stone);
+        RenderPipeline::<RenderPipelines>::update(&mut pipeline);
+        assert_eq!(pipeline.bundles_recorded(), recorded + 1);
+        RenderPipeline::<RenderPipelines>::update(&mut pipeline);
+        assert_eq!(pipeline.bundles_recorded(), recorded + 1);
+    }
+
+    #[test]
+    fn chunks_of_a_region_share_one_buffer() {
+        let state = headless_state(|world| {
+            for (x, z) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                world.set_block(bp(x * 16 + 8, 8, z * 16 + 8), Block::Stone);
+            }
+        });
+        let mut pipeline = SolidGeometryPipeline::new(&state);
+        pipeline.mesh_pending_chunks(usize::MAX);
+        assert_eq!(pipeline.chunk_meshes.len(), 4);
+        assert_eq!(pipeline.regions.len(), 1);
+
+        // Four lone blocks, with all six faces each.
+        let region = &pipeline.regions[&bp(0, 0, 0)];
+        assert_eq!(region.vertex_buffer.count(), 4 * 6 * 4);
+        assert_eq!(region.index_buffer.count(), 4 * 6 * 6);
+    }
+
+    #[test]
+    fn merged_indices_point_past_earlier_chunks() {
+        let vertex = SolidBlockVertex::new(Vec3::ZERO, Vec2::ZERO, 0, CardinalDirection::Up);
+        let mut first = Indices::new();
+        let mut second = Indices::new();
+        for index in [0, 1, 2] {
+            first.push(index);
+            second.push(index);
+        }
+        let (vertices, indices) =
+            merge_region_meshes([(&[vertex; 3][..], &first), (&[vertex; 4][..], &second)]);
+        assert_eq!(vertices.len(), 7);
+        assert_eq!(indices, [0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn new_queues_chunks_instead_of_meshing_them() {
+        let chunks = CHUNKS_MESHED_PER_FRAME as i64 + 2;
+        let state = headless_state(|world| {
+            for x in 0..chunks {
+                world.set_block(bp(x * 16, 0, 0), Block::Stone);
+            }
+        });
+        // The edits building the world aren't meant to be remeshed as well.
+        state.get_mut::<ActiveWorld>().pop_dirty_chunks(usize::MAX);
+        let mut pipeline = SolidGeometryPipeline::new(&state);
+        assert_eq!(pipeline.pending_chunks(), chunks as usize);
+        assert!(pipeline.chunk_meshes.is_empty());
+
+        // Each frame meshes a few more.
+        RenderPipeline::<RenderPipelines>::update(&mut pipeline);
+        assert_eq!(pipeline.chunk_meshes.len(), CHUNKS_MESHED_PER_FRAME);
+        RenderPipeline::<RenderPipelines>::update(&mut pipeline);
+        assert_eq!(pipeline.pending_chunks(), 0);
+        assert_eq!(pipeline.chunk_meshes.len(), chunks as usize);
+    }
+
+    #[test]
+    fn progress_counts_every_chunk_once() {
+        let state = headless_state(|world| {
+            for x in 0..5 {
+                world.set_block(bp(x * 16, 0, 0), Block::Stone);
+            }
+        });
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let mut pipeline = SolidGeometryPipeline::with_progress(&state, {
+            let calls = calls.clone();
+            move |done, total| calls.borrow_mut().push((done, total))
+        });
+        assert_eq!(pipeline.pending_chunks(), 5);
+        while pipeline.mesh_pending_chunks(2) > 0 {}
+
+        assert_eq!(
+            *calls.borrow(),
+            (1..=5).map(|done| (done, 5)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn atlas_swap_rebinds_without_rebuilding() {
+        let state = headless_state(|world| {
+            world.set_block(bp(0, 0, 0), Block::Stone);
+        });
+        let mut pipeline = SolidGeometryPipeline::new(&state);
+        RenderPipeline::<RenderPipelines>::update(&mut pipeline);
+        let render_pipeline = pipeline.pipeline.as_ref().unwrap().pipeline.clone();
+        let bind_group = pipeline.textures.bind_group().clone();
+
+        // Nothing changed, so the bind group stays.
+        RenderPipeline::<RenderPipelines>::update(&mut pipeline);
+        assert_eq!(pipeline.textures.bind_group(), &bind_group);
+
+        let mut collection = TextureCollection::new(&state, Some("Swapped Blocks"), (16, 16));
+        collection.push_invalid_texture();
+        state
+            .get_mut::<TextureAtlases>()
+            .register(BLOCK_ATLAS, collection);
+        RenderPipeline::<RenderPipelines>::update(&mut pipeline);
+
+        assert_ne!(pipeline.textures.bind_group(), &bind_group);
+        assert_eq!(
+            pipeline.pipeline.as_ref().unwrap().pipeline,
+            render_pipeline
+        );
+    }
+
+    fn mesh_blocks(blocks: &[((usize, usize, usize), Block)]) -> ChunkMesh {
+        let registry = BlockRegistry::new();
+        let mut atlas = BlockTextureAtlas::new();
+        for block in crate::world::block::BUILTIN_BLOCKS {
+            atlas.set_texture_handle(block, TextureHandle::new(0, 6));
+        }
+        let mut data = [[[Block::Air; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+        for &((x, y, z), block) in blocks {
+            data[x][y][z] = block;
+        }
+        build_mesh_for_chunk(&atlas, &registry, &Chunk::from_blocks(data), bp(0, 0, 0))
+    }
+
+    #[test]
+    fn adjacent_leaves_skip_their_shared_face() {
+        let mesh = mesh_blocks(&[((4, 4, 4), Block::OakLeaves), ((5, 4, 4), Block::OakLeaves)]);
+        assert!(mesh.vertices.is_empty());
+        assert_eq!(mesh.transparent_vertices.len(), 10 * 4);
+        // No face lies on the plane between the two blocks.
+        assert!(
+            mesh.transparent_vertices
+                .iter()
+                .filter(|v| v.normal == CardinalDirection::East as u8
+                    || v.normal == CardinalDirection::West as u8)
+                .all(|v| v.position.x != 5.0)
+        );
+    }
+
+    #[test]
+    fn leaves_dont_hide_their_neighbors() {
+        let mesh = mesh_blocks(&[((4, 4, 4), Block::OakLeaves), ((5, 4, 4), Block::Stone)]);
+        // The stone keeps its face towards the leaves, but the leaves' face against the stone
+        // is hidden.
+        assert_eq!(mesh.vertices.len(), 6 * 4);
+        assert_eq!(mesh.transparent_vertices.len(), 5 * 4);
+    }
+
+    #[test]
+    fn cornered_blocks_are_darker_than_open_ones() {
+        // The top face of a block on an open floor, and of one with walls on two sides above it.
+        let top_ao = |blocks: &[((usize, usize, usize), Block)]| {
+            mesh_blocks(blocks)
+                .vertices
+                .iter()
+                .filter(|v| {
+                    v.normal == CardinalDirection::Up as u8
+                        && v.position.y == 5.0
+                        && (4.0..=5.0).contains(&v.position.x)
+                        && (4.0..=5.0).contains(&v.position.z)
+                })
+                .map(|v| v.ao)
+                .collect::<Vec<_>>()
+        };
+        let open = top_ao(&[((4, 4, 4), Block::Stone)]);
+        let cornered = top_ao(&[
+            ((4, 4, 4), Block::Stone),
+            ((3, 5, 4), Block::Stone),
+            ((4, 5, 3), Block::Stone),
+            ((3, 5, 3), Block::Stone),
+        ]);
+
+        assert_eq!(open, [AO_UNOCCLUDED; 4]);
+        assert_eq!(cornered.len(), 4);
+        assert!(cornered.iter().sum::<u32>() < open.iter().sum::<u32>());
+        // The corner between both walls is fully occluded, the one away from them isn't.
+        assert!(cornered.contains(&0));
+        assert!(cornered.contains(&AO_UNOCCLUDED));
+    }
+
+    #[test]
+    fn dense_chunks_switch_to_32_bit_indices() {
+        // Two kinds of glass in a checkerboard show every face of every block, as transparent
+        // blocks only hide faces against their own kind.
+        let mut registry = BlockRegistry::new();
+        let glass = [
+            registry.register(BlockProperties::cube("glass", "glass").transparent()),
+            registry.register(BlockProperties::cube("red_glass", "red_glass").transparent()),
+        ]
+        .map(Result::unwrap);
+        let mut atlas = BlockTextureAtlas::new();
+        for block in glass {
+            atlas.set_texture_handle(block, TextureHandle::new(0, 6));
+        }
+        let mut data = [[[Block::Air; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+        for (x, plane) in data.iter_mut().enumerate() {
+            for (y, row) in plane.iter_mut().enumerate() {
+                for (z, block) in row.iter_mut().enumerate() {
+                    *block = glass[(x + y + z) % 2];
+                }
+            }
+        }
+        let mesh = build_mesh_for_chunk(&atlas, &registry, &Chunk::from_blocks(data), bp(0, 0, 0));
+
+        let vertices = mesh.transparent_vertices.len();
+        assert_eq!(vertices, CHUNK_SIZE.pow(3) * 6 * 4);
+        assert!(vertices > u16::MAX as usize + 1);
+        assert_eq!(mesh.transparent_indices.format(), wgpu::IndexFormat::Uint32);
+        assert_eq!(mesh.transparent_indices.len(), CHUNK_SIZE.pow(3) * 6 * 6);
+        // Indices past 65535 are kept whole instead of wrapping around to the first vertices.
+        let indices = mesh.transparent_indices.iter().collect::<Vec<_>>();
+        assert_eq!(indices.iter().max(), Some(&(vertices as u32 - 1)));
+        let last_face = &indices[indices.len() - 6..];
+        assert!(last_face.iter().all(|&i| i as usize >= vertices - 4));
+    }
+
+    #[test]
+    fn top_faces_are_brighter_than_bottom_faces() {
+        let mesh = mesh_blocks(&[((4, 4, 4), Block::Stone)]);
+        // Finds the face by where its quad lies, then shades it from its vertex attributes the
+        // way `chunk_solid.wgsl` does, so wrong normals or light levels show up here.
+        let brightness = |on_face: fn(Vec3) -> bool| {
+            let quad = mesh
+                .vertices
+                .chunks(4)
+                .find(|quad| quad.iter().all(|v| on_face(v.position)))
+                .expect("face wasn't meshed");
+            let shades = quad
+                .iter()
+                .map(|v| {
+                    let block_light =
+                        0.8f32.powi(i32::from(BlockRegistry::MAX_LIGHT.saturating_sub(v.light)));
+                    FACE_SHADE[v.normal as usize] * block_light
+                })
+                .collect::<Vec<_>>();
+            assert!(shades.iter().all(|shade| *shade == shades[0]));
+            shades[0]
+        };
+
+        let top = brightness(|p| p.y == 5.0);
+        let bottom = brightness(|p| p.y == 4.0);
+        assert!(top > bottom, "top {} bottom {}", top, bottom);
+        for side in [
+            brightness(|p| p.x == 5.0),
+            brightness(|p| p.x == 4.0),
+            brightness(|p| p.z == 5.0),
+            brightness(|p| p.z == 4.0),
+        ] {
+            assert!(bottom < side && side < top, "side {}", side);
+        }
+    }
+
+    /// The normal of the triangle `a, b, c` as seen with counter-clockwise front faces.
+    fn triangle_normal(a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
+        (b - a).cross(c - a).normalize()
+    }
+
+    #[test]
+    fn face_table_is_wound_counter_clockwise() {
+        for direction in CardinalDirection::iter() {
+            let corners = FACE_TABLE[direction as usize].map(|(position, _)| Vec3::from(position));
+            for indices in [
+                FACE_INDICES,
+                face_indices([0, 3, 0, 3]),
+                face_indices([3, 0, 3, 0]),
+            ] {
+                for triangle in indices.chunks(3) {
+                    let normal = triangle_normal(
+                        corners[triangle[0] as usize],
+                        corners[triangle[1] as usize],
+                        corners[triangle[2] as usize],
+                    );
+                    assert!(
+                        normal.abs_diff_eq(direction.normal(), 1e-6),
+                        "{:?} face triangle {:?} faces {}",
+                        direction,
+                        triangle,
+                        normal
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn meshed_faces_point_outwards() {
+        let registry = BlockRegistry::new();
+        let mut atlas = BlockTextureAtlas::new();
+        for block in crate::world::block::BUILTIN_BLOCKS {
+            atlas.set_texture_handle(block, TextureHandle::new(0, 6));
+        }
+        // A few blocks touching at edges and corners, so some faces have their quads flipped for
+        // ambient occlusion.
+        let mut blocks = [[[Block::Air; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+        for (x, y, z) in [(4, 4, 4), (5, 5, 4), (4, 5, 5), (3, 4, 5), (8, 8, 8)] {
+            blocks[x][y][z] = Block::Stone;
+        }
+        let chunk = Chunk::from_blocks(blocks);
+        let mesh = build_mesh_for_chunk(&atlas, &registry, &chunk, bp(0, 0, 0));
+
+        let indices = mesh.indices.iter().collect::<Vec<_>>();
+        assert!(!indices.is_empty());
+        for triangle in indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| &mesh.vertices[triangle[i] as usize]);
+            let face = CardinalDirection::iter()
+                .find(|face| *face as u8 == a.normal)
+                .expect("invalid face index");
+            let normal = triangle_normal(a.position, b.position, c.position);
+            assert!(
+                normal.abs_diff_eq(face.normal(), 1e-6),
+                "{:?} face at {} faces {}",
+                face,
+                a.position,
+                normal
+            );
+        }
+    }
+}
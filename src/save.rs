@@ -0,0 +1,264 @@
+//! Saving and loading the whole game.
+//!
+//! A save is a directory holding a versioned `manifest.toml` next to each part of the game: a
+//! directory of region files with the world's blocks, the camera and the settings. The manifest
+//! names the parts, so they can change format independently as long as the manifest version is
+//! bumped.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, bail, ensure};
+use engine::{
+    component::{ComponentStore, ComponentStoreHandle},
+    input::camera::CameraController,
+    tick::TickScheduler,
+};
+use glam::{Vec2, Vec3};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    physics::Player,
+    settings::Settings,
+    world::{ActiveWorld, World},
+};
+
+/// The version of the save layout. Saves with any other version are refused.
+pub const SAVE_VERSION: u32 = 3;
+/// The name of the manifest file in a save directory.
+pub const MANIFEST_FILE: &str = "manifest.toml";
+
+/// Describes a save and where its parts are stored, relative to the save directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SaveManifest {
+    pub version: u32,
+    /// The time of the world, in ticks.
+    pub tick: u64,
+    /// The directory holding the world's region files.
+    pub world: String,
+    pub camera: String,
+    pub settings: String,
+}
+
+impl Default for SaveManifest {
+    fn default() -> Self {
+        Self {
+            version: SAVE_VERSION,
+            tick: 0,
+            world: "region".to_string(),
+            camera: "camera.toml".to_string(),
+            settings: "settings.toml".to_string(),
+        }
+    }
+}
+
+/// Where the camera was and where it looked.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CameraState {
+    pub position: [f32; 3],
+    /// Yaw and pitch, in degrees.
+    pub rotation: [f32; 2],
+}
+
+impl CameraState {
+    /// Captures the state of the given camera.
+    pub fn of(camera: &CameraController) -> Self {
+        Self {
+            position: camera.position().to_array(),
+            rotation: camera.rot.to_array(),
+        }
+    }
+
+    /// Moves and turns the given camera to this state.
+    pub fn apply(&self, camera: &mut CameraController) {
+        camera.update_position(|_| Vec3::from_array(self.position));
+        camera.set_rotation(Vec2::from_array(self.rotation));
+    }
+}
+
+/// A save read from disk, ready to be applied to the game.
+pub struct SaveGame {
+    pub world: World,
+    pub camera: CameraState,
+    /// The time of the world, in ticks.
+    pub tick: u64,
+    pub settings: Settings,
+}
+
+/// Writes a save with the given parts to the directory at `path`, creating it if needed.
+///
+/// Saving over the save the world was loaded from only rewrites the chunks that changed.
+pub fn write_save(
+    path: impl AsRef<Path>,
+    world: &World,
+    camera: CameraState,
+    tick: u64,
+    settings: &Settings,
+) -> anyhow::Result<()> {
+    let dir = path.as_ref();
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create save directory {}", dir.display()))?;
+    let manifest = SaveManifest {
+        tick,
+        ..SaveManifest::default()
+    };
+
+    let world_path = dir.join(&manifest.world);
+    world
+        .save(&world_path)
+        .with_context(|| format!("Failed to write the world to {}", world_path.display()))?;
+    let camera_path = dir.join(&manifest.camera);
+    std::fs::write(&camera_path, toml::to_string_pretty(&camera)?)
+        .with_context(|| format!("Failed to write the camera to {}", camera_path.display()))?;
+    settings.save(dir.join(&manifest.settings))?;
+
+    // The manifest goes last, so an interrupted save is missing it and can't be loaded.
+    let manifest_path = dir.join(MANIFEST_FILE);
+    std::fs::write(&manifest_path, toml::to_string_pretty(&manifest)?).with_context(|| {
+        format!(
+            "Failed to write the manifest to {}",
+            manifest_path.display()
+        )
+    })
+}
+
+/// Reads the save in the directory at `path`.
+///
+/// Returns an error naming the part that is missing or invalid if the save is incomplete or
+/// corrupt.
+pub fn read_save(path: impl AsRef<Path>, state: &ComponentStoreHandle) -> anyhow::Result<SaveGame> {
+    let dir = path.as_ref();
+    let manifest: SaveManifest = toml::from_str(&read_part(dir, "manifest", MANIFEST_FILE)?)
+        .with_context(|| format!("Save at {} has an invalid manifest", dir.display()))?;
+    ensure!(
+        manifest.version == SAVE_VERSION,
+        "Save at {} has version {}, but only version {} is supported",
+        dir.display(),
+        manifest.version,
+        SAVE_VERSION
+    );
+
+    let world_path = dir.join(&manifest.world);
+    if !world_path.is_dir() {
+        bail!(
+            "Save at {} is missing its world ({})",
+            dir.display(),
+            manifest.world
+        );
+    }
+    let world = World::load(&world_path, state)
+        .with_context(|| format!("Save at {} has an invalid world", dir.display()))?;
+    let camera = toml::from_str(&read_part(dir, "camera", &manifest.camera)?)
+        .with_context(|| format!("Save at {} has an invalid camera", dir.display()))?;
+    let settings = Settings::load(part_path(dir, "settings", &manifest.settings)?)?;
+
+    Ok(SaveGame {
+        world,
+        camera,
+        tick: manifest.tick,
+        settings,
+    })
+}
+
+/// Saves the active world, camera, world time and settings to the directory at `path`.
+pub fn save_game(state: &ComponentStore, path: impl AsRef<Path>) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    let active_world = state.get::<ActiveWorld>();
+    let world = active_world
+        .get_world()
+        .context("There is no world to save")?;
+    write_save(
+        path,
+        world,
+        CameraState::of(&state.get::<CameraController>()),
+        state.get::<TickScheduler>().tick_count(),
+        &state.get::<Settings>(),
+    )?;
+    info!("Saved the game to {}", path.display());
+    Ok(())
+}
+
+/// Loads the save in the directory at `path`, replacing the active world, camera, world time and
+/// settings.
+///
+/// Nothing is changed if the save can't be read.
+pub fn load_game(state: &ComponentStore, path: impl AsRef<Path>) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    let save = read_save(path, &state.handle())?;
+    save.settings.apply(state)?;
+    *state.get_mut::<Settings>() = save.settings;
+    save.camera.apply(&mut state.get_mut::<CameraController>());
+    // The player's body would otherwise pull the camera back to where it was.
+    state
+        .get_mut::<Player>()
+        .set_eye_position(Vec3::from_array(save.camera.position));
+    state.get_mut::<TickScheduler>().set_tick_count(save.tick);
+    state.get_mut::<ActiveWorld>().set_world(save.world);
+    info!("Loaded the game from {}", path.display());
+    Ok(())
+}
+
+/// Returns the path of a part of the save, or an error naming the part if it's missing.
+fn part_path(dir: &Path, part: &str, file: &str) -> anyhow::Result<PathBuf> {
+    let path = dir.join(file);
+    if !path.is_file() {
+        bail!(
+            "Save at {} is missing its {} ({})",
+            dir.display(),
+            part,
+            file
+        );
+    }
+    Ok(path)
+}
+
+/// Reads a text part of the save, or returns an error naming the part if it's missing.
+fn read_part(dir: &Path, part: &str, file: &str) -> anyhow::Result<String> {
+    let path = part_path(dir, part, file)?;
+    std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read the {} from {}", part, path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{coords::bp, world::Block};
+
+    #[test]
+    fn round_trips_a_full_game_state() {
+        let store = ComponentStore::new();
+        store.finish_initialization();
+        let mut world = World::empty(&store.handle());
+        world.set_block(bp(1, 2, 3), Block::Stone);
+        world.set_block(bp(-20, 40, 7), Block::OakWood);
+        let camera = CameraState {
+            position: [1.5, 70.0, -3.25],
+            rotation: [45.0, -10.0],
+        };
+        let settings = Settings {
+            fov: 75.0,
+            vsync: false,
+            ..Settings::default()
+        };
+
+        let dir = std::env::temp_dir().join(format!("quackcraft-save-{}", std::process::id()));
+        write_save(&dir, &world, camera, 1234, &settings).unwrap();
+        let save = read_save(&dir, &store.handle()).unwrap();
+        assert_eq!(save.camera, camera);
+        assert_eq!(save.tick, 1234);
+        assert_eq!(save.settings, settings);
+        assert_eq!(save.world.get_block(bp(1, 2, 3)), Block::Stone);
+        assert_eq!(save.world.get_block(bp(-20, 40, 7)), Block::OakWood);
+        assert_eq!(save.world.total_non_air_blocks(), 2);
+
+        // A save missing a part names it.
+        std::fs::remove_file(dir.join(&SaveManifest::default().camera)).unwrap();
+        let error = format!("{:#}", read_save(&dir, &store.handle()).err().unwrap());
+        assert!(error.contains("missing its camera"), "{}", error);
+        std::fs::remove_file(dir.join(MANIFEST_FILE)).unwrap();
+        let error = format!("{:#}", read_save(&dir, &store.handle()).err().unwrap());
+        assert!(error.contains("missing its manifest"), "{}", error);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
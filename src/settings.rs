@@ -0,0 +1,263 @@
+use std::path::Path;
+
+use anyhow::Context;
+use engine::{
+    component::ComponentStore,
+    debug::DebugGraph,
+    graphics::lowlevel::WgpuRenderer,
+    input::{
+        camera::CameraController,
+        input_map::{Action, InputMap},
+    },
+};
+use glfw::Key;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::world::{self, StreamingWorld, chunk::CHUNK_SIZE};
+
+/// Where the game stores its settings, relative to the working directory.
+pub const SETTINGS_FILE: &str = "settings.toml";
+
+/// Everything the player can configure, persisted as TOML.
+///
+/// Fields missing from the file fall back to their defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// How far the world is drawn, in chunks.
+    pub render_distance: usize,
+    /// The vertical field of view, in degrees.
+    pub fov: f32,
+    /// Degrees the camera turns per pixel of mouse movement.
+    pub mouse_sensitivity: f32,
+    /// Whether presentation waits for vertical blank.
+    pub vsync: bool,
+    /// Whether the window title shows the frame rate while the debug overlay is closed.
+    pub fps_in_title: bool,
+    /// How many frames the frame time graph in the debug overlay shows.
+    pub frame_graph_samples: usize,
+    pub keybinds: Keybinds,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            render_distance: world::RENDER_DISTANCE,
+            fov: 90.0,
+            mouse_sensitivity: 0.1,
+            vsync: true,
+            fps_in_title: true,
+            frame_graph_samples: DebugGraph::DEFAULT_CAPACITY,
+            keybinds: Keybinds::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Loads settings from the TOML file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Settings> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read settings from {}", path.display()))?;
+        Self::from_toml(&text)
+            .with_context(|| format!("Failed to parse settings in {}", path.display()))
+    }
+
+    /// Loads settings from `path`, falling back to the defaults if the file is missing or invalid.
+    pub fn load_or_default(path: impl AsRef<Path>) -> Settings {
+        let path = path.as_ref();
+        if !path.exists() {
+            info!("No settings at {}, using defaults", path.display());
+            return Settings::default();
+        }
+        Self::load(path).unwrap_or_else(|e| {
+            warn!("{:#}, using default settings", e);
+            Settings::default()
+        })
+    }
+
+    /// Saves the settings as TOML to `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_toml()?)
+            .with_context(|| format!("Failed to write settings to {}", path.display()))
+    }
+
+    /// Parses settings from a TOML string.
+    pub fn from_toml(text: &str) -> anyhow::Result<Settings> {
+        Ok(toml::from_str(text)?)
+    }
+
+    /// Serializes the settings to a TOML string.
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    /// Applies the settings to the camera, key bindings, frame graph, renderer and chunk streaming.
+    pub fn apply(&self, state: &ComponentStore) -> anyhow::Result<()> {
+        let bindings = self.keybinds.bindings()?;
+
+        let mut camera = state.get_mut::<CameraController>();
+        camera.base_fov = self.fov.to_radians();
+        camera.zoom_fov = camera.base_fov / 3.0;
+        camera.sensitivity = self.mouse_sensitivity;
+        camera.set_view_distance((self.render_distance * CHUNK_SIZE) as f32)?;
+        state
+            .get_mut::<StreamingWorld>()
+            .set_render_distance(self.render_distance);
+
+        let mut input_map = state.get_mut::<InputMap>();
+        for (action, key) in bindings {
+            input_map.rebind(action, key);
+        }
+
+        state
+            .get_mut::<DebugGraph>()
+            .set_capacity(self.frame_graph_samples);
+        state.get::<WgpuRenderer>().set_vsync(self.vsync);
+        Ok(())
+    }
+}
+
+/// Key bindings, stored by key name (e.g. `"W"`, `"LeftShift"`, `"Num1"`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keybinds {
+    pub forward: String,
+    pub back: String,
+    pub left: String,
+    pub right: String,
+    pub zoom: String,
+    pub toggle_debug: String,
+    pub pause: String,
+    pub cycle_texture_quality: String,
+    /// Only used with the `hot-reload` feature.
+    pub reload_shaders: String,
+    pub jump: String,
+    pub toggle_fly: String,
+    pub toggle_fullscreen: String,
+    pub toggle_hud: String,
+}
+
+impl Default for Keybinds {
+    fn default() -> Self {
+        let name = |action: Action| key_name(action.default_key());
+        Self {
+            forward: name(Action::MoveForward),
+            back: name(Action::MoveBack),
+            left: name(Action::StrafeLeft),
+            right: name(Action::StrafeRight),
+            zoom: name(Action::Zoom),
+            toggle_debug: name(Action::ToggleDebug),
+            pause: name(Action::Pause),
+            cycle_texture_quality: name(Action::CycleTextureQuality),
+            reload_shaders: name(Action::ReloadShaders),
+            jump: name(Action::Jump),
+            toggle_fly: name(Action::ToggleFly),
+            toggle_fullscreen: name(Action::ToggleFullscreen),
+            toggle_hud: name(Action::ToggleHud),
+        }
+    }
+}
+
+impl Keybinds {
+    /// Resolves the key bound to every action. Fails if any key name isn't recognized.
+    pub fn bindings(&self) -> anyhow::Result<Vec<(Action, Key)>> {
+        [
+            (Action::MoveForward, &self.forward),
+            (Action::MoveBack, &self.back),
+            (Action::StrafeLeft, &self.left),
+            (Action::StrafeRight, &self.right),
+            (Action::Zoom, &self.zoom),
+            (Action::ToggleDebug, &self.toggle_debug),
+            (Action::Pause, &self.pause),
+            (Action::CycleTextureQuality, &self.cycle_texture_quality),
+            (Action::ReloadShaders, &self.reload_shaders),
+            (Action::Jump, &self.jump),
+            (Action::ToggleFly, &self.toggle_fly),
+            (Action::ToggleFullscreen, &self.toggle_fullscreen),
+            (Action::ToggleHud, &self.toggle_hud),
+        ]
+        .into_iter()
+        .map(|(action, name)| {
+            let key =
+                key_from_name(name).with_context(|| format!("Unknown key name {:?}", name))?;
+            Ok((action, key))
+        })
+        .collect()
+    }
+}
+
+macro_rules! named_keys {
+    ($($key: ident),* $(,)?) => {
+        /// Looks up a key by the name it's stored under in the settings file.
+        pub fn key_from_name(name: &str) -> Option<Key> {
+            match name {
+                $(stringify!($key) => Some(Key::$key),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+#[rustfmt::skip]
+named_keys![
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Num0, Num1, Num2, Num3, Num4, Num5, Num6, Num7, Num8, Num9,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    Space, Tab, Enter, Escape, Backspace,
+    Up, Down, Left, Right,
+    LeftShift, RightShift, LeftControl, RightControl, LeftAlt, RightAlt,
+];
+
+/// Gets the name a key is stored under in the settings file.
+pub fn key_name(key: Key) -> String {
+    format!("{:?}", key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip() {
+        let mut settings = Settings {
+            render_distance: 6,
+            fov: 75.0,
+            mouse_sensitivity: 0.25,
+            vsync: false,
+            fps_in_title: false,
+            frame_graph_samples: 60,
+            ..Settings::default()
+        };
+        settings.keybinds.jump = key_name(Key::J);
+
+        let path = std::env::temp_dir().join(format!("settings-{}.toml", std::process::id()));
+        settings.save(&path).unwrap();
+        let loaded = Settings::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), settings);
+    }
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let settings = Settings::from_toml("fov = 70.0\n\n[keybinds]\njump = \"J\"\n").unwrap();
+        let defaults = Settings::default();
+        assert_eq!(settings.fov, 70.0);
+        assert_eq!(settings.render_distance, defaults.render_distance);
+        assert_eq!(settings.vsync, defaults.vsync);
+        assert_eq!(settings.keybinds.jump, "J");
+        assert_eq!(settings.keybinds.forward, defaults.keybinds.forward);
+
+        assert_eq!(Settings::from_toml("").unwrap(), defaults);
+    }
+
+    #[test]
+    fn unknown_key_names_are_rejected() {
+        let mut keybinds = Keybinds::default();
+        assert!(keybinds.bindings().is_ok());
+        keybinds.zoom = "NotAKey".into();
+        assert!(keybinds.bindings().is_err());
+    }
+}
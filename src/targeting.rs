@@ -0,0 +1,112 @@
+use glam::Vec3;
+
+use crate::{
+    BlockPosition,
+    world::{World, raycast::RaycastHit},
+};
+
+/// How far away blocks can be targeted, in blocks.
+pub const REACH: f32 = 6.0;
+
+/// The block the camera is pointing at, raycast once per frame by the game loop.
+///
+/// Anything that needs the targeted block, like the block outline, breaking and placing, should
+/// read it from here instead of casting its own ray.
+#[derive(Debug, Clone)]
+pub struct Targeting {
+    hit: Option<RaycastHit>,
+    reach: f32,
+}
+
+impl Targeting {
+    /// Creates a targeting component with nothing targeted and the default reach.
+    pub fn new() -> Self {
+        Self {
+            hit: None,
+            reach: REACH,
+        }
+    }
+
+    /// Returns the result of the latest raycast.
+    pub fn current(&self) -> Option<RaycastHit> {
+        self.hit
+    }
+
+    /// Returns the position of the targeted block, if any.
+    pub fn target(&self) -> Option<BlockPosition> {
+        self.hit.map(|hit| hit.position)
+    }
+
+    /// Returns the empty position next to the targeted face, where a placed block would go.
+    pub fn placement(&self) -> Option<BlockPosition> {
+        self.hit?.adjacent
+    }
+
+    /// Returns how far away blocks can be targeted.
+    pub fn reach(&self) -> f32 {
+        self.reach
+    }
+
+    /// Sets how far away blocks can be targeted. Takes effect on the next `update`.
+    pub fn set_reach(&mut self, reach: f32) {
+        self.reach = reach;
+    }
+
+    /// Raycasts from `origin` along `direction` and stores the result.
+    pub fn update(&mut self, world: &World, origin: Vec3, direction: Vec3) -> Option<RaycastHit> {
+        self.hit = world.raycast(origin, direction, self.reach);
+        self.hit
+    }
+
+    /// Forgets the current target, e.g. while the game is paused.
+    pub fn clear(&mut self) {
+        self.hit = None;
+    }
+}
+
+impl Default for Targeting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use engine::component::ComponentStore;
+
+    use super::*;
+    use crate::{
+        coords::bp,
+        world::{Block, chunk::CHUNK_SIZE},
+    };
+
+    #[test]
+    fn current_is_the_latest_raycast() {
+        let store = ComponentStore::new();
+        store.finish_initialization();
+        let mut world = World::empty(&store.handle());
+        let size = CHUNK_SIZE as i64;
+        for x in 0..size {
+            for z in 0..size {
+                world.set_block(bp(x, 0, z), Block::Stone);
+            }
+        }
+
+        let mut targeting = Targeting::new();
+        assert_eq!(targeting.current(), None);
+
+        let hit = targeting.update(&world, Vec3::new(4.5, 3.5, 4.5), Vec3::NEG_Y);
+        assert_eq!(targeting.current(), hit);
+        assert_eq!(targeting.target(), Some(bp(4, 0, 4)));
+        assert_eq!(targeting.placement(), Some(bp(4, 1, 4)));
+
+        // Looking somewhere else replaces the cached hit.
+        targeting.update(&world, Vec3::new(9.5, 3.5, 2.5), Vec3::NEG_Y);
+        assert_eq!(targeting.target(), Some(bp(9, 0, 2)));
+
+        // Out of reach, nothing is targeted.
+        targeting.set_reach(2.0);
+        targeting.update(&world, Vec3::new(4.5, 5.5, 4.5), Vec3::NEG_Y);
+        assert_eq!(targeting.current(), None);
+    }
+}
@@ -0,0 +1,126 @@
+use std::time::{Duration, Instant};
+
+use engine::{
+    debug::{DebugProvider, DebugRenderer},
+    window::GlfwWindow,
+};
+
+/// How often the frame rate in the window title is updated.
+pub const TITLE_UPDATE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Per-frame timing breakdown, published as debug statistics.
+///
+/// Each phase of the frame is measured with a single `Instant` span and shown as a child of the
+/// total frame time in the debug overlay.
+#[derive(Debug)]
+pub struct FrameTimings {
+    /// Total time between frames, the parent of the phase statistics.
+    pub frame: DebugProvider,
+    /// Window event polling and keyboard handling.
+    pub input: DebugProvider,
+    /// World and camera updates.
+    pub update: DebugProvider,
+    /// Pipeline updates, which is where mesh data gets uploaded.
+    pub upload: DebugProvider,
+    /// Recording render commands.
+    pub encode: DebugProvider,
+    /// Submitting the frame and presenting it.
+    pub present: DebugProvider,
+}
+
+impl FrameTimings {
+    /// Registers the frame timing statistics with the debug renderer.
+    pub fn new(debug: &mut DebugRenderer) -> Self {
+        let frame = debug.add_statistic("Frame", "0.00 ms");
+        Self {
+            input: frame.add_child("Input", "0.00 ms"),
+            update: frame.add_child("Update", "0.00 ms"),
+            upload: frame.add_child("Upload", "0.00 ms"),
+            encode: frame.add_child("Encode", "0.00 ms"),
+            present: frame.add_child("Present", "0.00 ms"),
+            frame,
+        }
+    }
+}
+
+/// Ends a timing span, publishing the elapsed time to the given statistic.
+///
+/// Returns the instant the span ended, so consecutive spans can share a single `Instant::now`.
+pub fn end_span(start: Instant, stat: &DebugProvider) -> Instant {
+    let now = Instant::now();
+    stat.update_duration(now.duration_since(start));
+    now
+}
+
+/// Shows the frame rate in the window title, like "Quackcraft — 142 fps (7.04 ms)".
+///
+/// The rate is averaged over `TITLE_UPDATE_INTERVAL`, so the title changes about twice a second
+/// instead of flickering every frame.
+#[derive(Debug)]
+pub struct TitleFps {
+    base: String,
+    frames: u32,
+    since: Instant,
+    /// Whether the title currently shows the frame rate rather than just the base title.
+    showing: bool,
+}
+
+impl TitleFps {
+    /// Creates a counter for a window titled `base`.
+    pub fn new(base: impl Into<String>) -> Self {
+        Self {
+            base: base.into(),
+            frames: 0,
+            since: Instant::now(),
+            showing: false,
+        }
+    }
+
+    /// Returns the title shown without the frame rate.
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    /// Changes the title shown without the frame rate. It's applied on the next `update`.
+    pub fn set_base(&mut self, base: impl Into<String>) {
+        self.base = base.into();
+        self.showing = true;
+    }
+
+    /// Counts a frame finished at `now`, returning the title with the frame rate once
+    /// `TITLE_UPDATE_INTERVAL` has passed since the last one.
+    pub fn frame(&mut self, now: Instant) -> Option<String> {
+        self.frames += 1;
+        let elapsed = now.duration_since(self.since);
+        if elapsed < TITLE_UPDATE_INTERVAL {
+            return None;
+        }
+        let seconds = elapsed.as_secs_f64();
+        let title = format!(
+            "{} — {:.0} fps ({:.2} ms)",
+            self.base,
+            self.frames as f64 / seconds,
+            seconds * 1000.0 / self.frames as f64
+        );
+        self.frames = 0;
+        self.since = now;
+        Some(title)
+    }
+
+    /// Counts a frame finished at `now` and updates the window title. With `show` off, e.g.
+    /// while the debug overlay is open, the base title is put back instead.
+    pub fn update(&mut self, window: &mut GlfwWindow, show: bool, now: Instant) {
+        if show {
+            if let Some(title) = self.frame(now) {
+                window.set_title(&title);
+                self.showing = true;
+            }
+        } else if self.showing {
+            window.set_title(&self.base);
+            self.showing = false;
+            // Start a fresh average when the frame rate is shown again.
+            self.frames = 0;
+            self.since = now;
+        }
+    }
+}
@@ -1,27 +1,220 @@
 use engine::graphics::{CardinalDirection, textures::TextureHandle};
 
-// TODO: fancy optimizations for blocks
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[repr(u8)]
-pub enum Block {
-    Air = 0,
-    Dirt = 1,
-    Stone = 2,
-    Grass = 3,
-    OakWood = 4,
-    OakLeaves = 5,
+use crate::physics::Aabb;
+
+/// Identifies a kind of block, as registered with the `BlockRegistry`.
+///
+/// The blocks the game ships with are pre-registered under fixed IDs, and can be named through
+/// the associated constants, like `Block::Stone`. Blocks registered at runtime get the IDs after
+/// them.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct BlockId(pub u16);
+
+/// The name blocks went by before they could be registered at runtime.
+pub type Block = BlockId;
+
+#[allow(non_upper_case_globals)]
+impl BlockId {
+    pub const Air: BlockId = BlockId(0);
+    pub const Dirt: BlockId = BlockId(1);
+    pub const Stone: BlockId = BlockId(2);
+    pub const Grass: BlockId = BlockId(3);
+    pub const OakWood: BlockId = BlockId(4);
+    pub const OakLeaves: BlockId = BlockId(5);
+    pub const Water: BlockId = BlockId(6);
+}
+
+impl std::fmt::Debug for BlockId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.builtin_name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "BlockId({})", self.0),
+        }
+    }
 }
 
+/// What a block is made of. Sounds, particles and tools can branch on this instead of on
+/// individual blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Material {
+    Air,
+    Dirt,
+    Stone,
+    Wood,
+    Leaves,
+    Water,
+}
+
+impl Material {
+    /// Gets the lowercase name of the material, as used in sound IDs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Material::Air => "air",
+            Material::Dirt => "dirt",
+            Material::Stone => "stone",
+            Material::Wood => "wood",
+            Material::Leaves => "leaves",
+            Material::Water => "water",
+        }
+    }
+
+    /// Gets the ID of the sound this material makes for the given event, like `block.stone.break`.
+    ///
+    /// Air makes no sounds.
+    pub fn sound_id(&self, sound: BlockSound) -> Option<String> {
+        if *self == Material::Air {
+            return None;
+        }
+        Some(format!("block.{}.{}", self.name(), sound.name()))
+    }
+}
+
+/// Something that happened to a block that makes a sound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlockSound {
+    Break,
+    Place,
+    Step,
+}
+
+impl BlockSound {
+    /// Gets the lowercase name of the event, as used in sound IDs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BlockSound::Break => "break",
+            BlockSound::Place => "place",
+            BlockSound::Step => "step",
+        }
+    }
+}
+
+/// The built-in blocks, in ID order.
+pub const BUILTIN_BLOCKS: [Block; 7] = [
+    Block::Air,
+    Block::Dirt,
+    Block::Stone,
+    Block::Grass,
+    Block::OakWood,
+    Block::OakLeaves,
+    Block::Water,
+];
+
+/// The properties of blocks in this file are fixed for the built-in blocks. Blocks registered at
+/// runtime are treated as plain solid cubes here; ask the `BlockRegistry` for their properties.
 impl Block {
-    pub const MAX_DISCRIMINANT: u8 = 5;
+    /// Returns the numeric ID of the block, as stored on disk.
+    pub fn id(&self) -> u16 {
+        self.0
+    }
+
+    /// Returns the name of a built-in block, like `"Stone"`.
+    pub fn builtin_name(&self) -> Option<&'static str> {
+        Some(match *self {
+            Block::Air => "Air",
+            Block::Dirt => "Dirt",
+            Block::Stone => "Stone",
+            Block::Grass => "Grass",
+            Block::OakWood => "OakWood",
+            Block::OakLeaves => "OakLeaves",
+            Block::Water => "Water",
+            _ => return None,
+        })
+    }
+
+    /// Gets the material this block is made of.
+    pub fn material(&self) -> Material {
+        match *self {
+            Block::Air => Material::Air,
+            Block::Dirt | Block::Grass => Material::Dirt,
+            Block::OakWood => Material::Wood,
+            Block::OakLeaves => Material::Leaves,
+            Block::Water => Material::Water,
+            _ => Material::Stone,
+        }
+    }
+
+    /// Gets how hard the block is to break. Breaking time scales with this value.
+    ///
+    /// Air breaks instantly, and fluids can't be broken at all, so they are infinitely hard.
+    pub fn hardness(&self) -> f32 {
+        match *self {
+            Block::Air => 0.0,
+            Block::Dirt => 0.5,
+            Block::Grass => 0.6,
+            Block::Stone => 1.5,
+            Block::OakWood => 2.0,
+            Block::OakLeaves => 0.2,
+            Block::Water => f32::INFINITY,
+            _ => 1.0,
+        }
+    }
 
+    /// Returns true if the block takes up its whole cell and collides, even if it can be seen
+    /// through, like leaves.
     pub fn is_solid(&self) -> bool {
-        !matches!(self, Block::Air | Block::OakLeaves)
+        !matches!(*self, Block::Air | Block::Water)
+    }
+
+    /// Returns true if the block can be seen through, so it is drawn in a blended pass after
+    /// opaque blocks and doesn't hide the faces of its neighbors.
+    pub fn is_transparent(&self) -> bool {
+        matches!(*self, Block::OakLeaves | Block::Water)
+    }
+
+    /// Returns true if the block is solid and can't be seen through, so faces against it are
+    /// hidden.
+    pub fn is_opaque(&self) -> bool {
+        self.is_solid() && !self.is_transparent()
+    }
+
+    /// Returns true if the block is a fluid, drawn by the fluid pipeline instead of as a cube.
+    pub fn is_fluid(&self) -> bool {
+        matches!(*self, Block::Water)
+    }
+
+    /// Returns true if the block is drawn as a textured cube by the solid geometry pipeline.
+    pub fn is_cube(&self) -> bool {
+        *self != Block::Air && !self.is_fluid()
+    }
+
+    /// Returns true if faces between two blocks of this type are skipped, like between leaves.
+    ///
+    /// This only matters for blocks that aren't opaque, since faces against opaque blocks are
+    /// always skipped. Culling the shared face also keeps two transparent faces from z-fighting.
+    pub fn culls_same(&self) -> bool {
+        matches!(*self, Block::OakLeaves | Block::Water)
+    }
+
+    /// Returns true if this block's face towards `neighbor` is hidden and shouldn't be drawn.
+    pub fn face_hidden_by(&self, neighbor: Block) -> bool {
+        neighbor.is_opaque() || (neighbor == *self && self.culls_same())
+    }
+
+    /// Gets the collision boxes of this block, relative to the block's minimum corner.
+    ///
+    /// Solid blocks are a full cube; air and other non-solid blocks don't collide.
+    pub fn collision_boxes(&self) -> &'static [Aabb] {
+        if self.is_solid() { &[Aabb::UNIT] } else { &[] }
+    }
+
+    /// Gets the color used to draw this block on the minimap, as RGBA8.
+    pub fn map_color(&self) -> [u8; 4] {
+        match *self {
+            Block::Air => [0, 0, 0, 0],
+            Block::Dirt => [134, 96, 67, 255],
+            Block::Stone => [125, 125, 125, 255],
+            Block::Grass => [95, 159, 53, 255],
+            Block::OakWood => [102, 81, 51, 255],
+            Block::OakLeaves => [60, 120, 40, 255],
+            Block::Water => [63, 118, 228, 255],
+            _ => [150, 150, 150, 255],
+        }
     }
 
     /// Gets the block ID for the given texture handle and direction, if applicable.
     pub fn id_from(&self, handle: TextureHandle, direction: CardinalDirection) -> u32 {
-        match self {
+        match *self {
             Block::Grass => match direction {
                 CardinalDirection::Up => handle.layer(1),
                 CardinalDirection::Down => handle.layer(2),
@@ -35,3 +228,24 @@ impl Block {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grass_and_dirt_share_a_material() {
+        assert_eq!(Block::Grass.material(), Material::Dirt);
+        assert_eq!(Block::Dirt.material(), Material::Dirt);
+        assert_eq!(Block::Stone.material(), Material::Stone);
+        assert_ne!(Block::Stone.material(), Block::Dirt.material());
+    }
+
+    #[test]
+    fn hardness_orders_the_blocks() {
+        assert_eq!(Block::Air.hardness(), 0.0);
+        assert!(Block::OakLeaves.hardness() < Block::Dirt.hardness());
+        assert!(Block::Dirt.hardness() < Block::Stone.hardness());
+        assert!(Block::Water.hardness().is_infinite());
+    }
+}
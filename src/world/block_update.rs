@@ -0,0 +1,224 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use engine::{component::ComponentStoreHandle, graphics::CardinalDirection, tick::System};
+
+use crate::{
+    BlockPosition,
+    world::{ActiveWorld, Block, World},
+};
+
+/// The most neighbor updates dispatched per tick. The rest wait for the following ticks, so a
+/// chain reaction can't stall a tick.
+pub const NEIGHBOR_UPDATES_PER_TICK: usize = 1024;
+
+/// A notification that a block next to `position` changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NeighborUpdate {
+    /// The block being notified.
+    pub position: BlockPosition,
+    /// The block that changed.
+    pub source: BlockPosition,
+}
+
+/// Neighbor updates waiting to be dispatched on the next tick.
+///
+/// The same update is only queued once until it is dispatched, however often its source changes.
+#[derive(Debug, Default)]
+pub struct PendingUpdates {
+    queue: VecDeque<NeighborUpdate>,
+    queued: HashSet<NeighborUpdate>,
+}
+
+impl PendingUpdates {
+    /// Creates an empty queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues an update. Returns false if it was already queued.
+    pub fn push(&mut self, update: NeighborUpdate) -> bool {
+        if !self.queued.insert(update) {
+            return false;
+        }
+        self.queue.push_back(update);
+        true
+    }
+
+    /// Queues updates for the six neighbors of `source`.
+    pub fn notify_neighbors(&mut self, source: BlockPosition) {
+        for direction in CardinalDirection::iter() {
+            self.push(NeighborUpdate {
+                position: source.offset(direction),
+                source,
+            });
+        }
+    }
+
+    /// Takes the oldest queued update.
+    pub fn pop(&mut self) -> Option<NeighborUpdate> {
+        let update = self.queue.pop_front()?;
+        self.queued.remove(&update);
+        Some(update)
+    }
+
+    /// Returns the number of queued updates.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns true if no updates are queued.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Drops every queued update.
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.queued.clear();
+    }
+}
+
+/// Reacts to a neighbor of a block changing.
+pub type NeighborHook = Box<dyn FnMut(&mut World, NeighborUpdate)>;
+
+/// Dispatches the world's queued neighbor updates to the hooks registered for each block.
+///
+/// Only updates queued before a tick starts are dispatched during it, and at most `budget` of
+/// them. Changes made by hooks queue their own updates for the next tick, so blocks that keep
+/// changing each other can't loop forever within one tick.
+pub struct BlockUpdates {
+    hooks: HashMap<Block, NeighborHook>,
+    budget: usize,
+}
+
+impl BlockUpdates {
+    /// Creates a dispatcher with no hooks and a budget of `NEIGHBOR_UPDATES_PER_TICK`.
+    pub fn new() -> Self {
+        Self {
+            hooks: HashMap::new(),
+            budget: NEIGHBOR_UPDATES_PER_TICK,
+        }
+    }
+
+    /// Sets the most updates dispatched per tick.
+    pub fn with_budget(mut self, budget: usize) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Returns the most updates dispatched per tick.
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Registers the hook called when a neighbor of a `block` changes, replacing any previous
+    /// hook for it.
+    pub fn on_neighbor_changed(
+        &mut self,
+        block: Block,
+        hook: impl FnMut(&mut World, NeighborUpdate) + 'static,
+    ) {
+        self.hooks.insert(block, Box::new(hook));
+    }
+
+    /// Dispatches the updates queued in `world`, up to the budget. Returns how many were
+    /// dispatched, including ones for blocks without a hook.
+    pub fn dispatch(&mut self, world: &mut World) -> usize {
+        let count = world.pending_updates().len().min(self.budget);
+        for _ in 0..count {
+            let update = world
+                .pending_updates_mut()
+                .pop()
+                .expect("counted before dispatching");
+            let block = world.get_block(update.position);
+            if let Some(hook) = self.hooks.get_mut(&block) {
+                hook(world, update);
+            }
+        }
+        count
+    }
+}
+
+impl Default for BlockUpdates {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl System for BlockUpdates {
+    fn label(&self) -> Option<&str> {
+        Some("Block Updates")
+    }
+
+    fn tick(&mut self, state: &ComponentStoreHandle, _tick: u64) {
+        let mut active_world = state.get_mut::<ActiveWorld>();
+        if let Some(world) = active_world.get_world_mut() {
+            self.dispatch(world);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use engine::component::ComponentStore;
+
+    use super::*;
+    use crate::coords::bp;
+
+    #[test]
+    fn placing_a_block_notifies_each_neighbor_once() {
+        let store = ComponentStore::new();
+        store.finish_initialization();
+        let mut world = World::empty(&store.handle());
+        let mut updates = BlockUpdates::new();
+        let notified = Rc::new(RefCell::new(Vec::new()));
+        let recorded = notified.clone();
+        updates.on_neighbor_changed(Block::Air, move |_, update| {
+            recorded.borrow_mut().push(update)
+        });
+
+        let placed = bp(5, 5, 5);
+        world.set_block(placed, Block::Stone);
+        // Setting the same block again changes nothing, so nothing more is queued.
+        world.set_block(placed, Block::Stone);
+        assert_eq!(updates.dispatch(&mut world), 6);
+
+        let notified = notified.borrow();
+        assert!(notified.iter().all(|update| update.source == placed));
+        let positions = notified
+            .iter()
+            .map(|update| update.position)
+            .collect::<HashSet<_>>();
+        let expected = CardinalDirection::iter()
+            .map(|direction| placed.offset(direction))
+            .collect::<HashSet<_>>();
+        assert_eq!(positions, expected);
+        assert_eq!(updates.dispatch(&mut world), 0);
+    }
+
+    #[test]
+    fn hook_changes_wait_for_the_next_tick() {
+        let store = ComponentStore::new();
+        store.finish_initialization();
+        let mut world = World::empty(&store.handle());
+        // Every notified stone block flips to dirt and back, which would never settle.
+        let mut updates = BlockUpdates::new();
+        updates.on_neighbor_changed(Block::Stone, |world, update| {
+            world.set_block(update.position, Block::Dirt);
+        });
+        updates.on_neighbor_changed(Block::Dirt, |world, update| {
+            world.set_block(update.position, Block::Stone);
+        });
+        world.set_block(bp(0, 0, 0), Block::Stone);
+        world.set_block(bp(1, 0, 0), Block::Stone);
+        world.pending_updates_mut().clear();
+
+        world.set_block(bp(1, 0, 0), Block::Dirt);
+        assert_eq!(updates.dispatch(&mut world), 6);
+        // Only the stone block was hooked, and its change queued updates for the next tick.
+        assert_eq!(world.get_block(bp(0, 0, 0)), Block::Dirt);
+        assert_eq!(world.pending_updates().len(), 6);
+    }
+}
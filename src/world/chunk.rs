@@ -1,50 +1,221 @@
+use std::cell::Cell;
+
 use log::warn;
 
-use crate::{BlockPosition, world::Block};
+use crate::{
+    BlockPosition,
+    coords::bp,
+    world::{Block, BlockRegistry, light::LightLevels},
+};
 
 use engine::{component::ComponentStoreHandle, graphics::CardinalDirection, resource::Resource};
 
+/// The width, height and depth of the chunks the world is made of.
 pub const CHUNK_SIZE: usize = 16;
 
+/// The chunk size used by worlds.
+pub type Chunk = ChunkN<CHUNK_SIZE>;
+
+/// The blocks of a chunk, indexed by local X, Y and Z.
+///
+/// Unlike a chunk, this is plain data, so it can be built on another thread.
+pub type ChunkBlocks<const S: usize> = [[[Block; S]; S]; S];
+
+/// A cube of `S` x `S` x `S` blocks.
+///
+/// Worlds use `Chunk`; other sizes are for reusing the chunk and meshing code elsewhere.
 #[derive(Clone, Debug)]
-pub struct Chunk {
-    pub data: [[[Block; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE],
-    neighbors: [Option<Resource<Chunk>>; 6],
+pub struct ChunkN<const S: usize> {
+    pub data: ChunkBlocks<S>,
+    /// How brightly each block is lit, from 0 to `BlockRegistry::MAX_LIGHT`, indexed like `data`.
+    /// New chunks start fully lit until `World::relight_chunk` computes it.
+    pub light: LightLevels<S>,
+    neighbors: [Option<Resource<ChunkN<S>>>; 6],
+    /// Whether the chunk changed since it was last saved. Set when a block is written through
+    /// `IndexMut`; writes straight to `data` must call `mark_dirty` themselves.
+    dirty: Cell<bool>,
 }
 
-impl Chunk {
+impl<const S: usize> ChunkN<S> {
+    /// The width, height and depth of the chunk.
+    pub const SIZE: usize = S;
+
     pub fn empty(_state: ComponentStoreHandle) -> Self {
+        Self::from_blocks([[[Block::Air; S]; S]; S])
+    }
+
+    /// Creates an unlinked chunk holding the given blocks, e.g. ones generated on another thread.
+    pub fn from_blocks(data: ChunkBlocks<S>) -> Self {
         Self {
-            data: [[[Block::Air; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE],
+            data,
+            light: [[[BlockRegistry::MAX_LIGHT; S]; S]; S],
             neighbors: [None, None, None, None, None, None],
+            // A new chunk has never been saved.
+            dirty: Cell::new(true),
         }
     }
 
+    /// Returns true if the chunk changed since it was last saved or loaded.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.get()
+    }
+
+    /// Marks the chunk as changed, so it's written on the next save.
+    pub fn mark_dirty(&self) {
+        self.dirty.set(true);
+    }
+
+    /// Marks the chunk as matching what's on disk.
+    pub fn mark_clean(&self) {
+        self.dirty.set(false);
+    }
+
     pub fn set_neighbor(
         &mut self,
         direction: CardinalDirection,
-        neighbor: Option<Resource<Chunk>>,
+        neighbor: Option<Resource<ChunkN<S>>>,
     ) {
         self.neighbors[direction as usize] = neighbor;
     }
 
+    /// Gets the neighboring chunk in the given direction, if it's loaded and linked.
+    pub fn neighbor(&self, direction: CardinalDirection) -> Option<Resource<ChunkN<S>>> {
+        self.neighbors[direction as usize].clone()
+    }
+
+    /// Returns true if a neighbor is linked in every direction.
+    ///
+    /// Faces on the border of a chunk are culled against its neighbors, so a chunk meshed before
+    /// all of them are loaded may have extra faces on its border.
+    pub fn has_all_neighbors(&self) -> bool {
+        self.neighbors.iter().all(Option::is_some)
+    }
+
+    /// Returns the number of non-air blocks in the chunk.
+    pub fn non_air_count(&self) -> usize {
+        self.data
+            .iter()
+            .flatten()
+            .flatten()
+            .filter(|b| **b != Block::Air)
+            .count()
+    }
+
+    /// Iterates over every block in the chunk with its local position, in x, y, z order.
+    pub fn iter_blocks(&self) -> impl Iterator<Item = (BlockPosition, Block)> + '_ {
+        self.data.iter().enumerate().flat_map(|(x, plane)| {
+            plane.iter().enumerate().flat_map(move |(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(move |(z, block)| (bp(x as i64, y as i64, z as i64), *block))
+            })
+        })
+    }
+
+    /// Iterates over the solid blocks in the chunk with their local positions.
+    pub fn iter_solid(&self) -> impl Iterator<Item = (BlockPosition, Block)> + '_ {
+        self.iter_blocks().filter(|(_, block)| block.is_solid())
+    }
+
+    /// Returns the number of blocks of the given type in the chunk.
+    pub fn count(&self, block: Block) -> usize {
+        self.data
+            .iter()
+            .flatten()
+            .flatten()
+            .filter(|b| **b == block)
+            .count()
+    }
+
+    /// Returns a checksum of the chunk's blocks, stable across runs and platforms.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = Fnv1a::new();
+        for block in self.data.iter().flatten().flatten() {
+            hasher.write(&block.id().to_le_bytes());
+        }
+        hasher.finish()
+    }
+
     /// Inspects a block at the given local chunk position.
     pub fn inspect_block_exact(&self, position: BlockPosition) -> Block {
         self.data[position.0 as usize][position.1 as usize][position.2 as usize]
     }
 
+    /// Inspects a block at a local position that may lie up to one chunk outside this one on
+    /// each axis, e.g. diagonally across an edge, by going through linked neighbors.
+    ///
+    /// Blocks in chunks that aren't linked are treated as air.
+    pub fn inspect_block_relative(&self, position: BlockPosition) -> Block {
+        self.with_relative(position, Self::inspect_block_exact)
+            .unwrap_or(Block::Air)
+    }
+
+    /// Returns the light level at the given local chunk position.
+    pub fn light_exact(&self, position: BlockPosition) -> u8 {
+        self.light[position.0 as usize][position.1 as usize][position.2 as usize]
+    }
+
+    /// Returns the light level at a local position that may lie up to one chunk outside this one
+    /// on each axis, like `inspect_block_relative`.
+    ///
+    /// Chunks that aren't linked are treated as fully lit, like open sky.
+    pub fn light_relative(&self, position: BlockPosition) -> u8 {
+        self.with_relative(position, Self::light_exact)
+            .unwrap_or(BlockRegistry::MAX_LIGHT)
+    }
+
+    /// Calls `f` with the chunk a local position up to one chunk outside this one lies in, and
+    /// the position local to that chunk. Returns `None` if that chunk isn't linked.
+    fn with_relative<R>(
+        &self,
+        position: BlockPosition,
+        f: impl FnOnce(&Self, BlockPosition) -> R,
+    ) -> Option<R> {
+        let size = S as i64;
+        let crossed = [
+            (position.0, CardinalDirection::West, CardinalDirection::East),
+            (position.1, CardinalDirection::Down, CardinalDirection::Up),
+            (
+                position.2,
+                CardinalDirection::North,
+                CardinalDirection::South,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(coordinate, below, above)| match coordinate {
+            ..0 => Some(below),
+            c if c >= size => Some(above),
+            _ => None,
+        });
+
+        let mut chunk: Option<Resource<ChunkN<S>>> = None;
+        for direction in crossed {
+            let next = match &chunk {
+                Some(chunk) => chunk.get().neighbor(direction),
+                None => self.neighbor(direction),
+            };
+            chunk = Some(next?);
+        }
+
+        let local = position.normalize_in(S);
+        Some(match chunk {
+            Some(chunk) => f(&chunk.get(), local),
+            None => f(self, local),
+        })
+    }
+
     /// Inspects a block at the given world position + direction.
     pub fn inspect_block(&self, base: BlockPosition, direction: CardinalDirection) -> Block {
         // We need to return the block (if present) in the given direction from the base position.
         let true_pos = base.offset(direction);
-        if true_pos.all(|c| c > CHUNK_SIZE as i64 * 2) {
+        if true_pos.all(|c| c > S as i64 * 2) {
             warn!(
                 "Inspecting block at very large positive position {:?}",
                 true_pos
             );
             return Block::Air;
         }
-        let local_pos = true_pos.chunk_normalize();
+        let local_pos = true_pos.normalize_in(S);
         if true_pos == local_pos {
             // Still in this chunk
             self.inspect_block_exact(local_pos)
@@ -60,15 +231,15 @@ impl Chunk {
     }
 }
 
-impl std::ops::Deref for Chunk {
-    type Target = [[[Block; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+impl<const S: usize> std::ops::Deref for ChunkN<S> {
+    type Target = [[[Block; S]; S]; S];
 
     fn deref(&self) -> &Self::Target {
         &self.data
     }
 }
 
-impl std::ops::Index<(usize, usize, usize)> for Chunk {
+impl<const S: usize> std::ops::Index<(usize, usize, usize)> for ChunkN<S> {
     type Output = Block;
 
     fn index(&self, index: (usize, usize, usize)) -> &Self::Output {
@@ -76,8 +247,120 @@ impl std::ops::Index<(usize, usize, usize)> for Chunk {
     }
 }
 
-impl std::ops::IndexMut<(usize, usize, usize)> for Chunk {
+impl<const S: usize> std::ops::IndexMut<(usize, usize, usize)> for ChunkN<S> {
     fn index_mut(&mut self, index: (usize, usize, usize)) -> &mut Self::Output {
+        self.dirty.set(true);
         &mut self.data[index.0][index.1][index.2]
     }
 }
+
+/// The 64 bit FNV-1a hash. Unlike `DefaultHasher`, its output never changes between Rust
+/// versions, so it can be used for checksums stored on disk.
+#[derive(Debug, Clone, Copy)]
+pub struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    /// Creates a hasher with nothing written to it.
+    pub fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    /// Feeds a byte into the hash.
+    pub fn write_u8(&mut self, byte: u8) {
+        self.0 ^= byte as u64;
+        self.0 = self.0.wrapping_mul(Self::PRIME);
+    }
+
+    /// Feeds bytes into the hash.
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_u8(byte);
+        }
+    }
+
+    /// Returns the hash of everything written so far.
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use engine::graphics::textures::TextureHandle;
+    use glam::Vec3;
+
+    use super::*;
+    use crate::render::{
+        block_textures::BlockTextureAtlas, pipelines::solid::build_mesh_for_chunk,
+    };
+
+    type SmallChunk = ChunkN<8>;
+
+    #[test]
+    fn small_chunk_wraps_at_its_own_size() {
+        let mut chunk = SmallChunk::from_blocks([[[Block::Air; 8]; 8]; 8]);
+        assert_eq!(SmallChunk::SIZE, 8);
+        chunk[(7, 7, 7)] = Block::Stone;
+        assert_eq!(chunk.non_air_count(), 1);
+        assert_eq!(
+            chunk.inspect_block(bp(6, 7, 7), CardinalDirection::East),
+            Block::Stone
+        );
+        // Stepping past the edge leaves the chunk, which has no neighbors linked.
+        assert_eq!(
+            chunk.inspect_block(bp(7, 7, 7), CardinalDirection::Up),
+            Block::Air
+        );
+        assert_eq!(chunk.inspect_block_relative(bp(8, 7, 7)), Block::Air);
+
+        let world = bp(-1, 9, 17);
+        assert_eq!(world.normalize_in(8), bp(7, 1, 1));
+        assert_eq!(world.to_chunk_position_in(8), bp(-1, 1, 2));
+        assert_eq!(bp(-1, 1, 2).chunk_origin_in(8), bp(-8, 8, 16));
+    }
+
+    #[test]
+    fn small_chunk_meshes_only_its_surface() {
+        let registry = BlockRegistry::new();
+        let mut atlas = BlockTextureAtlas::new();
+        atlas.set_texture_handle(Block::Stone, TextureHandle::new(0, 1));
+        let chunk = SmallChunk::from_blocks([[[Block::Stone; 8]; 8]; 8]);
+
+        let mesh = build_mesh_for_chunk(&atlas, &registry, &chunk, bp(0, 0, 0));
+        let faces = 6 * 8 * 8;
+        assert_eq!(mesh.vertices.len(), faces * 4);
+        assert_eq!(mesh.indices.len(), faces * 6);
+        assert!(mesh.vertices.iter().all(
+            |v| v.position.cmpge(Vec3::ZERO).all() && v.position.cmple(Vec3::splat(8.0)).all()
+        ));
+    }
+
+    #[test]
+    fn iterates_every_block_of_an_empty_chunk() {
+        let mut chunk = Chunk::from_blocks([[[Block::Air; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE]);
+        assert_eq!(chunk.iter_blocks().count(), 4096);
+        assert!(chunk.iter_blocks().all(|(_, block)| block == Block::Air));
+        assert_eq!(chunk.count(Block::Air), 4096);
+        assert_eq!(chunk.iter_solid().count(), 0);
+
+        chunk[(1, 2, 3)] = Block::Stone;
+        chunk[(4, 5, 6)] = Block::Water;
+        assert_eq!(chunk.count(Block::Air), 4094);
+        assert_eq!(chunk.count(Block::Stone), 1);
+        assert_eq!(
+            chunk.iter_solid().collect::<Vec<_>>(),
+            vec![(bp(1, 2, 3), Block::Stone)]
+        );
+        // Positions come out in x, y, z order.
+        assert_eq!(chunk.iter_blocks().nth(1).unwrap().0, bp(0, 0, 1));
+    }
+}
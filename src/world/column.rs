@@ -0,0 +1,157 @@
+use engine::{component::ComponentStoreHandle, resource::Resource};
+
+use crate::world::{Block, Chunk, chunk::CHUNK_SIZE};
+
+/// The default height of a column, in blocks.
+pub const DEFAULT_COLUMN_HEIGHT: usize = 256;
+
+/// A vertical stack of chunks sharing the same X and Z, from Y 0 up to a maximum height.
+///
+/// Chunks stay 16³ so meshing and culling work on small pieces, while the column makes a tall
+/// world look like one piece of storage. Chunks are allocated from the bottom up: setting a block
+/// fills every chunk below it with air chunks, so the column never has gaps.
+pub struct ChunkColumn {
+    /// The chunks of the column, indexed by chunk Y.
+    chunks: Vec<Resource<Chunk>>,
+    /// The maximum number of chunks in the column.
+    max_chunks: usize,
+    /// The world revision the column's blocks last changed at.
+    revision: u64,
+    state: ComponentStoreHandle,
+}
+
+impl ChunkColumn {
+    /// Creates an empty column that can hold blocks from Y 0 up to `max_height`, rounded up to a
+    /// whole number of chunks.
+    pub fn new(max_height: usize, state: &ComponentStoreHandle) -> Self {
+        Self {
+            chunks: Vec::new(),
+            max_chunks: max_height.div_ceil(CHUNK_SIZE),
+            revision: 0,
+            state: state.clone(),
+        }
+    }
+
+    /// Returns the height the column can grow to, in blocks.
+    pub fn max_height(&self) -> usize {
+        self.max_chunks * CHUNK_SIZE
+    }
+
+    /// Returns the world revision the column's blocks or chunks last changed at, so caches of the
+    /// column can tell if they're out of date. See `World::revision`.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Records that the column changed at the given world revision.
+    pub fn mark_changed(&mut self, revision: u64) {
+        self.revision = revision;
+    }
+
+    /// Returns the number of chunks allocated in the column.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Returns true if the column has no chunks.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Returns true if the chunk Y is within the column's height.
+    pub fn contains_chunk_y(&self, chunk_y: i64) -> bool {
+        (0..self.max_chunks as i64).contains(&chunk_y)
+    }
+
+    /// Gets the chunk at the given chunk Y, if it's allocated.
+    pub fn chunk(&self, chunk_y: i64) -> Option<&Resource<Chunk>> {
+        usize::try_from(chunk_y)
+            .ok()
+            .and_then(|index| self.chunks.get(index))
+    }
+
+    /// Iterates over the allocated chunks and their chunk Y, from the bottom up.
+    pub fn chunks(&self) -> impl DoubleEndedIterator<Item = (i64, &Resource<Chunk>)> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .map(|(y, chunk)| (y as i64, chunk))
+    }
+
+    /// Allocates air chunks up to and including the given chunk Y, returning the Y of every
+    /// chunk that was added.
+    ///
+    /// Nothing is allocated if the chunk Y is outside the column's height.
+    pub fn allocate(&mut self, chunk_y: i64) -> std::ops::Range<i64> {
+        let start = self.chunks.len() as i64;
+        if !self.contains_chunk_y(chunk_y) {
+            return start..start;
+        }
+        while self.chunks.len() as i64 <= chunk_y {
+            self.chunks.push(Chunk::empty(self.state.clone()).into());
+        }
+        start..self.chunks.len() as i64
+    }
+
+    /// Inserts a chunk at the given chunk Y, allocating air chunks below it as needed.
+    ///
+    /// Returns the chunk it replaced, or `Err` with the chunk if the Y is outside the column's
+    /// height.
+    pub fn insert(
+        &mut self,
+        chunk_y: i64,
+        chunk: Resource<Chunk>,
+    ) -> Result<Option<Resource<Chunk>>, Resource<Chunk>> {
+        if !self.contains_chunk_y(chunk_y) {
+            return Err(chunk);
+        }
+        let index = chunk_y as usize;
+        if index < self.chunks.len() {
+            return Ok(Some(std::mem::replace(&mut self.chunks[index], chunk)));
+        }
+        self.allocate(chunk_y - 1);
+        self.chunks.push(chunk);
+        Ok(None)
+    }
+
+    /// Gets the block at the given position in the column. Blocks outside the allocated chunks
+    /// are air.
+    pub fn block_at(&self, local_x: usize, world_y: i64, local_z: usize) -> Block {
+        match self.chunk(world_y.div_euclid(CHUNK_SIZE as i64)) {
+            Some(chunk) => chunk.get()[(
+                local_x,
+                world_y.rem_euclid(CHUNK_SIZE as i64) as usize,
+                local_z,
+            )],
+            None => Block::Air,
+        }
+    }
+
+    /// Sets the block at the given position in the column, allocating chunks up to it as needed.
+    ///
+    /// Returns the block that was replaced, or `None` if `world_y` is outside the column's
+    /// height and nothing was set.
+    pub fn set_block(
+        &mut self,
+        world_y: i64,
+        local_x: usize,
+        local_z: usize,
+        block: Block,
+    ) -> Option<Block> {
+        let chunk_y = world_y.div_euclid(CHUNK_SIZE as i64);
+        if !self.contains_chunk_y(chunk_y) {
+            return None;
+        }
+        if self.chunk(chunk_y).is_none() && block == Block::Air {
+            return Some(Block::Air);
+        }
+        self.allocate(chunk_y);
+        let mut chunk = self.chunks[chunk_y as usize].get_mut();
+        let index = (
+            local_x,
+            world_y.rem_euclid(CHUNK_SIZE as i64) as usize,
+            local_z,
+        );
+        Some(std::mem::replace(&mut chunk[index], block))
+    }
+}
@@ -0,0 +1,170 @@
+use engine::component::ComponentStoreHandle;
+
+use crate::{
+    ChunkPosition,
+    coords::bp,
+    world::{
+        Block, Chunk, Structure,
+        chunk::{CHUNK_SIZE, ChunkBlocks},
+        noise::{SimplexNoise, hash2},
+    },
+};
+
+/// The width and depth of the grid cells trees are scattered in. Each cell holds at most one
+/// tree, so trees never grow into each other's trunks.
+pub const TREE_CELL_SIZE: i64 = 8;
+
+/// Mixed into the seed for tree placement, so trees don't line up with the terrain's noise.
+const TREE_SALT: u64 = 0x7472_6565;
+
+/// Creates the contents of chunks as the world is generated.
+pub trait TerrainGenerator {
+    /// Generates the blocks of the chunk at `chunk_pos`, in chunk coordinates.
+    ///
+    /// This must only depend on the generator's settings and `chunk_pos`, so chunks can be
+    /// generated in any order and the same seed gives the same world.
+    fn generate_blocks(&self, chunk_pos: ChunkPosition) -> ChunkBlocks<CHUNK_SIZE>;
+
+    /// Generates the chunk at `chunk_pos`, in chunk coordinates.
+    fn generate(&self, chunk_pos: ChunkPosition, state: &ComponentStoreHandle) -> Chunk {
+        let mut chunk = Chunk::empty(state.clone());
+        chunk.data = self.generate_blocks(chunk_pos);
+        chunk
+    }
+}
+
+/// Generates rolling hills from a noise heightmap, with stone below, a few layers of dirt, grass
+/// on top, and scattered oak trees.
+#[derive(Debug, Clone)]
+pub struct PerlinTerrainGenerator {
+    seed: u64,
+    noise: SimplexNoise,
+    base_height: i64,
+    amplitude: f64,
+    scale: f64,
+    octaves: u32,
+    tree_chance: f64,
+}
+
+impl PerlinTerrainGenerator {
+    /// Creates a generator whose terrain is determined by `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            noise: SimplexNoise::new(seed),
+            base_height: 24,
+            amplitude: 12.0,
+            scale: 1.0 / 96.0,
+            octaves: 4,
+            tree_chance: 0.5,
+        }
+    }
+
+    /// Sets the average height of the surface.
+    pub fn with_base_height(mut self, base_height: i64) -> Self {
+        self.base_height = base_height;
+        self
+    }
+
+    /// Sets how far the surface rises above and sinks below the base height.
+    pub fn with_amplitude(mut self, amplitude: f64) -> Self {
+        self.amplitude = amplitude;
+        self
+    }
+
+    /// Sets the chance, from 0.0 to 1.0, that a tree grows in each `TREE_CELL_SIZE` cell.
+    pub fn with_tree_chance(mut self, tree_chance: f64) -> Self {
+        self.tree_chance = tree_chance;
+        self
+    }
+
+    /// Returns the seed the terrain is generated from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the height of the grass block in the given column.
+    pub fn surface_height(&self, x: i64, z: i64) -> i64 {
+        let noise = self
+            .noise
+            .fractal(x as f64 * self.scale, z as f64 * self.scale, self.octaves);
+        self.base_height + (noise * self.amplitude).round() as i64
+    }
+
+    /// Returns the block at the given height in a column whose surface is at `surface`, ignoring
+    /// trees.
+    fn terrain_block(y: i64, surface: i64) -> Block {
+        match surface - y {
+            ..0 => Block::Air,
+            0 => Block::Grass,
+            1..=3 => Block::Dirt,
+            _ => Block::Stone,
+        }
+    }
+
+    /// Returns the column and trunk height of the tree in the given cell, if it has one.
+    fn tree_in_cell(&self, cell_x: i64, cell_z: i64) -> Option<(i64, i64, usize)> {
+        let hash = hash2(self.seed ^ TREE_SALT, cell_x, cell_z);
+        if (hash & 0xffff) as f64 / 65536.0 >= self.tree_chance {
+            return None;
+        }
+        // Keep trunks away from the cell's edges, so leaves of neighboring trees barely touch.
+        let margin = 2;
+        let span = (TREE_CELL_SIZE - 2 * margin) as u64;
+        let x = cell_x * TREE_CELL_SIZE + margin + ((hash >> 16) % span) as i64;
+        let z = cell_z * TREE_CELL_SIZE + margin + ((hash >> 24) % span) as i64;
+        let trunk_height = 4 + ((hash >> 32) % 3) as usize;
+        Some((x, z, trunk_height))
+    }
+
+    /// Places the parts of every tree that reach into the chunk at `origin`, only into air.
+    fn place_trees(&self, blocks: &mut ChunkBlocks<CHUNK_SIZE>, origin: ChunkPosition) {
+        let size = CHUNK_SIZE as i64;
+        // Leaves reach two blocks out from the trunk, so trees in bordering cells can overlap.
+        let reach = 2;
+        let cells = |min: i64| {
+            (min - reach).div_euclid(TREE_CELL_SIZE)
+                ..=(min + size + reach).div_euclid(TREE_CELL_SIZE)
+        };
+        for cell_x in cells(origin.0) {
+            for cell_z in cells(origin.2) {
+                let Some((x, z, trunk_height)) = self.tree_in_cell(cell_x, cell_z) else {
+                    continue;
+                };
+                let tree = Structure::oak_tree(trunk_height);
+                let root = bp(x, self.surface_height(x, z) + 1, z) - tree.anchor;
+                for (offset, block) in tree.iter() {
+                    let local = root + offset - origin;
+                    if block == Block::Air
+                        || !(0..size).contains(&local.0)
+                        || !(0..size).contains(&local.1)
+                        || !(0..size).contains(&local.2)
+                    {
+                        continue;
+                    }
+                    let cell = &mut blocks[local.0 as usize][local.1 as usize][local.2 as usize];
+                    if *cell == Block::Air {
+                        *cell = block;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl TerrainGenerator for PerlinTerrainGenerator {
+    fn generate_blocks(&self, chunk_pos: ChunkPosition) -> ChunkBlocks<CHUNK_SIZE> {
+        let origin = chunk_pos.chunk_origin();
+        let mut blocks = [[[Block::Air; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+        for (x, plane) in blocks.iter_mut().enumerate() {
+            for z in 0..CHUNK_SIZE {
+                let surface = self.surface_height(origin.0 + x as i64, origin.2 + z as i64);
+                for (y, row) in plane.iter_mut().enumerate() {
+                    row[z] = Self::terrain_block(origin.1 + y as i64, surface);
+                }
+            }
+        }
+        self.place_trees(&mut blocks, origin);
+        blocks
+    }
+}
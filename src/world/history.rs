@@ -0,0 +1,307 @@
+use std::collections::VecDeque;
+
+use log::warn;
+
+use crate::{
+    BlockPosition,
+    world::{Block, ReplacePolicy, Structure, World},
+};
+
+/// A single block change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockEdit {
+    pub position: BlockPosition,
+    pub old: Block,
+    pub new: Block,
+}
+
+/// Records block edits so they can be undone and redone.
+///
+/// Every undo step is a group of edits; single edits form a group of one, while batches
+/// (see [`EditHistory::begin_batch`]) collect everything until the matching `end_batch`. Batches
+/// can be nested, in which case everything up to the outermost `end_batch` is one step.
+#[derive(Debug)]
+pub struct EditHistory {
+    undo: VecDeque<Vec<BlockEdit>>,
+    redo: Vec<Vec<BlockEdit>>,
+    batch: Option<Vec<BlockEdit>>,
+    /// How many `begin_batch` calls are still waiting for their `end_batch`.
+    batch_depth: usize,
+    capacity: usize,
+}
+
+impl EditHistory {
+    /// The default number of undo steps kept.
+    pub const DEFAULT_CAPACITY: usize = 256;
+
+    /// Creates a new history that keeps at most `capacity` undo steps.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            batch: None,
+            batch_depth: 0,
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Sets a block through the world and records the edit.
+    ///
+    /// Nothing is recorded if the world didn't take the block, e.g. above the column height.
+    pub fn set_block(&mut self, world: &mut World, position: BlockPosition, block: Block) -> Block {
+        let old = world.set_block(position, block);
+        if world.get_block(position) == block {
+            self.record(BlockEdit {
+                position,
+                old,
+                new: block,
+            });
+        }
+        old
+    }
+
+    /// Places a structure through the world, recording all changed blocks as a single undo step.
+    pub fn place_structure(
+        &mut self,
+        world: &mut World,
+        origin: BlockPosition,
+        structure: &Structure,
+        policy: ReplacePolicy,
+    ) {
+        let edits = world.place_structure(origin, structure, policy);
+        self.begin_batch();
+        edits.into_iter().for_each(|edit| self.record(edit));
+        self.end_batch();
+    }
+
+    /// Records an edit that has already been applied to the world.
+    ///
+    /// Recording a new edit clears the redo stack. Edits that don't change anything are ignored.
+    pub fn record(&mut self, edit: BlockEdit) {
+        if edit.old == edit.new {
+            return;
+        }
+        match &mut self.batch {
+            Some(batch) => batch.push(edit),
+            None => self.push_step(vec![edit]),
+        }
+    }
+
+    /// Starts grouping recorded edits into a single undo step.
+    ///
+    /// If a batch is already open, this one is nested in it and its edits become part of the
+    /// outer step.
+    pub fn begin_batch(&mut self) {
+        self.batch_depth += 1;
+        self.batch.get_or_insert_with(Vec::new);
+    }
+
+    /// Finishes the current batch. Once the outermost batch is finished, its edits are pushed as
+    /// one undo step if there are any.
+    pub fn end_batch(&mut self) {
+        match self.batch_depth {
+            0 => warn!("EditHistory::end_batch called without an open batch"),
+            1 => self.close_batch(),
+            _ => self.batch_depth -= 1,
+        }
+    }
+
+    /// Closes the open batch and any batches nested in it.
+    fn close_batch(&mut self) {
+        self.batch_depth = 0;
+        if let Some(batch) = self.batch.take()
+            && !batch.is_empty()
+        {
+            self.push_step(batch);
+        }
+    }
+
+    fn push_step(&mut self, step: Vec<BlockEdit>) {
+        self.redo.clear();
+        self.undo.push_back(step);
+        while self.undo.len() > self.capacity {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Reverts the most recent undo step. Returns false if there was nothing to undo.
+    ///
+    /// Any open batch is closed first, so it can be undone as a whole.
+    pub fn undo(&mut self, world: &mut World) -> bool {
+        self.close_batch();
+        let Some(step) = self.undo.pop_back() else {
+            return false;
+        };
+        // Undo in reverse so overlapping edits within a step restore the original block.
+        for edit in step.iter().rev() {
+            world.set_block(edit.position, edit.old);
+        }
+        self.redo.push(step);
+        true
+    }
+
+    /// Reapplies the most recently undone step. Returns false if there was nothing to redo.
+    pub fn redo(&mut self, world: &mut World) -> bool {
+        self.close_batch();
+        let Some(step) = self.redo.pop() else {
+            return false;
+        };
+        for edit in step.iter() {
+            world.set_block(edit.position, edit.new);
+        }
+        self.undo.push_back(step);
+        true
+    }
+
+    /// Returns true if there is a step that can be undone.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Returns true if there is a step that can be redone.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Clears all recorded history.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.batch = None;
+        self.batch_depth = 0;
+    }
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use engine::component::ComponentStore;
+
+    use super::*;
+    use crate::coords::bp;
+
+    fn world(store: &ComponentStore) -> World {
+        store.finish_initialization();
+        World::empty(&store.handle())
+    }
+
+    #[test]
+    fn undo_and_redo_restore_blocks() {
+        let store = ComponentStore::new();
+        let mut world = world(&store);
+        let mut history = EditHistory::default();
+
+        history.set_block(&mut world, bp(1, 2, 3), Block::Stone);
+        history.set_block(&mut world, bp(1, 2, 3), Block::Dirt);
+        assert_eq!(world.get_block(bp(1, 2, 3)), Block::Dirt);
+
+        assert!(history.undo(&mut world));
+        assert_eq!(world.get_block(bp(1, 2, 3)), Block::Stone);
+        assert!(history.undo(&mut world));
+        assert_eq!(world.get_block(bp(1, 2, 3)), Block::Air);
+        assert!(!history.undo(&mut world));
+
+        assert!(history.redo(&mut world));
+        assert_eq!(world.get_block(bp(1, 2, 3)), Block::Stone);
+        assert!(history.redo(&mut world));
+        assert_eq!(world.get_block(bp(1, 2, 3)), Block::Dirt);
+        assert!(!history.redo(&mut world));
+    }
+
+    #[test]
+    fn new_edit_clears_redo() {
+        let store = ComponentStore::new();
+        let mut world = world(&store);
+        let mut history = EditHistory::default();
+
+        history.set_block(&mut world, bp(0, 0, 0), Block::Stone);
+        history.undo(&mut world);
+        assert!(history.can_redo());
+        history.set_block(&mut world, bp(0, 0, 0), Block::Dirt);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn batch_is_one_step() {
+        let store = ComponentStore::new();
+        let mut world = world(&store);
+        let mut history = EditHistory::default();
+
+        history.begin_batch();
+        for x in 0..4 {
+            history.set_block(&mut world, bp(x, 0, 0), Block::Stone);
+        }
+        history.end_batch();
+
+        assert!(history.undo(&mut world));
+        assert!(!history.can_undo());
+        for x in 0..4 {
+            assert_eq!(world.get_block(bp(x, 0, 0)), Block::Air);
+        }
+        assert!(history.redo(&mut world));
+        for x in 0..4 {
+            assert_eq!(world.get_block(bp(x, 0, 0)), Block::Stone);
+        }
+    }
+
+    #[test]
+    fn structure_in_open_batch_joins_it() {
+        let store = ComponentStore::new();
+        let mut world = world(&store);
+        let mut history = EditHistory::default();
+
+        history.begin_batch();
+        history.set_block(&mut world, bp(0, 0, 0), Block::Stone);
+        history.place_structure(
+            &mut world,
+            bp(0, 1, 0),
+            &Structure::oak_tree(4),
+            ReplacePolicy::AirOnly,
+        );
+        // Still inside the outer batch.
+        history.set_block(&mut world, bp(5, 0, 5), Block::Dirt);
+        history.end_batch();
+
+        assert!(history.undo(&mut world));
+        assert!(!history.can_undo());
+        assert_eq!(world.get_block(bp(0, 0, 0)), Block::Air);
+        assert_eq!(world.get_block(bp(0, 1, 0)), Block::Air);
+        assert_eq!(world.get_block(bp(5, 0, 5)), Block::Air);
+    }
+
+    #[test]
+    fn capacity_drops_oldest_steps() {
+        let store = ComponentStore::new();
+        let mut world = world(&store);
+        let mut history = EditHistory::new(2);
+
+        for x in 0..3 {
+            history.set_block(&mut world, bp(x, 0, 0), Block::Stone);
+        }
+        assert!(history.undo(&mut world));
+        assert!(history.undo(&mut world));
+        assert!(!history.undo(&mut world));
+        assert_eq!(world.get_block(bp(0, 0, 0)), Block::Stone);
+    }
+
+    #[test]
+    fn edits_outside_the_world_are_not_recorded() {
+        let store = ComponentStore::new();
+        let mut world = world(&store);
+        let mut history = EditHistory::default();
+
+        history.set_block(&mut world, bp(0, 0, 0), Block::Stone);
+        history.undo(&mut world);
+        let above = bp(0, world.column_height() as i64 + 5, 0);
+        history.set_block(&mut world, above, Block::Stone);
+        history.set_block(&mut world, bp(0, -1, 0), Block::Stone);
+
+        assert!(!history.can_undo());
+        assert!(history.can_redo());
+    }
+}
@@ -0,0 +1,214 @@
+//! Block light, flood filled from the sky and from blocks that emit light.
+//!
+//! Every cell of a chunk has a light level from 0 to `BlockRegistry::MAX_LIGHT`. Light loses one
+//! level for every block it spreads through and doesn't pass through opaque blocks. Chunks are
+//! lit one at a time: light only crosses into a neighboring chunk when that chunk is relit, seeded
+//! from the light already on its border.
+
+use std::collections::VecDeque;
+
+use engine::graphics::CardinalDirection;
+
+use crate::world::{BlockRegistry, ChunkN};
+
+/// The light level of every cell of a chunk, indexed by local X, Y and Z like `ChunkN::data`.
+pub type LightLevels<const S: usize> = [[[u8; S]; S]; S];
+
+/// Which columns of a chunk the sky reaches the top of, indexed by local X and Z.
+pub type SkyMask<const S: usize> = [[bool; S]; S];
+
+/// Computes the light of every cell in a chunk.
+///
+/// Light spreads from three kinds of sources:
+/// - The sky, which fully lights every column set in `sky` straight down to its first opaque
+///   block.
+/// - Blocks with a light emission, which are lit to it.
+/// - The border cells of linked neighbors, one level dimmer than the neighbor's cell.
+///
+/// Opaque blocks are unlit unless they emit light themselves.
+pub fn compute_light<const S: usize>(
+    chunk: &ChunkN<S>,
+    registry: &BlockRegistry,
+    sky: &SkyMask<S>,
+) -> LightLevels<S> {
+    let mut light = [[[0; S]; S]; S];
+    let mut queue = VecDeque::new();
+    let opaque = |[x, y, z]: [usize; 3]| registry.is_opaque(chunk.data[x][y][z]);
+
+    for (x, columns) in sky.iter().enumerate() {
+        for (z, open) in columns.iter().enumerate() {
+            if !open {
+                continue;
+            }
+            for y in (0..S).rev() {
+                if opaque([x, y, z]) {
+                    break;
+                }
+                raise(&mut light, &mut queue, [x, y, z], BlockRegistry::MAX_LIGHT);
+            }
+        }
+    }
+
+    for (position, block) in chunk.iter_blocks() {
+        let emission = registry.light_emission(block);
+        if emission > 0 {
+            let cell = [position.0, position.1, position.2].map(|c| c as usize);
+            raise(&mut light, &mut queue, cell, emission);
+        }
+    }
+
+    for direction in CardinalDirection::iter() {
+        let Some(neighbor) = chunk.neighbor(direction) else {
+            continue;
+        };
+        let neighbor = neighbor.get();
+        let (axis, positive) = axis_of(direction);
+        let [a, b] = match axis {
+            0 => [1, 2],
+            1 => [0, 2],
+            _ => [0, 1],
+        };
+        for i in 0..S {
+            for j in 0..S {
+                let mut ours = [0; 3];
+                ours[a] = i;
+                ours[b] = j;
+                let mut theirs = ours;
+                ours[axis] = if positive { S - 1 } else { 0 };
+                theirs[axis] = if positive { 0 } else { S - 1 };
+
+                let level = neighbor.light[theirs[0]][theirs[1]][theirs[2]].saturating_sub(1);
+                if level > 0 && !opaque(ours) {
+                    raise(&mut light, &mut queue, ours, level);
+                }
+            }
+        }
+    }
+
+    while let Some(cell) = queue.pop_front() {
+        let level = light[cell[0]][cell[1]][cell[2]];
+        if level <= 1 {
+            continue;
+        }
+        for direction in CardinalDirection::iter() {
+            let (axis, positive) = axis_of(direction);
+            let mut next = cell;
+            match (positive, cell[axis]) {
+                (false, 0) => continue,
+                (true, c) if c == S - 1 => continue,
+                (false, c) => next[axis] = c - 1,
+                (true, c) => next[axis] = c + 1,
+            }
+            if !opaque(next) {
+                raise(&mut light, &mut queue, next, level - 1);
+            }
+        }
+    }
+
+    light
+}
+
+/// Returns the columns of the chunk below the given one that the sky reaches, given the columns
+/// it reaches the top of this chunk through: those with no opaque block in this chunk.
+pub fn sky_below<const S: usize>(
+    chunk: &ChunkN<S>,
+    registry: &BlockRegistry,
+    sky: &SkyMask<S>,
+) -> SkyMask<S> {
+    let mut below = *sky;
+    for (x, columns) in below.iter_mut().enumerate() {
+        for (z, open) in columns.iter_mut().enumerate() {
+            *open = *open && (0..S).all(|y| !registry.is_opaque(chunk.data[x][y][z]));
+        }
+    }
+    below
+}
+
+/// Lights a cell to `level` and queues it to spread, unless it's already at least as bright.
+fn raise<const S: usize>(
+    light: &mut LightLevels<S>,
+    queue: &mut VecDeque<[usize; 3]>,
+    [x, y, z]: [usize; 3],
+    level: u8,
+) {
+    if light[x][y][z] < level {
+        light[x][y][z] = level;
+        queue.push_back([x, y, z]);
+    }
+}
+
+/// Returns the axis a direction points along, and whether it points towards positive
+/// coordinates.
+fn axis_of(direction: CardinalDirection) -> (usize, bool) {
+    match direction {
+        CardinalDirection::East => (0, true),
+        CardinalDirection::West => (0, false),
+        CardinalDirection::Up => (1, true),
+        CardinalDirection::Down => (1, false),
+        CardinalDirection::South => (2, true),
+        CardinalDirection::North => (2, false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use engine::component::ComponentStore;
+
+    use super::*;
+    use crate::{
+        coords::bp,
+        world::{Block, BlockProperties, Chunk, World, chunk::CHUNK_SIZE},
+    };
+
+    #[test]
+    fn torches_light_a_fading_gradient_until_removed() {
+        let mut registry = BlockRegistry::new();
+        let torch = registry
+            .register(
+                BlockProperties::cube("torch", "torch")
+                    .transparent()
+                    .with_light_emission(14),
+            )
+            .unwrap();
+        let mut store = ComponentStore::new();
+        store.insert(registry);
+        store.finish_initialization();
+
+        // A chunk roofed over, so the sky doesn't light it.
+        let mut chunk = Chunk::empty(store.handle());
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                chunk.data[x][CHUNK_SIZE - 1][z] = Block::Stone;
+            }
+        }
+        let mut world = World::empty(&store.handle());
+        world.push_chunk(bp(0, 0, 0), chunk);
+        world.relight_all();
+        let light = |world: &World, x: i64| {
+            world
+                .chunk(bp(0, 0, 0))
+                .unwrap()
+                .get()
+                .light_exact(bp(x, 5, 8))
+        };
+        assert_eq!(light(&world, 8), 0);
+
+        world.set_block(bp(8, 5, 8), torch);
+        let gradient = (8..CHUNK_SIZE as i64)
+            .map(|x| light(&world, x))
+            .collect::<Vec<_>>();
+        assert_eq!(gradient, [14, 13, 12, 11, 10, 9, 8, 7]);
+        // The roof blocks the light, and is unlit itself.
+        assert_eq!(
+            world
+                .chunk(bp(0, 0, 0))
+                .unwrap()
+                .get()
+                .light_exact(bp(8, CHUNK_SIZE as i64 - 1, 8)),
+            0
+        );
+
+        world.set_block(bp(8, 5, 8), Block::Air);
+        assert!((0..CHUNK_SIZE as i64).all(|x| light(&world, x) == 0));
+    }
+}
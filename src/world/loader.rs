@@ -0,0 +1,265 @@
+use std::{
+    collections::HashSet,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use log::warn;
+
+use crate::{
+    ChunkPosition,
+    render::{
+        block_textures::BlockTextureAtlas,
+        pipelines::solid::{ChunkMesh, build_mesh_for_chunk},
+    },
+    world::{
+        Block, BlockRegistry, Chunk, TerrainGenerator,
+        chunk::{CHUNK_SIZE, ChunkBlocks},
+    },
+};
+
+/// How many loaded chunks are handed to the world and uploaded to the GPU each frame.
+pub const CHUNK_UPLOADS_PER_FRAME: usize = 4;
+
+/// A chunk generated and meshed by a `ChunkLoader`.
+pub struct LoadedChunk {
+    pub position: ChunkPosition,
+    pub blocks: ChunkBlocks<CHUNK_SIZE>,
+    /// The chunk's mesh, built without its neighbors or light, so its border faces aren't culled
+    /// and it's fully lit. It's good enough to show until the chunk is remeshed in the world.
+    pub mesh: ChunkMesh,
+}
+
+impl LoadedChunk {
+    /// Returns true if the chunk is all air.
+    pub fn is_empty(&self) -> bool {
+        self.blocks
+            .iter()
+            .flatten()
+            .flatten()
+            .all(|block| *block == Block::Air)
+    }
+
+    /// Creates an unlinked chunk from the loaded blocks, to insert into the world with
+    /// `World::insert_chunk`.
+    pub fn to_chunk(&self) -> Chunk {
+        Chunk::from_blocks(self.blocks)
+    }
+}
+
+/// Generates and meshes chunks on a pool of background threads.
+///
+/// Chunks and GPU buffers can't leave the main thread, so the workers only produce the plain
+/// blocks and CPU mesh of each chunk. The main thread takes finished chunks with `poll`, a few at
+/// a time, and builds the chunk and its buffers from them.
+pub struct ChunkLoader {
+    /// Sends positions to the workers. Dropped on shutdown, which wakes any waiting for a job.
+    jobs: Option<mpsc::Sender<ChunkPosition>>,
+    results: mpsc::Receiver<LoadedChunk>,
+    workers: Vec<JoinHandle<()>>,
+    /// Set on shutdown, so workers skip the chunks still queued.
+    stopped: Arc<AtomicBool>,
+    /// Chunks submitted but not polled yet.
+    in_flight: HashSet<ChunkPosition>,
+}
+
+impl ChunkLoader {
+    /// Starts a loader with one worker per spare CPU core.
+    ///
+    /// Meshes are built with a snapshot of `atlas` and `registry`, so blocks registered or
+    /// retextured later only show up once the chunk is remeshed.
+    pub fn new(
+        generator: impl TerrainGenerator + Send + Sync + 'static,
+        atlas: BlockTextureAtlas,
+        registry: BlockRegistry,
+    ) -> Self {
+        let threads = thread::available_parallelism()
+            .map(|n| n.get().saturating_sub(1))
+            .unwrap_or(1);
+        Self::with_threads(generator, atlas, registry, threads)
+    }
+
+    /// Starts a loader with the given number of worker threads, at least one.
+    pub fn with_threads(
+        generator: impl TerrainGenerator + Send + Sync + 'static,
+        atlas: BlockTextureAtlas,
+        registry: BlockRegistry,
+        threads: usize,
+    ) -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<ChunkPosition>();
+        let (result_sender, results) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        let context = Arc::new((generator, atlas, registry));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let workers = (0..threads.max(1))
+            .filter_map(|i| {
+                let job_receiver = job_receiver.clone();
+                let result_sender = result_sender.clone();
+                let context = context.clone();
+                let stopped = stopped.clone();
+                thread::Builder::new()
+                    .name(format!("chunk loader {}", i))
+                    .spawn(move || {
+                        let (generator, atlas, registry) = &*context;
+                        loop {
+                            // The lock is only held while waiting, so other workers can take the
+                            // next job while this one generates.
+                            let Ok(position) = job_receiver.lock().unwrap().recv() else {
+                                break;
+                            };
+                            if stopped.load(Ordering::Relaxed) {
+                                break;
+                            }
+                            let blocks = generator.generate_blocks(position);
+                            let mesh = build_mesh_for_chunk(
+                                atlas,
+                                registry,
+                                &Chunk::from_blocks(blocks),
+                                position.chunk_origin(),
+                            );
+                            let loaded = LoadedChunk {
+                                position,
+                                blocks,
+                                mesh,
+                            };
+                            if result_sender.send(loaded).is_err() {
+                                break;
+                            }
+                        }
+                    })
+                    .inspect_err(|e| warn!("Failed to start chunk loader thread: {}", e))
+                    .ok()
+            })
+            .collect();
+
+        Self {
+            jobs: Some(jobs),
+            results,
+            workers,
+            stopped,
+            in_flight: HashSet::new(),
+        }
+    }
+
+    /// Queues the chunk at `position` to be loaded. Returns false if it's already queued, or if
+    /// there are no workers to load it, e.g. because none of their threads could be started.
+    pub fn submit(&mut self, position: ChunkPosition) -> bool {
+        if self.in_flight.contains(&position) {
+            return false;
+        }
+        let Some(jobs) = &self.jobs else {
+            return false;
+        };
+        // Sending only fails once every worker has dropped its end of the channel.
+        if jobs.send(position).is_err() {
+            warn!(
+                "Can't load chunk {:?}, the chunk loader has no workers",
+                position
+            );
+            return false;
+        }
+        self.in_flight.insert(position);
+        true
+    }
+
+    /// Returns true if the chunk at `position` is queued or loaded but not polled yet.
+    pub fn is_loading(&self, position: ChunkPosition) -> bool {
+        self.in_flight.contains(&position)
+    }
+
+    /// Returns the number of chunks queued or loaded but not polled yet.
+    pub fn pending(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Takes up to `limit` finished chunks, without waiting for more.
+    pub fn poll(&mut self, limit: usize) -> Vec<LoadedChunk> {
+        let loaded = self.results.try_iter().take(limit).collect::<Vec<_>>();
+        for chunk in &loaded {
+            self.in_flight.remove(&chunk.position);
+        }
+        loaded
+    }
+}
+
+impl Drop for ChunkLoader {
+    fn drop(&mut self) {
+        // Workers finish the chunk they're on, then stop at their next job or the closed channel.
+        self.stopped.store(true, Ordering::Relaxed);
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use engine::graphics::textures::TextureHandle;
+
+    use super::*;
+    use crate::{coords::bp, world::PerlinTerrainGenerator};
+
+    fn loader(threads: usize) -> ChunkLoader {
+        let mut atlas = BlockTextureAtlas::new();
+        for block in crate::world::block::BUILTIN_BLOCKS {
+            atlas.set_texture_handle(block, TextureHandle::new(0, 6));
+        }
+        ChunkLoader::with_threads(
+            PerlinTerrainGenerator::new(1),
+            atlas,
+            BlockRegistry::new(),
+            threads,
+        )
+    }
+
+    #[test]
+    fn submitted_chunks_all_arrive() {
+        let mut loader = loader(2);
+        let positions = (0..3)
+            .flat_map(|x| (0..3).map(move |y| bp(x, y, -1)))
+            .collect::<HashSet<_>>();
+        for &position in &positions {
+            assert!(loader.submit(position));
+        }
+        assert!(!loader.submit(bp(0, 0, -1)));
+        assert_eq!(loader.pending(), positions.len());
+
+        let mut arrived = HashSet::new();
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while arrived.len() < positions.len() {
+            assert!(
+                Instant::now() < deadline,
+                "only {} chunks arrived",
+                arrived.len()
+            );
+            for chunk in loader.poll(CHUNK_UPLOADS_PER_FRAME) {
+                assert!(arrived.insert(chunk.position));
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(arrived, positions);
+        assert_eq!(loader.pending(), 0);
+        assert!(!loader.is_loading(bp(0, 0, -1)));
+    }
+
+    #[test]
+    fn submitting_without_workers_fails() {
+        let mut loader = loader(1);
+        // Stop the only worker, as if its thread couldn't be started. It stops at its next job.
+        loader.stopped.store(true, Ordering::Relaxed);
+        loader.jobs.as_ref().unwrap().send(bp(0, 0, 0)).unwrap();
+        for worker in loader.workers.drain(..) {
+            worker.join().unwrap();
+        }
+        assert!(!loader.submit(bp(1, 0, 0)));
+        assert_eq!(loader.pending(), 0);
+    }
+}
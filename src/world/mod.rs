@@ -1,53 +1,163 @@
-use std::collections::HashMap;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet, VecDeque},
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
 
-use crate::{BlockPosition, coords::bp};
+use glam::Vec3;
+use log::warn;
 
-use engine::{component::ComponentStoreHandle, graphics::CardinalDirection, resource::Resource};
+use crate::{
+    BlockPosition, ChunkPosition,
+    coords::bp,
+    mesh::write_obj_object,
+    physics::{self, Aabb},
+    render::{block_textures::BlockTextureAtlas, pipelines::solid::build_mesh_for_chunk},
+};
+
+use engine::{
+    audio::Audio, component::ComponentStoreHandle, graphics::CardinalDirection, profile_span,
+    resource::Resource,
+};
 
 pub mod block;
+pub mod block_update;
 pub mod chunk;
+pub mod column;
+pub mod generation;
+pub mod history;
+pub mod light;
+pub mod loader;
+pub mod noise;
+pub mod random_tick;
+pub mod raycast;
+pub mod region;
+pub mod registry;
+pub mod schematic;
+pub mod streaming;
+pub mod structure;
+
+use chunk::CHUNK_SIZE;
+use light::SkyMask;
+
+pub use block::{Block, BlockId, BlockSound, Material};
+pub use block_update::{BlockUpdates, NeighborUpdate, PendingUpdates};
+pub use chunk::{Chunk, ChunkN};
+pub use column::{ChunkColumn, DEFAULT_COLUMN_HEIGHT};
+pub use generation::{PerlinTerrainGenerator, TerrainGenerator};
+pub use history::{BlockEdit, EditHistory};
+pub use loader::{ChunkLoader, LoadedChunk};
+pub use raycast::RaycastHit;
+pub use registry::{BlockProperties, BlockRegistry};
+pub use schematic::Schematic;
+pub use streaming::{StreamedChunks, StreamingWorld};
+pub use structure::{ReplacePolicy, Structure};
+
+/// How far from the camera the world is drawn, in chunks.
+pub const RENDER_DISTANCE: usize = 10;
 
-pub use block::Block;
-pub use chunk::Chunk;
+/// The built-in blocks, for worlds without a `BlockRegistry` component.
+static BUILTIN_REGISTRY: LazyLock<BlockRegistry> = LazyLock::new(BlockRegistry::new);
 
 pub struct World {
-    pub chunks: HashMap<BlockPosition, Resource<Chunk>>,
+    /// The loaded chunks, stacked into columns keyed by their chunk X and Z.
+    columns: HashMap<(i64, i64), ChunkColumn>,
+    /// The height new columns can grow to, in blocks.
+    column_height: usize,
+    revision: u64,
+    non_air_blocks: usize,
+    pending_updates: PendingUpdates,
+    /// Chunks whose geometry changed since the last `take_changed_chunks`, in the order they
+    /// changed.
+    changed_chunks: Vec<ChunkPosition>,
+    /// The chunks in `changed_chunks`, so recording a chunk twice is cheap to catch.
+    changed_set: HashSet<ChunkPosition>,
+    /// The directory the world was last saved to or loaded from, which only needs its changed
+    /// chunks rewritten.
+    save_dir: RefCell<Option<PathBuf>>,
+    state: ComponentStoreHandle,
 }
 
 impl World {
     /// Creates an empty World.
-    pub fn empty(_resource_state: &ComponentStoreHandle) -> Self {
+    pub fn empty(resource_state: &ComponentStoreHandle) -> Self {
         Self {
-            chunks: HashMap::new(),
+            columns: HashMap::new(),
+            column_height: DEFAULT_COLUMN_HEIGHT,
+            revision: 0,
+            non_air_blocks: 0,
+            pending_updates: PendingUpdates::new(),
+            changed_chunks: Vec::new(),
+            changed_set: HashSet::new(),
+            save_dir: RefCell::new(None),
+            state: resource_state.clone(),
         }
     }
 
     /// Creates a new World from the given chunks.
     pub fn new(
         chunks: Vec<((i64, i64, i64), Chunk)>,
-        _resource_state: &ComponentStoreHandle,
+        resource_state: &ComponentStoreHandle,
     ) -> Self {
-        Self {
-            chunks: chunks
-                .into_iter()
-                .map(|(k, v)| (k.into(), v.into()))
-                .collect(),
+        let mut world = Self::empty(resource_state);
+        for (position, chunk) in chunks {
+            world.push_chunk(position.into(), chunk);
+        }
+        world.relight_all();
+        world
+    }
+
+    /// Sets the height new columns can grow to, in blocks. It's rounded up to a whole number of
+    /// chunks.
+    pub fn with_column_height(mut self, height: usize) -> Self {
+        self.column_height = height;
+        self
+    }
+
+    /// Returns the height new columns can grow to, in blocks.
+    pub fn column_height(&self) -> usize {
+        self.column_height
+    }
+
+    /// Generates the columns within `radius` chunks of the origin on X and Z.
+    ///
+    /// Each column is generated from the bottom up and stops at the first chunk that is all air.
+    pub fn generate(
+        generator: &dyn TerrainGenerator,
+        radius: i64,
+        resource_state: &ComponentStoreHandle,
+    ) -> Self {
+        let _span = profile_span!("generate_world", radius = radius);
+        let mut world = Self::empty(resource_state);
+        let max_chunks = world.column_height.div_ceil(CHUNK_SIZE) as i64;
+        for x in -radius..=radius {
+            for z in -radius..=radius {
+                for y in 0..max_chunks {
+                    let position = bp(x, y, z);
+                    let chunk = generator.generate(position, resource_state);
+                    if chunk.non_air_count() == 0 {
+                        break;
+                    }
+                    world.push_chunk(position, chunk);
+                }
+            }
         }
+        world.populate_neighbors();
+        world.relight_all();
+        world
     }
 
     /// Creates a test world with some simple terrain.
     pub fn test(wgpu: &ComponentStoreHandle) -> Self {
+        let _span = profile_span!("generate_test_world");
         let mut world = Self::empty(wgpu);
         for x in 0..5 {
             for z in 0..5 {
                 let mut chunk = Chunk::empty(wgpu.clone());
-                for i in 0..16 {
-                    for j in 0..16 {
-                        if (i + j) % 2 == 0 {
-                            chunk.data[i][15][j] = Block::OakWood;
-                        } else {
-                            chunk.data[i][14][j] = Block::OakLeaves;
-                        }
+                for i in 0..CHUNK_SIZE {
+                    for j in 0..CHUNK_SIZE {
                         chunk.data[i][3][j] = Block::Grass;
                         chunk.data[i][2][j] = Block::Dirt;
                         chunk.data[i][1][j] = Block::Dirt;
@@ -60,6 +170,26 @@ impl World {
         }
 
         world.populate_neighbors();
+        world.relight_all();
+
+        // A small pond, so there's some water to look at.
+        for x in 36..44 {
+            for z in 36..44 {
+                for y in 17..20 {
+                    world.set_block(bp(x, y, z), Block::Water);
+                }
+            }
+        }
+
+        let tree = Structure::oak_tree(4);
+        for x in 0..5 {
+            for z in 0..5 {
+                // Scatter the trees a bit so they don't line up on the chunk grid.
+                let offset = bp((x * 7 + z * 3) % 10 + 3, 0, (x * 5 + z * 9) % 10 + 3);
+                let origin = bp(x, 0, z).chunk_origin() + bp(0, 20, 0) + offset;
+                world.place_structure(origin, &tree, ReplacePolicy::AirOnly);
+            }
+        }
 
         world
     }
@@ -71,23 +201,622 @@ impl World {
             chunk.data[8][8][8] = block;
             chunk
         };
-        let mut chunks = HashMap::new();
-        chunks.insert(bp(0, 0, 0), chunk.into());
-        Self { chunks }
+        let mut world = Self::empty(resource_state);
+        world.push_chunk(bp(0, 0, 0), chunk);
+        world.relight_all();
+        world
     }
 
-    /// Inserts a chunk at the given position.
+    /// Inserts a chunk at the given position, replacing any chunk already there.
+    ///
+    /// Air chunks are added below it if its column has gaps. Chunks outside the column height
+    /// are dropped with a warning. The chunk isn't linked to its neighbors or lit; see
+    /// `populate_neighbors` and `relight_all`.
     pub fn push_chunk(&mut self, position: BlockPosition, chunk: Chunk) {
-        self.chunks.insert(position, chunk.into());
+        let non_air = chunk.non_air_count();
+        let column = self
+            .columns
+            .entry((position.0, position.2))
+            .or_insert_with(|| ChunkColumn::new(self.column_height, &self.state));
+        match column.insert(position.1, chunk.into()) {
+            Ok(old) => {
+                self.non_air_blocks += non_air;
+                if let Some(old) = old {
+                    self.non_air_blocks -= old.get().non_air_count();
+                }
+                self.revision += 1;
+                column.mark_changed(self.revision);
+            }
+            Err(_) => {
+                warn!(
+                    "Dropping chunk {:?}, which is outside the world's height of {} blocks",
+                    position,
+                    column.max_height()
+                );
+                if column.is_empty() {
+                    self.columns.remove(&(position.0, position.2));
+                }
+            }
+        }
+    }
+
+    /// Inserts a chunk into a world that's already been linked and lit, e.g. one streamed in
+    /// after the world was created, replacing any chunk already there.
+    ///
+    /// Unlike `push_chunk`, the chunk is linked to its neighbors and lit along with them. Its
+    /// loaded neighbors are recorded as changed, as their border faces are culled against it; the
+    /// chunk itself isn't, as whoever inserts it is expected to supply its mesh, like the one a
+    /// `ChunkLoader` builds. Chunks outside the column height are dropped with a warning.
+    pub fn insert_chunk(&mut self, position: ChunkPosition, chunk: Chunk) {
+        self.allocate_chunks(position);
+        let Some(slot) = self.chunk(position).cloned() else {
+            warn!(
+                "Dropping chunk {:?}, which is outside the world's height of {} blocks",
+                position, self.column_height
+            );
+            return;
+        };
+        let non_air = chunk.non_air_count();
+        // Fill the allocated chunk in place, so its neighbor links are kept.
+        let mut slot = slot.get_mut();
+        self.non_air_blocks = self.non_air_blocks + non_air - slot.non_air_count();
+        slot.data = chunk.data;
+        slot.mark_dirty();
+        drop(slot);
+        self.bump_revision(position);
+
+        self.relight_around(position);
+        for neighbor in CardinalDirection::iter().map(|dir| position.offset(dir)) {
+            if self.contains_chunk(neighbor) {
+                self.mark_changed(neighbor);
+            }
+        }
+    }
+
+    /// Removes the column at chunk X and Z `x` and `z`, returning the positions of its chunks.
+    ///
+    /// The chunks are unlinked from their neighbors, which are recorded as changed, as their
+    /// border faces are no longer culled against them. Unsaved edits to the chunks are lost.
+    pub fn remove_column(&mut self, x: i64, z: i64) -> Vec<ChunkPosition> {
+        let Some(column) = self.columns.remove(&(x, z)) else {
+            return Vec::new();
+        };
+        let mut removed = Vec::new();
+        for (y, chunk) in column.chunks() {
+            let position = bp(x, y, z);
+            self.non_air_blocks -= chunk.get().non_air_count();
+            for dir in CardinalDirection::iter() {
+                // Links within the column are cleared too, or the chunks would keep each other
+                // alive.
+                chunk.get_mut().set_neighbor(dir, None);
+                let neighbor_pos = position.offset(dir);
+                if let Some(neighbor) = self.chunk(neighbor_pos) {
+                    neighbor.get_mut().set_neighbor(dir.opposite(), None);
+                    self.mark_changed(neighbor_pos);
+                }
+            }
+            removed.push(position);
+        }
+        self.revision += 1;
+        removed
+    }
+
+    /// Gets the chunk at the given chunk position, if it's loaded.
+    pub fn chunk(&self, position: ChunkPosition) -> Option<&Resource<Chunk>> {
+        self.columns
+            .get(&(position.0, position.2))?
+            .chunk(position.1)
+    }
+
+    /// Returns true if the chunk at the given chunk position is loaded.
+    pub fn contains_chunk(&self, position: ChunkPosition) -> bool {
+        self.chunk(position).is_some()
+    }
+
+    /// Iterates over every loaded chunk and its chunk position, in no particular order.
+    pub fn chunks(&self) -> impl Iterator<Item = (ChunkPosition, &Resource<Chunk>)> {
+        self.columns.iter().flat_map(|((x, z), column)| {
+            column
+                .chunks()
+                .map(move |(y, chunk)| (bp(*x, y, *z), chunk))
+        })
+    }
+
+    /// Iterates over the loaded columns, keyed by their chunk X and Z.
+    pub fn columns(&self) -> impl Iterator<Item = ((i64, i64), &ChunkColumn)> {
+        self.columns.iter().map(|(key, column)| (*key, column))
+    }
+
+    /// Gets the column at the given chunk X and Z, if any of it is loaded.
+    pub fn column(&self, x: i64, z: i64) -> Option<&ChunkColumn> {
+        self.columns.get(&(x, z))
+    }
+
+    /// Returns the number of loaded chunks.
+    pub fn chunk_count(&self) -> usize {
+        self.columns.values().map(ChunkColumn::len).sum()
+    }
+
+    /// Returns the inclusive minimum and maximum block positions covered by loaded chunks,
+    /// or `None` if no chunks are loaded.
+    pub fn loaded_bounds(&self) -> Option<(BlockPosition, BlockPosition)> {
+        let mut positions = self.chunks().map(|(position, _)| position);
+        let first = positions.next()?;
+        let (min, max) = positions.fold((first, first), |(min, max), p| {
+            (
+                bp(min.0.min(p.0), min.1.min(p.1), min.2.min(p.2)),
+                bp(max.0.max(p.0), max.1.max(p.1), max.2.max(p.2)),
+            )
+        });
+        Some((
+            min.chunk_origin(),
+            (max + bp(1, 1, 1)).chunk_origin() - bp(1, 1, 1),
+        ))
+    }
+
+    /// Returns the number of non-air blocks in the world.
+    ///
+    /// This is maintained incrementally by `push_chunk` and `set_block`, so edits made directly
+    /// to chunks aren't reflected.
+    pub fn total_non_air_blocks(&self) -> usize {
+        self.non_air_blocks
+    }
+
+    /// Returns a counter that changes whenever blocks or chunks are modified through the world.
+    ///
+    /// Useful for caches that only need rebuilding when the world changes.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Gets the block at the given world position. Unloaded chunks are treated as air.
+    pub fn get_block(&self, position: BlockPosition) -> Block {
+        match self.columns.get(&(
+            position.to_chunk_position().0,
+            position.to_chunk_position().2,
+        )) {
+            Some(column) => {
+                let local = position.chunk_normalize();
+                column.block_at(local.0 as usize, position.1, local.2 as usize)
+            }
+            None => Block::Air,
+        }
+    }
+
+    /// Collects the world space collision boxes of every block overlapping `region`.
+    pub fn collision_boxes(&self, region: Aabb) -> Vec<Aabb> {
+        region
+            .overlapping_blocks()
+            .flat_map(|position| {
+                let offset = Vec3::new(position.0 as f32, position.1 as f32, position.2 as f32);
+                self.get_block(position)
+                    .collision_boxes()
+                    .iter()
+                    .map(move |aabb| aabb.offset(offset))
+            })
+            .collect()
+    }
+
+    /// Moves `body` by `motion` through the world, stopping at block collision boxes.
+    ///
+    /// Returns the motion that was actually applied.
+    pub fn sweep_aabb(&self, body: Aabb, motion: Vec3) -> Vec3 {
+        let obstacles = self.collision_boxes(body.expand_towards(motion));
+        physics::sweep(body, motion, &obstacles)
+    }
+
+    /// Finds the first block along a ray that is drawn as a cube, within `max_distance`.
+    ///
+    /// Air and fluids are passed through, as they can't be targeted. The ray ends once it enters
+    /// a column that isn't loaded or leaves the height of the columns, and a ray starting inside
+    /// a block hits that block.
+    pub fn raycast(&self, origin: Vec3, direction: Vec3, max_distance: f32) -> Option<RaycastHit> {
+        // Chunks only exist within the column height, so the ray can't hit anything outside it.
+        let height = Aabb::new(
+            Vec3::new(f32::MIN, 0.0, f32::MIN),
+            Vec3::new(f32::MAX, self.column_height as f32, f32::MAX),
+        );
+        let (_, exit) = raycast::clip_to_box(origin, direction.normalize_or_zero(), height)?;
+
+        // Consecutive blocks along a ray are usually in the same chunk, so keep it around
+        // instead of looking it up for every block.
+        let mut current: Option<(ChunkPosition, Option<Resource<Chunk>>)> = None;
+        raycast::cast(origin, direction, max_distance.min(exit), |position| {
+            let chunk_pos = position.to_chunk_position();
+            if current
+                .as_ref()
+                .is_none_or(|(cached, _)| *cached != chunk_pos)
+            {
+                if self.column(chunk_pos.0, chunk_pos.2).is_none() {
+                    return ControlFlow::Break(None);
+                }
+                current = Some((chunk_pos, self.chunk(chunk_pos).cloned()));
+            }
+            let (_, chunk) = current.as_ref().expect("chunk was just cached");
+            // Chunks above the top of a column aren't allocated, and are all air.
+            let Some(chunk) = chunk else {
+                return ControlFlow::Continue(());
+            };
+            let block = chunk.get().inspect_block_exact(position.chunk_normalize());
+            if block.is_cube() {
+                ControlFlow::Break(Some(block))
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+    }
+
+    /// Sets the block at the given world position, returning the block that was replaced.
+    ///
+    /// If the containing chunk doesn't exist yet, its column is filled with empty chunks up to it,
+    /// which are linked to their neighbors. Blocks outside the column height can't be set, and
+    /// air is returned for them.
+    /// If the block changed, its six neighbors are notified on the next tick; see `BlockUpdates`.
+    /// The chunk and any neighboring chunks it borders are recorded as changed, so their meshes
+    /// can be rebuilt; see `take_changed_chunks`. If the block changes how light spreads, the
+    /// chunk and its neighbors are relit, and those whose light changed are recorded too.
+    pub fn set_block(&mut self, position: BlockPosition, block: Block) -> Block {
+        let chunk_pos = position.to_chunk_position();
+        let local = position.chunk_normalize();
+        if !self.contains_chunk(chunk_pos) && block != Block::Air {
+            self.allocate_chunks(chunk_pos);
+        }
+        let Some(old) = self
+            .columns
+            .get_mut(&(chunk_pos.0, chunk_pos.2))
+            .and_then(|column| {
+                column.set_block(position.1, local.0 as usize, local.2 as usize, block)
+            })
+        else {
+            return Block::Air;
+        };
+        if old != block {
+            self.bump_revision(chunk_pos);
+            self.pending_updates.notify_neighbors(position);
+            for chunk in Self::touched_chunks(position) {
+                if self.contains_chunk(chunk) {
+                    self.mark_changed(chunk);
+                }
+            }
+            let affects_light = self.with_registry(|registry| {
+                registry.is_opaque(old) != registry.is_opaque(block)
+                    || registry.light_emission(old) > 0
+                    || registry.light_emission(block) > 0
+            });
+            if affects_light {
+                for chunk in self.relight_around(chunk_pos) {
+                    self.mark_changed(chunk);
+                }
+            }
+        }
+        match (old == Block::Air, block == Block::Air) {
+            (true, false) => self.non_air_blocks += 1,
+            (false, true) => self.non_air_blocks -= 1,
+            _ => {}
+        }
+        old
+    }
+
+    /// Recomputes the light of the chunk at `position` from its blocks, the sky above it and its
+    /// neighbors' light, returning true if it changed. See `light::compute_light`.
+    ///
+    /// Nothing happens if the chunk isn't loaded.
+    pub fn relight_chunk(&self, position: ChunkPosition) -> bool {
+        let Some(chunk) = self.chunk(position) else {
+            return false;
+        };
+        self.with_registry(|registry| {
+            let sky = self.sky_mask(position, registry);
+            let light = light::compute_light(&chunk.get(), registry, &sky);
+            let mut chunk = chunk.get_mut();
+            let changed = chunk.light != light;
+            chunk.light = light;
+            changed
+        })
+    }
+
+    /// Recomputes the light of every loaded chunk, e.g. after generating or loading the world.
+    ///
+    /// Every chunk is lit twice, in a fixed order, so light crosses chunk borders and the result
+    /// doesn't depend on how the chunks are stored.
+    pub fn relight_all(&self) {
+        let _span = profile_span!("relight_world");
+        let mut columns = self.columns.iter().collect::<Vec<_>>();
+        columns.sort_by_key(|(key, _)| **key);
+        for (_, chunk) in self.chunks() {
+            chunk.get_mut().light = [[[0; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+        }
+        self.with_registry(|registry| {
+            for _ in 0..2 {
+                for (_, column) in &columns {
+                    let mut sky = [[true; CHUNK_SIZE]; CHUNK_SIZE];
+                    for (_, chunk) in column.chunks().rev() {
+                        let light = light::compute_light(&chunk.get(), registry, &sky);
+                        sky = light::sky_below(&chunk.get(), registry, &sky);
+                        chunk.get_mut().light = light;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Relights the chunk at `position` and its loaded neighbors after one of its blocks changed
+    /// how light spreads, returning every chunk whose light changed.
+    ///
+    /// Their light is cleared first, or light that spread out of the chunk would flow back into
+    /// it, e.g. after a light source is removed. The chunk is lit both before and after its
+    /// neighbors, so light crosses the borders both ways.
+    fn relight_around(&self, position: ChunkPosition) -> Vec<ChunkPosition> {
+        let chunks = std::iter::once(position)
+            .chain(CardinalDirection::iter().map(|dir| position.offset(dir)))
+            .filter_map(|position| Some((position, self.chunk(position)?.clone())))
+            .collect::<Vec<_>>();
+        let old = chunks
+            .iter()
+            .map(|(_, chunk)| {
+                std::mem::replace(
+                    &mut chunk.get_mut().light,
+                    [[[0; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE],
+                )
+            })
+            .collect::<Vec<_>>();
+        for (position, _) in &chunks {
+            self.relight_chunk(*position);
+        }
+        self.relight_chunk(position);
+        chunks
+            .into_iter()
+            .zip(old)
+            .filter(|((_, chunk), old)| chunk.get().light != *old)
+            .map(|((position, _), _)| position)
+            .collect()
+    }
+
+    /// Returns the columns of the chunk at `position` that the sky reaches the top of, through
+    /// the chunks above it.
+    fn sky_mask(&self, position: ChunkPosition, registry: &BlockRegistry) -> SkyMask<CHUNK_SIZE> {
+        let mut sky = [[true; CHUNK_SIZE]; CHUNK_SIZE];
+        let Some(column) = self.column(position.0, position.2) else {
+            return sky;
+        };
+        for (_, chunk) in column.chunks().rev().take_while(|(y, _)| *y > position.1) {
+            sky = light::sky_below(&chunk.get(), registry, &sky);
+        }
+        sky
+    }
+
+    /// Calls `f` with the `BlockRegistry` component, or the built-in blocks if there isn't one.
+    fn with_registry<R>(&self, f: impl FnOnce(&BlockRegistry) -> R) -> R {
+        match self.state.get_checked::<BlockRegistry>() {
+            Some(registry) => f(&registry),
+            None => f(&BUILTIN_REGISTRY),
+        }
+    }
+
+    /// Returns the chunks whose geometry depends on the block at `position`: the chunk containing
+    /// it, and every neighboring chunk whose border it lies on, as their faces are culled against
+    /// it.
+    pub fn touched_chunks(position: BlockPosition) -> Vec<ChunkPosition> {
+        let chunk = position.to_chunk_position();
+        let mut chunks = vec![chunk];
+        for dir in CardinalDirection::iter() {
+            let neighbor = position.offset(dir).to_chunk_position();
+            if neighbor != chunk {
+                chunks.push(neighbor);
+            }
+        }
+        chunks
+    }
+
+    /// Returns the chunks whose geometry changed since the last `take_changed_chunks`.
+    pub fn changed_chunks(&self) -> &[ChunkPosition] {
+        &self.changed_chunks
+    }
+
+    /// Returns and forgets the chunks whose geometry changed, e.g. to queue them for meshing.
+    pub fn take_changed_chunks(&mut self) -> Vec<ChunkPosition> {
+        self.changed_set.clear();
+        std::mem::take(&mut self.changed_chunks)
+    }
+
+    /// Records the chunk at `position` as changed, unless it already is.
+    fn mark_changed(&mut self, position: ChunkPosition) {
+        if self.changed_set.insert(position) {
+            self.changed_chunks.push(position);
+        }
+    }
+
+    /// Returns the neighbor updates waiting for the next tick.
+    pub fn pending_updates(&self) -> &PendingUpdates {
+        &self.pending_updates
+    }
+
+    /// Returns the neighbor updates waiting for the next tick, mutably.
+    pub fn pending_updates_mut(&mut self) -> &mut PendingUpdates {
+        &mut self.pending_updates
+    }
+
+    /// Breaks the block at the given position, replacing it with air and playing its break sound.
+    ///
+    /// Returns the block that was broken.
+    pub fn break_block(&mut self, position: BlockPosition) -> Block {
+        let old = self.set_block(position, Block::Air);
+        self.play_block_sound(position, old, BlockSound::Break);
+        old
+    }
+
+    /// Places a block at the given position and plays its place sound.
+    ///
+    /// Returns the block that was replaced.
+    pub fn place_block(&mut self, position: BlockPosition, block: Block) -> Block {
+        let old = self.set_block(position, block);
+        if old != block {
+            self.play_block_sound(position, block, BlockSound::Place);
+        }
+        old
+    }
+
+    /// Plays the step sound of the block at the given position, e.g. the block under a player.
+    pub fn play_step_sound(&self, position: BlockPosition) {
+        self.play_block_sound(position, self.get_block(position), BlockSound::Step);
+    }
+
+    /// Plays the sound `block` makes for `sound` at the center of the block at `position`.
+    ///
+    /// Nothing is played if there is no `Audio` component.
+    fn play_block_sound(&self, position: BlockPosition, block: Block, sound: BlockSound) {
+        let Some(sound_id) = block.material().sound_id(sound) else {
+            return;
+        };
+        if let Some(mut audio) = self.state.get_mut_checked::<Audio>() {
+            let center = Vec3::new(position.0 as f32, position.1 as f32, position.2 as f32)
+                + Vec3::splat(0.5);
+            audio.play(&sound_id, Some(center));
+        }
+    }
+
+    /// Stamps the given structure into the world, with the structure's anchor placed at `origin`.
+    ///
+    /// Structures can freely cross chunk boundaries; missing chunks are created as needed.
+    /// Returns every block that was actually changed.
+    pub fn place_structure(
+        &mut self,
+        origin: BlockPosition,
+        structure: &Structure,
+        policy: ReplacePolicy,
+    ) -> Vec<BlockEdit> {
+        let mut edits = Vec::new();
+        let base = origin - structure.anchor;
+        for (local, block) in structure.iter() {
+            let position = base + local;
+            match policy {
+                ReplacePolicy::AirOnly => {
+                    if block == Block::Air || self.get_block(position) != Block::Air {
+                        continue;
+                    }
+                }
+                ReplacePolicy::All => {}
+            }
+            let old = self.set_block(position, block);
+            if old != block && self.get_block(position) == block {
+                edits.push(BlockEdit {
+                    position,
+                    old,
+                    new: block,
+                });
+            }
+        }
+        edits
+    }
+
+    /// Fills the column of the given chunk position with empty chunks up to it, and links each new
+    /// chunk with its loaded neighbors.
+    ///
+    /// Nothing is created if the position is outside the column height.
+    fn allocate_chunks(&mut self, position: ChunkPosition) {
+        let column = self
+            .columns
+            .entry((position.0, position.2))
+            .or_insert_with(|| ChunkColumn::new(self.column_height, &self.state));
+        let created = column.allocate(position.1);
+        if column.is_empty() {
+            self.columns.remove(&(position.0, position.2));
+        }
+        for y in created {
+            let position = bp(position.0, y, position.2);
+            let chunk = self
+                .chunk(position)
+                .expect("chunk was just allocated")
+                .clone();
+            for dir in CardinalDirection::iter() {
+                if let Some(neighbor) = self.chunk(position.offset(dir)) {
+                    chunk.get_mut().set_neighbor(dir, Some(neighbor.clone()));
+                    neighbor
+                        .get_mut()
+                        .set_neighbor(dir.opposite(), Some(chunk.clone()));
+                }
+            }
+            self.relight_chunk(position);
+            self.bump_revision(position);
+        }
+    }
+
+    /// Counts a change to the world, recording it as the latest change of the chunk's column.
+    fn bump_revision(&mut self, chunk: ChunkPosition) {
+        self.revision += 1;
+        if let Some(column) = self.columns.get_mut(&(chunk.0, chunk.2)) {
+            column.mark_changed(self.revision);
+        }
+    }
+
+    /// Saves the world's chunks to region files in `dir`. See `region` for the layout.
+    ///
+    /// If the world was last saved to or loaded from `dir`, only chunks changed since then are
+    /// rewritten. Otherwise every chunk is written, replacing any region files already there.
+    pub fn save(&self, dir: &Path) -> anyhow::Result<()> {
+        let _span = profile_span!("save_world");
+        let full = self.save_dir.borrow().as_deref() != Some(dir);
+        region::save_world(self, dir, full)?;
+        *self.save_dir.borrow_mut() = Some(dir.to_path_buf());
+        Ok(())
+    }
+
+    /// Loads a world saved with `save` from `dir`.
+    ///
+    /// Regions with no region file, or a missing `dir`, are left empty rather than failing.
+    pub fn load(dir: &Path, state: &ComponentStoreHandle) -> anyhow::Result<World> {
+        let _span = profile_span!("load_world");
+        let world = region::load_world(dir, state)?;
+        *world.save_dir.borrow_mut() = Some(dir.to_path_buf());
+        Ok(world)
     }
 
-    /// Populates neighbor references for all chunks in the world.
+    /// Meshes every chunk and exports the geometry as a Wavefront OBJ, with one object per chunk,
+    /// for inspecting it outside the engine.
+    ///
+    /// Transparent faces are exported as a separate object following their chunk's.
+    pub fn export_obj(&self, atlas: &BlockTextureAtlas, registry: &BlockRegistry) -> String {
+        let mut chunks = self.chunks().collect::<Vec<_>>();
+        chunks.sort_by_key(|(position, _)| (position.0, position.1, position.2));
+
+        let mut obj = String::new();
+        let mut vertex_count = 0;
+        for (position, chunk) in chunks {
+            let mesh = build_mesh_for_chunk(atlas, registry, &chunk.get(), position.chunk_origin());
+            let name = format!("chunk_{}_{}_{}", position.0, position.1, position.2);
+            for (name, vertices, indices) in [
+                (name.clone(), &mesh.vertices, &mesh.indices),
+                (
+                    format!("{}_transparent", name),
+                    &mesh.transparent_vertices,
+                    &mesh.transparent_indices,
+                ),
+            ] {
+                if indices.is_empty() {
+                    continue;
+                }
+                vertex_count += write_obj_object(
+                    &mut obj,
+                    &name,
+                    vertices
+                        .iter()
+                        .map(|vertex| (vertex.position.to_array(), vertex.tex_coord.to_array())),
+                    indices,
+                    vertex_count,
+                );
+            }
+        }
+        obj
+    }
+
+    /// Populates neighbor references for all chunks in the world, both within and across
+    /// columns.
     /// TODO: populate_neighbors(pos: ChunkPosition)
     pub fn populate_neighbors(&mut self) {
-        for (pos, chunk) in self.chunks.iter() {
+        for (pos, chunk) in self.chunks() {
             CardinalDirection::iter().for_each(|dir| {
                 let neighbor_pos = pos.offset(dir);
-                if let Some(neighbor) = self.chunks.get(&neighbor_pos) {
+                if let Some(neighbor) = self.chunk(neighbor_pos) {
                     chunk.get_mut().set_neighbor(dir, Some(neighbor.clone()));
                 }
             });
@@ -95,25 +824,111 @@ impl World {
     }
 }
 
+impl Drop for World {
+    fn drop(&mut self) {
+        // Neighboring chunks hold each other, so the links have to be broken for them to be
+        // freed, e.g. when `ActiveWorld::set_world` replaces the world.
+        for (_, chunk) in self.chunks() {
+            for dir in CardinalDirection::iter() {
+                chunk.get_mut().set_neighbor(dir, None);
+            }
+        }
+    }
+}
+
 /// The currently active world.
 pub struct ActiveWorld {
     world: Option<World>,
+    generation: u64,
+    /// Chunks whose meshes are out of date, oldest first.
+    dirty_chunks: VecDeque<BlockPosition>,
+    /// The chunks in `dirty_chunks`, for quick lookups.
+    dirty_set: HashSet<BlockPosition>,
+    /// Chunks whose fluid meshes are out of date. Filled alongside `dirty_chunks`, but taken all
+    /// at once since fluid meshes are cheap to build.
+    fluid_dirty: HashSet<BlockPosition>,
 }
 
 impl ActiveWorld {
     /// Creates a new, empty ActiveWorld.
     pub fn new() -> Self {
-        Self { world: None }
+        Self {
+            world: None,
+            generation: 0,
+            dirty_chunks: VecDeque::new(),
+            dirty_set: HashSet::new(),
+            fluid_dirty: HashSet::new(),
+        }
     }
 
     /// Creates a new ActiveWorld with the given world.
     pub fn with_world(world: World) -> Self {
-        Self { world: Some(world) }
+        Self {
+            world: Some(world),
+            generation: 0,
+            dirty_chunks: VecDeque::new(),
+            dirty_set: HashSet::new(),
+            fluid_dirty: HashSet::new(),
+        }
     }
 
     /// Sets the active world.
     pub fn set_world(&mut self, world: World) {
         self.world = Some(world);
+        self.generation += 1;
+        // The new world is meshed from scratch.
+        self.dirty_chunks.clear();
+        self.dirty_set.clear();
+        self.fluid_dirty.clear();
+    }
+
+    /// Moves the chunks changed by `World::set_block` since the last call onto the dirty queue.
+    fn collect_dirty_chunks(&mut self) {
+        let Some(world) = self.world.as_mut() else {
+            return;
+        };
+        for chunk in world.take_changed_chunks() {
+            self.fluid_dirty.insert(chunk);
+            if self.dirty_set.insert(chunk) {
+                self.dirty_chunks.push_back(chunk);
+            }
+        }
+    }
+
+    /// Returns the number of chunks waiting to be re-meshed.
+    pub fn dirty_chunk_count(&mut self) -> usize {
+        self.collect_dirty_chunks();
+        self.dirty_chunks.len()
+    }
+
+    /// Returns true if the chunk is waiting on the dirty queue. Edits since the last
+    /// `pop_dirty_chunks` or `dirty_chunk_count` aren't included.
+    pub fn is_chunk_dirty(&self, chunk: BlockPosition) -> bool {
+        self.dirty_set.contains(&chunk)
+    }
+
+    /// Takes up to `limit` chunks off the dirty queue, oldest first, for re-meshing.
+    pub fn pop_dirty_chunks(&mut self, limit: usize) -> Vec<BlockPosition> {
+        self.collect_dirty_chunks();
+        let count = limit.min(self.dirty_chunks.len());
+        let chunks = self.dirty_chunks.drain(..count).collect::<Vec<_>>();
+        for chunk in &chunks {
+            self.dirty_set.remove(chunk);
+        }
+        chunks
+    }
+
+    /// Takes every chunk whose fluid mesh is out of date. This is separate from the dirty queue,
+    /// so taking these doesn't hold back solid re-meshing or the other way around.
+    pub fn take_fluid_dirty_chunks(&mut self) -> Vec<BlockPosition> {
+        self.collect_dirty_chunks();
+        self.fluid_dirty.drain().collect()
+    }
+
+    /// Returns a number that changes whenever the active world is replaced, so users caching
+    /// data about the world know to rebuild it.
+    pub fn generation(&self) -> u64 {
+        self.generation
     }
 
     /// Gets a reference to the active world.
@@ -126,3 +941,293 @@ impl ActiveWorld {
         self.world.as_mut()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use engine::{
+        audio::{PlayedSound, RecordingAudio},
+        component::ComponentStore,
+        graphics::textures::TextureHandle,
+    };
+
+    use super::*;
+
+    fn empty_world(store: &ComponentStore) -> World {
+        store.finish_initialization();
+        World::empty(&store.handle())
+    }
+
+    #[test]
+    fn neighbors_are_linked_by_populate_neighbors() {
+        let store = ComponentStore::new();
+        let mut world = empty_world(&store);
+        for x in 0..3 {
+            for y in 0..3 {
+                for z in 0..3 {
+                    world.push_chunk(bp(x, y, z), Chunk::empty(store.handle()));
+                }
+            }
+        }
+        world.populate_neighbors();
+
+        let center = world.chunk(bp(1, 1, 1)).unwrap().get();
+        assert!(center.has_all_neighbors());
+        for direction in CardinalDirection::iter() {
+            let neighbor = center.neighbor(direction).unwrap();
+            let expected = world.chunk(bp(1, 1, 1).offset(direction)).unwrap();
+            assert!(std::rc::Rc::ptr_eq(&neighbor.inner, &expected.inner));
+        }
+
+        // A corner chunk is missing the neighbors outside the loaded area.
+        let corner = world.chunk(bp(0, 0, 0)).unwrap().get();
+        assert!(!corner.has_all_neighbors());
+        assert!(corner.neighbor(CardinalDirection::West).is_none());
+        assert!(corner.neighbor(CardinalDirection::East).is_some());
+    }
+
+    #[test]
+    fn replacing_the_world_frees_its_chunks() {
+        let store = ComponentStore::new();
+        let mut world = empty_world(&store);
+        for x in 0..2 {
+            world.set_block(bp(x * CHUNK_SIZE as i64, 20, 0), Block::Stone);
+        }
+        let chunk = std::rc::Rc::downgrade(&world.chunk(bp(0, 0, 0)).unwrap().inner);
+        assert!(
+            world
+                .chunk(bp(0, 0, 0))
+                .unwrap()
+                .get()
+                .neighbor(CardinalDirection::Up)
+                .is_some()
+        );
+
+        let mut active_world = ActiveWorld::with_world(world);
+        active_world.set_world(empty_world(&store));
+        assert!(chunk.upgrade().is_none());
+    }
+
+    #[test]
+    fn raycast_ends_outside_loaded_columns() {
+        let store = ComponentStore::new();
+        let mut world = empty_world(&store);
+        world.set_block(bp(8, 0, 8), Block::Stone);
+        world.set_block(bp(40, 0, 8), Block::Stone);
+        world.remove_column(2, 0);
+
+        let hit = world.raycast(Vec3::new(8.5, 5.5, 8.5), Vec3::NEG_Y, 100.0);
+        assert_eq!(hit.map(|hit| hit.position), Some(bp(8, 0, 8)));
+        // The stone in the removed column is gone with it.
+        assert!(
+            world
+                .raycast(Vec3::new(40.5, 5.5, 8.5), Vec3::NEG_Y, 100.0)
+                .is_none()
+        );
+        // Heading towards it stops at the edge of the loaded column.
+        assert!(
+            world
+                .raycast(Vec3::new(9.5, 0.5, 8.5), Vec3::X, 100.0)
+                .is_none()
+        );
+        assert!(
+            world
+                .raycast(Vec3::new(8.5, 5.5, 8.5), Vec3::Y, 1.0e6)
+                .is_none()
+        );
+    }
+
+    /// Parses an OBJ back into its vertex count and triangles.
+    fn parse_obj(obj: &str) -> (usize, Vec<[usize; 3]>) {
+        let vertices = obj.lines().filter(|line| line.starts_with("v ")).count();
+        let faces = obj
+            .lines()
+            .filter_map(|line| line.strip_prefix("f "))
+            .map(|face| {
+                let mut corners = face.split(' ').map(|corner| {
+                    let (position, tex_coord) = corner.split_once('/').unwrap();
+                    assert_eq!(position, tex_coord);
+                    position.parse::<usize>().unwrap()
+                });
+                [(); 3].map(|_| corners.next().unwrap())
+            })
+            .collect();
+        (vertices, faces)
+    }
+
+    #[test]
+    fn single_block_exports_a_cube() {
+        let store = ComponentStore::new();
+        let mut world = empty_world(&store);
+        let mut atlas = BlockTextureAtlas::new();
+        atlas.set_texture_handle(Block::Stone, TextureHandle::new(0, 6));
+        let registry = BlockRegistry::new();
+
+        world.set_block(bp(3, 3, 3), Block::Stone);
+        let obj = world.export_obj(&atlas, &registry);
+        assert_eq!(
+            obj.lines().filter(|line| line.starts_with("vt ")).count(),
+            24
+        );
+        let (vertices, faces) = parse_obj(&obj);
+        assert_eq!(vertices, 24);
+        assert_eq!(faces.len(), 12);
+        assert!(faces.iter().flatten().all(|&i| (1..=24).contains(&i)));
+
+        // The second chunk's faces point past the first chunk's vertices.
+        world.set_block(bp(CHUNK_SIZE as i64 + 3, 3, 3), Block::Stone);
+        let (vertices, faces) = parse_obj(&world.export_obj(&atlas, &registry));
+        assert_eq!(vertices, 48);
+        assert_eq!(faces.len(), 24);
+        assert!(faces[..12].iter().flatten().all(|&i| (1..=24).contains(&i)));
+        assert!(
+            faces[12..]
+                .iter()
+                .flatten()
+                .all(|&i| (25..=48).contains(&i))
+        );
+    }
+
+    #[test]
+    fn setting_on_a_border_changes_both_chunks() {
+        let store = ComponentStore::new();
+        let mut world = empty_world(&store);
+        world.set_block(bp(0, 0, 0), Block::Stone);
+        world.set_block(bp(CHUNK_SIZE as i64, 0, 0), Block::Stone);
+        world.take_changed_chunks();
+
+        let edge = bp(CHUNK_SIZE as i64 - 1, 5, 3);
+        assert_eq!(world.set_block(edge, Block::Dirt), Block::Air);
+        assert_eq!(world.get_block(edge), Block::Dirt);
+        let changed = world.take_changed_chunks();
+        assert!(changed.contains(&bp(0, 0, 0)), "{:?}", changed);
+        assert!(changed.contains(&bp(1, 0, 0)), "{:?}", changed);
+
+        // Replacing it returns the old block.
+        assert_eq!(world.set_block(edge, Block::Grass), Block::Dirt);
+        assert!(world.take_changed_chunks().contains(&bp(1, 0, 0)));
+
+        // Inside the chunk, the neighbor is left alone.
+        world.set_block(bp(5, 5, 3), Block::Dirt);
+        assert!(!world.take_changed_chunks().contains(&bp(1, 0, 0)));
+    }
+
+    #[test]
+    fn placing_into_a_missing_chunk_creates_it() {
+        let store = ComponentStore::new();
+        let mut world = empty_world(&store);
+        let position = bp(3, 2 * CHUNK_SIZE as i64 + 4, -5);
+        assert!(!world.contains_chunk(position.to_chunk_position()));
+
+        // Removing a block from a missing chunk doesn't create it.
+        assert_eq!(world.set_block(position, Block::Air), Block::Air);
+        assert_eq!(world.chunk_count(), 0);
+
+        assert_eq!(world.set_block(position, Block::Stone), Block::Air);
+        assert_eq!(world.get_block(position), Block::Stone);
+        // The column is filled up to the new chunk.
+        assert_eq!(world.chunk_count(), 3);
+        assert!(world.take_changed_chunks().contains(&bp(0, 2, -1)));
+        assert_eq!(world.total_non_air_blocks(), 1);
+    }
+
+    #[test]
+    fn edits_dirty_only_the_chunks_they_touch() {
+        let store = ComponentStore::new();
+        let mut world = empty_world(&store);
+        for x in 0..3 {
+            world.set_block(bp(x * CHUNK_SIZE as i64 + 5, 0, 5), Block::Stone);
+        }
+        let mut active_world = ActiveWorld::with_world(world);
+        active_world.pop_dirty_chunks(usize::MAX);
+        let world = active_world.get_world_mut().unwrap();
+
+        // Stone to dirt doesn't change the light, so only the block's own chunk is dirtied.
+        world.set_block(bp(5, 0, 5), Block::Dirt);
+        world.set_block(bp(5, 0, 5), Block::Stone);
+        assert_eq!(active_world.dirty_chunk_count(), 1);
+        assert!(active_world.is_chunk_dirty(bp(0, 0, 0)));
+        assert_eq!(active_world.pop_dirty_chunks(usize::MAX), vec![bp(0, 0, 0)]);
+        assert!(!active_world.is_chunk_dirty(bp(0, 0, 0)));
+
+        // On the border, the neighbor across it is dirtied too, but not the chunk past that.
+        let world = active_world.get_world_mut().unwrap();
+        world.set_block(bp(CHUNK_SIZE as i64 - 1, 0, 5), Block::Stone);
+        let mut dirty = active_world.pop_dirty_chunks(usize::MAX);
+        dirty.sort_by_key(|position| position.0);
+        assert_eq!(dirty, vec![bp(0, 0, 0), bp(1, 0, 0)]);
+        assert_eq!(active_world.dirty_chunk_count(), 0);
+    }
+
+    #[test]
+    fn inserted_chunks_only_queue_their_neighbors() {
+        let store = ComponentStore::new();
+        let mut world = empty_world(&store);
+        let stone = [[[Block::Stone; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+        world.insert_chunk(bp(0, 0, 0), Chunk::from_blocks(stone));
+        assert!(world.take_changed_chunks().is_empty());
+
+        world.insert_chunk(bp(1, 0, 0), Chunk::from_blocks(stone));
+        assert_eq!(world.take_changed_chunks(), vec![bp(0, 0, 0)]);
+        let chunk = world.chunk(bp(1, 0, 0)).unwrap().get();
+        assert!(chunk.neighbor(CardinalDirection::West).is_some());
+    }
+
+    #[test]
+    fn counts_follow_edits() {
+        let store = ComponentStore::new();
+        let mut world = empty_world(&store);
+        assert_eq!(world.chunk_count(), 0);
+        assert_eq!(world.loaded_bounds(), None);
+        assert_eq!(world.total_non_air_blocks(), 0);
+
+        // Setting a block in the second chunk of a column allocates the one below it too.
+        world.set_block(bp(0, 20, 0), Block::Stone);
+        assert_eq!(world.chunk_count(), 2);
+        assert_eq!(world.total_non_air_blocks(), 1);
+        assert_eq!(world.loaded_bounds(), Some((bp(0, 0, 0), bp(15, 31, 15))));
+
+        world.set_block(bp(-1, 0, 0), Block::Dirt);
+        assert_eq!(world.chunk_count(), 3);
+        assert_eq!(world.total_non_air_blocks(), 2);
+        assert_eq!(world.loaded_bounds(), Some((bp(-16, 0, 0), bp(15, 31, 15))));
+
+        // Replacing a block keeps the count, removing one lowers it.
+        world.set_block(bp(0, 20, 0), Block::Dirt);
+        assert_eq!(world.total_non_air_blocks(), 2);
+        world.set_block(bp(0, 20, 0), Block::Air);
+        world.set_block(bp(5, 5, 5), Block::Air);
+        assert_eq!(world.total_non_air_blocks(), 1);
+        assert_eq!(world.chunk_count(), 3);
+    }
+
+    #[test]
+    fn break_and_place_play_sounds() {
+        let mut store = ComponentStore::new();
+        let recording = RecordingAudio::new();
+        store.insert(Audio::new(recording.clone()));
+        let mut world = empty_world(&store);
+
+        world.place_block(bp(1, 2, 3), Block::Stone);
+        // Placing the same block again changes nothing, so it's silent.
+        world.place_block(bp(1, 2, 3), Block::Stone);
+        world.play_step_sound(bp(1, 2, 3));
+        world.break_block(bp(1, 2, 3));
+        // There's nothing left to break or step on.
+        world.break_block(bp(1, 2, 3));
+        world.play_step_sound(bp(1, 2, 3));
+
+        let center = Some(Vec3::new(1.5, 2.5, 3.5));
+        let played = |sound: &str| PlayedSound {
+            sound: sound.into(),
+            position: center,
+        };
+        assert_eq!(
+            recording.played(),
+            [
+                played("block.stone.place"),
+                played("block.stone.step"),
+                played("block.stone.break"),
+            ]
+        );
+    }
+}
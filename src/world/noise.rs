@@ -0,0 +1,116 @@
+//! Seeded noise and hashing for terrain generation.
+//!
+//! Everything here is a pure function of the seed and the input coordinates, so the same seed
+//! generates the same world on every run.
+
+/// Advances `state` and returns the next value of a SplitMix64 sequence.
+pub fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Hashes a seed and a column position into a well mixed 64 bit value.
+pub fn hash2(seed: u64, x: i64, z: i64) -> u64 {
+    let mut state = seed ^ (x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    splitmix64(&mut state);
+    state ^= (z as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+    splitmix64(&mut state)
+}
+
+/// The gradients noise values are interpolated from, the 12 edges of a cube projected to 2D.
+const GRADIENTS: [(f64, f64); 12] = [
+    (1.0, 1.0),
+    (-1.0, 1.0),
+    (1.0, -1.0),
+    (-1.0, -1.0),
+    (1.0, 0.0),
+    (-1.0, 0.0),
+    (1.0, 0.0),
+    (-1.0, 0.0),
+    (0.0, 1.0),
+    (0.0, -1.0),
+    (0.0, 1.0),
+    (0.0, -1.0),
+];
+
+/// Seeded 2D simplex noise, after Stefan Gustavson's reference implementation.
+#[derive(Debug, Clone)]
+pub struct SimplexNoise {
+    /// A shuffled permutation of 0..256, repeated twice so lookups don't have to wrap.
+    permutation: [u8; 512],
+}
+
+impl SimplexNoise {
+    /// Creates noise whose values are determined by `seed`.
+    pub fn new(seed: u64) -> Self {
+        let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+        let mut state = seed;
+        for i in (1..table.len()).rev() {
+            let j = (splitmix64(&mut state) % (i as u64 + 1)) as usize;
+            table.swap(i, j);
+        }
+        Self {
+            permutation: std::array::from_fn(|i| table[i & 255]),
+        }
+    }
+
+    fn gradient(&self, i: i64, j: i64) -> (f64, f64) {
+        let i = (i & 255) as usize;
+        let j = (j & 255) as usize;
+        GRADIENTS[self.permutation[i + self.permutation[j] as usize] as usize % GRADIENTS.len()]
+    }
+
+    /// Samples the noise at the given point. Returns a value from -1.0 to 1.0.
+    pub fn sample(&self, x: f64, y: f64) -> f64 {
+        let skew = 0.5 * (3f64.sqrt() - 1.0);
+        let unskew = (3.0 - 3f64.sqrt()) / 6.0;
+
+        // Find the simplex cell containing the point, and the point's offset from its corners.
+        let s = (x + y) * skew;
+        let i = (x + s).floor();
+        let j = (y + s).floor();
+        let t = (i + j) * unskew;
+        let x0 = x - (i - t);
+        let y0 = y - (j - t);
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+        let corners = [
+            (x0, y0, 0, 0),
+            (x0 - i1 as f64 + unskew, y0 - j1 as f64 + unskew, i1, j1),
+            (x0 - 1.0 + 2.0 * unskew, y0 - 1.0 + 2.0 * unskew, 1, 1),
+        ];
+
+        let (i, j) = (i as i64, j as i64);
+        let total = corners
+            .into_iter()
+            .map(|(dx, dy, ci, cj)| {
+                let falloff = 0.5 - dx * dx - dy * dy;
+                if falloff < 0.0 {
+                    return 0.0;
+                }
+                let (gx, gy) = self.gradient(i + ci, j + cj);
+                falloff.powi(4) * (gx * dx + gy * dy)
+            })
+            .sum::<f64>();
+        // Scales the result to roughly -1.0 to 1.0.
+        70.0 * total
+    }
+
+    /// Sums `octaves` layers of noise, each at twice the frequency and half the amplitude of the
+    /// last. Returns a value from -1.0 to 1.0.
+    pub fn fractal(&self, x: f64, y: f64, octaves: u32) -> f64 {
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut max = 0.0;
+        for _ in 0..octaves.max(1) {
+            total += self.sample(x * frequency, y * frequency) * amplitude;
+            max += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        total / max
+    }
+}
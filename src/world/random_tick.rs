@@ -0,0 +1,103 @@
+use engine::{component::ComponentStoreHandle, graphics::CardinalDirection, tick::System};
+
+use crate::{
+    BlockPosition,
+    coords::bp,
+    world::{ActiveWorld, Block, World, chunk::CHUNK_SIZE},
+};
+
+/// How many random blocks in each chunk are ticked every tick, the same as Minecraft's default.
+pub const RANDOM_TICKS_PER_CHUNK: u32 = 3;
+
+/// Called with each randomly picked block and its position.
+type RandomTickHandler = dyn FnMut(&mut World, BlockPosition, Block);
+
+/// Ticks a few random blocks in every loaded chunk each tick.
+///
+/// Slow, ambient changes like grass spreading or crops growing are driven by this, rather than by
+/// scanning every block. `handler` is called with each picked block, including air, and may
+/// change the world however it likes.
+pub struct RandomTicks {
+    per_chunk: u32,
+    seed: u32,
+    handler: Box<RandomTickHandler>,
+}
+
+impl RandomTicks {
+    /// Creates random ticks calling `handler` for `RANDOM_TICKS_PER_CHUNK` blocks per chunk.
+    pub fn new(handler: impl FnMut(&mut World, BlockPosition, Block) + 'static) -> Self {
+        Self {
+            per_chunk: RANDOM_TICKS_PER_CHUNK,
+            seed: 0x2545_F491,
+            handler: Box::new(handler),
+        }
+    }
+
+    /// Sets how many blocks in each chunk are ticked every tick.
+    pub fn with_per_chunk(mut self, per_chunk: u32) -> Self {
+        self.per_chunk = per_chunk;
+        self
+    }
+
+    /// Returns how many blocks in each chunk are ticked every tick.
+    pub fn per_chunk(&self) -> u32 {
+        self.per_chunk
+    }
+
+    /// Returns a pseudo random block coordinate within a chunk.
+    fn random_coordinate(&mut self) -> i64 {
+        // xorshift32, plenty for picking blocks.
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed % CHUNK_SIZE as u32) as i64
+    }
+
+    /// Ticks `per_chunk` random blocks in every chunk of `world`. Returns how many blocks were
+    /// ticked.
+    pub fn tick_world(&mut self, world: &mut World) -> usize {
+        let chunks = world
+            .chunks()
+            .map(|(position, _)| position)
+            .collect::<Vec<_>>();
+        let mut ticked = 0;
+        for chunk in chunks {
+            let origin = chunk.chunk_origin();
+            for _ in 0..self.per_chunk {
+                let position = bp(
+                    origin.0 + self.random_coordinate(),
+                    origin.1 + self.random_coordinate(),
+                    origin.2 + self.random_coordinate(),
+                );
+                let block = world.get_block(position);
+                (self.handler)(world, position, block);
+                ticked += 1;
+            }
+        }
+        ticked
+    }
+}
+
+impl System for RandomTicks {
+    fn label(&self) -> Option<&str> {
+        Some("Random Block Ticks")
+    }
+
+    fn tick(&mut self, state: &ComponentStoreHandle, _tick: u64) {
+        let mut active_world = state.get_mut::<ActiveWorld>();
+        if let Some(world) = active_world.get_world_mut() {
+            self.tick_world(world);
+        }
+    }
+}
+
+/// A random tick handler turning grass covered by an opaque block back into dirt.
+pub fn decay_covered_grass(world: &mut World, position: BlockPosition, block: Block) {
+    if block == Block::Grass
+        && world
+            .get_block(position.offset(CardinalDirection::Up))
+            .is_opaque()
+    {
+        world.set_block(position, Block::Dirt);
+    }
+}
@@ -0,0 +1,113 @@
+use std::ops::ControlFlow;
+
+use engine::graphics::CardinalDirection;
+use glam::Vec3;
+
+use crate::{BlockPosition, coords::bp, physics::Aabb, world::Block};
+
+/// The first block a ray hit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaycastHit {
+    /// The position of the block that was hit.
+    pub position: BlockPosition,
+    /// The block that was hit.
+    pub block: Block,
+    /// The face of the block the ray entered through.
+    pub face: CardinalDirection,
+    /// How far along the ray the block was entered.
+    pub distance: f32,
+    /// The empty block the ray passed through right before the hit, against `face`. This is
+    /// where a block placed on the hit face goes.
+    ///
+    /// `None` if the ray started inside the block it hit, since it passed through nothing.
+    pub adjacent: Option<BlockPosition>,
+}
+
+/// Walks the blocks along a ray with a voxel DDA (Amanatides & Woo), returning the first block
+/// `hit` accepts within `max_distance`.
+///
+/// `hit` is called with every block position the ray passes through, in order. It breaks with the
+/// block there if the ray hit it, or with `None` if the ray should end without hitting anything,
+/// e.g. because it left the loaded world. A ray starting inside a block `hit` accepts stops there
+/// straight away, at distance 0.
+pub fn cast(
+    origin: Vec3,
+    direction: Vec3,
+    max_distance: f32,
+    mut hit: impl FnMut(BlockPosition) -> ControlFlow<Option<Block>>,
+) -> Option<RaycastHit> {
+    let direction = direction.normalize_or_zero();
+    if direction == Vec3::ZERO {
+        return None;
+    }
+
+    let start = origin.floor();
+    let mut position = bp(start.x as i64, start.y as i64, start.z as i64);
+    let step = direction.signum();
+    // How far along the ray one block is on each axis, and how far the next block boundary is.
+    let t_delta = direction.recip().abs();
+    let mut t_max = Vec3::select(
+        direction.cmpgt(Vec3::ZERO),
+        (start + Vec3::ONE - origin) * t_delta,
+        (origin - start) * t_delta,
+    );
+    // A ray that doesn't move along an axis never crosses a boundary on it.
+    t_max = Vec3::select(direction.cmpeq(Vec3::ZERO), Vec3::INFINITY, t_max);
+
+    // The ray starts inside this block, so it entered against its main direction.
+    let mut face = entry_face(direction.abs().max_position(), step);
+    let mut distance = 0.0;
+    let mut previous = None;
+    loop {
+        if let ControlFlow::Break(block) = hit(position) {
+            return block.map(|block| RaycastHit {
+                position,
+                block,
+                face,
+                distance,
+                adjacent: previous,
+            });
+        }
+        previous = Some(position);
+
+        let axis = t_max.min_position();
+        distance = t_max[axis];
+        if distance > max_distance {
+            return None;
+        }
+        t_max[axis] += t_delta[axis];
+        match axis {
+            0 => position.0 += step.x as i64,
+            1 => position.1 += step.y as i64,
+            _ => position.2 += step.z as i64,
+        }
+        face = entry_face(axis, step);
+    }
+}
+
+/// Returns the face a ray stepping along `axis` enters a block through.
+fn entry_face(axis: usize, step: Vec3) -> CardinalDirection {
+    match (axis, step[axis] > 0.0) {
+        (0, true) => CardinalDirection::West,
+        (0, false) => CardinalDirection::East,
+        (1, true) => CardinalDirection::Down,
+        (1, false) => CardinalDirection::Up,
+        (_, true) => CardinalDirection::North,
+        (_, false) => CardinalDirection::South,
+    }
+}
+
+/// Returns the distances along a ray at which it enters and leaves `aabb`, or `None` if it
+/// misses. The entry distance is 0 if the ray starts inside the box.
+///
+/// `direction` must be normalized for the distances to be in blocks.
+pub fn clip_to_box(origin: Vec3, direction: Vec3, aabb: Aabb) -> Option<(f32, f32)> {
+    let inverse = direction.recip();
+    let t0 = (aabb.min - origin) * inverse;
+    let t1 = (aabb.max - origin) * inverse;
+    // Axes the ray doesn't move along give NaN when the origin is on a slab boundary; those are
+    // ignored by `min_element` and `max_element`, which skip NaN.
+    let enter = t0.min(t1).max_element().max(0.0);
+    let exit = t0.max(t1).min_element();
+    (enter <= exit).then_some((enter, exit))
+}
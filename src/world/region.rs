@@ -0,0 +1,378 @@
+//! Region files, storing the world's chunks on disk.
+//!
+//! Chunks are grouped into regions of `REGION_FILE_SIZE`³ chunks, and each region is stored in
+//! its own file, like Minecraft's `.mca` files. A region file starts with a table of where each
+//! of its chunks is stored, so a single chunk can be read or rewritten without touching the rest.
+//!
+//! Every chunk record has the same size, so a chunk that is saved again is overwritten in place.
+//! New chunks are appended to the end of the file.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, bail, ensure};
+use engine::component::ComponentStoreHandle;
+use log::info;
+
+use crate::{
+    BlockPosition, ChunkPosition,
+    coords::bp,
+    world::{
+        BlockId, Chunk, World,
+        chunk::{CHUNK_SIZE, Fnv1a},
+    },
+};
+
+/// The width, height and depth of a region, in chunks.
+pub const REGION_FILE_SIZE: i64 = 16;
+/// The extension of region files.
+pub const REGION_FILE_EXTENSION: &str = "qcr";
+
+/// Identifies region files.
+const MAGIC: &[u8; 4] = b"QCRG";
+/// Bumped whenever the file layout changes.
+const VERSION: u32 = 2;
+/// The number of chunks in a region.
+const REGION_CHUNKS: usize = (REGION_FILE_SIZE * REGION_FILE_SIZE * REGION_FILE_SIZE) as usize;
+/// The size of the file header: magic, version and the offset of every chunk.
+const HEADER_LEN: usize = 4 + 4 + REGION_CHUNKS * 4;
+/// The size of a stored chunk: its little endian `u16` block IDs followed by a checksum of them.
+const CHUNK_RECORD_LEN: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE * 2 + 8;
+
+/// Returns the position of the region containing the given chunk.
+pub fn region_file_of(chunk: ChunkPosition) -> BlockPosition {
+    bp(
+        chunk.0.div_euclid(REGION_FILE_SIZE),
+        chunk.1.div_euclid(REGION_FILE_SIZE),
+        chunk.2.div_euclid(REGION_FILE_SIZE),
+    )
+}
+
+/// Returns the name of the file the given region is stored in.
+pub fn region_file_name(region: BlockPosition) -> String {
+    format!(
+        "r.{}.{}.{}.{}",
+        region.0, region.1, region.2, REGION_FILE_EXTENSION
+    )
+}
+
+/// Parses a region file name made by `region_file_name` back into the region's position.
+pub fn parse_region_file_name(name: &str) -> Option<BlockPosition> {
+    let rest = name
+        .strip_prefix("r.")?
+        .strip_suffix(REGION_FILE_EXTENSION)?
+        .strip_suffix('.')?;
+    let mut coordinates = rest.split('.').map(str::parse::<i64>);
+    let region = bp(
+        coordinates.next()?.ok()?,
+        coordinates.next()?.ok()?,
+        coordinates.next()?.ok()?,
+    );
+    coordinates.next().is_none().then_some(region)
+}
+
+/// Returns the index of a chunk in its region's offset table.
+fn chunk_index(chunk: ChunkPosition) -> usize {
+    let size = REGION_FILE_SIZE;
+    let (x, y, z) = (
+        chunk.0.rem_euclid(size),
+        chunk.1.rem_euclid(size),
+        chunk.2.rem_euclid(size),
+    );
+    ((x * size + y) * size + z) as usize
+}
+
+/// Encodes a chunk's blocks into a record.
+fn encode_chunk(chunk: &Chunk) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(CHUNK_RECORD_LEN);
+    bytes.extend(
+        chunk
+            .data
+            .iter()
+            .flatten()
+            .flatten()
+            .flat_map(|block| block.id().to_le_bytes()),
+    );
+    let mut hasher = Fnv1a::new();
+    hasher.write(&bytes);
+    bytes.extend_from_slice(&hasher.finish().to_le_bytes());
+    bytes
+}
+
+/// Decodes a record made by `encode_chunk`. The chunk is marked clean, as it matches the disk.
+fn decode_chunk(bytes: &[u8], state: &ComponentStoreHandle) -> anyhow::Result<Chunk> {
+    let (blocks, checksum) = bytes.split_at(bytes.len() - 8);
+    let mut hasher = Fnv1a::new();
+    hasher.write(blocks);
+    ensure!(
+        hasher.finish().to_le_bytes() == checksum,
+        "checksum mismatch"
+    );
+
+    let mut chunk = Chunk::empty(state.clone());
+    // Unknown IDs are kept, so blocks registered by whoever made the save aren't lost.
+    for (block, id) in chunk
+        .data
+        .iter_mut()
+        .flatten()
+        .flatten()
+        .zip(blocks.chunks_exact(2))
+    {
+        *block = BlockId(u16::from_le_bytes([id[0], id[1]]));
+    }
+    chunk.mark_clean();
+    Ok(chunk)
+}
+
+/// An open region file.
+pub struct RegionFile {
+    path: PathBuf,
+    file: File,
+    /// Where each chunk's record starts in the file, or 0 if the chunk isn't stored.
+    offsets: Vec<u32>,
+}
+
+impl RegionFile {
+    /// Opens the region file at `path`, creating an empty one if it doesn't exist.
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .with_context(|| format!("Failed to open region file {}", path.display()))?;
+        let len = file.metadata()?.len();
+        if len == 0 {
+            return Self::init(path, file);
+        }
+
+        let mut header = vec![0; HEADER_LEN];
+        file.read_exact(&mut header)
+            .with_context(|| format!("Region file {} is truncated", path.display()))?;
+        if &header[0..4] != MAGIC {
+            bail!("{} isn't a region file", path.display());
+        }
+        let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        ensure!(
+            version == VERSION,
+            "Region file {} has version {}, but only version {} is supported",
+            path.display(),
+            version,
+            VERSION
+        );
+        let offsets = header[8..]
+            .chunks_exact(4)
+            .map(|offset| u32::from_le_bytes(offset.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        if let Some(offset) = offsets
+            .iter()
+            .find(|offset| **offset != 0 && **offset as u64 + CHUNK_RECORD_LEN as u64 > len)
+        {
+            bail!(
+                "Region file {} is truncated: a chunk is stored at {} but the file is only {} bytes",
+                path.display(),
+                offset,
+                len
+            );
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            offsets,
+        })
+    }
+
+    /// Creates an empty region file at `path`, replacing any existing one.
+    pub fn create(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .with_context(|| format!("Failed to create region file {}", path.display()))?;
+        Self::init(path, file)
+    }
+
+    /// Writes an empty header to a new file.
+    fn init(path: &Path, mut file: File) -> anyhow::Result<Self> {
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.resize(HEADER_LEN, 0);
+        file.write_all(&header)
+            .with_context(|| format!("Failed to write region file {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            offsets: vec![0; REGION_CHUNKS],
+        })
+    }
+
+    /// Returns the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns true if the given chunk is stored in this region file.
+    pub fn contains(&self, chunk: ChunkPosition) -> bool {
+        self.offsets[chunk_index(chunk)] != 0
+    }
+
+    /// Returns the number of chunks stored in the file.
+    pub fn chunk_count(&self) -> usize {
+        self.offsets.iter().filter(|offset| **offset != 0).count()
+    }
+
+    /// Reads the given chunk, or returns `None` if it isn't stored in this region file.
+    pub fn read_chunk(
+        &mut self,
+        chunk: ChunkPosition,
+        state: &ComponentStoreHandle,
+    ) -> anyhow::Result<Option<Chunk>> {
+        let offset = self.offsets[chunk_index(chunk)];
+        if offset == 0 {
+            return Ok(None);
+        }
+        let mut record = vec![0; CHUNK_RECORD_LEN];
+        self.file.seek(SeekFrom::Start(offset as u64))?;
+        self.file.read_exact(&mut record)?;
+        decode_chunk(&record, state).map(Some).with_context(|| {
+            format!(
+                "Chunk {:?} in region file {} is corrupt",
+                chunk,
+                self.path.display()
+            )
+        })
+    }
+
+    /// Writes the given chunk, overwriting its old record if it was already stored.
+    pub fn write_chunk(&mut self, position: ChunkPosition, chunk: &Chunk) -> anyhow::Result<()> {
+        let index = chunk_index(position);
+        let offset = match self.offsets[index] {
+            0 => {
+                let end = self.file.seek(SeekFrom::End(0))?;
+                let offset = u32::try_from(end).context("Region file is too large")?;
+                // Record where the chunk is, so the file can be read even if the game stops
+                // halfway through saving.
+                self.offsets[index] = offset;
+                self.file.seek(SeekFrom::Start((8 + index * 4) as u64))?;
+                self.file.write_all(&offset.to_le_bytes())?;
+                offset
+            }
+            offset => offset,
+        };
+        self.file.seek(SeekFrom::Start(offset as u64))?;
+        self.file
+            .write_all(&encode_chunk(chunk))
+            .with_context(|| format!("Failed to write region file {}", self.path.display()))
+    }
+
+    /// Returns the positions of every chunk stored in the file, which holds the given region.
+    pub fn chunks(&self, region: BlockPosition) -> impl Iterator<Item = ChunkPosition> + '_ {
+        let size = REGION_FILE_SIZE;
+        let origin = region * bp(size, size, size);
+        (0..REGION_CHUNKS)
+            .filter(|index| self.offsets[*index] != 0)
+            .map(move |index| {
+                let index = index as i64;
+                origin + bp(index / (size * size), index / size % size, index % size)
+            })
+    }
+}
+
+/// Writes the world's chunks to region files in `dir`.
+///
+/// If `full` is false, only chunks changed since they were last saved or loaded are written,
+/// and the rest of each region file is left alone. Otherwise every region file in `dir` is
+/// replaced, so nothing from an older world is left behind.
+pub fn save_world(world: &World, dir: &Path, full: bool) -> anyhow::Result<()> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create region directory {}", dir.display()))?;
+    if full {
+        for path in region_files(dir)? {
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+    }
+
+    let mut regions = std::collections::HashMap::<BlockPosition, Vec<ChunkPosition>>::new();
+    for (position, chunk) in world.chunks() {
+        if full || chunk.get().is_dirty() {
+            regions
+                .entry(region_file_of(position))
+                .or_default()
+                .push(position);
+        }
+    }
+
+    let mut written = 0;
+    for (region, chunks) in &regions {
+        let mut file = RegionFile::open(dir.join(region_file_name(*region)))?;
+        for position in chunks {
+            let chunk = world.chunk(*position).expect("chunk is loaded").get();
+            file.write_chunk(*position, &chunk)?;
+            chunk.mark_clean();
+            written += 1;
+        }
+    }
+    info!(
+        "Saved {} chunks in {} region files to {}",
+        written,
+        regions.len(),
+        dir.display()
+    );
+    Ok(())
+}
+
+/// Reads every chunk stored in the region files in `dir`.
+///
+/// Regions without a file, or a missing `dir` altogether, are simply left empty.
+pub fn load_world(dir: &Path, state: &ComponentStoreHandle) -> anyhow::Result<World> {
+    let mut world = World::empty(state);
+    if !dir.is_dir() {
+        return Ok(world);
+    }
+    for path in region_files(dir)? {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let Some(region) = parse_region_file_name(name) else {
+            continue;
+        };
+        let mut file = RegionFile::open(&path)?;
+        for position in file.chunks(region).collect::<Vec<_>>() {
+            let chunk = file
+                .read_chunk(position, state)?
+                .expect("listed chunks are stored");
+            world.push_chunk(position, chunk);
+        }
+    }
+    world.populate_neighbors();
+    world.relight_all();
+    Ok(world)
+}
+
+/// Returns the paths of the region files in `dir`.
+fn region_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to list region directory {}", dir.display()))?
+    {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|ext| ext == REGION_FILE_EXTENSION)
+        {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, ensure};
+
+use crate::world::{Block, BlockId, chunk::Fnv1a};
+
+/// What the game needs to know about a kind of block to simulate and draw it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockProperties {
+    /// The unique name of the block, like `"stone"`.
+    pub name: String,
+    /// Whether the block takes up its whole cell and collides.
+    pub solid: bool,
+    /// Whether the block can be seen through. Transparent blocks are drawn in the blended pass,
+    /// don't hide the faces of their neighbors, and hide the faces between two of themselves.
+    pub transparent: bool,
+    /// How much light the block gives off, from 0 to 15.
+    pub light_emission: u8,
+    /// The name of the block's texture in the block atlas, or `None` if the block isn't drawn as
+    /// a cube, like air and fluids.
+    pub texture_key: Option<String>,
+}
+
+impl BlockProperties {
+    /// Creates the properties of a solid, opaque cube drawn with the given texture.
+    pub fn cube(name: impl Into<String>, texture_key: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            solid: true,
+            transparent: false,
+            light_emission: 0,
+            texture_key: Some(texture_key.into()),
+        }
+    }
+
+    /// Returns the properties with `transparent` set.
+    pub fn transparent(mut self) -> Self {
+        self.transparent = true;
+        self
+    }
+
+    /// Returns the properties with the given light emission, from 0 to 15.
+    pub fn with_light_emission(mut self, light_emission: u8) -> Self {
+        self.light_emission = light_emission;
+        self
+    }
+
+    /// Returns true if the block is solid and can't be seen through, so faces against it are
+    /// hidden.
+    pub fn is_opaque(&self) -> bool {
+        self.solid && !self.transparent
+    }
+
+    /// Returns true if the block is drawn as a textured cube by the solid geometry pipeline.
+    pub fn is_cube(&self) -> bool {
+        self.texture_key.is_some()
+    }
+}
+
+/// Maps block IDs to their properties, so blocks can be added without changing the `Block`
+/// type.
+///
+/// The built-in blocks are registered up front under their fixed IDs. Blocks that aren't
+/// registered, e.g. from a save made with more blocks, are treated as opaque cubes with the
+/// missing texture.
+#[derive(Debug, Clone)]
+pub struct BlockRegistry {
+    blocks: Vec<BlockProperties>,
+    names: HashMap<String, BlockId>,
+}
+
+impl BlockRegistry {
+    /// The most light a block can give off.
+    pub const MAX_LIGHT: u8 = 15;
+
+    /// Creates a registry holding the built-in blocks.
+    pub fn new() -> Self {
+        let mut registry = Self {
+            blocks: Vec::new(),
+            names: HashMap::new(),
+        };
+        let fluid = |name: &str| BlockProperties {
+            name: name.to_string(),
+            solid: false,
+            transparent: true,
+            light_emission: 0,
+            texture_key: None,
+        };
+        let builtins = [
+            (
+                Block::Air,
+                BlockProperties {
+                    transparent: false,
+                    ..fluid("air")
+                },
+            ),
+            (Block::Dirt, BlockProperties::cube("dirt", "dirt")),
+            (Block::Stone, BlockProperties::cube("stone", "stone")),
+            (Block::Grass, BlockProperties::cube("grass", "grass_block")),
+            (
+                Block::OakWood,
+                BlockProperties::cube("oak_wood", "oak_wood"),
+            ),
+            (
+                Block::OakLeaves,
+                BlockProperties::cube("oak_leaves", "oak_leaves").transparent(),
+            ),
+            (Block::Water, fluid("water")),
+        ];
+        for (block, properties) in builtins {
+            let id = registry
+                .register(properties)
+                .expect("built-in blocks have unique names");
+            debug_assert_eq!(id, block);
+        }
+        registry
+    }
+
+    /// Registers a new kind of block, returning its ID.
+    ///
+    /// Fails if a block with the same name is already registered, or every ID is taken.
+    pub fn register(&mut self, properties: BlockProperties) -> anyhow::Result<BlockId> {
+        if self.names.contains_key(&properties.name) {
+            bail!("A block named {:?} is already registered", properties.name);
+        }
+        ensure!(
+            properties.light_emission <= Self::MAX_LIGHT,
+            "Block {:?} emits light level {}, but the most is {}",
+            properties.name,
+            properties.light_emission,
+            Self::MAX_LIGHT
+        );
+        let id = u16::try_from(self.blocks.len())
+            .map(BlockId)
+            .map_err(|_| anyhow::anyhow!("Every block ID is taken"))?;
+        self.names.insert(properties.name.clone(), id);
+        self.blocks.push(properties);
+        Ok(id)
+    }
+
+    /// Returns the properties of a block, if it's registered.
+    pub fn get(&self, block: BlockId) -> Option<&BlockProperties> {
+        self.blocks.get(block.0 as usize)
+    }
+
+    /// Returns the ID of the block registered under `name`.
+    pub fn by_name(&self, name: &str) -> Option<BlockId> {
+        self.names.get(name).copied()
+    }
+
+    /// Returns every registered block with its properties, in ID order.
+    pub fn iter(&self) -> impl Iterator<Item = (BlockId, &BlockProperties)> {
+        self.blocks
+            .iter()
+            .enumerate()
+            .map(|(id, properties)| (BlockId(id as u16), properties))
+    }
+
+    /// Returns the number of registered blocks.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns true if no blocks are registered. Never true for a registry from `new`.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Returns true if the block takes up its whole cell and collides.
+    pub fn is_solid(&self, block: BlockId) -> bool {
+        self.get(block).is_none_or(|properties| properties.solid)
+    }
+
+    /// Returns true if the block can be seen through.
+    pub fn is_transparent(&self, block: BlockId) -> bool {
+        self.get(block)
+            .is_some_and(|properties| properties.transparent)
+    }
+
+    /// Returns true if the block is solid and can't be seen through.
+    pub fn is_opaque(&self, block: BlockId) -> bool {
+        self.get(block).is_none_or(BlockProperties::is_opaque)
+    }
+
+    /// Returns true if the block is drawn as a textured cube.
+    pub fn is_cube(&self, block: BlockId) -> bool {
+        self.get(block).is_none_or(BlockProperties::is_cube)
+    }
+
+    /// Returns how much light the block gives off, from 0 to 15.
+    pub fn light_emission(&self, block: BlockId) -> u8 {
+        self.get(block)
+            .map_or(0, |properties| properties.light_emission)
+    }
+
+    /// Returns true if the face of `block` towards `neighbor` is hidden and shouldn't be drawn.
+    ///
+    /// Faces against opaque blocks are always hidden. Faces between two of the same transparent
+    /// block are too, like between leaves, which also keeps them from z-fighting.
+    pub fn face_hidden_by(&self, block: BlockId, neighbor: BlockId) -> bool {
+        self.is_opaque(neighbor) || (neighbor == block && self.is_transparent(block))
+    }
+
+    /// Returns a checksum of every block's properties, which changes whenever meshes built with
+    /// the registry may have.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = Fnv1a::new();
+        for properties in &self.blocks {
+            hasher.write(properties.name.as_bytes());
+            hasher.write(&[
+                properties.solid as u8,
+                properties.transparent as u8,
+                properties.light_emission,
+            ]);
+            hasher.write(properties.texture_key.as_deref().unwrap_or("").as_bytes());
+            // Keeps adjacent strings from running together.
+            hasher.write_u8(0xff);
+        }
+        hasher.finish()
+    }
+}
+
+impl Default for BlockRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use engine::{
+        component::ComponentStore,
+        graphics::{image::Image, textures::TextureCollection},
+    };
+
+    use super::*;
+    use crate::{
+        coords::bp,
+        render::{block_textures::BlockTextureAtlas, pipelines::solid::build_mesh_for_chunk},
+        world::{Chunk, chunk::CHUNK_SIZE},
+    };
+
+    #[test]
+    fn custom_blocks_are_meshed_with_their_texture() {
+        let mut registry = BlockRegistry::new();
+        let cobblestone = registry
+            .register(BlockProperties::cube("cobblestone", "cobblestone"))
+            .unwrap();
+        let glass = registry
+            .register(BlockProperties::cube("glass", "glass").transparent())
+            .unwrap();
+        assert_eq!(registry.by_name("glass"), Some(glass));
+        assert!(
+            registry
+                .register(BlockProperties::cube("glass", "glass"))
+                .is_err()
+        );
+
+        let state = ComponentStore::new();
+        let mut collection = TextureCollection::new(&state, Some("Blocks"), (16, 16));
+        collection.push_invalid_texture();
+        let image = Image::from_rgba(16, 16, vec![128; 16 * 16 * 4]).unwrap();
+        let cobblestone_texture = collection.add_texture("cobblestone", &image);
+        let glass_texture = collection.add_texture("glass", &image);
+        let mut atlas = BlockTextureAtlas::new();
+        atlas.assign_textures(&registry, &collection);
+
+        let mut blocks = [[[Block::Air; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+        blocks[1][1][1] = cobblestone;
+        blocks[2][1][1] = glass;
+        let mesh =
+            build_mesh_for_chunk(&atlas, &registry, &Chunk::from_blocks(blocks), bp(0, 0, 0));
+
+        // The glass doesn't hide the cobblestone, but the cobblestone hides the glass' face
+        // against it.
+        assert_eq!(mesh.vertices.len(), 6 * 4);
+        assert!(
+            mesh.vertices
+                .iter()
+                .all(|v| v.texture_index == cobblestone_texture.base_layer)
+        );
+        assert_eq!(mesh.transparent_vertices.len(), 5 * 4);
+        assert!(
+            mesh.transparent_vertices
+                .iter()
+                .all(|v| v.texture_index == glass_texture.base_layer)
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::{
+    BlockPosition,
+    coords::bp,
+    world::{BlockEdit, ReplacePolicy, Structure, World},
+};
+
+/// A copied region of the world, usable as a clipboard for building.
+///
+/// Schematics are anchored at their minimum corner, so pasting at an origin places the
+/// copied region's minimum corner there.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Schematic {
+    pub structure: Structure,
+}
+
+impl Schematic {
+    /// Returns the size of the copied region.
+    pub fn size(&self) -> (usize, usize, usize) {
+        self.structure.size()
+    }
+
+    /// Loads a schematic from the TOML file at `path`.
+    ///
+    /// Fails if the file can't be read or doesn't hold a valid schematic.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Schematic> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read schematic {}", path.display()))?;
+        toml::from_str(&text)
+            .with_context(|| format!("Failed to parse schematic {}", path.display()))
+    }
+
+    /// Saves the schematic as TOML to `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, toml::to_string(self)?)
+            .with_context(|| format!("Failed to write schematic {}", path.display()))
+    }
+}
+
+impl World {
+    /// Copies the inclusive region between `min` and `max` into a schematic.
+    ///
+    /// The corners may be given in any order.
+    pub fn copy_region(&self, min: BlockPosition, max: BlockPosition) -> Schematic {
+        let low = bp(min.0.min(max.0), min.1.min(max.1), min.2.min(max.2));
+        let high = bp(min.0.max(max.0), min.1.max(max.1), min.2.max(max.2));
+        let size = high - low + bp(1, 1, 1);
+        let mut structure = Structure::new(
+            (size.0 as usize, size.1 as usize, size.2 as usize),
+            bp(0, 0, 0),
+        );
+        for x in 0..size.0 {
+            for y in 0..size.1 {
+                for z in 0..size.2 {
+                    let block = self.get_block(low + bp(x, y, z));
+                    structure.set(x as usize, y as usize, z as usize, block);
+                }
+            }
+        }
+        Schematic { structure }
+    }
+
+    /// Pastes a schematic with its minimum corner at `origin`.
+    pub fn paste(
+        &mut self,
+        origin: BlockPosition,
+        schematic: &Schematic,
+        policy: ReplacePolicy,
+    ) -> Vec<BlockEdit> {
+        self.place_structure(origin, &schematic.structure, policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use engine::component::ComponentStore;
+
+    use super::*;
+    use crate::world::Block;
+
+    #[test]
+    fn copy_then_paste_across_chunks() {
+        let store = ComponentStore::new();
+        store.finish_initialization();
+        let mut world = World::empty(&store.handle());
+        // A 4x3x2 region straddling the chunk border at X 16.
+        for x in 14..18 {
+            for y in 0..3 {
+                for z in 0..2 {
+                    let block = if (x + y + z) % 2 == 0 {
+                        Block::Stone
+                    } else {
+                        Block::Dirt
+                    };
+                    world.set_block(bp(x, y, z), block);
+                }
+            }
+        }
+
+        let schematic = world.copy_region(bp(17, 2, 1), bp(14, 0, 0));
+        assert_eq!(schematic.size(), (4, 3, 2));
+
+        // Pasted across the chunk border at Z -16.
+        let origin = bp(30, 5, -17);
+        world.paste(origin, &schematic, ReplacePolicy::All);
+        for x in 0..4 {
+            for y in 0..3 {
+                for z in 0..2 {
+                    assert_eq!(
+                        world.get_block(origin + bp(x, y, z)),
+                        world.get_block(bp(14 + x, y, z))
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut structure = Structure::new((2, 1, 2), bp(0, 0, 0));
+        structure.set(1, 0, 1, Block::Stone);
+        let schematic = Schematic { structure };
+
+        let path = std::env::temp_dir().join(format!("schematic-{}.toml", std::process::id()));
+        schematic.save(&path).unwrap();
+        let loaded = Schematic::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), schematic);
+    }
+
+    #[test]
+    fn load_rejects_mismatched_blocks() {
+        let structure = Structure::new((2, 2, 2), bp(0, 0, 0));
+        let mut text = toml::to_string(&Schematic { structure }).unwrap();
+        text = text.replace("size = [2, 2, 2]", "size = [3, 2, 2]");
+        assert!(toml::from_str::<Schematic>(&text).is_err());
+
+        assert!(Structure::from_blocks((1, 1, 1), bp(0, 1, 0), vec![Block::Air]).is_err());
+        assert!(Structure::from_blocks((1, 1, 1), bp(0, 0, 0), vec![Block::Air]).is_ok());
+    }
+}
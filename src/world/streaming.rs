@@ -0,0 +1,187 @@
+use std::collections::{HashSet, VecDeque};
+
+use glam::Vec3;
+
+use crate::{
+    ChunkPosition,
+    coords::bp,
+    world::{
+        ActiveWorld, ChunkLoader, LoadedChunk, World, chunk::CHUNK_SIZE,
+        loader::CHUNK_UPLOADS_PER_FRAME,
+    },
+};
+
+/// How many columns past the render distance loaded columns are kept, so moving back and forth
+/// across a chunk border doesn't unload and reload the same columns.
+pub const UNLOAD_MARGIN: usize = 2;
+
+/// How many chunks are handed to the loader at a time. Keeping its queue short lets nearer
+/// chunks jump ahead when the camera moves.
+const MAX_IN_FLIGHT: usize = 32;
+
+/// The chunks a `StreamingWorld::update` added to and removed from the world.
+#[derive(Default)]
+pub struct StreamedChunks {
+    /// Chunks inserted into the world, with the meshes the loader built for them.
+    pub loaded: Vec<LoadedChunk>,
+    /// Chunks removed from the world.
+    pub unloaded: Vec<ChunkPosition>,
+}
+
+/// Loads the columns of chunks around the camera as it moves, and unloads those it leaves
+/// behind.
+///
+/// Columns within the render distance of the camera's column are loaded through a `ChunkLoader`,
+/// nearest first. Columns further away than the render distance plus the unload margin are
+/// removed from the world. Which columns are needed is only recomputed when the camera crosses
+/// into another column or the render distance changes.
+///
+/// Unloaded chunks are generated again when they come back into range, so edits to them are lost
+/// unless the world was saved first.
+pub struct StreamingWorld {
+    loader: ChunkLoader,
+    render_distance: usize,
+    margin: usize,
+    /// The column the needed columns were last computed around, if they're up to date.
+    center: Option<(i64, i64)>,
+    /// The generation of the active world the columns were computed for.
+    world_generation: u64,
+    /// Chunks waiting to be handed to the loader, nearest first.
+    queue: VecDeque<ChunkPosition>,
+    /// Columns queued, loading or loaded by the streamer.
+    requested: HashSet<(i64, i64)>,
+}
+
+impl StreamingWorld {
+    /// Creates a streamer loading chunks through `loader`, up to `render_distance` columns from
+    /// the camera.
+    pub fn new(loader: ChunkLoader, render_distance: usize) -> Self {
+        Self {
+            loader,
+            render_distance,
+            margin: UNLOAD_MARGIN,
+            center: None,
+            world_generation: 0,
+            queue: VecDeque::new(),
+            requested: HashSet::new(),
+        }
+    }
+
+    /// Sets how many columns past the render distance loaded columns are kept.
+    pub fn with_margin(mut self, margin: usize) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Returns how far from the camera columns are loaded, in chunks.
+    pub fn render_distance(&self) -> usize {
+        self.render_distance
+    }
+
+    /// Sets how far from the camera columns are loaded, in chunks. Takes effect on the next
+    /// `update`.
+    pub fn set_render_distance(&mut self, render_distance: usize) {
+        if render_distance != self.render_distance {
+            self.render_distance = render_distance;
+            self.center = None;
+        }
+    }
+
+    /// Returns the number of chunks waiting to be loaded.
+    pub fn pending(&self) -> usize {
+        self.queue.len() + self.loader.pending()
+    }
+
+    /// Loads and unloads chunks around the camera at `camera`, inserting up to
+    /// `CHUNK_UPLOADS_PER_FRAME` loaded chunks into the active world.
+    ///
+    /// Chunks that are all air aren't inserted, like the ones above the terrain in
+    /// `World::generate`.
+    pub fn update(&mut self, camera: Vec3, active_world: &mut ActiveWorld) -> StreamedChunks {
+        let mut streamed = StreamedChunks::default();
+        if active_world.generation() != self.world_generation {
+            // Anything requested was for the old world.
+            self.world_generation = active_world.generation();
+            self.center = None;
+            self.queue.clear();
+            self.requested.clear();
+        }
+        let Some(world) = active_world.get_world_mut() else {
+            return streamed;
+        };
+
+        let column = (
+            (camera.x / CHUNK_SIZE as f32).floor() as i64,
+            (camera.z / CHUNK_SIZE as f32).floor() as i64,
+        );
+        if self.center != Some(column) {
+            self.center = Some(column);
+            streamed.unloaded = self.recompute(column, world);
+        }
+
+        while self.loader.pending() < MAX_IN_FLIGHT {
+            let Some(position) = self.queue.pop_front() else {
+                break;
+            };
+            self.loader.submit(position);
+        }
+
+        for chunk in self.loader.poll(CHUNK_UPLOADS_PER_FRAME) {
+            // The column may have been unloaded while the chunk was loading.
+            if chunk.is_empty()
+                || !self
+                    .requested
+                    .contains(&(chunk.position.0, chunk.position.2))
+            {
+                continue;
+            }
+            world.insert_chunk(chunk.position, chunk.to_chunk());
+            streamed.loaded.push(chunk);
+        }
+        streamed
+    }
+
+    /// Unloads the columns too far from `center` and queues the missing ones within the render
+    /// distance of it, returning the unloaded chunks.
+    fn recompute(&mut self, center: (i64, i64), world: &mut World) -> Vec<ChunkPosition> {
+        let distance = |(x, z): (i64, i64)| (x - center.0).pow(2) + (z - center.1).pow(2);
+        let radius = self.render_distance as i64;
+        let keep = (radius + self.margin as i64).pow(2);
+
+        let far = world
+            .columns()
+            .map(|(column, _)| column)
+            .filter(|column| distance(*column) > keep)
+            .collect::<Vec<_>>();
+        let mut unloaded = Vec::new();
+        for (x, z) in far {
+            unloaded.extend(world.remove_column(x, z));
+        }
+        self.requested.retain(|column| distance(*column) <= keep);
+        self.queue
+            .retain(|position| self.requested.contains(&(position.0, position.2)));
+
+        let mut needed = Vec::new();
+        for x in center.0 - radius..=center.0 + radius {
+            for z in center.1 - radius..=center.1 + radius {
+                if distance((x, z)) <= radius.pow(2)
+                    && world.column(x, z).is_none()
+                    && !self.requested.contains(&(x, z))
+                {
+                    needed.push((x, z));
+                }
+            }
+        }
+        let height = world.column_height().div_ceil(CHUNK_SIZE) as i64;
+        for (x, z) in needed {
+            self.requested.insert((x, z));
+            self.queue.extend((0..height).map(|y| bp(x, y, z)));
+        }
+        // Chunks queued around the old center are reordered too. Columns load bottom up, as the
+        // terrain is near the bottom.
+        self.queue
+            .make_contiguous()
+            .sort_by_key(|position| (distance((position.0, position.2)), position.1));
+        unloaded
+    }
+}
@@ -0,0 +1,219 @@
+use anyhow::{anyhow, ensure};
+
+use crate::{BlockPosition, coords::bp, world::Block};
+
+/// Controls which blocks a structure is allowed to overwrite when placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplacePolicy {
+    /// Only write into cells that are currently air. Air cells in the structure are skipped.
+    AirOnly,
+    /// Write every cell of the structure, including air cells.
+    All,
+}
+
+/// A small 3D block template that can be stamped into a world.
+///
+/// Deserializing checks the blocks match the size, like [`Structure::from_blocks`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "StructureData")]
+pub struct Structure {
+    size: (usize, usize, usize),
+    /// The local position that ends up at the placement origin.
+    pub anchor: BlockPosition,
+    blocks: Vec<Block>,
+}
+
+/// The unchecked form of a `Structure`, as it's deserialized.
+#[derive(serde::Deserialize)]
+struct StructureData {
+    size: (usize, usize, usize),
+    anchor: BlockPosition,
+    blocks: Vec<Block>,
+}
+
+impl TryFrom<StructureData> for Structure {
+    type Error = anyhow::Error;
+
+    fn try_from(data: StructureData) -> anyhow::Result<Self> {
+        Self::from_blocks(data.size, data.anchor, data.blocks)
+    }
+}
+
+impl Structure {
+    /// Creates a new structure of the given size filled with air.
+    pub fn new(size: (usize, usize, usize), anchor: BlockPosition) -> Self {
+        Self {
+            size,
+            anchor,
+            blocks: vec![Block::Air; size.0 * size.1 * size.2],
+        }
+    }
+
+    /// Creates a structure from its blocks, ordered by X, then Y, then Z.
+    ///
+    /// Fails if there isn't exactly one block per cell or the anchor is outside the structure.
+    pub fn from_blocks(
+        size: (usize, usize, usize),
+        anchor: BlockPosition,
+        blocks: Vec<Block>,
+    ) -> anyhow::Result<Self> {
+        let cells = size
+            .0
+            .checked_mul(size.1)
+            .and_then(|cells| cells.checked_mul(size.2))
+            .ok_or_else(|| anyhow!("Structure of size {:?} has too many cells", size))?;
+        ensure!(
+            blocks.len() == cells,
+            "Structure of size {:?} needs {} blocks, but has {}",
+            size,
+            cells,
+            blocks.len()
+        );
+        let in_bounds = |value: i64, size: usize| value >= 0 && (value as usize) < size;
+        ensure!(
+            in_bounds(anchor.0, size.0)
+                && in_bounds(anchor.1, size.1)
+                && in_bounds(anchor.2, size.2),
+            "Anchor {:?} is outside the structure of size {:?}",
+            anchor,
+            size
+        );
+        Ok(Self {
+            size,
+            anchor,
+            blocks,
+        })
+    }
+
+    /// Returns the size of the structure.
+    pub fn size(&self) -> (usize, usize, usize) {
+        self.size
+    }
+
+    fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        assert!(
+            x < self.size.0 && y < self.size.1 && z < self.size.2,
+            "Position ({x}, {y}, {z}) out of bounds for structure of size {:?}",
+            self.size
+        );
+        (x * self.size.1 + y) * self.size.2 + z
+    }
+
+    /// Gets the block at the given local position.
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Block {
+        self.blocks[self.index(x, y, z)]
+    }
+
+    /// Sets the block at the given local position.
+    pub fn set(&mut self, x: usize, y: usize, z: usize, block: Block) {
+        let index = self.index(x, y, z);
+        self.blocks[index] = block;
+    }
+
+    /// Fills the inclusive local region between `min` and `max` with the given block.
+    pub fn fill(&mut self, min: (usize, usize, usize), max: (usize, usize, usize), block: Block) {
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                for z in min.2..=max.2 {
+                    self.set(x, y, z, block);
+                }
+            }
+        }
+    }
+
+    /// Iterates over every cell in the structure as (local position, block).
+    pub fn iter(&self) -> impl Iterator<Item = (BlockPosition, Block)> + '_ {
+        let (sx, sy, sz) = self.size;
+        (0..sx).flat_map(move |x| {
+            (0..sy).flat_map(move |y| {
+                (0..sz).map(move |z| (bp(x as i64, y as i64, z as i64), self.get(x, y, z)))
+            })
+        })
+    }
+
+    /// Creates a simple oak tree with the given trunk height.
+    ///
+    /// The anchor is the bottom of the trunk, so the origin passed to `World::place_structure`
+    /// should be the block directly above the ground.
+    pub fn oak_tree(trunk_height: usize) -> Self {
+        let trunk_height = trunk_height.max(3);
+        let height = trunk_height + 2;
+        let mut tree = Self::new((5, height, 5), bp(2, 0, 2));
+
+        // Two wide layers of leaves around the top of the trunk, then a small cap.
+        tree.fill(
+            (0, trunk_height - 2, 0),
+            (4, trunk_height - 1, 4),
+            Block::OakLeaves,
+        );
+        tree.fill((1, trunk_height, 1), (3, height - 1, 3), Block::OakLeaves);
+        for corner in [(0, 0), (0, 4), (4, 0), (4, 4)] {
+            tree.set(corner.0, trunk_height - 1, corner.1, Block::Air);
+        }
+
+        tree.fill((2, 0, 2), (2, trunk_height, 2), Block::OakWood);
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use engine::component::ComponentStore;
+
+    use super::*;
+    use crate::world::World;
+
+    #[test]
+    fn place_structure_across_chunks() {
+        let store = ComponentStore::new();
+        store.finish_initialization();
+        let mut world = World::empty(&store.handle());
+
+        let mut structure = Structure::new((3, 3, 3), bp(0, 0, 0));
+        for (local, _) in structure.clone().iter() {
+            let block = if (local.0 + local.1 + local.2) % 2 == 0 {
+                Block::Stone
+            } else {
+                Block::Dirt
+            };
+            structure.set(local.0 as usize, local.1 as usize, local.2 as usize, block);
+        }
+
+        // X 14..=16 crosses from chunk 0 into chunk 1.
+        let origin = bp(14, 0, 0);
+        let edits = world.place_structure(origin, &structure, ReplacePolicy::All);
+        assert_eq!(edits.len(), 27);
+        assert!(world.column(0, 0).is_some());
+        assert!(world.column(1, 0).is_some());
+        for (local, block) in structure.iter() {
+            assert_eq!(world.get_block(origin + local), block);
+        }
+    }
+
+    #[test]
+    fn air_only_keeps_existing_blocks() {
+        let store = ComponentStore::new();
+        store.finish_initialization();
+        let mut world = World::empty(&store.handle());
+        world.set_block(bp(0, 0, 0), Block::Stone);
+
+        world.place_structure(bp(0, 0, 0), &Structure::oak_tree(4), ReplacePolicy::AirOnly);
+        assert_eq!(world.get_block(bp(0, 0, 0)), Block::Stone);
+        assert_eq!(world.get_block(bp(0, 1, 0)), Block::OakWood);
+    }
+
+    #[test]
+    fn huge_sizes_are_rejected() {
+        let size = (usize::MAX, 2, 1);
+        assert!(Structure::from_blocks(size, bp(0, 0, 0), vec![]).is_err());
+
+        // Wraps around to zero cells with unchecked math, matching the empty block list.
+        let size = (1 << (usize::BITS / 2), 1 << (usize::BITS / 2), 1);
+        let data = StructureData {
+            size,
+            anchor: bp(0, 0, 0),
+            blocks: vec![],
+        };
+        assert!(Structure::try_from(data).is_err());
+    }
+}